@@ -0,0 +1,659 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use clap::{Parser, Subcommand};
+use itertools::Itertools as _;
+use log::info;
+
+use flipull_advance_solver::rom::Rom;
+use flipull_advance_solver::*;
+
+/// 問題を解析する。
+#[derive(Debug, Parser)]
+struct Cli {
+    /// メッセージの言語 (ja, en)。
+    #[arg(long, global = true, default_value = "ja")]
+    lang: Locale,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// どのような着手列によっても消せないブロックを列挙する。
+    DeadBlocks {
+        /// 問題ファイル。
+        path_problem: PathBuf,
+    },
+
+    /// コスト上限以内の全ての着手列を列挙し、解空間の統計情報を出力する。
+    Stats {
+        /// 最終面かどうか。
+        #[arg(long)]
+        last_stage: bool,
+
+        /// コスト上限。
+        #[arg(long)]
+        cost_bound: Cost,
+
+        /// 問題ファイル。
+        path_problem: PathBuf,
+    },
+
+    /// コスト上限以内の全ての解を、見つけた順に 1 行に 1 つ「コスト: 解」の形で出力する。
+    Solutions {
+        /// 最終面かどうか。
+        #[arg(long)]
+        last_stage: bool,
+
+        /// コスト上限。
+        #[arg(long)]
+        cost_bound: Cost,
+
+        /// 問題ファイル。
+        path_problem: PathBuf,
+    },
+
+    /// 複数の問題を解き、ソルバーの探索局面数と下界の tightness による難易度順の表を出力する。
+    Rank {
+        /// 最後の問題 (--rom なら面 50) を最終面として扱う。
+        #[arg(long)]
+        last_stage: bool,
+
+        /// 問題ファイルたちの代わりに、この ROM ファイル (iNES 形式) の全面を解く。
+        #[arg(long, value_name = "FILE", conflicts_with = "paths_problem")]
+        rom: Option<PathBuf>,
+
+        /// 問題ファイルたち。
+        #[arg(required_unless_present = "rom")]
+        paths_problem: Vec<PathBuf>,
+    },
+
+    /// 最適解の各手が強制手か (他の着手では最適解が得られないか) どうかを出力する。
+    Critical {
+        /// 最終面かどうか。
+        #[arg(long)]
+        last_stage: bool,
+
+        /// 問題ファイル。
+        path_problem: PathBuf,
+    },
+
+    /// 与えられた解が最適解からどこで外れ、各手で何フレーム失っているかを出力する。
+    Gap {
+        /// 最終面かどうか。
+        #[arg(long)]
+        last_stage: bool,
+
+        /// 問題ファイル。
+        path_problem: PathBuf,
+
+        /// 解ファイル。
+        path_solution: PathBuf,
+    },
+
+    /// ランダムに生成した問題たちを解き、可解性を集計する。
+    Census {
+        /// 生成する問題の数。
+        #[arg(long, default_value_t = 100)]
+        samples: usize,
+
+        /// 1 問あたりの探索局面数の上限。
+        #[arg(long, default_value_t = 1_000_000)]
+        node_limit: u64,
+
+        /// 乱数シード。
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+
+        /// ブロックを置く列数。
+        #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u8).range(1..=6))]
+        cols: u8,
+
+        /// ブロックを置く行数。
+        #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u8).range(1..=6))]
+        rows: u8,
+
+        /// 使うブロックの種類数。
+        #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u8).range(1..=4))]
+        kinds: u8,
+
+        /// 階段状の壁の段数。
+        #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u8).range(0..=6))]
+        walls: u8,
+
+        /// 残り手数。
+        #[arg(long, default_value_t = 20)]
+        moves: u8,
+
+        /// 初期保持ブロックをワイルドカードにする。
+        #[arg(long)]
+        wild: bool,
+    },
+
+    /// 数手以内で到達できる局面たちの (局面, 最適な残りコスト, 最善手) を機械学習用に出力する。
+    Training {
+        /// 最終面かどうか。
+        #[arg(long)]
+        last_stage: bool,
+
+        /// 初期局面からの最大手数。
+        #[arg(long, default_value_t = 2)]
+        depth: usize,
+
+        /// CSV でなく NumPy の .npy 形式で出力する。
+        #[arg(long)]
+        npy: bool,
+
+        /// 問題ファイル。
+        path_problem: PathBuf,
+    },
+
+    /// 初期保持ブロックを各値に変えて解き直し、最適解のコストの変化を出力する。
+    Holding {
+        /// 最終面かどうか。
+        #[arg(long)]
+        last_stage: bool,
+
+        /// 問題ファイル。
+        path_problem: PathBuf,
+    },
+
+    /// ブロック 1, 2 個の色や初期保持ブロックを変えた問題を解き、目標のコストや難易度に近いものを出力する。
+    Edit {
+        /// 最終面かどうか。
+        #[arg(long)]
+        last_stage: bool,
+
+        /// 目標とする最適解のコスト。
+        #[arg(
+            long,
+            conflicts_with = "target_tightness",
+            required_unless_present = "target_tightness"
+        )]
+        target_cost: Option<Cost>,
+
+        /// 目標とする最適解のコストと下界の比 (大きいほど難しい)。
+        #[arg(long)]
+        target_tightness: Option<f64>,
+
+        /// 一度に行う改変の最大数。
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=2))]
+        max_edits: u8,
+
+        /// 1 候補あたりの探索局面数の上限。
+        #[arg(long, default_value_t = 1_000_000)]
+        node_limit: u64,
+
+        /// 出力する候補の数。
+        #[arg(long, default_value_t = 10)]
+        count: usize,
+
+        /// 問題ファイル。
+        path_problem: PathBuf,
+    },
+
+    /// 初手ごとに上限付きで探索し、どの行・列やブロックから崩すべきかの要約と初手の一覧を出力する。
+    Advise {
+        /// 最終面かどうか。
+        #[arg(long)]
+        last_stage: bool,
+
+        /// 初手 1 つあたりの探索局面数の上限。
+        #[arg(long, default_value_t = 1_000_000)]
+        node_limit: u64,
+
+        /// 問題ファイル。
+        path_problem: PathBuf,
+    },
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init_from_env(env_logger::Env::new().default_filter_or("warn"));
+
+    let cli = Cli::parse();
+    cli.lang.set_current();
+
+    match cli.command {
+        Command::DeadBlocks { path_problem } => {
+            let problem = read_problem(&path_problem)?;
+            cmd_dead_blocks(&problem);
+        }
+        Command::Stats {
+            last_stage,
+            cost_bound,
+            path_problem,
+        } => {
+            let problem = read_problem(&path_problem)?;
+            cmd_stats(&problem, last_stage, cost_bound);
+        }
+        Command::Solutions {
+            last_stage,
+            cost_bound,
+            path_problem,
+        } => {
+            let problem = read_problem(&path_problem)?;
+            cmd_solutions(&problem, last_stage, cost_bound);
+        }
+        Command::Rank {
+            last_stage,
+            rom,
+            paths_problem,
+        } => {
+            let (names, problems): (Vec<String>, _) = if let Some(path_rom) = rom {
+                let rom = Rom::from_ines_file(&path_rom).with_context(|| {
+                    tr(
+                        format!("ROM ファイル '{}' を読めない", path_rom.display()),
+                        format!("cannot read ROM file '{}'", path_rom.display()),
+                    )
+                })?;
+                let problems = rom.problems()?;
+                let names = (1..=problems.len())
+                    .map(|stage| format!("stage {stage}"))
+                    .collect();
+                (names, problems)
+            } else {
+                let problems = paths_problem
+                    .iter()
+                    .map(|path| read_problem(path))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let names = paths_problem
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                (names, problems)
+            };
+            cmd_rank(&names, &problems, last_stage);
+        }
+        Command::Critical {
+            last_stage,
+            path_problem,
+        } => {
+            let problem = read_problem(&path_problem)?;
+            cmd_critical(&problem, last_stage);
+        }
+        Command::Gap {
+            last_stage,
+            path_problem,
+            path_solution,
+        } => {
+            let problem = read_problem(&path_problem)?;
+            let solution = read_solution(&path_solution)?;
+            cmd_gap(&problem, &solution, last_stage)?;
+        }
+        Command::Census {
+            samples,
+            node_limit,
+            seed,
+            cols,
+            rows,
+            kinds,
+            walls,
+            moves,
+            wild,
+        } => {
+            let config = generator::GeneratorConfig::new()
+                .with_col_count(usize::from(cols))
+                .with_row_count(usize::from(rows))
+                .with_block_kind_count(kinds)
+                .with_wall_height(usize::from(walls))
+                .with_move_remain(moves)
+                .with_wild_holding(wild);
+            cmd_census(&config, samples, node_limit, seed);
+        }
+        Command::Training {
+            last_stage,
+            depth,
+            npy,
+            path_problem,
+        } => {
+            let problem = read_problem(&path_problem)?;
+            cmd_training(&problem, last_stage, depth, npy)?;
+        }
+        Command::Holding {
+            last_stage,
+            path_problem,
+        } => {
+            let problem = read_problem(&path_problem)?;
+            cmd_holding(&problem, last_stage);
+        }
+        Command::Edit {
+            last_stage,
+            target_cost,
+            target_tightness,
+            max_edits,
+            node_limit,
+            count,
+            path_problem,
+        } => {
+            let problem = read_problem(&path_problem)?;
+            let target = match (target_cost, target_tightness) {
+                (Some(cost), _) => analysis::EditTarget::Cost(cost),
+                (None, Some(tightness)) => analysis::EditTarget::Tightness(tightness),
+                (None, None) => unreachable!(),
+            };
+            cmd_edit(
+                &problem,
+                last_stage,
+                target,
+                usize::from(max_edits),
+                node_limit,
+                count,
+            );
+        }
+        Command::Advise {
+            last_stage,
+            node_limit,
+            path_problem,
+        } => {
+            let problem = read_problem(&path_problem)?;
+            cmd_advise(&problem, last_stage, node_limit);
+        }
+    }
+
+    Ok(())
+}
+
+fn cmd_dead_blocks(problem: &Problem) {
+    let sqs = analysis::find_dead_blocks(problem);
+
+    for &sq in &sqs {
+        println!("{sq:?}");
+    }
+
+    println!("{}: {}", tr("死にブロック数", "dead blocks"), sqs.len());
+    if sqs.len() > problem.ruleset().clear_max_blocks() {
+        println!(
+            "{}",
+            tr(
+                "死にブロックが多すぎるため、この問題は解けない",
+                "unsolvable: too many dead blocks"
+            )
+        );
+    }
+}
+
+fn cmd_stats(problem: &Problem, last_stage: bool, cost_bound: Cost) {
+    let stats = analysis::solution_space_stats(problem, last_stage, cost_bound);
+
+    println!("{}: {}", tr("探索局面数", "nodes"), stats.node_count());
+    println!(
+        "{}: {:.3}",
+        tr("平均分岐数", "average branching factor"),
+        stats.average_branching_factor()
+    );
+    println!("{}: {}", tr("解の数", "solutions"), stats.clear_count());
+    for (depth, &count) in stats.clear_depth_counts().iter().enumerate() {
+        if count > 0 {
+            println!("  {depth} {}: {count}", tr("手", "moves"));
+        }
+    }
+}
+
+fn cmd_solutions(problem: &Problem, last_stage: bool, cost_bound: Cost) {
+    let mut count = 0_u64;
+    for (solution, cost) in enumerate_solutions(problem, last_stage, cost_bound) {
+        println!("{cost}: {solution}");
+        count += 1;
+    }
+    info!("{}: {count}", tr("解の数", "solutions"));
+}
+
+fn cmd_rank(names: &[String], problems: &[Problem], last_stage: bool) {
+    let ranking = analysis::rank_stages(problems, last_stage);
+
+    println!("rank\tproblem\tcost\tlower_bound\ttightness\tnodes");
+    for (rank, (i, difficulty)) in ranking.into_iter().enumerate() {
+        info!("{}: {difficulty:?}", names[i]);
+        let cost = difficulty
+            .cost()
+            .map_or_else(|| "-".to_owned(), |cost| cost.to_string());
+        let tightness = difficulty
+            .tightness()
+            .map_or_else(|| "-".to_owned(), |tightness| format!("{tightness:.3}"));
+        println!(
+            "{}\t{}\t{cost}\t{}\t{tightness}\t{}",
+            rank + 1,
+            names[i],
+            difficulty.cost_lower_bound(),
+            difficulty.node_count()
+        );
+    }
+}
+
+fn cmd_critical(problem: &Problem, last_stage: bool) {
+    let Some((solution, cost, choices)) = analysis::find_critical_moves(problem, last_stage) else {
+        println!("{}", tr("解なし", "no solution"));
+        return;
+    };
+
+    println!("{solution}");
+    println!("{}: {cost}", tr("総コスト", "total cost"));
+
+    let (mut pos, moves) = problem.to_position_and_moves();
+
+    for (i, choice) in choices.iter().enumerate() {
+        let mark = if choice.is_forced() { "forced" } else { "free" };
+        let alternatives = choice
+            .alternatives()
+            .iter()
+            .map(|&(src, penalty)| match penalty {
+                Some(penalty) => format!("{}:+{penalty}", src.to_inner()),
+                None => format!("{}:{}", src.to_inner(), tr("解なし", "none")),
+            })
+            .join(" ");
+        println!(
+            "{} {i}: {} [{mark}] {alternatives}",
+            tr("着手", "move"),
+            choice.src().to_inner()
+        );
+
+        let mv = moves
+            .iter()
+            .copied()
+            .find(|mv| mv.src() == choice.src())
+            .unwrap();
+        println!("    {}", move_rationale(&pos, mv, &moves).unwrap());
+        pos = pos.do_move(mv).unwrap().0;
+    }
+}
+
+fn cmd_gap(problem: &Problem, solution: &Solution, last_stage: bool) -> anyhow::Result<()> {
+    let gap = analysis::explain_gap(problem, solution, last_stage)?;
+
+    println!(
+        "{}: {} ({}: {}, {}: {})",
+        tr("コスト", "cost"),
+        gap.cost_mine(),
+        tr("最適", "optimal"),
+        gap.cost_optimal(),
+        tr("差", "gap"),
+        gap.cost_mine() - gap.cost_optimal()
+    );
+
+    for (i, (&src, &penalty)) in std::iter::zip(solution.moves(), gap.move_penalties()).enumerate()
+    {
+        println!(
+            "{} {i}: {} (+{penalty})",
+            tr("着手", "move"),
+            src.to_inner()
+        );
+    }
+
+    if let Some(i) = gap.first_divergence() {
+        let continuation = gap.best_continuation().moves()[i..]
+            .iter()
+            .map(|src| src.to_inner())
+            .join(" ");
+        println!(
+            "{}",
+            tr(
+                format!("最初に最適解から外れた手: 着手 {i}"),
+                format!("first divergence from optimal: move {i}")
+            )
+        );
+        println!(
+            "{}: {continuation}",
+            tr("その直前からの最善の続き", "best continuation from there")
+        );
+    } else {
+        println!("{}", tr("最適解である", "optimal"));
+    }
+
+    Ok(())
+}
+
+fn cmd_census(config: &generator::GeneratorConfig, samples: usize, node_limit: u64, seed: u64) {
+    use analysis::CensusFailure;
+
+    let report = analysis::solvability_census(config, samples, node_limit, seed);
+
+    println!("{}: {}", tr("問題数", "samples"), report.sample_count());
+    println!(
+        "{}: {} ({:.1}%)",
+        tr("最適解が得られた", "solved optimally"),
+        report.solved_count(),
+        100.0 * report.solved_fraction()
+    );
+    if let Some(mean_cost) = report.mean_cost() {
+        println!(
+            "{}: {mean_cost:.1}",
+            tr("最適解のコストの平均", "mean optimal cost")
+        );
+    }
+    for (failure, desc) in [
+        (
+            CensusFailure::DeadBlocks,
+            tr("死にブロックが多すぎる", "too many dead blocks"),
+        ),
+        (CensusFailure::NoSolution, tr("解なし", "no solution")),
+        (
+            CensusFailure::NodeLimit,
+            tr("探索局面数の上限に達した", "node limit reached"),
+        ),
+    ] {
+        println!("{desc}: {}", report.failure_count(failure));
+    }
+}
+
+fn cmd_training(
+    problem: &Problem,
+    last_stage: bool,
+    depth: usize,
+    npy: bool,
+) -> anyhow::Result<()> {
+    let samples = training::collect_training_samples(problem, last_stage, depth);
+
+    let mut wtr = std::io::BufWriter::new(std::io::stdout().lock());
+    if npy {
+        training::write_training_npy(&mut wtr, &samples)?;
+    } else {
+        training::write_training_csv(&mut wtr, &samples)?;
+    }
+
+    Ok(())
+}
+
+fn cmd_holding(problem: &Problem, last_stage: bool) {
+    let costs = analysis::holding_block_sensitivity(problem, last_stage);
+
+    let cost_orig = costs
+        .iter()
+        .find(|&&(block, _)| block == problem.block_holding())
+        .and_then(|&(_, cost)| cost);
+
+    for (block, cost) in costs {
+        let mark = if block == problem.block_holding() {
+            "*"
+        } else {
+            " "
+        };
+        match (cost, cost_orig) {
+            (Some(cost), Some(cost_orig)) => {
+                let delta = i64::from(cost) - i64::from(cost_orig);
+                println!("{mark} {}: {cost} ({delta:+})", block.to_inner());
+            }
+            (Some(cost), None) => println!("{mark} {}: {cost}", block.to_inner()),
+            (None, _) => println!(
+                "{mark} {}: {}",
+                block.to_inner(),
+                tr("解なし", "no solution")
+            ),
+        }
+    }
+}
+
+fn cmd_edit(
+    problem: &Problem,
+    last_stage: bool,
+    target: analysis::EditTarget,
+    max_edits: usize,
+    node_limit: u64,
+    count: usize,
+) {
+    let candidates =
+        analysis::search_stage_edits(problem, last_stage, target, max_edits, node_limit, count);
+
+    for candidate in candidates {
+        let tightness = candidate
+            .tightness()
+            .map_or_else(|| "-".to_owned(), |t| format!("{t:.3}"));
+        println!(
+            "{}\t{}\t{tightness}\t{}",
+            candidate.edits().iter().join(" "),
+            candidate.cost(),
+            candidate.solution()
+        );
+    }
+}
+
+fn cmd_advise(problem: &Problem, last_stage: bool, node_limit: u64) {
+    let advice = analysis::advise_strategy(problem, last_stage, node_limit);
+
+    println!("{}", advice.summary());
+    println!();
+
+    for opening in advice.openings() {
+        let erased = opening
+            .erased_block()
+            .map_or_else(|| "-".to_owned(), |block| block.to_inner().to_string());
+        let cost = opening
+            .cost()
+            .map_or_else(|| "-".to_owned(), |cost| cost.to_string());
+        let mark = if opening.is_exact() { "" } else { "?" };
+        let dst = match opening.dst() {
+            MoveDst::Horizontal(row) => row.to_inner().to_string(),
+            MoveDst::Vertical(col) => char::from(b'A' + col.to_inner() - 1).to_string(),
+        };
+        println!(
+            "{}\t{dst}\t{erased}x{}\t{cost}{mark}",
+            opening.src().to_inner(),
+            opening.erase_count()
+        );
+    }
+}
+
+fn read_problem(path: &Path) -> anyhow::Result<Problem> {
+    let problem = std::fs::read_to_string(path).with_context(|| {
+        tr(
+            format!("問題ファイル '{}' を読み取れない", path.display()),
+            format!("cannot read problem file '{}'", path.display()),
+        )
+    })?;
+    let problem: Problem = problem.parse()?;
+
+    Ok(problem)
+}
+
+fn read_solution(path: &Path) -> anyhow::Result<Solution> {
+    let solution = std::fs::read_to_string(path).with_context(|| {
+        tr(
+            format!("解ファイル '{}' を読み取れない", path.display()),
+            format!("cannot read solution file '{}'", path.display()),
+        )
+    })?;
+    let solution: Solution = solution.parse()?;
+
+    Ok(solution)
+}
@@ -0,0 +1,141 @@
+use std::io::BufReader;
+use std::net::TcpListener;
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use clap::Parser;
+use log::info;
+
+use flipull_advance_solver::assistant::serve_assistant;
+use flipull_advance_solver::ram::RamLayout;
+use flipull_advance_solver::rom::Rom;
+use flipull_advance_solver::*;
+
+/// 練習用のリアルタイムアシスタント。エミュレータ側の Lua スクリプトから TCP で現在の状態を受け取り、
+/// バックグラウンドで探索して最善の次の手を送り返す。
+///
+/// 通信の形式は `assistant::serve_assistant` を参照。接続が切れたら次の接続を待つ。
+#[derive(Debug, Parser)]
+struct Cli {
+    /// 待ち受けるアドレス。
+    #[arg(long, default_value = "127.0.0.1:47100")]
+    listen: String,
+
+    /// 1 つの状態について探索する局面数の上限。
+    #[arg(long, default_value_t = 100_000_000)]
+    node_limit: u64,
+
+    /// FCEUX 用の Lua ブリッジスクリプトを出力して終了する。
+    #[arg(long, requires = "ram_layout")]
+    print_bridge: bool,
+
+    /// ゲームの RAM の配置のファイル (`ram::RamLayout` 参照)。ブリッジはこれに従って RAM から状態を読む。
+    #[arg(long)]
+    ram_layout: Option<PathBuf>,
+
+    /// メッセージの言語 (ja, en)。
+    #[arg(long, default_value = "ja")]
+    lang: Locale,
+
+    /// 原作の ROM ファイル (iNES 形式)。各面の問題をここから読む。
+    #[arg(required_unless_present = "print_bridge")]
+    path_ines: Option<PathBuf>,
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+
+    let cli = Cli::parse();
+    cli.lang.set_current();
+
+    if cli.print_bridge {
+        let path_layout = cli.ram_layout.as_ref().unwrap();
+        let layout = std::fs::read_to_string(path_layout).with_context(|| {
+            tr(
+                format!(
+                    "RAM の配置のファイル '{}' を読み取れない",
+                    path_layout.display()
+                ),
+                format!("cannot read RAM layout file '{}'", path_layout.display()),
+            )
+        })?;
+        let layout: RamLayout = layout.parse()?;
+        print!(
+            "{}",
+            LUA_BRIDGE
+                .replace("{LISTEN}", &cli.listen)
+                .replace("{READ_STATE}", &layout.to_lua_read_state())
+        );
+        return Ok(());
+    }
+
+    let path_ines = cli.path_ines.as_ref().unwrap();
+    let rom = Rom::from_ines_file(path_ines).with_context(|| {
+        tr(
+            format!("ROM ファイル '{}' を読み取れない", path_ines.display()),
+            format!("cannot read ROM file '{}'", path_ines.display()),
+        )
+    })?;
+    let problems = rom.problems()?;
+
+    let listener = TcpListener::bind(&cli.listen).with_context(|| {
+        tr(
+            format!("'{}' で待ち受けられない", cli.listen),
+            format!("cannot listen on '{}'", cli.listen),
+        )
+    })?;
+    eprintln!(
+        "{}",
+        tr(
+            format!("'{}' で接続を待っている", cli.listen),
+            format!("listening on '{}'", cli.listen),
+        )
+    );
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        info!("connected: {:?}", stream.peer_addr());
+        let input = BufReader::new(stream.try_clone()?);
+        if let Err(e) = serve_assistant(&problems, cli.node_limit, input, stream) {
+            eprintln!("{e}");
+        }
+        info!("disconnected");
+    }
+
+    Ok(())
+}
+
+const LUA_BRIDGE: &str = r#"-- flipull_advance_solver の assistant 用ブリッジスクリプト (FCEUX + LuaSocket)。
+-- read_state() は RAM から現在の状態を読み、「面 自機の位置 保持ブロック 残り手数 ブロック領域」の 1 行で返す
+-- (ブロック領域は 6 行を "/" で連結したもの)。
+local socket = require("socket")
+
+local HOST, PORT = string.match("{LISTEN}", "^(.*):(%d+)$")
+
+{READ_STATE}
+local conn = assert(socket.connect(HOST, tonumber(PORT)))
+conn:settimeout(0)
+
+local last_state = nil
+local advice = "..."
+
+while true do
+  local state = read_state()
+  if state ~= nil and state ~= last_state then
+    conn:send(state .. "\n")
+    last_state = state
+    advice = "..."
+  end
+
+  while true do
+    local line = conn:receive("*l")
+    if line == nil then
+      break
+    end
+    advice = line
+  end
+
+  gui.text(8, 8, advice)
+  emu.frameadvance()
+end
+"#;
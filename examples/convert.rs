@@ -0,0 +1,125 @@
+use std::io::{Read as _, Write as _};
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use clap::{Parser, Subcommand, ValueEnum};
+
+use flipull_advance_solver::convert::{self, ProblemFormat, SolutionFormat};
+use flipull_advance_solver::*;
+
+/// 問題/解のファイル形式を変換する。結果は標準出力に出力する。
+#[derive(Debug, Parser)]
+struct Cli {
+    /// メッセージの言語 (ja, en)。
+    #[arg(long, global = true, default_value = "ja")]
+    lang: Locale,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// 問題ファイルを変換する。
+    Problem {
+        #[arg(long, value_enum)]
+        from: ProblemFormatArg,
+
+        #[arg(long, value_enum)]
+        to: ProblemFormatArg,
+
+        /// 入力ファイル。省略時は標準入力。
+        path: Option<PathBuf>,
+    },
+
+    /// 解ファイルを変換する。
+    Solution {
+        #[arg(long, value_enum)]
+        from: SolutionFormatArg,
+
+        #[arg(long, value_enum)]
+        to: SolutionFormatArg,
+
+        /// 入力ファイル。省略時は標準入力。
+        path: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum ProblemFormatArg {
+    /// 問題の文字列表現 (1 問のみ)。
+    Text,
+    /// JSON。
+    Json,
+    /// 原作 ROM 内のバイト列。
+    Rom,
+    /// `---` 区切りの問題集。
+    Collection,
+}
+
+impl From<ProblemFormatArg> for ProblemFormat {
+    fn from(arg: ProblemFormatArg) -> Self {
+        match arg {
+            ProblemFormatArg::Text => Self::Text,
+            ProblemFormatArg::Json => Self::Json,
+            ProblemFormatArg::Rom => Self::Rom,
+            ProblemFormatArg::Collection => Self::Collection,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum SolutionFormatArg {
+    /// 解の文字列表現 (1 個のみ)。
+    Text,
+    /// JSON。
+    Json,
+    /// 1 行 1 個の解集。
+    Collection,
+}
+
+impl From<SolutionFormatArg> for SolutionFormat {
+    fn from(arg: SolutionFormatArg) -> Self {
+        match arg {
+            SolutionFormatArg::Text => Self::Text,
+            SolutionFormatArg::Json => Self::Json,
+            SolutionFormatArg::Collection => Self::Collection,
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    cli.lang.set_current();
+
+    let buf = match cli.command {
+        Command::Problem { from, to, path } => {
+            let problems = convert::read_problems(&read_input(path)?, from.into())?;
+            convert::write_problems(&problems, to.into())?
+        }
+        Command::Solution { from, to, path } => {
+            let solutions = convert::read_solutions(&read_input(path)?, from.into())?;
+            convert::write_solutions(&solutions, to.into())?
+        }
+    };
+
+    std::io::stdout().write_all(&buf)?;
+
+    Ok(())
+}
+
+fn read_input(path: Option<PathBuf>) -> anyhow::Result<Vec<u8>> {
+    match path {
+        Some(path) => std::fs::read(&path).with_context(|| {
+            tr(
+                format!("ファイル '{}' を読み取れない", path.display()),
+                format!("cannot read file '{}'", path.display()),
+            )
+        }),
+        None => {
+            let mut buf = Vec::<u8>::new();
+            std::io::stdin().read_to_end(&mut buf)?;
+            Ok(buf)
+        }
+    }
+}
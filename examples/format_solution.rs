@@ -11,6 +11,35 @@ struct Cli {
     #[arg(long, value_enum, default_value_t = Format::Pretty)]
     format: Format,
 
+    /// timeline, lua フォーマットでの最初の手の開始フレーム (lua ではムービーを貼り付けたエミュレータ上のフレーム)。
+    #[arg(long, default_value_t = 0)]
+    origin: Cost,
+
+    /// エミュレータごとのタイミングプロファイル (fceux-old-ppu, fceux-new-ppu, neshawk, mesen, console)。
+    /// ムービーと timeline, lua フォーマットに反映される。
+    #[arg(long, default_value = "neshawk")]
+    profile: TimingProfile,
+
+    /// 入れ替え着手 (最初に当たったブロックが消せない着手) を許すかどうか。
+    #[arg(long)]
+    allow_swap: bool,
+
+    /// 1 回の着手でブロックを N 個以上消すと保持ブロックがワイルドになるルールで解く (改造版や別モード用)。
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u8).range(1..=36))]
+    wild_on_erase: Option<u8>,
+
+    /// ブロックを全て消したときだけ面クリアとするルールで検証する (パーフェクトクリア)。
+    #[arg(long)]
+    perfect_clear: bool,
+
+    /// 残りブロックが N 個以下になれば面クリアとするルールで検証する (ノルマの異なる別バージョンや改造版用)。
+    #[arg(long, value_name = "N", default_value_t = 3, value_parser = clap::value_parser!(u8).range(0..=36), conflicts_with = "perfect_clear")]
+    clear_max_blocks: u8,
+
+    /// メッセージの言語 (ja, en)。
+    #[arg(long, default_value = "ja")]
+    lang: Locale,
+
     /// 問題ファイル。
     path_problem: PathBuf,
 
@@ -28,33 +57,75 @@ enum Format {
 
     /// Neshawk の TAStudio にペーストできるムービーを出力する。
     Neshawk,
+
+    /// 着手ごとに、自機の移動開始、A ボタン、盤面が落ち着くフレームを出力する。
+    Timeline,
+
+    /// ムービー再生中に着手番号、着手位置、予測盤面、モデルとのフレーム差を表示する
+    /// FCEUX/BizHawk 用の Lua スクリプトを出力する。
+    Lua,
+
+    /// RTA 走者向けに、着手ごとの操作 ("3 up, throw" など) と消えるブロックを出力する。
+    Rta,
 }
 
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    cli.lang.set_current();
 
     let problem = std::fs::read_to_string(&cli.path_problem).with_context(|| {
-        format!(
-            "問題ファイル '{}' を読み取れない",
-            cli.path_problem.display()
+        tr(
+            format!(
+                "問題ファイル '{}' を読み取れない",
+                cli.path_problem.display()
+            ),
+            format!("cannot read problem file '{}'", cli.path_problem.display()),
         )
     })?;
     let problem: Problem = problem.parse()?;
+    let clear_max_blocks = if cli.perfect_clear {
+        0
+    } else {
+        cli.clear_max_blocks
+    };
+    let problem = problem.with_ruleset(
+        Ruleset::new()
+            .with_allow_swap(cli.allow_swap)
+            .with_wild_rule(cli.wild_on_erase.map(WildRule::EraseCount))
+            .with_clear_max_blocks(clear_max_blocks.into()),
+    );
 
     let solution = std::fs::read_to_string(&cli.path_solution).with_context(|| {
-        format!(
-            "解ファイル '{}' を読み取れない",
-            cli.path_solution.display()
+        tr(
+            format!(
+                "解ファイル '{}' を読み取れない",
+                cli.path_solution.display()
+            ),
+            format!(
+                "cannot read solution file '{}'",
+                cli.path_solution.display()
+            ),
         )
     })?;
     let solution: Solution = solution.parse()?;
 
+    // 不正な着手があれば、通り道を色付けした盤面を添えて報告する。
+    if let Err(e) = solution_timeline(&problem, &solution, 0) {
+        if let Some(board) = e.render(true) {
+            eprint!("{board}");
+        }
+        return Err(e.into());
+    }
+
     let (pos, moves) = problem.to_position_and_moves();
 
     match cli.format {
         Format::Pretty => format_pretty(pos, &moves, &solution),
-        Format::Fceux => format_fceux(pos, &moves, &solution),
-        Format::Neshawk => format_neshawk(pos, &moves, &solution),
+        Format::Fceux => format_fceux(pos, &moves, &solution, cli.profile),
+        Format::Neshawk => format_neshawk(pos, &moves, &solution, cli.profile),
+        Format::Timeline => format_timeline(&problem, &solution, cli.origin, cli.profile)?,
+        Format::Lua => format_lua(&problem, pos, &moves, &solution, cli.origin, cli.profile)?,
+        Format::Rta => format_rta(pos, &moves, &solution),
     }
 
     Ok(())
@@ -69,22 +140,27 @@ fn format_pretty(mut pos: Position, moves: &[Move], solution: &Solution) {
         assert_ne!(pos.move_remain(), 0);
 
         let mv = moves.iter().copied().find(|mv| mv.src() == src).unwrap();
+        let rationale = move_rationale(&pos, mv, moves).unwrap();
         let (pos_nxt, cost_mv, _) = pos.do_move(mv).unwrap();
 
         pos = pos_nxt;
         cost_total += cost_mv;
 
-        println!("着手 {i}: {} (cost={cost_mv})", src.to_inner());
+        println!(
+            "{} {i}: {} (cost={cost_mv}) -- {rationale}",
+            tr("着手", "move"),
+            src.to_inner()
+        );
         println!("{pos}");
     }
 
     cost_total += COST_CLEAR_ERASE_BLOCK * pos.block_count() as Cost;
 
-    println!("総コスト: {cost_total}");
+    println!("{}: {cost_total}", tr("総コスト", "total cost"));
 }
 
-fn format_fceux(pos: Position, moves: &[Move], solution: &Solution) {
-    let inputs = solution_to_movie(pos, moves, solution);
+fn format_fceux(pos: Position, moves: &[Move], solution: &Solution, profile: TimingProfile) {
+    let inputs = solution_to_movie(pos, moves, solution, profile);
 
     println!("TAS {}", inputs.len());
 
@@ -93,16 +169,173 @@ fn format_fceux(pos: Position, moves: &[Move], solution: &Solution) {
     }
 }
 
-fn format_neshawk(pos: Position, moves: &[Move], solution: &Solution) {
-    let inputs = solution_to_movie(pos, moves, solution);
+fn format_neshawk(pos: Position, moves: &[Move], solution: &Solution, profile: TimingProfile) {
+    let inputs = solution_to_movie(pos, moves, solution, profile);
 
     for input in inputs {
         println!("{}", input.display_neshawk());
     }
 }
 
-fn solution_to_movie(mut pos: Position, moves: &[Move], solution: &Solution) -> Vec<MovieInput> {
-    let mut inputs = Vec::<MovieInput>::new();
+fn format_timeline(
+    problem: &Problem,
+    solution: &Solution,
+    origin: Cost,
+    profile: TimingProfile,
+) -> anyhow::Result<()> {
+    let timings = solution_timeline_with_profile(problem, solution, origin, profile)?;
+
+    println!("move\tsrc\tstart\tpress\tsettle");
+
+    for (i, timing) in timings.iter().enumerate() {
+        println!(
+            "{i}\t{}\t{}\t{}\t{}",
+            timing.src().to_inner(),
+            timing.frame_start(),
+            timing.frame_press(),
+            timing.frame_settle()
+        );
+    }
+
+    Ok(())
+}
+
+fn format_rta(mut pos: Position, moves: &[Move], solution: &Solution) {
+    use std::cmp::Ordering;
+
+    for (i, &src) in solution.moves().iter().enumerate() {
+        let from = pos.hero_row().to_inner();
+        let to = src.to_inner();
+        let walk = match from.cmp(&to) {
+            Ordering::Less => tr(format!("下へ {}", to - from), format!("{} down", to - from)),
+            Ordering::Greater => tr(format!("上へ {}", from - to), format!("{} up", from - to)),
+            Ordering::Equal => tr("移動なし".to_owned(), "stay".to_owned()),
+        };
+
+        let block_thrown = pos.block_holding();
+        let mv = moves.iter().copied().find(|mv| mv.src() == src).unwrap();
+        let (pos_nxt, _, _) = pos.do_move(mv).unwrap();
+        let erase_count = pos.block_count() - pos_nxt.block_count();
+
+        println!(
+            "{}",
+            tr(
+                format!(
+                    "{}: {walk}, 投げる (行 {to}) -- {} を {erase_count} 個消し、{} を持つ",
+                    i + 1,
+                    block_thrown.to_inner(),
+                    pos_nxt.block_holding().to_inner()
+                ),
+                format!(
+                    "{}: {walk}, throw (row {to}) -- erases {erase_count} of {}, now holding {}",
+                    i + 1,
+                    block_thrown.to_inner(),
+                    pos_nxt.block_holding().to_inner()
+                ),
+            )
+        );
+
+        pos = pos_nxt;
+    }
+}
+
+fn format_lua(
+    problem: &Problem,
+    mut pos: Position,
+    moves: &[Move],
+    solution: &Solution,
+    origin: Cost,
+    profile: TimingProfile,
+) -> anyhow::Result<()> {
+    let timings = solution_timeline_with_profile(problem, solution, origin, profile)?;
+
+    println!("-- flipull_advance_solver が生成した HUD スクリプト (FCEUX/BizHawk 共通)。");
+    println!("local ORIGIN = {origin}");
+    println!("local MOVES = {{");
+    for timing in &timings {
+        let mv = moves
+            .iter()
+            .copied()
+            .find(|mv| mv.src() == timing.src())
+            .unwrap();
+        let (pos_nxt, _, _) = pos.do_move(mv).unwrap();
+        pos = pos_nxt;
+
+        let board: Vec<_> = pos
+            .to_string()
+            .lines()
+            .skip(1)
+            .map(|line| format!("\"{line}\""))
+            .collect();
+        println!(
+            "  {{ src = {}, start = {}, press = {}, settle = {}, board = {{ {} }} }},",
+            timing.src().to_inner(),
+            timing.frame_start(),
+            timing.frame_press(),
+            timing.frame_settle(),
+            board.join(", ")
+        );
+    }
+    println!("}}");
+    print!("{}", LUA_HUD_MAIN);
+
+    Ok(())
+}
+
+const LUA_HUD_MAIN: &str = r#"
+local function current_move(frame)
+  for i = #MOVES, 1, -1 do
+    if frame >= MOVES[i].start then
+      return i
+    end
+  end
+  return nil
+end
+
+-- 実際に A が押された手の数と、その押下フレームのモデルとのずれ (各手のずれの累積)。
+local observed = 0
+local drift = 0
+local drift_last = 0
+local press_last = ORIGIN
+local a_prev = false
+
+while true do
+  local frame = emu.framecount()
+  local buttons = joypad.get(1)
+  local a = buttons.A == true or buttons["P1 A"] == true
+  if a and not a_prev and observed < #MOVES then
+    -- 直前の押下からの実際の間隔と、モデル上の間隔との差を累積する。
+    local model_prev = observed == 0 and ORIGIN or MOVES[observed].press
+    observed = observed + 1
+    drift_last = (frame - press_last) - (MOVES[observed].press - model_prev)
+    drift = drift + drift_last
+    press_last = frame
+  end
+  a_prev = a
+
+  -- ずれを差し引いたフレームでモデル上の手を引く。
+  local i = current_move(frame - drift)
+  if i ~= nil then
+    local m = MOVES[i]
+    gui.text(8, 8, string.format("move %d/%d  src %d", i, #MOVES, m.src))
+    local model = frame - drift
+    local phase = model < m.press and "move" or (model < m.settle and "throw" or "done")
+    gui.text(8, 18, string.format("frame %d  drift %+d (last %+d, %s)", frame - ORIGIN, drift, drift_last, phase))
+    for row, line in ipairs(m.board) do
+      gui.text(8, 28 + 10 * row, line)
+    end
+  end
+  emu.frameadvance()
+end
+"#;
+
+fn solution_to_movie(
+    mut pos: Position,
+    moves: &[Move],
+    solution: &Solution,
+    profile: TimingProfile,
+) -> Vec<MovieInput> {
+    let mut inputs = vec![MovieInput::None; profile.input_delay() as usize];
 
     for &src in solution.moves().iter() {
         assert_ne!(pos.move_remain(), 0);
@@ -115,7 +348,7 @@ fn solution_to_movie(mut pos: Position, moves: &[Move], solution: &Solution) ->
         let (pos_nxt, cost_mv, _) = pos.do_move(mv).unwrap();
 
         // ブロックを投げて待つ。
-        let wait_len = cost_mv as usize - inputs_hero.len() - 1;
+        let wait_len = profile.adjust_move_cost(cost_mv) as usize - inputs_hero.len() - 1;
         inputs.push(MovieInput::A);
         inputs.extend(vec![MovieInput::None; wait_len]);
 
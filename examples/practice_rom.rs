@@ -0,0 +1,154 @@
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use clap::{Parser, ValueEnum};
+
+use flipull_advance_solver::ram::RamLayout;
+use flipull_advance_solver::rom::Rom;
+use flipull_advance_solver::*;
+
+/// 指定した問題を原作で練習できるようにする。
+///
+/// `--format=lua`, `--format=codes` では、プレイ中のゲームの RAM を書き換えて面 `--stage` を問題の初期局面にする
+/// 書き込みを出力する。RAM の配置は `--ram-layout` で与える (`ram::RamLayout` 参照)。
+/// 壁/パイプの配置は書き換えないので、問題は面 `--stage` と同じ壁/パイプの配置でなければならない。
+/// 問題を指定しなければ面 `--stage` 自身を使う (その面の最初からやり直す)。
+///
+/// `--format=raw`, `--format=ines` では、ROM の面 `--stage` のデータを問題で書き換える。
+/// 面のデータは CHR-ROM 上にあるので、書き換えた ROM でゲームを起動し直せば、その面から問題を練習できる。
+#[derive(Debug, Parser)]
+struct Cli {
+    /// 書き換える面 (1..=50)。
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(Stage).range(1..=STAGE_COUNT as i64))]
+    stage: Stage,
+
+    /// 書き込む問題ファイル。
+    #[arg(long, conflicts_with = "from_stage")]
+    problem: Option<PathBuf>,
+
+    /// 書き込む問題として ROM 内の面 (1..=50) を使う (例えば 1 面に 37 面を書き込む)。
+    #[arg(long, value_parser = clap::value_parser!(Stage).range(1..=STAGE_COUNT as i64))]
+    from_stage: Option<Stage>,
+
+    /// 出力形式。
+    #[arg(long, value_enum, default_value_t = Format::Raw)]
+    format: Format,
+
+    /// ines 形式の出力先ファイル。
+    #[arg(long, short, required_if_eq("format", "ines"))]
+    output: Option<PathBuf>,
+
+    /// RAM の配置のファイル (`--format=lua`, `--format=codes` で必要)。
+    #[arg(long, required_if_eq_any([("format", "lua"), ("format", "codes")]))]
+    ram_layout: Option<PathBuf>,
+
+    /// メッセージの言語 (ja, en)。
+    #[arg(long, default_value = "ja")]
+    lang: Locale,
+
+    /// 原作の ROM ファイル (iNES 形式)。RAM への書き込みを `--problem` の問題で出力する場合は不要。
+    path_ines: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum Format {
+    /// 書き換える iNES ファイル上のオフセットと値を 1 行に 1 つずつ (`0x0A1B2: 0x12` の形式で) 出力する。
+    Raw,
+
+    /// 書き換えた ROM を iNES 形式で出力する。
+    Ines,
+
+    /// RAM に書き込む Lua のコード (FCEUX, BizHawk の `memory.writebyte`) を出力する。
+    Lua,
+
+    /// RAM に書き込むアドレスと値を 1 行に 1 つずつ (`0040:12` の形式で) 出力する。
+    Codes,
+}
+
+impl Format {
+    fn writes_ram(self) -> bool {
+        matches!(self, Self::Lua | Self::Codes)
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    cli.lang.set_current();
+
+    let rom = cli
+        .path_ines
+        .as_ref()
+        .map(|path_ines| {
+            Rom::from_ines_file(path_ines).with_context(|| {
+                tr(
+                    format!("ROM ファイル '{}' を読めない", path_ines.display()),
+                    format!("cannot read ROM file '{}'", path_ines.display()),
+                )
+            })
+        })
+        .transpose()?;
+    let no_rom = || anyhow::anyhow!(tr("ROM ファイルを指定していない", "no ROM file given"));
+
+    let problem = match (&cli.problem, cli.from_stage) {
+        (Some(path_problem), _) => {
+            let problem = std::fs::read_to_string(path_problem).with_context(|| {
+                tr(
+                    format!("問題ファイル '{}' を読み取れない", path_problem.display()),
+                    format!("cannot read problem file '{}'", path_problem.display()),
+                )
+            })?;
+            problem.parse::<Problem>()?
+        }
+        (None, Some(stage)) => rom.as_ref().ok_or_else(no_rom)?.problem(stage)?,
+        (None, None) if cli.format.writes_ram() => {
+            rom.as_ref().ok_or_else(no_rom)?.problem(cli.stage)?
+        }
+        (None, None) => anyhow::bail!(tr(
+            "--problem または --from-stage が必要",
+            "--problem or --from-stage is required",
+        )),
+    };
+
+    if cli.format.writes_ram() {
+        let path_layout = cli.ram_layout.as_ref().unwrap();
+        let layout = std::fs::read_to_string(path_layout).with_context(|| {
+            tr(
+                format!(
+                    "RAM の配置のファイル '{}' を読み取れない",
+                    path_layout.display()
+                ),
+                format!("cannot read RAM layout file '{}'", path_layout.display()),
+            )
+        })?;
+        let layout: RamLayout = layout.parse()?;
+        let (pos, _) = problem.to_position_and_moves();
+        match cli.format {
+            Format::Lua => print!("{}", layout.to_lua_writes(cli.stage, &pos)),
+            Format::Codes => print!("{}", layout.to_raw_codes(cli.stage, &pos)),
+            Format::Raw | Format::Ines => unreachable!(),
+        }
+        return Ok(());
+    }
+
+    let mut rom = rom.ok_or_else(no_rom)?;
+    match cli.format {
+        Format::Raw => {
+            for (offset, value) in rom.problem_patch(cli.stage, &problem) {
+                println!("{offset:#07X}: {value:#04X}");
+            }
+        }
+        Format::Ines => {
+            rom.set_problem(cli.stage, &problem);
+            let path_output = cli.output.as_ref().unwrap();
+            std::fs::write(path_output, rom.to_ines()).with_context(|| {
+                tr(
+                    format!("ファイル '{}' に書き込めない", path_output.display()),
+                    format!("cannot write file '{}'", path_output.display()),
+                )
+            })?;
+        }
+        Format::Lua | Format::Codes => unreachable!(),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,247 @@
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use clap::{Parser, Subcommand};
+
+use flipull_advance_solver::records::{self, ProofStatus, Record, Records};
+use flipull_advance_solver::run_time::RunTime;
+use flipull_advance_solver::*;
+
+/// 面ごとの既知の最善解のデータベースを操作する。
+#[derive(Debug, Parser)]
+struct Cli {
+    /// メッセージの言語 (ja, en)。
+    #[arg(long, global = true, default_value = "ja")]
+    lang: Locale,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// 問題を解き、結果でデータベースを更新する。
+    Solve {
+        /// 最終面かどうか。
+        #[arg(long)]
+        last_stage: bool,
+
+        /// データベースファイル (存在しなければ作る)。
+        path_records: PathBuf,
+
+        /// 面。
+        #[arg(value_parser = clap::value_parser!(Stage).range(1..=STAGE_COUNT as i64))]
+        stage: Stage,
+
+        /// 問題ファイル。
+        path_problem: PathBuf,
+    },
+
+    /// 別のデータベースの記録のうち良いものを取り込む。
+    Merge {
+        /// 取り込み先のデータベースファイル (存在しなければ作る)。
+        path_dst: PathBuf,
+
+        /// 取り込み元のデータベースファイル。
+        path_src: PathBuf,
+    },
+
+    /// データベースの全ての解を並列に検証し、記録されたコストと一致しない面を報告する。
+    Verify {
+        /// データベースファイル。
+        path_records: PathBuf,
+
+        /// 問題ファイル (面番号を 2 桁にした `01.in` など) のあるディレクトリ。
+        dir_problem: PathBuf,
+    },
+
+    /// 記録のコストとメニュー/面の切り替えのフレーム数から全面通しの所要フレーム数を見積もり、
+    /// 基準 (公開されている TAS など) と面ごと、ルーティング/切り替えごとに比べる。
+    RunTime {
+        /// 電源投入から最初の面の最初の入力までのフレーム数。
+        #[arg(long, default_value_t = 0)]
+        menu: Cost,
+
+        /// 面の終了から次の面の最初の入力までのフレーム数。
+        #[arg(long, default_value_t = 0)]
+        transition: Cost,
+
+        /// 基準の所要フレーム数のファイル (`verify_movie --times` の出力など)。
+        #[arg(long)]
+        reference: Option<PathBuf>,
+
+        /// データベースファイル。
+        path_records: PathBuf,
+    },
+
+    /// 2 つのデータベースで記録が異なる面を出力する。
+    Diff {
+        path_old: PathBuf,
+
+        path_new: PathBuf,
+    },
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init_from_env(env_logger::Env::new().default_filter_or("warn"));
+
+    let cli = Cli::parse();
+    cli.lang.set_current();
+
+    match cli.command {
+        Command::Solve {
+            last_stage,
+            path_records,
+            stage,
+            path_problem,
+        } => {
+            let problem = std::fs::read_to_string(&path_problem).with_context(|| {
+                tr(
+                    format!("問題ファイル '{}' を読み取れない", path_problem.display()),
+                    format!("cannot read problem file '{}'", path_problem.display()),
+                )
+            })?;
+            let problem: Problem = problem.parse()?;
+
+            match solve_problem(&problem, last_stage) {
+                Some((solution, cost)) => {
+                    let record = Record::new(stage, solution, cost, ProofStatus::Optimal);
+                    let stages = Records::update_file(&path_records, [record])?;
+                    println!("{cost} ({})", tr_updated(!stages.is_empty()));
+                }
+                None => println!("{}", tr("解なし", "no solution")),
+            }
+        }
+        Command::Merge { path_dst, path_src } => {
+            let src = Records::load(&path_src)?;
+            let stages = Records::update_file(&path_dst, src.iter().cloned())?;
+            for stage in stages {
+                println!("{stage}");
+            }
+        }
+        Command::Verify {
+            path_records,
+            dir_problem,
+        } => {
+            let records = Records::load(&path_records)?;
+
+            let pairs = records
+                .iter()
+                .map(|record| {
+                    let path_problem = dir_problem.join(format!("{:02}.in", record.stage()));
+                    let problem = std::fs::read_to_string(&path_problem).with_context(|| {
+                        tr(
+                            format!("問題ファイル '{}' を読み取れない", path_problem.display()),
+                            format!("cannot read problem file '{}'", path_problem.display()),
+                        )
+                    })?;
+                    let problem: Problem = problem.parse()?;
+                    let mut metadata = problem.metadata().clone();
+                    metadata.set(ProblemMetadata::KEY_STAGE, record.stage().to_string());
+                    let problem = problem.with_metadata(metadata);
+                    Ok((problem, record.solution().clone()))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            let mut ok = true;
+            for (record, res) in records.iter().zip(records::verify_many(&pairs)) {
+                match res {
+                    Ok(cost) if cost == record.cost() => println!("{}	OK	{cost}", record.stage()),
+                    Ok(cost) => {
+                        ok = false;
+                        println!(
+                            "{}	{}	{} -> {cost}",
+                            record.stage(),
+                            tr("コスト不一致", "cost mismatch"),
+                            record.cost()
+                        );
+                    }
+                    Err(e) => {
+                        ok = false;
+                        println!("{}	{}	{e}", record.stage(), tr("不正", "invalid"));
+                    }
+                }
+            }
+            anyhow::ensure!(
+                ok,
+                "{}",
+                tr("検証に失敗した面がある", "some stages failed verification")
+            );
+        }
+        Command::RunTime {
+            menu,
+            transition,
+            reference,
+            path_records,
+        } => {
+            let records = Records::load(&path_records)?;
+            let mine = RunTime::from_records(&records, menu, transition);
+
+            let Some(path_reference) = reference else {
+                print!("{mine}");
+                println!("{}: {}", tr("総フレーム数", "total frames"), mine.total());
+                return Ok(());
+            };
+            let reference = std::fs::read_to_string(&path_reference).with_context(|| {
+                tr(
+                    format!("ファイル '{}' を読み取れない", path_reference.display()),
+                    format!("cannot read file '{}'", path_reference.display()),
+                )
+            })?;
+            let reference: RunTime = reference.parse()?;
+
+            for delta in mine.compare(&reference) {
+                println!(
+                    "{}\t{:+}\t{:+}\t{:+}",
+                    delta.stage(),
+                    delta.routing(),
+                    delta.transition(),
+                    delta.total()
+                );
+            }
+            println!(
+                "{}: {} ({}: {}, {:+})",
+                tr("総フレーム数", "total frames"),
+                mine.total(),
+                tr("基準", "reference"),
+                reference.total(),
+                i64::from(mine.total()) - i64::from(reference.total())
+            );
+            println!(
+                "  {}: {:+}, {}: {:+}",
+                tr("ルーティング", "routing"),
+                i64::from(mine.routing()) - i64::from(reference.routing()),
+                tr("切り替え", "transitions"),
+                i64::from(mine.transition()) - i64::from(reference.transition())
+            );
+        }
+        Command::Diff { path_old, path_new } => {
+            let old = Records::load(&path_old)?;
+            let new = Records::load(&path_new)?;
+            for diff in records::diff_records(&old, &new) {
+                let fmt = |record: Option<&Record>| {
+                    record.map_or_else(
+                        || "-".to_owned(),
+                        |record| format!("{} ({})", record.cost(), record.status()),
+                    )
+                };
+                println!(
+                    "{}\t{}\t{}",
+                    diff.stage(),
+                    fmt(diff.record_old()),
+                    fmt(diff.record_new())
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn tr_updated(updated: bool) -> &'static str {
+    if updated {
+        tr("更新", "updated")
+    } else {
+        tr("更新なし", "not updated")
+    }
+}
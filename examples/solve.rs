@@ -1,10 +1,15 @@
+use std::io::Write as _;
+use std::num::{NonZeroU64, NonZeroUsize};
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::{ensure, Context as _};
-use clap::Parser;
-use log::info;
+use clap::{Parser, ValueEnum};
+use log::{info, warn};
 
+use flipull_advance_solver::stream::{solve_stream, StreamFormat};
 use flipull_advance_solver::*;
+use flipull_advance_solver::{mcts, parallel, restart};
 
 /// 指定した問題に対する実時間最速の解を求める。
 #[derive(Debug, Parser)]
@@ -13,33 +18,1112 @@ struct Cli {
     #[arg(long)]
     last_stage: bool,
 
-    /// 問題ファイル。
-    path_problem: PathBuf,
+    /// 入れ替え着手 (最初に当たったブロックが消せない着手) を許すかどうか。
+    #[arg(long)]
+    allow_swap: bool,
+
+    /// 1 回の着手でブロックを N 個以上消すと保持ブロックがワイルドになるルールで解く (改造版や別モード用)。
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u8).range(1..=36))]
+    wild_on_erase: Option<u8>,
+
+    /// ブロックを全て消したときだけ面クリアとするルールで解く (パーフェクトクリア)。
+    #[arg(long)]
+    perfect_clear: bool,
+
+    /// 残りブロックが N 個以下になれば面クリアとするルールで解く (ノルマの異なる別バージョンや改造版用)。
+    #[arg(long, value_name = "N", default_value_t = 3, value_parser = clap::value_parser!(u8).range(0..=36), conflicts_with = "perfect_clear")]
+    clear_max_blocks: u8,
+
+    /// メッセージの言語 (ja, en)。
+    #[arg(long, default_value = "ja")]
+    lang: Locale,
+
+    /// 人手で作った序盤の着手列のファイル。指定すると、これに続く最適な残りの着手列を求める。
+    #[arg(long)]
+    prefix: Option<PathBuf>,
+
+    /// 同じ行から 2 回連続でブロックを投げない解を求める。
+    #[arg(long)]
+    no_repeat_src: bool,
+
+    /// 最後の着手を指定した行から行う解を求める。
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=11))]
+    end_at: Option<u8>,
+
+    /// 指定した行から投げない解を求める。複数回指定できる。
+    #[arg(long, value_name = "ROW", value_parser = clap::value_parser!(u8).range(0..=11))]
+    ban_src: Vec<u8>,
+
+    /// 自機の総移動歩数が指定した値以上の解を求める。
+    #[arg(long)]
+    min_hero_travel: Option<u32>,
+
+    /// 1 手の所要フレーム数 (自機の移動とブロック投げの合計) が指定した値以下の解を求める。
+    #[arg(long)]
+    max_move_cost: Option<Cost>,
+
+    /// 総所要フレーム数 (面クリア演出を含む) が指定した値以下の解を求める。
+    /// ゲーム内タイマーで時間切れにならない手順を求めるのに使う。
+    #[arg(long, conflicts_with = "time_limit_secs")]
+    time_limit: Option<Cost>,
+
+    /// --time-limit を秒で指定する。
+    #[arg(long)]
+    time_limit_secs: Option<f64>,
+
+    /// 解のコストを補正して報告するタイミングプロファイル
+    /// (fceux-old-ppu, fceux-new-ppu, neshawk, mesen, console)。
+    #[arg(long, default_value = "neshawk")]
+    profile: TimingProfile,
+
+    /// 最小化する目的関数。省略時は総所要フレーム数。
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with_all = [
+            "tt", "rpc", "stdin", "no_repeat_src", "end_at", "ban_src", "min_hero_travel",
+            "max_move_cost", "time_limit", "time_limit_secs",
+        ],
+    )]
+    objective: Option<ObjectiveArg>,
+
+    /// 目的関数の値が等しい解のうち、指定した基準の値が最小のものを求める。
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with_all = [
+            "tt", "rpc", "stdin", "no_repeat_src", "end_at", "ban_src", "min_hero_travel",
+            "max_move_cost", "time_limit", "time_limit_secs",
+        ],
+    )]
+    tie_break: Option<TieBreakArg>,
+
+    /// 置換表ファイル。存在すれば読み込んで探索に使い、探索後に書き戻す。
+    /// 同じ問題をオプションを変えて何度も解く場合に、前回までの探索結果を再利用できる。
+    #[arg(long)]
+    tt: Option<PathBuf>,
+
+    /// 新しく作る置換表のキーを 64 ビットのハッシュ値でなく 128 ビットの局面そのものにする。
+    /// メモリとファイルサイズは増えるが、ハッシュ衝突で誤った解を最適と報告することがなくなる。
+    #[arg(long, requires = "tt")]
+    tt_full_keys: bool,
+
+    /// 問題を解く代わりに、標準入出力で JSON-RPC 2.0 のリクエストを 1 行ずつ処理する
+    /// (エディタのプラグインなどから子プロセスとして使う場合)。
+    #[arg(long, conflicts_with = "path_problem")]
+    rpc: bool,
+
+    /// 終盤の着手列のライブラリファイル。存在すれば読み込んで探索に使い、求めた最適解の終盤を追加して書き戻す。
+    /// 壁やパイプの配置が同じ面を何面も解く場合に、共通の終盤の探索を省ける。
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "prefix", "tt", "objective", "tie_break", "no_repeat_src", "end_at", "ban_src",
+            "min_hero_travel", "max_move_cost", "time_limit", "time_limit_secs",
+        ],
+    )]
+    patterns: Option<PathBuf>,
+
+    /// ライブラリに追加する終盤の局面のブロック数の上限。
+    #[arg(long, default_value_t = 8, requires = "patterns")]
+    pattern_max_blocks: usize,
+
+    /// ブロック数がこの値以下の局面を終盤表で解く。終盤表は探索中に埋まり、同じ終盤の探索し直しを省ける。
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "prefix", "tt", "patterns", "objective", "tie_break", "no_repeat_src", "end_at",
+            "ban_src", "min_hero_travel", "max_move_cost", "time_limit", "time_limit_secs",
+        ],
+    )]
+    tablebase_blocks: Option<usize>,
+
+    /// 終盤表ファイル。存在すれば読み込んで探索に使い、探索後に書き戻す。
+    #[arg(long, requires = "tablebase_blocks")]
+    tablebase: Option<PathBuf>,
+
+    /// 1 問を解くのに使うスレッド数。2 以上なら探索木を部分木に分けて並列に探索する。
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "tt", "patterns", "tablebase_blocks", "objective", "tie_break", "no_repeat_src",
+            "end_at", "ban_src", "min_hero_travel", "max_move_cost", "time_limit",
+            "time_limit_secs", "stdin",
+        ],
+    )]
+    threads: Option<NonZeroUsize>,
+
+    /// 探索する局面数の上限。達したらそれまでの最善の解を出力する (最適とは限らない)。
+    #[arg(long, conflicts_with_all = SEARCH_LIMIT_CONFLICTS)]
+    node_limit: Option<u64>,
+
+    /// 探索時間の上限 (秒)。過ぎたらそれまでの最善の解を出力する (最適とは限らない)。
+    #[arg(long, conflicts_with_all = SEARCH_LIMIT_CONFLICTS)]
+    search_secs: Option<f64>,
+
+    /// 解の手数の上限。
+    #[arg(long, conflicts_with_all = SEARCH_LIMIT_CONFLICTS)]
+    max_depth: Option<usize>,
+
+    /// 残りに必要な着手数の下界による枝刈りを行わない (比較用)。
+    #[arg(long, conflicts_with_all = SEARCH_LIMIT_CONFLICTS)]
+    no_lower_bound: bool,
+
+    /// 既に訪れた局面に支配される局面の枝刈りを行わない (比較用)。
+    #[arg(long, conflicts_with_all = SEARCH_LIMIT_CONFLICTS)]
+    no_dominance: bool,
+
+    /// 探索の前に貪欲法で求めた解の総コストで枝刈りを始めない (比較用)。
+    #[arg(long, conflicts_with_all = SEARCH_LIMIT_CONFLICTS)]
+    no_warm_start: bool,
+
+    /// 置換表を使って探索し、置換表と支配による枝刈りの表のメモリ使用量をこの値 (MiB、概算) 以下に抑える。
+    /// 上限に達したら最も長く参照されていないエントリから捨てる。結果は変わらない。
+    #[arg(long, conflicts_with_all = SEARCH_LIMIT_CONFLICTS)]
+    memory_limit_mb: Option<usize>,
+
+    /// 各局面で着手を試す順序。総コストは変わらないが、総コストの等しい解のどれを出力するかは変わりうる。
+    #[arg(long, value_enum, conflicts_with_all = SEARCH_LIMIT_CONFLICTS)]
+    move_ordering: Option<MoveOrderingArg>,
+
+    /// 探索の途中経過 (局面数、手数、暫定解のコスト、経過時間) を標準エラー出力に表示する。
+    #[arg(long, conflicts_with_all = SEARCH_LIMIT_CONFLICTS)]
+    progress: bool,
+
+    /// 探索の統計 (局面数、最大の手数、種類ごとの枝刈り回数、置換表のヒット数、経過時間) を表示する。
+    #[arg(long, conflicts_with_all = SEARCH_LIMIT_CONFLICTS)]
+    stats: bool,
+
+    /// 面の最初から --search-tree-depth 手までに探索した木を、局面ごとの目的関数の値と枝刈りの理由付きでこのファイルに書き出す。
+    /// 拡張子が .json なら JSON、それ以外なら Graphviz の DOT 形式。ソルバーが苦戦する理由を調べるのに使う。
+    #[arg(long, conflicts_with_all = SEARCH_LIMIT_CONFLICTS, conflicts_with = "threads")]
+    search_tree: Option<PathBuf>,
+
+    /// --search-tree で書き出す手数の上限。
+    #[arg(long, value_name = "N", default_value_t = 3, requires = "search_tree")]
+    search_tree_depth: usize,
+
+    /// 暫定解が更新されるたびに、その解をこのファイルに書き出す (上書き)。時間のかかる探索の途中の解を残すのに使う。
+    #[arg(long, conflicts_with_all = SEARCH_LIMIT_CONFLICTS, conflicts_with = "progress")]
+    save_improvements: Option<PathBuf>,
+
+    /// 探索のスナップショットをこのファイルに定期的に保存する (上書き)。ファイルが既にあれば、そのスナップショットから探索を再開する。
+    /// 再起動などで中断した長い探索を続けるのに使う。
+    #[arg(
+        long,
+        conflicts_with_all = SEARCH_LIMIT_CONFLICTS,
+        conflicts_with_all = [
+            "prefix", "threads", "tie_break", "progress", "save_improvements", "pareto",
+            "beam_width", "weight", "mcts_playouts",
+        ],
+    )]
+    checkpoint: Option<PathBuf>,
+
+    /// --checkpoint のスナップショットを保存する間隔 (探索局面数)。
+    #[arg(long, default_value_t = 1 << 24, requires = "checkpoint")]
+    checkpoint_interval: u64,
+
+    /// 局面数の上限付きの探索を、着手順をランダムに変えながら繰り返す (ランダムリスタート)。
+    /// i 回目の探索の上限はこの値に Luby 列 (1, 1, 2, 1, 1, 2, 4, ...) の第 i 項を掛けた局面数。
+    /// 暫定解は次の探索に引き継ぐ。固定の着手順では悪い部分木に長時間はまり込む巨大な面向け。
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = SEARCH_LIMIT_CONFLICTS,
+        conflicts_with_all = [
+            "tie_break", "progress", "save_improvements", "checkpoint", "search_tree",
+        ],
+    )]
+    restart_nodes: Option<NonZeroU64>,
+
+    /// --restart-nodes の着手順の乱数のシード。
+    #[arg(long, default_value_t = 0, requires = "restart_nodes")]
+    restart_seed: u64,
+
+    /// 総コストと手数のパレート最適な解をすべて求め、手数の昇順に「手数 総コスト: 解」の形式で 1 行ずつ出力する。
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "prefix", "tt", "patterns", "tablebase_blocks", "threads", "objective", "tie_break",
+            "no_repeat_src", "end_at", "ban_src", "min_hero_travel", "max_move_cost", "time_limit",
+            "time_limit_secs", "stdin", "rpc", "ida", "node_limit", "search_secs", "max_depth",
+            "no_lower_bound", "progress", "stats", "save_improvements",
+        ],
+    )]
+    pareto: bool,
+
+    /// 指定したビーム幅のビームサーチで素早く解を求める (最適とは限らない)。
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "prefix", "tt", "patterns", "tablebase_blocks", "threads", "objective", "tie_break",
+            "no_repeat_src", "end_at", "ban_src", "min_hero_travel", "max_move_cost", "time_limit",
+            "time_limit_secs", "stdin", "rpc", "ida", "node_limit", "search_secs", "max_depth",
+            "no_lower_bound", "progress", "stats", "save_improvements", "pareto",
+        ],
+    )]
+    beam_width: Option<NonZeroUsize>,
+
+    /// 最適解の総コストの指定倍以内であることが保証された解を求める (1 以上)。
+    /// 最適解の総コストの下界も表示する。
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "prefix", "tt", "patterns", "tablebase_blocks", "threads", "objective", "tie_break",
+            "no_repeat_src", "end_at", "ban_src", "min_hero_travel", "max_move_cost", "time_limit",
+            "time_limit_secs", "stdin", "rpc", "ida", "node_limit", "search_secs", "max_depth",
+            "no_lower_bound", "progress", "stats", "save_improvements", "pareto", "beam_width",
+        ],
+    )]
+    weight: Option<f64>,
+
+    /// 分岐限定法の代わりに、指定した回数のプレイアウトでモンテカルロ木探索を行う。
+    /// 木全体を調べ終えなければ、解は最適とは限らない。
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "prefix", "tt", "patterns", "tablebase_blocks", "threads", "objective", "tie_break",
+            "no_repeat_src", "end_at", "ban_src", "min_hero_travel", "max_move_cost", "time_limit",
+            "time_limit_secs", "stdin", "rpc", "ida", "node_limit", "search_secs", "max_depth",
+            "no_lower_bound", "progress", "stats", "save_improvements", "pareto", "beam_width",
+            "weight",
+        ],
+    )]
+    mcts_playouts: Option<u64>,
+
+    /// --mcts-playouts のプレイアウトの乱数のシード。
+    #[arg(long, default_value_t = 0, requires = "mcts_playouts")]
+    mcts_seed: u64,
+
+    /// 分岐限定法の代わりに反復深化 A* で探索する。
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "prefix", "tt", "patterns", "tablebase_blocks", "threads", "objective", "tie_break",
+            "no_repeat_src", "end_at", "ban_src", "min_hero_travel", "max_move_cost", "time_limit",
+            "time_limit_secs", "stdin",
+        ],
+    )]
+    ida: bool,
+
+    /// 総所要フレーム数を最小化する代わりに、ゲーム内の得点 (連鎖ボーナスと残り手数ボーナス) を最大化する解を求める。
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "prefix", "tt", "patterns", "tablebase_blocks", "threads", "objective", "tie_break",
+            "no_repeat_src", "end_at", "ban_src", "min_hero_travel", "max_move_cost", "time_limit",
+            "time_limit_secs", "stdin", "rpc", "ida", "node_limit", "search_secs", "max_depth",
+            "no_lower_bound", "progress", "stats", "save_improvements", "pareto", "beam_width",
+            "weight", "mcts_playouts", "campaign", "jobs",
+        ],
+    )]
+    max_score: bool,
+
+    /// 解を求めると同時に、最適性証明書 (合法な初手ごとの、その着手から始まる解のコストの下界) を FILE に書き込む。
+    /// 初手の数だけ探索するので遅い。
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "prefix", "tt", "patterns", "tablebase_blocks", "threads", "objective", "tie_break",
+            "no_repeat_src", "end_at", "ban_src", "min_hero_travel", "max_move_cost", "time_limit",
+            "time_limit_secs", "stdin", "rpc", "ida", "node_limit", "search_secs", "max_depth",
+            "no_lower_bound", "progress", "stats", "save_improvements", "pareto", "beam_width",
+            "weight", "mcts_playouts", "campaign", "jobs", "max_score",
+        ],
+    )]
+    certificate: Option<PathBuf>,
+
+    /// 解く代わりに、FILE の最適性証明書をソルバーとは独立な探索で検証する。
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "prefix", "tt", "patterns", "tablebase_blocks", "threads", "objective", "tie_break",
+            "no_repeat_src", "end_at", "ban_src", "min_hero_travel", "max_move_cost", "time_limit",
+            "time_limit_secs", "stdin", "rpc", "ida", "node_limit", "search_secs", "max_depth",
+            "no_lower_bound", "progress", "stats", "save_improvements", "pareto", "beam_width",
+            "weight", "mcts_playouts", "campaign", "jobs", "max_score", "certificate",
+        ],
+    )]
+    verify_certificate: Option<PathBuf>,
+
+    /// 問題ファイルの代わりに標準入力から問題を逐次読み、解き終わった問題から 1 行ずつ JSON で結果を出力する
+    /// (キューの後ろでワーカーとして使う場合)。
+    #[arg(long, conflicts_with_all = ["path_problem", "rpc", "prefix", "tt"])]
+    stdin: bool,
+
+    /// --stdin で読む問題の形式。
+    #[arg(long, value_enum, default_value = "collection", requires = "stdin")]
+    stdin_format: StreamFormatArg,
+
+    /// --stdin で並列に解く問題数の上限。省略時は CPU のスレッド数。
+    /// 複数の問題がある問題ファイルに指定すると、その数だけ並列に解く (探索の上限は問題ごとに適用する)。
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "prefix", "tt", "patterns", "tablebase_blocks", "threads", "tie_break", "no_repeat_src",
+            "end_at", "ban_src", "min_hero_travel", "max_move_cost", "time_limit",
+            "time_limit_secs", "ida", "progress", "stats", "save_improvements", "checkpoint",
+            "pareto", "beam_width", "weight", "mcts_playouts", "campaign",
+        ],
+    )]
+    jobs: Option<NonZeroUsize>,
+
+    /// --jobs で問題ファイルの問題を解くとき、全ての問題の探索で置換表を共有する。
+    /// 同じ面の変種をまとめて解く場合、他の問題で調べた局面の下界で枝刈りできる。結果は変わらない。
+    #[arg(long, requires = "jobs", conflicts_with_all = ["stdin", "max_depth"])]
+    shared_tt: bool,
+
+    /// 問題ファイルの問題を全面通しの面の列として順に解く。最後の問題は最終面として解く。
+    /// 1 面につき 1 行ずつ解を出力し、最後に全面の総コストを表示する。
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "last_stage", "prefix", "tt", "patterns", "tablebase_blocks", "threads", "objective",
+            "tie_break", "no_repeat_src", "end_at", "ban_src", "min_hero_travel", "max_move_cost",
+            "time_limit", "time_limit_secs", "stdin", "rpc", "ida", "node_limit", "search_secs",
+            "max_depth", "no_lower_bound", "progress", "stats", "save_improvements", "checkpoint",
+            "pareto", "beam_width", "weight", "mcts_playouts",
+        ],
+    )]
+    campaign: bool,
+
+    /// 問題ファイル。空行または `---` で区切って複数の問題を書くと、順に解く。
+    #[arg(required_unless_present_any = ["rpc", "stdin"])]
+    path_problem: Option<PathBuf>,
+}
+
+/// 探索の上限などのオプションと同時に指定できないオプション。
+const SEARCH_LIMIT_CONFLICTS: [&str; 12] = [
+    "tt",
+    "patterns",
+    "tablebase_blocks",
+    "ida",
+    "stdin",
+    "no_repeat_src",
+    "end_at",
+    "ban_src",
+    "min_hero_travel",
+    "max_move_cost",
+    "time_limit",
+    "time_limit_secs",
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum ObjectiveArg {
+    /// 総所要フレーム数。
+    Frames,
+    /// ブロック投げの所要フレーム数のみ (自機の移動は 0 フレームとみなす)。
+    ThrowOnly,
+    /// ブロックを投げる回数。
+    MoveCount,
+}
+
+impl From<ObjectiveArg> for Objective {
+    fn from(arg: ObjectiveArg) -> Self {
+        match arg {
+            ObjectiveArg::Frames => Self::Frames,
+            ObjectiveArg::ThrowOnly => Self::ThrowOnly,
+            ObjectiveArg::MoveCount => Self::MoveCount,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum TieBreakArg {
+    /// 入力数 (ブロックを投げる回数と自機の歩数の合計)。
+    Inputs,
+    /// 自機の総移動歩数。
+    HeroTravel,
+    /// 自機の移動方向の切り替え回数。
+    DirectionChanges,
+    /// 着手位置の列そのもの (辞書式順序で最小の列)。
+    Lexicographic,
+    /// 手数。
+    MoveCount,
+}
+
+impl From<TieBreakArg> for TieBreak {
+    fn from(arg: TieBreakArg) -> Self {
+        match arg {
+            TieBreakArg::Inputs => Self::Inputs,
+            TieBreakArg::HeroTravel => Self::HeroTravel,
+            TieBreakArg::DirectionChanges => Self::DirectionChanges,
+            TieBreakArg::Lexicographic => Self::Lexicographic,
+            TieBreakArg::MoveCount => Self::MoveCount,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum MoveOrderingArg {
+    /// 着手位置の固定の順。
+    Fixed,
+    /// 多くのブロックを消す着手から順に。
+    MostErased,
+    /// 暫定解の更新につながった回数の多い着手位置から順に。
+    History,
+}
+
+impl From<MoveOrderingArg> for MoveOrdering {
+    fn from(arg: MoveOrderingArg) -> Self {
+        match arg {
+            MoveOrderingArg::Fixed => Self::Fixed,
+            MoveOrderingArg::MostErased => Self::MostErased,
+            MoveOrderingArg::History => Self::History,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum StreamFormatArg {
+    /// `---` 区切りの問題集。
+    Collection,
+    /// 1 行に 1 問の JSON。
+    JsonLines,
+}
+
+impl From<StreamFormatArg> for StreamFormat {
+    fn from(arg: StreamFormatArg) -> Self {
+        match arg {
+            StreamFormatArg::Collection => Self::Collection,
+            StreamFormatArg::JsonLines => Self::JsonLines,
+        }
+    }
 }
 
 fn main() -> anyhow::Result<()> {
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
 
     let cli = Cli::parse();
+    cli.lang.set_current();
 
-    let problem = std::fs::read_to_string(&cli.path_problem).with_context(|| {
-        format!(
-            "問題ファイル '{}' を読み取れない",
-            cli.path_problem.display()
+    if cli.rpc {
+        let stdin = std::io::stdin().lock();
+        let stdout = std::io::stdout().lock();
+        flipull_advance_solver::rpc::serve(stdin, stdout)?;
+        return Ok(());
+    }
+
+    if cli.stdin {
+        ensure!(
+            !cli.no_repeat_src
+                && cli.end_at.is_none()
+                && cli.ban_src.is_empty()
+                && cli.min_hero_travel.is_none()
+                && cli.max_move_cost.is_none()
+                && cli.time_limit.is_none()
+                && cli.time_limit_secs.is_none(),
+            "{}",
+            tr(
+                "--stdin は制約と同時に指定できない",
+                "--stdin cannot be combined with constraints"
+            )
+        );
+        let jobs = cli
+            .jobs
+            .unwrap_or_else(|| std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN));
+        let ruleset = ruleset(&cli);
+        solve_stream(
+            std::io::stdin().lock(),
+            cli.stdin_format.into(),
+            cli.last_stage,
+            ruleset,
+            jobs,
+            |result| {
+                let mut stdout = std::io::stdout().lock();
+                // 出力先が閉じられたら以降の結果は捨てる。
+                let _ = writeln!(stdout, "{}", result.to_json_line()).and_then(|()| stdout.flush());
+            },
+        );
+        return Ok(());
+    }
+
+    let path_problem = cli.path_problem.as_ref().unwrap();
+    let problem = std::fs::read_to_string(path_problem).with_context(|| {
+        tr(
+            format!("問題ファイル '{}' を読み取れない", path_problem.display()),
+            format!("cannot read problem file '{}'", path_problem.display()),
         )
     })?;
-    let problem: Problem = problem.parse()?;
+    let problems = parse_problems(&problem)?;
+    let ruleset = ruleset(&cli);
+
+    if cli.campaign {
+        let problems: Vec<_> = problems
+            .into_iter()
+            .map(|problem| problem.with_ruleset(ruleset))
+            .collect();
+        let campaign = solve_campaign(&problems);
+        for (i, stage) in campaign.stages().iter().enumerate() {
+            match stage {
+                Some((solution, cost)) => {
+                    println!("{solution}");
+                    info!("{} {}: {cost}", tr("面", "stage"), i + 1);
+                    warn_if_approximate(&problems[i], solution);
+                }
+                None => {
+                    println!();
+                    warn!("{} {}: NO SOLUTION FOUND", tr("面", "stage"), i + 1);
+                }
+            }
+        }
+        match campaign.total_cost() {
+            Some(cost) => info!("{}: {cost}", tr("総コスト", "total cost")),
+            None => warn!(
+                "{}",
+                tr("解けない面があった", "some stages have no solution")
+            ),
+        }
+        return Ok(());
+    }
+
+    let mut prefix = Solution::new(vec![]);
+    if let Some(path_prefix) = &cli.prefix {
+        ensure!(
+            problems.len() == 1,
+            "{}",
+            tr(
+                "--prefix は 1 問だけの問題ファイルにしか使えない",
+                "--prefix requires a problem file with exactly one problem"
+            )
+        );
+        prefix = std::fs::read_to_string(path_prefix)
+            .with_context(|| {
+                tr(
+                    format!("解ファイル '{}' を読み取れない", path_prefix.display()),
+                    format!("cannot read solution file '{}'", path_prefix.display()),
+                )
+            })?
+            .parse()?;
+        // 進捗の表示などを使わなければ、prefix と残りのコストの内訳も表示する。
+        if !uses_solver_config(&cli) {
+            let problem = problems[0].clone().with_ruleset(ruleset);
+            let objective = cli.objective.map_or(Objective::Frames, Objective::from);
+            let config = solver_config(&cli, objective, &[]);
+            return solve_continuation_cli(&problem, &prefix, cli.last_stage, &config);
+        }
+    }
+
+    let mut constraints = Vec::<Constraint>::new();
+    if cli.no_repeat_src {
+        constraints.push(Constraint::NoRepeatSrc);
+    }
+    if let Some(row) = cli.end_at {
+        constraints.push(Constraint::EndAt(MoveSrc::from_inner(row).unwrap()));
+    }
+    for &row in &cli.ban_src {
+        constraints.push(Constraint::BanSrc(MoveSrc::from_inner(row).unwrap()));
+    }
+    if let Some(travel) = cli.min_hero_travel {
+        constraints.push(Constraint::MinHeroTravel(travel));
+    }
+    if let Some(cost) = cli.max_move_cost {
+        constraints.push(Constraint::MaxMoveCost(cost));
+    }
+    let time_limit = cli.time_limit.or_else(|| {
+        cli.time_limit_secs
+            .map(|secs| (secs * FRAMES_PER_SEC) as Cost)
+    });
+    if let Some(cost) = time_limit {
+        constraints.push(Constraint::TimeLimit(cost));
+    }
+
+    ensure!(
+        cli.tt.is_none() || (problems.len() == 1 && constraints.is_empty()),
+        "{}",
+        tr(
+            "--tt は 1 問だけの問題ファイルにしか使えず、制約と同時に指定できない",
+            "--tt requires a problem file with exactly one problem and no constraints"
+        )
+    );
+
+    if let Some(jobs) = cli.jobs {
+        let problems: Vec<_> = problems
+            .into_iter()
+            .map(|problem| problem.with_ruleset(ruleset))
+            .collect();
+        let objective = cli.objective.map_or(Objective::Frames, Objective::from);
+        let config = solver_config(&cli, objective, &[])
+            .with_shared_tt(cli.shared_tt.then(parallel::SharedTranspositionTable::new));
+        let results = parallel::solve_batch(&problems, cli.last_stage, &config, jobs);
+        for (i, res) in results.iter().enumerate() {
+            match res {
+                Some((solution, cost)) => {
+                    println!("{solution}");
+                    info!("{} {}: {cost}", tr("問題", "problem"), i + 1);
+                    warn_if_approximate(&problems[i], solution);
+                }
+                None => {
+                    println!();
+                    warn!("{} {}: NO SOLUTION FOUND", tr("問題", "problem"), i + 1);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    // 複数の問題があれば順に解き、1 問につき 1 行ずつ解を出力する。
+    let problem_count = problems.len();
+    for (i, problem) in problems.into_iter().enumerate() {
+        if problem_count > 1 {
+            info!("{} {}/{problem_count}", tr("問題", "problem"), i + 1);
+        }
+        solve_cli(
+            &cli,
+            &problem.with_ruleset(ruleset),
+            &constraints,
+            prefix.moves(),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// オプションで指定されたルール設定。
+fn ruleset(cli: &Cli) -> Ruleset {
+    let clear_max_blocks = if cli.perfect_clear {
+        0
+    } else {
+        cli.clear_max_blocks
+    };
 
-    if let Some((solution, cost)) = solve_problem(&problem, cli.last_stage) {
+    Ruleset::new()
+        .with_allow_swap(cli.allow_swap)
+        .with_wild_rule(cli.wild_on_erase.map(WildRule::EraseCount))
+        .with_clear_max_blocks(clear_max_blocks.into())
+}
+
+/// 探索の上限などのオプションが指定されたかどうか (`solve_problem_with()` で解くかどうか)。
+fn uses_solver_config(cli: &Cli) -> bool {
+    cli.node_limit.is_some()
+        || cli.search_secs.is_some()
+        || cli.max_depth.is_some()
+        || cli.no_lower_bound
+        || cli.no_dominance
+        || cli.no_warm_start
+        || cli.memory_limit_mb.is_some()
+        || cli.progress
+        || cli.stats
+        || cli.save_improvements.is_some()
+        || cli.checkpoint.is_some()
+        || cli.move_ordering.is_some()
+        || cli.search_tree.is_some()
+        || cli.restart_nodes.is_some()
+}
+
+/// 探索の上限などのオプションと先頭に固定する着手列 `prefix` からソルバーの設定を作る。
+fn solver_config(cli: &Cli, objective: Objective, prefix: &[MoveSrc]) -> SolverConfig {
+    SolverConfig::new()
+        .with_node_limit(cli.node_limit.unwrap_or(u64::MAX))
+        .with_time_limit(cli.search_secs.map(Duration::from_secs_f64))
+        .with_max_depth(cli.max_depth.unwrap_or(usize::MAX))
+        .with_lower_bound(!cli.no_lower_bound)
+        .with_dominance(!cli.no_dominance)
+        .with_warm_start(!cli.no_warm_start)
+        .with_memory_limit(cli.memory_limit_mb.map(|mb| mb << 20))
+        .with_threads(cli.threads.map_or(1, NonZeroUsize::get))
+        .with_objective(objective)
+        .with_tie_break(cli.tie_break.map(TieBreak::from))
+        .with_move_ordering(
+            cli.move_ordering
+                .map_or(MoveOrdering::Fixed, MoveOrdering::from),
+        )
+        .with_prefix(prefix.to_vec())
+        .with_search_tree_depth(cli.search_tree.is_some().then_some(cli.search_tree_depth))
+}
+
+fn solve_cli(
+    cli: &Cli,
+    problem: &Problem,
+    constraints: &[Constraint],
+    prefix: &[MoveSrc],
+) -> anyhow::Result<()> {
+    let cost_lb = analysis::cost_lower_bound(problem, cli.last_stage);
+    let objective = cli.objective.map_or(Objective::Frames, Objective::from);
+
+    if cli.pareto {
+        for (solution, cost) in solve_problem_pareto(problem, cli.last_stage) {
+            println!("{} {cost}: {solution}", solution.moves().len());
+        }
+        return Ok(());
+    }
+
+    if cli.max_score {
+        match solve_problem_max_score(problem, &ScoreModel::new()) {
+            Some((solution, score)) => {
+                println!("{solution}");
+                info!("score: {score}");
+                info!("cost: {}", solution.verify(problem, cli.last_stage)?);
+            }
+            None => info!("NO SOLUTION FOUND"),
+        }
+        return Ok(());
+    }
+
+    if let Some(path) = &cli.certificate {
+        let cert = solve_problem_certified(problem, cli.last_stage);
+        cert.save(path)?;
+        match cert.best() {
+            Some((solution, cost)) => {
+                println!("{solution}");
+                info!("cost: {cost}");
+                warn_if_approximate(problem, solution);
+            }
+            None => info!("NO SOLUTION FOUND"),
+        }
+        return Ok(());
+    }
+
+    if let Some(path) = &cli.verify_certificate {
+        let cert = OptimalityCertificate::load(path)?;
+        match verify_optimality(problem, cli.last_stage, &cert)? {
+            Some(cost) => info!("{}: {cost}", tr("最適性を確認", "optimality verified")),
+            None => info!("{}", tr("解がないことを確認", "unsolvability verified")),
+        }
+        return Ok(());
+    }
+
+    let res = if let Some(path_tt) = &cli.tt {
+        let key_mode = if cli.tt_full_keys {
+            TtKeyMode::Full128
+        } else {
+            TtKeyMode::Hash64
+        };
+        let mut tt = TranspositionTable::load(path_tt, problem, cli.last_stage)?;
+        if tt.is_empty() {
+            tt = tt.with_key_mode(key_mode);
+        }
+        ensure!(
+            tt.key_mode() == key_mode,
+            "{}",
+            tr(
+                "置換表ファイルのキーの形式が --tt-full-keys の指定と一致しない",
+                "key mode of the transposition table file does not match --tt-full-keys"
+            )
+        );
+        info!("tt: {} {}", tt.len(), tr("エントリ", "entries"));
+        let res = solve_problem_with_tt(problem, cli.last_stage, &mut tt);
+        tt.save(path_tt)?;
+        res
+    } else if let Some(path_patterns) = &cli.patterns {
+        let mut library = PatternLibrary::load(path_patterns)?;
+        info!("patterns: {} {}", library.len(), tr("局面", "positions"));
+        let res = solve_problem_with_patterns(problem, cli.last_stage, &library);
+        if let Some((solution, _)) = &res {
+            let count = library.learn(problem, cli.last_stage, solution, cli.pattern_max_blocks)?;
+            info!("patterns: +{count}");
+            library.save(path_patterns)?;
+        }
+        res
+    } else if let Some(max_blocks) = cli.tablebase_blocks {
+        let mut tablebase = match &cli.tablebase {
+            Some(path) => Tablebase::load(path, problem, cli.last_stage, max_blocks)?,
+            None => Tablebase::new(problem, cli.last_stage, max_blocks),
+        };
+        info!("tablebase: {} {}", tablebase.len(), tr("局面", "positions"));
+        let res = solve_problem_with_tablebase(problem, cli.last_stage, &mut tablebase);
+        info!("tablebase: {} {}", tablebase.len(), tr("局面", "positions"));
+        if let Some(path) = &cli.tablebase {
+            tablebase.save(path)?;
+        }
+        res
+    } else if uses_solver_config(cli) || !prefix.is_empty() {
+        let config = solver_config(cli, objective, prefix);
+        let outcome = if cli.progress {
+            solve_problem_with_progress(problem, cli.last_stage, &config, |progress| {
+                eprintln!(
+                    "nodes: {}  depth: {}  best: {}  elapsed: {:.1}s",
+                    progress.node_count(),
+                    progress.depth(),
+                    progress
+                        .best_cost()
+                        .map_or_else(|| "-".to_owned(), |cost| cost.to_string()),
+                    progress.elapsed().as_secs_f64()
+                );
+            })
+        } else if let Some(path) = &cli.save_improvements {
+            let mut save_err = None;
+            let outcome =
+                solve_problem_with_improvements(problem, cli.last_stage, &config, |solution, _| {
+                    if save_err.is_none() {
+                        save_err = std::fs::write(path, format!("{solution}\n")).err();
+                    }
+                });
+            if let Some(e) = save_err {
+                return Err(e).with_context(|| {
+                    tr(
+                        format!("ファイル '{}' に書き込めない", path.display()),
+                        format!("cannot write file '{}'", path.display()),
+                    )
+                });
+            }
+            outcome
+        } else if let Some(path) = &cli.checkpoint {
+            ensure!(
+                config.move_ordering() != MoveOrdering::History,
+                "{}",
+                tr(
+                    "--checkpoint は --move-ordering=history と併用できない",
+                    "--checkpoint cannot be used with --move-ordering=history"
+                )
+            );
+            let resume = SearchCheckpoint::load(path, problem, cli.last_stage, objective)?;
+            if let Some(resume) = &resume {
+                info!(
+                    "{}: {} ({} {})",
+                    tr("スナップショットから再開", "resuming from checkpoint"),
+                    path.display(),
+                    resume.node_count(),
+                    tr("局面", "positions")
+                );
+            }
+            let mut save_err = None;
+            let outcome = solve_problem_resumable(
+                problem,
+                cli.last_stage,
+                &config,
+                resume.as_ref(),
+                cli.checkpoint_interval,
+                |snapshot| {
+                    if save_err.is_none() {
+                        save_err = snapshot.save(path).err();
+                    }
+                },
+            );
+            if let Some(e) = save_err {
+                return Err(e).with_context(|| {
+                    tr(
+                        format!("ファイル '{}' に書き込めない", path.display()),
+                        format!("cannot write file '{}'", path.display()),
+                    )
+                });
+            }
+            outcome
+        } else if let Some(restart_nodes) = cli.restart_nodes {
+            restart::solve_problem_restarts(
+                problem,
+                cli.last_stage,
+                &config,
+                restart_nodes,
+                cli.restart_seed,
+            )
+        } else {
+            solve_problem_with(problem, cli.last_stage, &config)
+        };
+        if !outcome.is_complete() {
+            warn!(
+                "{}",
+                tr(
+                    "探索を打ち切ったので、解は最適とは限らない",
+                    "search was cut off; the solution may not be optimal"
+                )
+            );
+        }
+        if cli.stats {
+            let stats = outcome.stats();
+            info!(
+                "nodes: {}  max depth: {}  elapsed: {:.3}s",
+                stats.node_count(),
+                stats.max_depth(),
+                stats.elapsed().as_secs_f64()
+            );
+            info!(
+                "prunes: cost {}  lower bound {}  dominance {}  tt {}  (tt hits: {})",
+                stats.prune_cost(),
+                stats.prune_lower_bound(),
+                stats.prune_dominance(),
+                stats.prune_tt(),
+                stats.tt_hits()
+            );
+        }
+        if let (Some(path), Some(tree)) = (&cli.search_tree, outcome.search_tree()) {
+            let s = if path.extension().is_some_and(|ext| ext == "json") {
+                tree.to_json()
+            } else {
+                tree.to_dot()
+            };
+            std::fs::write(path, s).with_context(|| {
+                tr(
+                    format!("ファイル '{}' に書き込めない", path.display()),
+                    format!("cannot write file '{}'", path.display()),
+                )
+            })?;
+            info!(
+                "search tree: {} {}",
+                tree.nodes().len(),
+                tr("局面", "positions")
+            );
+        }
+        outcome.into_best()
+    } else if let Some(threads) = cli.threads {
+        parallel::solve_problem_parallel(problem, cli.last_stage, threads)
+    } else if let Some(beam_width) = cli.beam_width {
+        warn!(
+            "{}",
+            tr(
+                "ビームサーチの解は最適とは限らない",
+                "beam search solutions are not necessarily optimal"
+            )
+        );
+        solve_problem_beam(problem, cli.last_stage, beam_width)
+    } else if let Some(weight) = cli.weight {
+        ensure!(
+            weight >= 1.0,
+            "{}",
+            tr(
+                "--weight は 1 以上でなければならない",
+                "--weight must be at least 1"
+            )
+        );
+        solve_problem_weighted(problem, cli.last_stage, weight).map(|res| {
+            info!(
+                "{}: {} ({}: {:.3})",
+                tr("最適解の総コストの下界", "lower bound of the optimal cost"),
+                res.lower_bound(),
+                tr("比", "ratio"),
+                f64::from(res.cost()) / f64::from(res.lower_bound().max(1))
+            );
+            (res.solution().clone(), res.cost())
+        })
+    } else if let Some(playouts) = cli.mcts_playouts {
+        let outcome = mcts::solve_problem_mcts(problem, cli.last_stage, playouts, cli.mcts_seed);
+        if !outcome.is_complete() {
+            warn!(
+                "{}",
+                tr(
+                    "木全体を調べ終えなかったので、解は最適とは限らない",
+                    "the search tree was not exhausted; the solution may not be optimal"
+                )
+            );
+        }
+        outcome.into_best()
+    } else if cli.ida {
+        solve_problem_ida(problem, cli.last_stage)
+    } else if let Some(tie_break) = cli.tie_break {
+        solve_problem_lexicographic(problem, cli.last_stage, objective, tie_break.into())
+    } else if cli.objective.is_some() {
+        solve_problem_with_objective(problem, cli.last_stage, objective)
+    } else {
+        solve_problem_with_constraints(problem, cli.last_stage, constraints)
+    };
+
+    if let Some((solution, cost)) = res {
         println!("{solution}");
+        let cost_frames = if cli.objective.is_some() {
+            info!("objective: {cost}");
+            let cost_frames = solution.verify(problem, cli.last_stage)?;
+            info!("cost: {cost_frames}");
+            cost_frames
+        } else {
+            info!("cost: {cost} (lower bound: {cost_lb})");
+            cost
+        };
+        warn_if_approximate(problem, &solution);
+        if let Some(tie_break) = cli.tie_break {
+            let tie_break = TieBreak::from(tie_break);
+            if let Some(value) = tie_break.value(MOVE_SRC_ROW_11, solution.moves()) {
+                info!("{tie_break:?}: {value}");
+            }
+        }
+        if cli.profile != TimingProfile::default() {
+            info!(
+                "cost ({}): {}",
+                cli.profile,
+                cli.profile
+                    .adjust_total_cost(cost_frames, solution.moves().len())
+            );
+        }
 
         let cost_verify = solution
-            .verify(&problem, cli.last_stage)
-            .context("最適解の verify に失敗")?;
+            .verify_with_objective(problem, cli.last_stage, objective)
+            .inspect_err(|e| {
+                if let Some(board) = e.render(true) {
+                    eprint!("{board}");
+                }
+            })
+            .context(tr(
+                "最適解の verify に失敗",
+                "failed to verify optimal solution",
+            ))?;
         ensure!(
             cost_verify == cost,
-            "最適解の verify に失敗: コストが一致しない (solve: {cost}, verify: {cost_verify})"
+            "{} (solve: {cost}, verify: {cost_verify})",
+            tr(
+                "最適解の verify に失敗: コストが一致しない",
+                "failed to verify optimal solution: cost mismatch"
+            )
+        );
+        solution
+            .verify_with_constraints(problem, cli.last_stage, constraints)
+            .context(tr(
+                "最適解が制約を満たさない",
+                "optimal solution violates constraints",
+            ))?;
+    } else {
+        info!("NO SOLUTION FOUND");
+        if cli.max_move_cost.is_some() && solve_problem(problem, cli.last_stage).is_some() {
+            info!(
+                "{}",
+                tr(
+                    "--max-move-cost の上限を超える着手を除くと解けなくなる",
+                    "the stage becomes unsolvable without moves exceeding --max-move-cost"
+                )
+            );
+        }
+        if let Some(cost_limit) = time_limit_of(constraints) {
+            if let Some((_, cost)) = solve_problem(problem, cli.last_stage) {
+                info!(
+                    "{} (limit: {cost_limit}, optimal: {cost})",
+                    tr(
+                        "時間制限内に解く手順がない",
+                        "no route finishes within the time limit"
+                    )
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 解が入れ替え着手を含むなら、そのコストが近似であることを警告する。
+fn warn_if_approximate(problem: &Problem, solution: &Solution) {
+    let swap_count = solution.swap_count(problem);
+    if swap_count > 0 {
+        warn!(
+            "{} ({swap_count})",
+            tr(
+                "解が入れ替え着手を含むので、コストは近似値",
+                "the solution contains swap moves; its cost is approximate"
+            )
+        );
+    }
+}
+
+fn time_limit_of(constraints: &[Constraint]) -> Option<Cost> {
+    constraints.iter().find_map(|constraint| match constraint {
+        Constraint::TimeLimit(cost) => Some(*cost),
+        _ => None,
+    })
+}
+
+fn solve_continuation_cli(
+    problem: &Problem,
+    prefix: &Solution,
+    last_stage: bool,
+    config: &SolverConfig,
+) -> anyhow::Result<()> {
+    if let Some(cont) = solve_continuation(problem, prefix, last_stage, config)? {
+        println!("{}", cont.solution());
+        info!(
+            "cost: {} (prefix: {}, suffix: {})",
+            cont.cost_total(),
+            cont.cost_prefix(),
+            cont.cost_suffix()
         );
+        warn_if_approximate(problem, cont.solution());
     } else {
         info!("NO SOLUTION FOUND");
     }
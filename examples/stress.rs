@@ -0,0 +1,40 @@
+use clap::Parser;
+
+use flipull_advance_solver::stress::stress_do_move;
+use flipull_advance_solver::*;
+
+/// ランダムな盤面と着手で、ブロック投げの実装を素朴な実装と突き合わせる。
+#[derive(Debug, Parser)]
+struct Cli {
+    /// 乱数シード。
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// 試行回数。
+    #[arg(long, default_value_t = 1_000_000)]
+    iterations: usize,
+
+    /// メッセージの言語 (ja, en)。
+    #[arg(long, default_value = "ja")]
+    lang: Locale,
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    cli.lang.set_current();
+
+    if let Err(cex) = stress_do_move(cli.seed, cli.iterations) {
+        print!("{cex}");
+        anyhow::bail!(tr("食い違いが見つかった", "mismatch found"));
+    }
+
+    println!(
+        "{}",
+        tr(
+            format!("{} 回の試行で食い違いなし", cli.iterations),
+            format!("no mismatch in {} iterations", cli.iterations),
+        )
+    );
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use clap::Parser;
+
+use flipull_advance_solver::movie::{self, Divergence};
+use flipull_advance_solver::rom::Rom;
+use flipull_advance_solver::run_time::RunTime;
+use flipull_advance_solver::*;
+
+/// 全面通しのムービーと原作の ROM から面ごとの解を復元し、コストモデルで検証する。
+#[derive(Debug, Parser)]
+struct Cli {
+    /// 最初の面が始まるムービー上のフレーム。
+    #[arg(long, default_value_t = 0)]
+    first_frame: usize,
+
+    /// 面の終了から次の面の入力を受け付けるまでのフレーム数。この間の入力は無視する。
+    #[arg(long, default_value_t = 0)]
+    transition: usize,
+
+    /// エミュレータごとのタイミングプロファイル (fceux-old-ppu, fceux-new-ppu, neshawk, mesen, console)。
+    #[arg(long, default_value = "neshawk")]
+    profile: TimingProfile,
+
+    /// 面ごとの所要フレーム数 (`records run-time --reference` で使う形式) を書き出すファイル。
+    #[arg(long)]
+    times: Option<PathBuf>,
+
+    /// メッセージの言語 (ja, en)。
+    #[arg(long, default_value = "ja")]
+    lang: Locale,
+
+    /// 原作の ROM ファイル (iNES 形式)。
+    path_ines: PathBuf,
+
+    /// ムービーファイル (FCEUX の fm2、または BizHawk の bk2 内の Input Log.txt)。
+    path_movie: PathBuf,
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    cli.lang.set_current();
+
+    let rom = Rom::from_ines_file(&cli.path_ines).with_context(|| {
+        tr(
+            format!("ROM ファイル '{}' を読めない", cli.path_ines.display()),
+            format!("cannot read ROM file '{}'", cli.path_ines.display()),
+        )
+    })?;
+    let problems: Vec<_> = rom
+        .problems()?
+        .into_iter()
+        .enumerate()
+        .map(|(i, problem)| {
+            let mut metadata = ProblemMetadata::new();
+            metadata.set(ProblemMetadata::KEY_STAGE, (i + 1).to_string());
+            problem.with_metadata(metadata)
+        })
+        .collect();
+
+    let movie = std::fs::read_to_string(&cli.path_movie).with_context(|| {
+        tr(
+            format!(
+                "ムービーファイル '{}' を読み取れない",
+                cli.path_movie.display()
+            ),
+            format!("cannot read movie file '{}'", cli.path_movie.display()),
+        )
+    })?;
+    let frames = movie::parse_movie(&movie)?;
+
+    let reports = movie::verify_movie(
+        &problems,
+        &frames,
+        cli.first_frame,
+        cli.transition,
+        cli.profile,
+    );
+
+    let mut cost_total = 0;
+    let mut ok = true;
+
+    for report in &reports {
+        let frames = format!("{}..{}", report.frame_start(), report.frame_end());
+        match report.result() {
+            Ok(cost) => {
+                cost_total += cost;
+                println!(
+                    "{}\t{cost}\t{frames}\t{}",
+                    report.stage(),
+                    report.solution()
+                );
+            }
+            Err(e) => println!(
+                "{}\t{}\t{frames}\t{}\n{e}",
+                report.stage(),
+                tr("不正", "invalid"),
+                report.solution()
+            ),
+        }
+
+        for divergence in report.divergences() {
+            match divergence {
+                Divergence::InputWhileBusy { frame } => println!(
+                    "  {}",
+                    tr(
+                        format!("フレーム {frame}: モデル上は入力を受け付けない"),
+                        format!("frame {frame}: input while the model is busy"),
+                    )
+                ),
+                Divergence::MovieEnded => println!(
+                    "  {}",
+                    tr("面の途中でムービーが終わった", "movie ended mid-stage")
+                ),
+            }
+        }
+
+        ok &= report.is_ok();
+        if report.divergences().contains(&Divergence::MovieEnded) {
+            break;
+        }
+    }
+
+    println!("{}: {cost_total}", tr("総コスト", "total cost"));
+
+    if let Some(path_times) = &cli.times {
+        // ムービーが途中で終わった面以降は含めない。
+        let len = reports
+            .iter()
+            .position(|report| report.divergences().contains(&Divergence::MovieEnded))
+            .unwrap_or(reports.len());
+        let times = RunTime::from_stage_reports(&reports[..len]);
+        std::fs::write(path_times, times.to_string())?;
+    }
+
+    anyhow::ensure!(
+        ok,
+        "{}",
+        tr(
+            "モデルと食い違う面がある",
+            "some stages diverge from the model"
+        )
+    );
+
+    Ok(())
+}
@@ -0,0 +1,1349 @@
+use itertools::Itertools as _;
+
+use crate::block::{Block, BlocksSquare};
+use crate::config::SolverConfig;
+use crate::cost::{calc_min_move_cost, Cost, COST_CLEAR_ERASE_BLOCK, COST_HERO_STEP};
+use crate::error::VerifyError;
+use crate::events::{simulate, target_name, MoveEvent};
+use crate::generator::{generate_problem, GeneratorConfig};
+use crate::locale::tr;
+use crate::move_::{Move, MoveDst, MoveSrc};
+use crate::position::Position;
+use crate::precheck::dead_blocks;
+use crate::problem::{Problem, ProblemTile};
+use crate::solver::{
+    solve_problem, solve_problem_with, solve_with_node_limit, solve_with_prefix, Solution,
+};
+
+/// どのような着手列によっても消せないブロック (死にブロック) の位置を列挙する。
+///
+/// ブロックは下にしか移動しないので、以下のいずれも満たさないブロックは投げたブロックが届かず、死にブロックとなる:
+///
+/// * そのブロックの列に縦に投げる着手がある。
+/// * そのブロックの行またはそれより下の行に横に投げる着手がある。
+/// * そのブロックが A 列にあり、横に投げる着手がある (横に投げたブロックは A 列を落ちていく)。
+///
+/// 死にブロックが面クリアとなる個数 (`Ruleset::clear_max_blocks()`) より多い問題は解けない。
+pub fn find_dead_blocks(problem: &Problem) -> Vec<BlocksSquare> {
+    let (pos, moves) = problem.to_position_and_moves();
+
+    dead_blocks(&pos, &moves)
+}
+
+/// 問題の最適解のコストの下界を求める。解けないことが分かった場合は `Cost::MAX` を返す。
+///
+/// 以下の緩和を組み合わせる:
+///
+/// * 1 回の着手では 1 種類のブロックしか消せないので、必要な着手回数はブロックの種類数から下から抑えられる。
+///   各着手のブロック投げコストは投げる位置ごとの最小値で下から抑える。
+/// * 自機は少なくとも 1 回は着手可能な行へ移動しなければならない。
+///   また、使わないと解けなくなる (死にブロックが多すぎる) 行は全て訪れなければならない。
+/// * 最終面でない場合、死にブロックは面クリア時に必ず自動消去される。
+pub fn cost_lower_bound(problem: &Problem, last_stage: bool) -> Cost {
+    let (pos, moves) = problem.to_position_and_moves();
+
+    let sqs_dead = dead_blocks(&pos, &moves);
+    if sqs_dead.len() > pos.ruleset().clear_max_blocks() {
+        return Cost::MAX;
+    }
+
+    // 消せるブロックの種類ごとの個数を降順に並べ、必要な着手回数の下界を求める。
+    let mut counts = [0_usize; 4];
+    for sq in BlocksSquare::all() {
+        let Some(block) = pos.blocks()[sq] else {
+            continue;
+        };
+        if !sqs_dead.contains(&sq) {
+            counts[usize::from(block.to_inner() - Block::MIN_VALUE)] += 1;
+        }
+    }
+    counts.sort_unstable_by(|lhs, rhs| rhs.cmp(lhs));
+
+    let erase_need = pos
+        .block_count()
+        .saturating_sub(pos.ruleset().clear_max_blocks());
+    let Some(throw_count) =
+        (0..=counts.len()).find(|&n| counts[..n].iter().sum::<usize>() >= erase_need)
+    else {
+        return Cost::MAX;
+    };
+    if throw_count == 0 {
+        return if last_stage {
+            0
+        } else {
+            COST_CLEAR_ERASE_BLOCK * pos.block_count() as Cost
+        };
+    }
+
+    let cost_throw_min = moves
+        .iter()
+        .map(|mv| calc_min_move_cost(mv.src()))
+        .min()
+        .unwrap();
+
+    let cost_hero_move = COST_HERO_STEP * Cost::from(hero_travel_lower_bound(&pos, &moves));
+
+    let cost_throw = if last_stage {
+        // 最終面の最終手のブロック投げコストは 1 とみなす。
+        cost_throw_min * (throw_count as Cost - 1) + 1
+    } else {
+        cost_throw_min * throw_count as Cost
+    };
+
+    let cost_clear = if last_stage {
+        0
+    } else {
+        COST_CLEAR_ERASE_BLOCK * sqs_dead.len() as Cost
+    };
+
+    cost_hero_move + cost_throw + cost_clear
+}
+
+/// 解空間の統計情報。
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SolutionSpaceStats {
+    node_count: u64,
+    inner_node_count: u64,
+    branch_count: u64,
+    clear_depth_counts: Vec<u64>,
+}
+
+impl SolutionSpaceStats {
+    /// 探索した局面数。
+    pub fn node_count(&self) -> u64 {
+        self.node_count
+    }
+
+    /// 合法手を持つ局面における合法手数の平均 (平均分岐数)。
+    pub fn average_branching_factor(&self) -> f64 {
+        if self.inner_node_count == 0 {
+            return 0.0;
+        }
+
+        self.branch_count as f64 / self.inner_node_count as f64
+    }
+
+    /// 手数ごとの、コスト上限以内で解ける着手列の数。添字が手数に対応する。
+    pub fn clear_depth_counts(&self) -> &[u64] {
+        &self.clear_depth_counts
+    }
+
+    /// コスト上限以内で解ける着手列の数。
+    pub fn clear_count(&self) -> u64 {
+        self.clear_depth_counts.iter().sum()
+    }
+}
+
+/// 総コストが `cost_bound` 以下の範囲で全ての着手列を列挙し、解空間の統計情報を求める。
+pub fn solution_space_stats(
+    problem: &Problem,
+    last_stage: bool,
+    cost_bound: Cost,
+) -> SolutionSpaceStats {
+    let (pos, moves) = problem.to_position_and_moves();
+
+    let mut collector = SolutionSpaceStatsCollector {
+        stats: SolutionSpaceStats::default(),
+        last_stage,
+        cost_bound,
+    };
+    collector.dfs(&moves, pos, 0, 0, 0);
+
+    collector.stats
+}
+
+#[derive(Debug)]
+struct SolutionSpaceStatsCollector {
+    stats: SolutionSpaceStats,
+    last_stage: bool,
+    cost_bound: Cost,
+}
+
+impl SolutionSpaceStatsCollector {
+    fn dfs(
+        &mut self,
+        moves: &[Move],
+        pos: Position,
+        depth: usize,
+        cost: Cost,
+        cost_last_throw: Cost,
+    ) {
+        // コストの下界が上限を超えたら枝刈り。
+        // 最終面でない場合の面クリア時のブロック自動消去コストは、以後の着手で減りうるので含めない。
+        let cost_lb = if self.last_stage {
+            cost - cost_last_throw + 1
+        } else {
+            cost
+        };
+        if cost_lb > self.cost_bound {
+            return;
+        }
+
+        self.stats.node_count += 1;
+
+        if pos.is_stuck(moves) {
+            let cost_total = if self.last_stage {
+                cost_lb
+            } else {
+                cost + COST_CLEAR_ERASE_BLOCK * pos.block_count() as Cost
+            };
+            if pos.is_cleared() && cost_total <= self.cost_bound {
+                let counts = &mut self.stats.clear_depth_counts;
+                if counts.len() <= depth {
+                    counts.resize(depth + 1, 0);
+                }
+                counts[depth] += 1;
+            }
+            return;
+        }
+
+        if pos.move_remain() == 0 {
+            return;
+        }
+
+        self.stats.inner_node_count += 1;
+
+        for &mv in moves {
+            let Some((pos_nxt, cost_mv, cost_throw)) = pos.do_move(mv) else {
+                continue;
+            };
+            self.stats.branch_count += 1;
+            self.dfs(moves, pos_nxt, depth + 1, cost + cost_mv, cost_throw);
+        }
+    }
+}
+
+/// 問題の難しさの指標。
+#[derive(Clone, Debug)]
+pub struct StageDifficulty {
+    cost: Option<Cost>,
+    cost_lower_bound: Cost,
+    node_count: u64,
+}
+
+impl StageDifficulty {
+    /// 最適解のコスト。解がなければ `None`。
+    pub fn cost(&self) -> Option<Cost> {
+        self.cost
+    }
+
+    /// 最適解のコストの下界 ([`cost_lower_bound`] を参照)。
+    pub fn cost_lower_bound(&self) -> Cost {
+        self.cost_lower_bound
+    }
+
+    /// ソルバーが探索した局面数 (既定の設定、1 スレッド)。
+    ///
+    /// 所要時間と異なり実行環境によらず決まるので、難しさの比較に使える。
+    pub fn node_count(&self) -> u64 {
+        self.node_count
+    }
+
+    /// 最適解のコストと下界の比。1 に近いほど下界が tight (探索の余地が小さい)。
+    /// 解がない場合、または下界が 0 の場合は `None` を返す。
+    pub fn tightness(&self) -> Option<f64> {
+        let cost = self.cost?;
+        (self.cost_lower_bound > 0).then(|| f64::from(cost) / f64::from(self.cost_lower_bound))
+    }
+}
+
+/// 問題を解き、難しさの指標を求める。
+pub fn stage_difficulty(problem: &Problem, last_stage: bool) -> StageDifficulty {
+    let cost_lower_bound = cost_lower_bound(problem, last_stage);
+
+    let outcome = solve_problem_with(problem, last_stage, &SolverConfig::new());
+    let node_count = outcome.stats().node_count();
+    let cost = outcome.into_best().map(|(_, cost)| cost);
+
+    StageDifficulty {
+        cost,
+        cost_lower_bound,
+        node_count,
+    }
+}
+
+/// 問題たち `problems` (ROM なら `Rom::problems()` の全 50 面) の難しさを求め、(添字, 難しさ) を難しい順に返す。
+///
+/// 最後の問題は `last_stage` が真なら最終面として解く。
+/// 探索した局面数の降順、次いで tightness の降順 (解なしは最後) に並べる。
+pub fn rank_stages(problems: &[Problem], last_stage: bool) -> Vec<(usize, StageDifficulty)> {
+    let mut ranking: Vec<_> = problems
+        .iter()
+        .enumerate()
+        .map(|(i, problem)| {
+            let last_stage = last_stage && i + 1 == problems.len();
+            (i, stage_difficulty(problem, last_stage))
+        })
+        .collect();
+
+    ranking.sort_by(|(_, lhs), (_, rhs)| {
+        rhs.node_count.cmp(&lhs.node_count).then_with(|| {
+            let lhs = lhs.tightness().unwrap_or(f64::NEG_INFINITY);
+            let rhs = rhs.tightness().unwrap_or(f64::NEG_INFINITY);
+            rhs.total_cmp(&lhs)
+        })
+    });
+
+    ranking
+}
+
+/// 初期保持ブロックを各値に変えて問題を解き直し、(初期保持ブロック, 最適解のコスト) のリストを返す。
+/// 解がない場合のコストは `None` となる。
+pub fn holding_block_sensitivity(
+    problem: &Problem,
+    last_stage: bool,
+) -> Vec<(Block, Option<Cost>)> {
+    (Block::MIN_VALUE..=Block::MAX_VALUE)
+        .map(|inner| {
+            let block = Block::from_inner(inner).unwrap();
+            let problem = Problem::new(problem.board().clone(), block, problem.move_remain())
+                .expect("盤面は検証済みのはず")
+                .with_ruleset(problem.ruleset());
+            let cost = solve_problem(&problem, last_stage).map(|(_, cost)| cost);
+            (block, cost)
+        })
+        .collect()
+}
+
+/// 最適解のある 1 手についての、他の着手との比較結果。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MoveChoice {
+    src: MoveSrc,
+    alternatives: Vec<(MoveSrc, Option<Cost>)>,
+}
+
+impl MoveChoice {
+    /// 最適解における着手。
+    pub fn src(&self) -> MoveSrc {
+        self.src
+    }
+
+    /// 代わりに指せる各着手と、それを指した場合の最善のコストの最適解に対する増分。
+    /// 代わりの着手で解けなくなる場合、増分は `None` となる。
+    pub fn alternatives(&self) -> &[(MoveSrc, Option<Cost>)] {
+        &self.alternatives
+    }
+
+    /// 他のどの着手を指しても最適解が得られないかどうか。
+    pub fn is_forced(&self) -> bool {
+        self.alternatives
+            .iter()
+            .all(|&(_, penalty)| penalty != Some(0))
+    }
+
+    /// 代わりの着手のコスト増分の最小値。代わりの着手で解けるものがなければ `None`。
+    pub fn min_penalty(&self) -> Option<Cost> {
+        self.alternatives
+            .iter()
+            .filter_map(|&(_, penalty)| penalty)
+            .min()
+    }
+}
+
+/// 最適解を求め、その各手について他の着手を指した場合の最善のコストを調べる。
+/// (最適解, コスト, 各手の比較結果) を返す。解がなければ `None` を返す。
+///
+/// 各手の比較は、最適解のそれ以前の手を固定した上で行う。
+/// 強制手 ([`MoveChoice::is_forced`]) は、その手順に沿う限り全ての最適解に現れる。
+pub fn find_critical_moves(
+    problem: &Problem,
+    last_stage: bool,
+) -> Option<(Solution, Cost, Vec<MoveChoice>)> {
+    let (solution, cost) = solve_problem(problem, last_stage)?;
+
+    let (mut pos, moves) = problem.to_position_and_moves();
+    let mut prefix = Vec::<Move>::new();
+    let mut choices = Vec::<MoveChoice>::new();
+
+    for &src in solution.moves() {
+        let alternatives = moves
+            .iter()
+            .copied()
+            .filter(|mv| mv.src() != src && pos.do_move(*mv).is_some())
+            .map(|mv| {
+                prefix.push(mv);
+                let penalty = solve_with_prefix(problem, last_stage, &prefix)
+                    .map(|(_, cost_alt)| cost_alt - cost);
+                prefix.pop().unwrap();
+                (mv.src(), penalty)
+            })
+            .collect();
+        choices.push(MoveChoice { src, alternatives });
+
+        let mv = moves.iter().copied().find(|mv| mv.src() == src).unwrap();
+        pos = pos.do_move(mv).unwrap().0;
+        prefix.push(mv);
+    }
+
+    Some((solution, cost, choices))
+}
+
+/// 与えられた解と最適解との差の説明。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GapExplanation {
+    cost_optimal: Cost,
+    cost_mine: Cost,
+    move_penalties: Vec<Cost>,
+    first_divergence: Option<usize>,
+    best_continuation: Solution,
+}
+
+impl GapExplanation {
+    /// 最適解のコスト。
+    pub fn cost_optimal(&self) -> Cost {
+        self.cost_optimal
+    }
+
+    /// 与えられた解のコスト。
+    pub fn cost_mine(&self) -> Cost {
+        self.cost_mine
+    }
+
+    /// 与えられた解の各手について、その手を指したことで失われたコスト。
+    /// すなわち、その手までを固定した場合の最善のコストと、その手の直前までを固定した場合の最善のコストの差。
+    /// 総和は `cost_mine() - cost_optimal()` に等しい。
+    pub fn move_penalties(&self) -> &[Cost] {
+        &self.move_penalties
+    }
+
+    /// 与えられた解が初めて全ての最適解から外れた手の添字。与えられた解が最適なら `None`。
+    pub fn first_divergence(&self) -> Option<usize> {
+        self.first_divergence
+    }
+
+    /// 最初に外れた手の直前までは与えられた解と同じ手順をたどる最適解。
+    /// 与えられた解が最適ならそれ自身。
+    pub fn best_continuation(&self) -> &Solution {
+        &self.best_continuation
+    }
+}
+
+/// 与えられた解が最適解からどこで外れ、各手で何フレーム失っているかを調べる。
+pub fn explain_gap(
+    problem: &Problem,
+    solution: &Solution,
+    last_stage: bool,
+) -> Result<GapExplanation, VerifyError> {
+    let cost_mine = solution.verify(problem, last_stage)?;
+
+    let (_, moves) = problem.to_position_and_moves();
+    let mvs: Vec<_> = solution
+        .moves()
+        .iter()
+        .map(|&src| moves.iter().copied().find(|mv| mv.src() == src).unwrap())
+        .collect();
+
+    // 与えられた解の最初の i 手を固定した場合の最善の解とコスト。
+    // 与えられた解自身が候補となるので、必ず解は存在する。
+    let bests: Vec<_> = (0..=mvs.len())
+        .map(|i| solve_with_prefix(problem, last_stage, &mvs[..i]).unwrap())
+        .collect();
+
+    let cost_optimal = bests[0].1;
+    let move_penalties: Vec<_> = bests.windows(2).map(|pair| pair[1].1 - pair[0].1).collect();
+    let first_divergence = move_penalties.iter().position(|&penalty| penalty > 0);
+
+    let (best_continuation, _) = bests[first_divergence.unwrap_or(mvs.len())].clone();
+
+    Ok(GapExplanation {
+        cost_optimal,
+        cost_mine,
+        move_penalties,
+        first_divergence,
+        best_continuation,
+    })
+}
+
+/// センサスで問題の最適解が得られなかった理由。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CensusFailure {
+    /// 死にブロックが多すぎて、明らかに解けない。
+    DeadBlocks,
+    /// 探索を完了し、解がないことが分かった。
+    NoSolution,
+    /// 探索局面数の上限に達し、最適解が得られなかった (解けるかどうかは不明)。
+    NodeLimit,
+}
+
+/// ランダムに生成した問題たちの可解性の集計結果。
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CensusReport {
+    costs: Vec<Cost>,
+    failures: Vec<CensusFailure>,
+}
+
+impl CensusReport {
+    /// 生成した問題の数。
+    pub fn sample_count(&self) -> usize {
+        self.costs.len() + self.failures.len()
+    }
+
+    /// 最適解が得られた問題の数。
+    pub fn solved_count(&self) -> usize {
+        self.costs.len()
+    }
+
+    /// 最適解が得られた問題の割合。
+    pub fn solved_fraction(&self) -> f64 {
+        if self.sample_count() == 0 {
+            return 0.0;
+        }
+
+        self.solved_count() as f64 / self.sample_count() as f64
+    }
+
+    /// 最適解が得られた問題たちの最適解のコストの平均。
+    pub fn mean_cost(&self) -> Option<f64> {
+        (!self.costs.is_empty()).then(|| {
+            self.costs.iter().copied().map(f64::from).sum::<f64>() / self.costs.len() as f64
+        })
+    }
+
+    /// 指定した理由で最適解が得られなかった問題の数。
+    pub fn failure_count(&self, failure: CensusFailure) -> usize {
+        self.failures.iter().filter(|&&e| e == failure).count()
+    }
+}
+
+/// 設定に従って問題を `sample_count` 個生成し、それぞれを探索局面数の上限 `node_limit` 付きで解いて集計する。
+/// 生成した問題は最終面でないものとして扱う。
+///
+/// `i` 番目 (0-based) の問題はシード `seed.wrapping_add(i)` で生成する。
+/// よって気になる問題は `generate_problem` で個別に再現できる。
+pub fn solvability_census(
+    config: &GeneratorConfig,
+    sample_count: usize,
+    node_limit: u64,
+    seed: u64,
+) -> CensusReport {
+    let mut report = CensusReport::default();
+
+    for i in 0..sample_count {
+        let problem = generate_problem(config, seed.wrapping_add(i as u64));
+
+        if find_dead_blocks(&problem).len() > problem.ruleset().clear_max_blocks() {
+            report.failures.push(CensusFailure::DeadBlocks);
+            continue;
+        }
+
+        match solve_with_node_limit(&problem, false, &[], node_limit) {
+            (Some((_, cost)), true) => report.costs.push(cost),
+            (None, true) => report.failures.push(CensusFailure::NoSolution),
+            (_, false) => report.failures.push(CensusFailure::NodeLimit),
+        }
+    }
+
+    report
+}
+
+/// 問題の小さな改変。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum StageEdit {
+    /// ブロック領域のマス `sq` にあるブロックを `block` に変える。
+    Block { sq: BlocksSquare, block: Block },
+
+    /// 初期保持ブロックを変える。
+    Holding(Block),
+}
+
+impl StageEdit {
+    /// 改変を問題に適用する。盤面が ADVANCE モードの制約を満たさなくなる場合は `None` を返す。
+    pub fn apply(self, problem: &Problem) -> Option<Problem> {
+        let mut board = problem.board().clone();
+        let mut block_holding = problem.block_holding();
+
+        match self {
+            Self::Block { sq, block } => {
+                let col = usize::from(sq.col().to_inner() - 1);
+                let row = usize::from(sq.row().to_inner() - 1) + 6;
+                *board.get_mut(col, row).unwrap() = Some(ProblemTile::Block(block));
+            }
+            Self::Holding(block) => block_holding = block,
+        }
+
+        Problem::new(board, block_holding, problem.move_remain())
+            .ok()
+            .map(|res| res.with_ruleset(problem.ruleset()))
+    }
+
+    /// 同じ対象 (マスまたは初期保持ブロック) への改変かどうか。
+    fn same_target(self, other: Self) -> bool {
+        match (self, other) {
+            (Self::Block { sq: sq1, .. }, Self::Block { sq: sq2, .. }) => sq1 == sq2,
+            (Self::Holding(_), Self::Holding(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl core::fmt::Display for StageEdit {
+    /// `C4=2` (マス C4 のブロックを 2 に変える), `hold=5` (初期保持ブロックを 5 に変える) の形式。
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Block { sq, block } => write!(
+                f,
+                "{}{}={}",
+                char::from(b'A' + sq.col().to_inner() - 1),
+                sq.row().to_inner(),
+                block.to_inner()
+            ),
+            Self::Holding(block) => write!(f, "hold={}", block.to_inner()),
+        }
+    }
+}
+
+/// 改変の目標。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EditTarget {
+    /// 最適解のコスト。
+    Cost(Cost),
+
+    /// 最適解のコストと下界の比 ([`StageDifficulty::tightness`] を参照)。大きいほど難しい。
+    Tightness(f64),
+}
+
+/// 改変した問題の候補。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EditCandidate {
+    edits: Vec<StageEdit>,
+    problem: Problem,
+    solution: Solution,
+    cost: Cost,
+    cost_lower_bound: Cost,
+}
+
+impl EditCandidate {
+    pub fn edits(&self) -> &[StageEdit] {
+        &self.edits
+    }
+
+    /// 改変後の問題。
+    pub fn problem(&self) -> &Problem {
+        &self.problem
+    }
+
+    /// 改変後の問題の最適解。
+    pub fn solution(&self) -> &Solution {
+        &self.solution
+    }
+
+    pub fn cost(&self) -> Cost {
+        self.cost
+    }
+
+    /// 改変後の問題の最適解のコストと下界の比。下界が 0 の場合は `None` を返す。
+    pub fn tightness(&self) -> Option<f64> {
+        (self.cost_lower_bound > 0).then(|| f64::from(self.cost) / f64::from(self.cost_lower_bound))
+    }
+
+    fn distance(&self, target: EditTarget) -> f64 {
+        match target {
+            EditTarget::Cost(cost) => f64::from(self.cost.abs_diff(cost)),
+            EditTarget::Tightness(tightness) => self
+                .tightness()
+                .map_or(f64::INFINITY, |t| (t - tightness).abs()),
+        }
+    }
+}
+
+/// ブロック 1, 2 個の色または初期保持ブロックを変えた問題たちを探索局面数の上限 `node_limit` 付きで解き、
+/// 目標 `target` に近い順に最大 `count` 個の候補を返す。
+///
+/// 一度に行う改変は `max_edits` 個 (1 または 2) まで。上限内に最適解が得られなかった問題、解けない問題は除く。
+/// カスタム問題集の難易度調整に使う。
+pub fn search_stage_edits(
+    problem: &Problem,
+    last_stage: bool,
+    target: EditTarget,
+    max_edits: usize,
+    node_limit: u64,
+    count: usize,
+) -> Vec<EditCandidate> {
+    assert!((1..=2).contains(&max_edits));
+
+    let (pos, _) = problem.to_position_and_moves();
+
+    let mut singles = Vec::<StageEdit>::new();
+    for sq in BlocksSquare::all() {
+        let Some(block_orig) = pos.blocks()[sq] else {
+            continue;
+        };
+        for inner in Block::MIN_VALUE..=Block::MAX_VALUE {
+            let block = Block::from_inner(inner).unwrap();
+            if block.is_normal() && block != block_orig {
+                singles.push(StageEdit::Block { sq, block });
+            }
+        }
+    }
+    for inner in Block::MIN_VALUE..=Block::MAX_VALUE {
+        let block = Block::from_inner(inner).unwrap();
+        if block != problem.block_holding() {
+            singles.push(StageEdit::Holding(block));
+        }
+    }
+
+    let mut edit_sets: Vec<Vec<StageEdit>> = singles.iter().map(|&edit| vec![edit]).collect();
+    if max_edits >= 2 {
+        for (i, &edit1) in singles.iter().enumerate() {
+            for &edit2 in &singles[i + 1..] {
+                if !edit1.same_target(edit2) {
+                    edit_sets.push(vec![edit1, edit2]);
+                }
+            }
+        }
+    }
+
+    let mut candidates: Vec<_> = edit_sets
+        .into_iter()
+        .filter_map(|edits| {
+            let problem = edits
+                .iter()
+                .try_fold(problem.clone(), |problem, edit| edit.apply(&problem))?;
+            if find_dead_blocks(&problem).len() > problem.ruleset().clear_max_blocks() {
+                return None;
+            }
+            let (Some((solution, cost)), true) =
+                solve_with_node_limit(&problem, last_stage, &[], node_limit)
+            else {
+                return None;
+            };
+            let cost_lower_bound = cost_lower_bound(&problem, last_stage);
+            Some(EditCandidate {
+                edits,
+                problem,
+                solution,
+                cost,
+                cost_lower_bound,
+            })
+        })
+        .collect();
+
+    candidates.sort_by(|lhs, rhs| {
+        lhs.distance(target)
+            .total_cmp(&rhs.distance(target))
+            .then_with(|| lhs.edits.len().cmp(&rhs.edits.len()))
+    });
+    candidates.truncate(count);
+
+    candidates
+}
+
+/// 初手の候補 1 つについての評価。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OpeningAdvice {
+    mv: Move,
+    erased_block: Option<Block>,
+    erase_count: usize,
+    cost: Option<Cost>,
+    exact: bool,
+}
+
+impl OpeningAdvice {
+    pub fn src(&self) -> MoveSrc {
+        self.mv.src()
+    }
+
+    /// 投げ込み先 (狙う行または列)。
+    pub fn dst(&self) -> MoveDst {
+        self.mv.dst()
+    }
+
+    /// この初手で消すブロックの種類。入れ替え着手なら `None`。
+    pub fn erased_block(&self) -> Option<Block> {
+        self.erased_block
+    }
+
+    /// この初手で消すブロックの数。
+    pub fn erase_count(&self) -> usize {
+        self.erase_count
+    }
+
+    /// この初手から始めた場合に見つかった最善のコスト。見つからなければ `None`。
+    pub fn cost(&self) -> Option<Cost> {
+        self.cost
+    }
+
+    /// 探索を完了したかどうか (`cost()` がこの初手から始める場合の最適値かどうか)。
+    pub fn is_exact(&self) -> bool {
+        self.exact
+    }
+}
+
+/// 問題の攻略方針の助言。フレーム単位の最適解ではなく、どこから崩すべきかの目安を知りたい人向け。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StrategyAdvice {
+    openings: Vec<OpeningAdvice>,
+}
+
+impl StrategyAdvice {
+    /// 初手の候補たち。見つかった最善のコストが小さい順 (解が見つからなかったものは最後)。
+    pub fn openings(&self) -> &[OpeningAdvice] {
+        &self.openings
+    }
+
+    /// 最初に消すブロックの種類ごとの、見つかった最善のコスト。コストが小さい順。
+    pub fn block_order(&self) -> Vec<(Block, Option<Cost>)> {
+        let mut order = Vec::<(Block, Option<Cost>)>::new();
+
+        for opening in &self.openings {
+            let Some(block) = opening.erased_block else {
+                continue;
+            };
+            if !order.iter().any(|&(block_seen, _)| block_seen == block) {
+                order.push((block, opening.cost));
+            }
+        }
+
+        order
+    }
+
+    /// どの初手からも解が見つからなかった投げ込み先。
+    pub fn dead_targets(&self) -> Vec<MoveDst> {
+        let mut targets: Vec<_> = self.openings.iter().map(|opening| opening.dst()).collect();
+        targets.sort_unstable();
+        targets.dedup();
+        targets.retain(|&dst| {
+            self.openings
+                .iter()
+                .filter(|opening| opening.dst() == dst)
+                .all(|opening| opening.cost.is_none())
+        });
+
+        targets
+    }
+
+    /// 数行の要約。
+    pub fn summary(&self) -> String {
+        let mut lines = Vec::<String>::new();
+
+        let Some(best) = self
+            .openings
+            .first()
+            .filter(|opening| opening.cost.is_some())
+        else {
+            return tr(
+                "どの初手からも解が見つからなかった",
+                "no solution found from any opening",
+            )
+            .to_owned();
+        };
+        let cost_best = best.cost.unwrap();
+
+        let action = match best.erased_block {
+            Some(block) => tr(
+                format!(
+                    "ブロック {} を {} 個消す",
+                    block.to_inner(),
+                    best.erase_count
+                ),
+                format!(
+                    "erase {} block(s) of {}",
+                    best.erase_count,
+                    block.to_inner()
+                ),
+            ),
+            None => tr("入れ替える".to_owned(), "swap".to_owned()),
+        };
+        let approx = if self.openings.iter().all(|opening| opening.exact) {
+            ""
+        } else {
+            tr(" (上限内の最善)", " (best within the node limit)")
+        };
+        lines.push(tr(
+            format!(
+                "まず {} を狙う: 行 {} から投げて{action}。{cost_best} フレーム{approx}",
+                target_name(best.dst()),
+                best.src().to_inner()
+            ),
+            format!(
+                "attack {} first: throw from row {} and {action}, {cost_best} frames{approx}",
+                target_name(best.dst()),
+                best.src().to_inner()
+            ),
+        ));
+
+        let order = self
+            .block_order()
+            .into_iter()
+            .map(|(block, cost)| match cost {
+                Some(cost) => format!("{} (+{})", block.to_inner(), cost - cost_best),
+                None => format!("{} (-)", block.to_inner()),
+            })
+            .join(", ");
+        lines.push(tr(
+            format!("最初に消すブロック: {order}"),
+            format!("block to clear first: {order}"),
+        ));
+
+        let dead = self.dead_targets();
+        if !dead.is_empty() {
+            let dead = dead.into_iter().map(target_name).join(", ");
+            lines.push(tr(
+                format!("最初に狙うと解が見つからない: {dead}"),
+                format!("no solution found when attacked first: {dead}"),
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// 可能な初手ごとに、その手を固定して探索局面数の上限 `node_limit` 付きで解き、どこから崩すべきかを助言する。
+pub fn advise_strategy(problem: &Problem, last_stage: bool, node_limit: u64) -> StrategyAdvice {
+    let (pos, moves) = problem.to_position_and_moves();
+
+    let mut openings: Vec<_> = moves
+        .iter()
+        .filter_map(|&mv| {
+            let events = simulate(&pos, mv)?;
+            let erased: Vec<_> = events
+                .events()
+                .iter()
+                .filter_map(|event| match *event {
+                    MoveEvent::BlockErased { block, .. } => Some(block),
+                    _ => None,
+                })
+                .collect();
+            let (res, exact) = solve_with_node_limit(problem, last_stage, &[mv], node_limit);
+            Some(OpeningAdvice {
+                mv,
+                erased_block: erased.first().copied(),
+                erase_count: erased.len(),
+                cost: res.map(|(_, cost)| cost),
+                exact,
+            })
+        })
+        .collect();
+    openings.sort_by_key(|opening| (opening.cost.is_none(), opening.cost, opening.src()));
+
+    StrategyAdvice { openings }
+}
+
+/// 自機の移動歩数の下界を求める。少なくとも 1 回は着手することを仮定する。
+fn hero_travel_lower_bound(pos: &Position, moves: &[Move]) -> u8 {
+    let hero_row = pos.hero_row().to_inner();
+
+    // 最初の着手を行うための移動。
+    let travel_first = moves
+        .iter()
+        .map(|mv| hero_row.abs_diff(mv.src().to_inner()))
+        .min()
+        .unwrap();
+
+    // 使わないと死にブロックが面クリアとなる個数より多くなる行は必ず訪れる必要がある。
+    let rows_required: Vec<_> = moves
+        .iter()
+        .map(|mv| mv.src().to_inner())
+        .filter(|&src| {
+            let moves_without: Vec<_> = moves
+                .iter()
+                .copied()
+                .filter(|mv| mv.src().to_inner() != src)
+                .collect();
+            dead_blocks(pos, &moves_without).len() > pos.ruleset().clear_max_blocks()
+        })
+        .collect();
+    let travel_required = match (rows_required.iter().min(), rows_required.iter().max()) {
+        (Some(&lo), Some(&hi)) => (hi - lo) + hero_row.abs_diff(lo).min(hero_row.abs_diff(hi)),
+        _ => 0,
+    };
+
+    travel_first.max(travel_required)
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn parse_problem(s: impl AsRef<str>) -> Problem {
+        s.as_ref().parse().unwrap()
+    }
+
+    #[test]
+    fn test_find_dead_blocks() {
+        let problem = parse_problem(indoc! {"
+            1 10
+            #######.
+            #######.
+            #######.
+            #######.
+            #######.
+            #######.
+            1.......
+            .2....|.
+            ..3...|.
+            ...4..|.
+            ....1.|.
+            1.....|.
+        "});
+
+        assert_eq!(
+            find_dead_blocks(&problem),
+            [
+                BlocksSquare::SqB2,
+                BlocksSquare::SqC3,
+                BlocksSquare::SqD4,
+                BlocksSquare::SqE5,
+            ]
+        );
+
+        let problem = parse_problem(indoc! {"
+            2 33
+            #####...
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            31143...
+            22224.|.
+            33442...
+            42222.|.
+            34424...
+            13334...
+        "});
+
+        assert_eq!(find_dead_blocks(&problem), []);
+    }
+
+    #[test]
+    fn test_cost_lower_bound() {
+        let problem = parse_problem(indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "});
+
+        for last_stage in [false, true] {
+            let (_, cost) = solve_problem(&problem, last_stage).unwrap();
+            let cost_lb = cost_lower_bound(&problem, last_stage);
+            assert!(0 < cost_lb && cost_lb <= cost);
+        }
+
+        let problem = parse_problem(indoc! {"
+            1 10
+            #######.
+            #######.
+            #######.
+            #######.
+            #######.
+            #######.
+            1.......
+            .2....|.
+            ..3...|.
+            ...4..|.
+            ....1.|.
+            1.....|.
+        "});
+
+        assert_eq!(cost_lower_bound(&problem, false), Cost::MAX);
+    }
+
+    #[test]
+    fn test_solution_space_stats() {
+        let problem = parse_problem(indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "});
+
+        let (solution, cost) = solve_problem(&problem, false).unwrap();
+
+        let stats = solution_space_stats(&problem, false, cost);
+        assert!(stats.clear_count() >= 1);
+        assert!(stats.clear_depth_counts()[solution.moves().len()] >= 1);
+        assert!(stats.average_branching_factor() > 0.0);
+
+        let stats = solution_space_stats(&problem, false, cost - 1);
+        assert_eq!(stats.clear_count(), 0);
+    }
+
+    #[test]
+    fn test_stage_difficulty() {
+        let problem = parse_problem(indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "});
+
+        let difficulty = stage_difficulty(&problem, false);
+        assert_eq!(difficulty.cost(), Some(443));
+        assert_eq!(
+            difficulty.cost_lower_bound(),
+            cost_lower_bound(&problem, false)
+        );
+        assert!(difficulty.tightness().unwrap() >= 1.0);
+        assert!(difficulty.node_count() > 0);
+        assert_eq!(
+            stage_difficulty(&problem, false).node_count(),
+            difficulty.node_count()
+        );
+    }
+
+    #[test]
+    fn test_rank_stages() {
+        let problems: Vec<_> = [
+            include_str!("../problem/01.in"),
+            include_str!("../problem/10.in"),
+            include_str!("../problem/05.in"),
+        ]
+        .into_iter()
+        .map(parse_problem)
+        .collect();
+
+        // 探索した局面数の降順。実行環境によらず同じ順になる。
+        let ranking = rank_stages(&problems, true);
+        assert_eq!(ranking.len(), problems.len());
+        assert!(ranking
+            .windows(2)
+            .all(|w| w[0].1.node_count() >= w[1].1.node_count()));
+        let order: Vec<_> = ranking.iter().map(|&(i, _)| i).collect();
+        let order_again: Vec<_> = rank_stages(&problems, true)
+            .iter()
+            .map(|&(i, _)| i)
+            .collect();
+        assert_eq!(order, order_again);
+
+        // 最後の問題だけを最終面として解く。
+        for (i, difficulty) in &ranking {
+            let last_stage = *i == problems.len() - 1;
+            assert_eq!(
+                difficulty.cost(),
+                solve_problem(&problems[*i], last_stage).map(|(_, cost)| cost)
+            );
+        }
+    }
+
+    #[test]
+    fn test_holding_block_sensitivity() {
+        let problem = parse_problem(indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "});
+
+        let costs = holding_block_sensitivity(&problem, false);
+        assert_eq!(costs.len(), 5);
+        assert!(costs.contains(&(Block::Normal3, Some(443))));
+    }
+
+    #[test]
+    fn test_find_critical_moves() {
+        let problem = parse_problem(indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "});
+
+        let (solution, cost, choices) = find_critical_moves(&problem, false).unwrap();
+        assert_eq!(cost, 443);
+        assert_eq!(choices.len(), solution.moves().len());
+
+        for (&src, choice) in std::iter::zip(solution.moves(), &choices) {
+            assert_eq!(choice.src(), src);
+            assert!(choice
+                .alternatives()
+                .iter()
+                .all(|&(src_alt, _)| src_alt != src));
+            assert_eq!(choice.is_forced(), choice.min_penalty() != Some(0));
+        }
+    }
+
+    #[test]
+    fn test_explain_gap() {
+        let problem = parse_problem(indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "});
+
+        let (solution, cost) = solve_problem(&problem, false).unwrap();
+        let gap = explain_gap(&problem, &solution, false).unwrap();
+        assert_eq!(gap.cost_optimal(), cost);
+        assert_eq!(gap.cost_mine(), cost);
+        assert_eq!(gap.first_divergence(), None);
+        assert_eq!(gap.best_continuation(), &solution);
+
+        // 4 手目で最適解から外れる解。
+        let (_, moves) = problem.to_position_and_moves();
+        let prefix: Vec<_> = [10, 9, 10, 3]
+            .into_iter()
+            .map(|src| {
+                let src = MoveSrc::from_inner(src).unwrap();
+                moves.iter().copied().find(|mv| mv.src() == src).unwrap()
+            })
+            .collect();
+        let (mine, _) = solve_with_prefix(&problem, false, &prefix).unwrap();
+
+        let gap = explain_gap(&problem, &mine, false).unwrap();
+        assert_eq!(gap.first_divergence(), Some(3));
+        assert_eq!(
+            gap.move_penalties().iter().sum::<Cost>(),
+            gap.cost_mine() - gap.cost_optimal()
+        );
+        assert_eq!(gap.move_penalties()[3], 341);
+        assert_eq!(gap.best_continuation(), &solution);
+    }
+
+    #[test]
+    fn test_search_stage_edits() {
+        let problem = parse_problem(indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "});
+
+        let edit = StageEdit::Block {
+            sq: BlocksSquare::SqA3,
+            block: Block::Normal4,
+        };
+        assert_eq!(edit.to_string(), "A3=4");
+        assert_eq!(StageEdit::Holding(Block::Wild).to_string(), "hold=5");
+
+        // 元の問題の最適解のコスト (443) を目標にすると、同じコストの改変が最初に来る。
+        let candidates = search_stage_edits(&problem, false, EditTarget::Cost(443), 1, 100_000, 3);
+        assert_eq!(candidates.len(), 3);
+        for candidate in &candidates {
+            assert_eq!(candidate.edits().len(), 1);
+            assert_eq!(
+                candidate.solution().verify(candidate.problem(), false),
+                Ok(candidate.cost())
+            );
+        }
+        assert_eq!(candidates[0].cost(), 443);
+    }
+
+    #[test]
+    fn test_advise_strategy() {
+        let problem = parse_problem(indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "});
+        let (solution, cost) = solve_problem(&problem, false).unwrap();
+
+        // 上限なしなら、最善の初手の評価は最適解のコストに一致する。
+        let advice = advise_strategy(&problem, false, u64::MAX);
+        let best = &advice.openings()[0];
+        assert!(best.is_exact());
+        assert_eq!(best.cost(), Some(cost));
+        assert!(advice
+            .openings()
+            .iter()
+            .any(|opening| opening.src() == solution.moves()[0] && opening.cost() == Some(cost)));
+        assert!(advice
+            .openings()
+            .windows(2)
+            .all(|pair| pair[0].cost().is_some() || pair[1].cost().is_none()));
+        assert_eq!(advice.block_order()[0].1, Some(cost));
+        assert!(advice.summary().contains(&cost.to_string()));
+    }
+
+    #[test]
+    fn test_solvability_census() {
+        let config = GeneratorConfig::new()
+            .with_col_count(3)
+            .with_row_count(3)
+            .with_block_kind_count(2)
+            .with_wall_height(3)
+            .with_move_remain(10);
+
+        let report = solvability_census(&config, 10, 100_000, 0);
+        assert_eq!(report.sample_count(), 10);
+        assert_eq!(
+            report.solved_count()
+                + report.failure_count(CensusFailure::DeadBlocks)
+                + report.failure_count(CensusFailure::NoSolution)
+                + report.failure_count(CensusFailure::NodeLimit),
+            10
+        );
+        assert_eq!(report.mean_cost().is_some(), report.solved_count() > 0);
+
+        // 探索局面数の上限が小さすぎれば最適解は得られない。
+        let report = solvability_census(&config, 10, 1, 0);
+        assert_eq!(report.solved_count(), 0);
+    }
+}
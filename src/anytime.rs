@@ -0,0 +1,106 @@
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+
+use crate::config::{CancelToken, SearchOutcome, SolverConfig};
+use crate::cost::Cost;
+use crate::problem::Problem;
+use crate::solver::{solve_problem_with_improvements, Solution};
+
+/// 問題 `problem` をバックグラウンドのスレッドで探索し、暫定解が更新されるたびにそれを返すイテレータを返す。
+///
+/// イテレータは (解, 目的関数の値) を見つかった順に返し (値は単調減少)、探索が終わると終了する。
+/// 最初に返すのは貪欲法の解のことがあり (`SolverConfig::with_warm_start()`)、その次の解だけは同じ値のことがある。
+/// 最後に返した解が `finish()` の結果の解と同じになる。
+/// `config` に打ち切りトークンがなければ新しく作る。
+pub fn solve_problem_anytime(
+    problem: Problem,
+    last_stage: bool,
+    config: SolverConfig,
+) -> AnytimeSearch {
+    let cancel = config.cancel_token().cloned().unwrap_or_default();
+    let config = config.with_cancel_token(Some(cancel.clone()));
+
+    let (tx, rx) = mpsc::channel();
+    let handle = std::thread::spawn(move || {
+        solve_problem_with_improvements(&problem, last_stage, &config, |solution, cost| {
+            // 受信側が捨てられていても探索は続ける。
+            let _ = tx.send((solution.clone(), cost));
+        })
+    });
+
+    AnytimeSearch {
+        rx,
+        cancel,
+        handle: Some(handle),
+    }
+}
+
+/// `solve_problem_anytime()` が返す、暫定解のイテレータ。
+///
+/// 捨てると探索を打ち切る。
+#[derive(Debug)]
+pub struct AnytimeSearch {
+    rx: mpsc::Receiver<(Solution, Cost)>,
+    cancel: CancelToken,
+    handle: Option<JoinHandle<SearchOutcome>>,
+}
+
+impl AnytimeSearch {
+    /// 探索の打ち切りを要求する。イテレータはまだ受け取っていない解を返してから終了する。
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// 探索の終了を待ち、その結果を返す。
+    pub fn finish(mut self) -> SearchOutcome {
+        self.handle.take().unwrap().join().unwrap()
+    }
+}
+
+impl Iterator for AnytimeSearch {
+    type Item = (Solution, Cost);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rx.recv().ok()
+    }
+}
+
+impl Drop for AnytimeSearch {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.cancel.cancel();
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::solver::solve_problem;
+
+    use super::*;
+
+    #[test]
+    fn test_solve_problem_anytime() {
+        let problem: Problem = include_str!("../problem/01.in").parse().unwrap();
+        let (_, cost_opt) = solve_problem(&problem, false).unwrap();
+
+        for threads in [1, 2] {
+            let config = SolverConfig::new().with_threads(threads);
+            let mut search = solve_problem_anytime(problem.clone(), false, config);
+            let improvements: Vec<_> = search.by_ref().collect();
+            let outcome = search.finish();
+
+            assert!(outcome.is_complete());
+            // 貪欲法の解の次の解だけは同じ値のことがある。
+            assert!(improvements.windows(2).all(|w| w[0].1 >= w[1].1));
+            assert!(improvements.windows(2).skip(1).all(|w| w[0].1 > w[1].1));
+            assert_eq!(improvements.last(), outcome.best());
+            assert_eq!(outcome.best().unwrap().1, cost_opt);
+            let (solution, cost) = improvements.last().unwrap();
+            assert_eq!(solution.verify(&problem, false), Ok(*cost));
+        }
+    }
+}
@@ -0,0 +1,379 @@
+use std::io::{BufRead, Write};
+use std::sync::{Condvar, Mutex};
+
+use log::info;
+
+use crate::cost::Cost;
+use crate::error::{token_col, Error, ParseError, ParseErrorKind};
+use crate::move_::MoveSrc;
+use crate::position::Position;
+use crate::problem::Problem;
+use crate::solver::solve_position_with_node_limit;
+use crate::stage::{Stage, STAGE_COUNT};
+use crate::tt::TranspositionTable;
+
+/// 最初の探索の局面数の上限。以降は探索を完了するか `node_limit` に達するまで倍々に増やす。
+const NODE_LIMIT_START: u64 = 10_000;
+
+/// 探索で使い回す置換表のメモリ使用量の上限 (バイト)。
+const TT_MEMORY_LIMIT: usize = 256 << 20;
+
+/// エミュレータから送られてくるプレイ中の状態 (面番号と現在の局面)。
+///
+/// 文字列表現は 1 行で、空白区切りの「面 (1..=50) 自機の位置 保持ブロック 残り手数 ブロック領域」。
+/// ブロック領域は `Position` の文字列表現の 6 行を `/` で連結したもの。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LiveState {
+    stage: Stage,
+    position: Position,
+}
+
+impl LiveState {
+    pub fn new(stage: Stage, position: Position) -> Self {
+        Self { stage, position }
+    }
+
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+}
+
+impl core::str::FromStr for LiveState {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens: Vec<_> = s.split_ascii_whitespace().collect();
+        if tokens.len() != 5 {
+            return Err(ParseError::new(
+                1,
+                ParseErrorKind::TokenCount {
+                    expected: 5,
+                    actual: tokens.len(),
+                },
+            ));
+        }
+
+        let stage: Stage = tokens[0].parse().map_err(|_| {
+            ParseError::new(1, ParseErrorKind::NotNumber(tokens[0].to_owned()))
+                .with_col(token_col(s, tokens[0]))
+        })?;
+
+        let position = format!(
+            "{} {} {}\n{}\n",
+            tokens[1],
+            tokens[2],
+            tokens[3],
+            tokens[4].replace('/', "\n")
+        );
+        let position: Position = position.parse()?;
+
+        Ok(Self { stage, position })
+    }
+}
+
+impl core::fmt::Display for LiveState {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let position = self.position.to_string();
+        let mut lines = position.lines();
+        write!(
+            f,
+            "{} {} {}",
+            self.stage,
+            lines.next().unwrap(),
+            lines.collect::<Vec<_>>().join("/")
+        )
+    }
+}
+
+/// 現在の局面での最善の次の手と、その手以降面が終わるまでのコスト。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct LiveAdvice {
+    src: MoveSrc,
+    cost: Cost,
+    /// 探索を完了したかどうか (真なら最善手であることが保証される)。
+    exact: bool,
+}
+
+impl core::fmt::Display for LiveAdvice {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "best {} {} {}",
+            self.src.to_inner(),
+            self.cost,
+            u8::from(self.exact)
+        )
+    }
+}
+
+/// 練習用のリアルタイムアシスタント。エミュレータ側のスクリプトと 1 行単位のテキストで通信する。
+///
+/// `input` から `LiveState` の文字列表現を 1 行ずつ読み、面 `stage` の問題 `problems[stage - 1]` で
+/// その局面から面の残りをバックグラウンドで探索する。探索は局面数の上限を倍々に増やしながら繰り返し、
+/// 最善の次の手が変わるたびに `output` に 1 行書く。新しい状態が届いたら古い探索の結果は捨てる。
+/// 置換表は同じ面の間は使い回すので、上限を増やした探索や次の局面の探索は前回までに調べた部分木を枝刈りできる。
+/// 第 50 面は最終面として扱う。
+///
+/// 出力の形式:
+///
+/// * `best 行 コスト 厳密フラグ`: 最善の次の手 (厳密フラグは探索を完了したら 1)。
+/// * `none`: 解がない、または面が終了している。
+/// * `error メッセージ`: 状態を解釈できない。
+///
+/// `input` が EOF に達したら、最後の状態の探索を終えてから終了する。
+pub fn serve_assistant(
+    problems: &[Problem],
+    node_limit: u64,
+    input: impl BufRead,
+    output: impl Write + Send,
+) -> Result<(), Error> {
+    let worker = Worker {
+        problems,
+        node_limit,
+        pending: Mutex::new(Pending {
+            generation: 0,
+            state: None,
+            eof: false,
+        }),
+        pending_cond: Condvar::new(),
+        output: Mutex::new(output),
+    };
+
+    std::thread::scope(|scope| {
+        let handle = scope.spawn(|| worker.run());
+
+        let res = worker.read_input(input);
+        let mut pending = worker.pending.lock().unwrap();
+        pending.eof = true;
+        worker.pending_cond.notify_all();
+        drop(pending);
+
+        handle.join().unwrap().and(res)
+    })
+}
+
+#[derive(Debug)]
+struct Pending {
+    /// 状態が届くたびに増える。探索中に変わったら、その探索の結果は古い。
+    generation: u64,
+    state: Option<LiveState>,
+    eof: bool,
+}
+
+#[derive(Debug)]
+struct Worker<'a, W> {
+    problems: &'a [Problem],
+    node_limit: u64,
+    pending: Mutex<Pending>,
+    pending_cond: Condvar,
+    output: Mutex<W>,
+}
+
+impl<W: Write> Worker<'_, W> {
+    fn read_input(&self, input: impl BufRead) -> Result<(), Error> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let state = match line.parse::<LiveState>() {
+                Ok(state) if (1..=STAGE_COUNT).contains(&state.stage) => state,
+                Ok(state) => {
+                    self.write_line(&format!("error invalid stage: {}", state.stage))?;
+                    continue;
+                }
+                Err(e) => {
+                    self.write_line(&format!("error {e}"))?;
+                    continue;
+                }
+            };
+
+            let mut pending = self.pending.lock().unwrap();
+            pending.generation += 1;
+            pending.state = Some(state);
+            self.pending_cond.notify_all();
+        }
+
+        Ok(())
+    }
+
+    fn run(&self) -> Result<(), Error> {
+        // 直前に探索した面の置換表。
+        let mut tt = None::<TranspositionTable>;
+
+        loop {
+            let (generation, state) = {
+                let mut pending = self.pending.lock().unwrap();
+                loop {
+                    if let Some(state) = pending.state.take() {
+                        break (pending.generation, state);
+                    }
+                    if pending.eof {
+                        return Ok(());
+                    }
+                    pending = self.pending_cond.wait(pending).unwrap();
+                }
+            };
+
+            self.search(generation, &state, &mut tt)?;
+        }
+    }
+
+    /// 局面数の上限を増やしながら探索し、最善の次の手が変わるたびに出力する。
+    ///
+    /// 上限を増やした探索は、置換表 `tt` と前回の最善の解を引き継ぐ。`tt` が別の面のものなら作り直す。
+    fn search(
+        &self,
+        generation: u64,
+        state: &LiveState,
+        tt: &mut Option<TranspositionTable>,
+    ) -> Result<(), Error> {
+        let Some(problem) = self.problems.get(usize::from(state.stage) - 1) else {
+            return self.write_line(&format!("error no problem for stage {}", state.stage));
+        };
+        let last_stage = state.stage == STAGE_COUNT;
+        let (_, moves) = problem.to_position_and_moves();
+        let pos = state.position.clone().with_ruleset(problem.ruleset());
+        if !tt
+            .as_ref()
+            .is_some_and(|tt| tt.matches(problem, last_stage))
+        {
+            *tt = Some(
+                TranspositionTable::new(problem, last_stage)
+                    .with_memory_limit(Some(TT_MEMORY_LIMIT)),
+            );
+        }
+        let tt = tt.as_mut().unwrap();
+
+        info!("assistant search start: {state}");
+        let mut best = None;
+        let mut advice_last = None;
+        let mut node_limit = NODE_LIMIT_START.min(self.node_limit);
+        loop {
+            let (res, complete) = solve_position_with_node_limit(
+                &moves,
+                pos.clone(),
+                last_stage,
+                node_limit,
+                best.take(),
+                Some(tt),
+            );
+            best = res;
+            let advice = best.as_ref().and_then(|(line, cost)| {
+                line.first().map(|mv| LiveAdvice {
+                    src: mv.src(),
+                    cost: *cost,
+                    exact: complete,
+                })
+            });
+
+            // 結果を書く前に、新しい状態が届いていないか確かめる。
+            let pending = self.pending.lock().unwrap();
+            if pending.generation != generation {
+                return Ok(());
+            }
+            if advice != advice_last || (complete && advice.is_none()) {
+                match advice {
+                    Some(advice) => self.write_line(&advice.to_string())?,
+                    None if complete => self.write_line("none")?,
+                    None => {}
+                }
+                advice_last = advice;
+            }
+            drop(pending);
+
+            if complete || node_limit >= self.node_limit {
+                info!("assistant search end (complete: {complete})");
+                return Ok(());
+            }
+            node_limit = node_limit.saturating_mul(2).min(self.node_limit);
+        }
+    }
+
+    fn write_line(&self, line: &str) -> Result<(), Error> {
+        let mut output = self.output.lock().unwrap();
+        writeln!(output, "{line}")?;
+        output.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use crate::config::SolverConfig;
+    use crate::solver::{solve_continuation, solve_problem, Solution};
+
+    use super::*;
+
+    #[test]
+    fn test_live_state_io() {
+        let position: Position = indoc! {"
+            11 3 5
+            ......
+            ......
+            222222
+            333333
+            344444
+            311111
+        "}
+        .parse()
+        .unwrap();
+        let state = LiveState::new(12, position);
+        let s = "12 11 3 5 ....../....../222222/333333/344444/311111";
+        assert_eq!(state.to_string(), s);
+        assert_eq!(s.parse::<LiveState>(), Ok(state));
+
+        assert!("12 11 3 5".parse::<LiveState>().is_err());
+    }
+
+    #[test]
+    fn test_serve_assistant() {
+        let problem: Problem = include_str!("../problem/01.in").parse().unwrap();
+        let (solution, cost) = solve_problem(&problem, false).unwrap();
+        let (pos, _) = problem.to_position_and_moves();
+
+        let input = format!("{}\nxxx\n", LiveState::new(1, pos));
+        let mut output = Vec::<u8>::new();
+        serve_assistant(
+            core::slice::from_ref(&problem),
+            u64::MAX,
+            input.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+
+        assert!(lines.iter().any(|line| line.starts_with("error ")));
+        let best: Vec<_> = lines
+            .iter()
+            .rev()
+            .find(|line| line.starts_with("best "))
+            .unwrap()
+            .split(' ')
+            .collect();
+        assert_eq!(best[0], "best");
+        assert_eq!(best[2], cost.to_string());
+        assert_eq!(best[3], "1");
+        // 最善手は複数ありうるので、その手から始まる解のコストが最適であることを確かめる。
+        let src = MoveSrc::from_inner(best[1].parse().unwrap()).unwrap();
+        if src != solution.moves()[0] {
+            let cont = solve_continuation(
+                &problem,
+                &Solution::new(vec![src]),
+                false,
+                &SolverConfig::new(),
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(cont.cost_total(), cost);
+        }
+    }
+}
@@ -1,9 +1,14 @@
-use std::fmt::Write as _;
+use alloc::vec::Vec;
+use core::fmt::Write as _;
 
-use anyhow::{anyhow, ensure};
+use crate::error::{ensure_line_count, ParseError, ParseErrorKind};
+use crate::events::MoveEvent;
+use crate::move_::MoveDst;
+use crate::ruleset::Ruleset;
 
+/// ブロック。順序は値 (`to_inner()`) の順。
 #[repr(u8)]
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Block {
     Normal1 = 1,
     Normal2,
@@ -26,7 +31,7 @@ impl Block {
     pub unsafe fn from_inner_unchecked(inner: u8) -> Self {
         assert!(Self::is_valid(inner));
 
-        std::mem::transmute(inner)
+        core::mem::transmute(inner)
     }
 
     pub fn to_inner(self) -> u8 {
@@ -55,7 +60,7 @@ impl Block {
 
 /// 盤面左下 6x6 のブロック領域の列。
 #[repr(u8)]
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum BlocksCol {
     ColA = 1,
     ColB,
@@ -88,7 +93,7 @@ impl BlocksCol {
     pub unsafe fn from_inner_unchecked(inner: u8) -> Self {
         assert!(Self::is_valid(inner));
 
-        std::mem::transmute(inner)
+        core::mem::transmute(inner)
     }
 
     pub const fn to_inner(self) -> u8 {
@@ -116,7 +121,7 @@ impl BlocksCol {
 }
 
 #[repr(u8)]
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum BlocksRow {
     Row1 = 1,
     Row2,
@@ -149,7 +154,7 @@ impl BlocksRow {
     pub unsafe fn from_inner_unchecked(inner: u8) -> Self {
         assert!(Self::is_valid(inner));
 
-        std::mem::transmute(inner)
+        core::mem::transmute(inner)
     }
 
     pub const fn to_inner(self) -> u8 {
@@ -176,8 +181,9 @@ impl BlocksRow {
     }
 }
 
+/// ブロック領域のマス。順序は値 (`to_inner()`) の順、すなわち上の行が先で、同じ行では左が先。
 #[repr(u8)]
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum BlocksSquare {
     SqA1 = 1,
     SqB1,
@@ -233,7 +239,7 @@ impl BlocksSquare {
     pub unsafe fn from_inner_unchecked(inner: u8) -> Self {
         assert!(Self::is_valid(inner));
 
-        std::mem::transmute(inner)
+        core::mem::transmute(inner)
     }
 
     pub fn new(col: BlocksCol, row: BlocksRow) -> Self {
@@ -262,6 +268,37 @@ impl BlocksSquare {
         BlocksRow::from_inner(row).unwrap()
     }
 
+    /// 全てのマスを上の行から順に (各行は左から順に) 返す。
+    pub fn all() -> [Self; Self::NUM] {
+        core::array::from_fn(|i| Self::from_inner(i as u8 + 1).unwrap())
+    }
+
+    /// 列を `dc`、行を `dr` だけずらしたマスを返す (右、下が正)。範囲外なら `None` を返す。
+    pub fn offset(self, dc: i8, dr: i8) -> Option<Self> {
+        let col = self.col().to_inner().checked_add_signed(dc)?;
+        let row = self.row().to_inner().checked_add_signed(dr)?;
+        let col = BlocksCol::from_inner(col)?;
+        let row = BlocksRow::from_inner(row)?;
+
+        Some(Self::new(col, row))
+    }
+
+    pub fn up(self) -> Option<Self> {
+        self.offset(0, -1)
+    }
+
+    pub fn down(self) -> Option<Self> {
+        self.offset(0, 1)
+    }
+
+    pub fn left(self) -> Option<Self> {
+        self.offset(-1, 0)
+    }
+
+    pub fn right(self) -> Option<Self> {
+        self.offset(1, 0)
+    }
+
     pub fn is_valid(inner: u8) -> bool {
         matches!(inner, Self::MIN_VALUE..=Self::MAX_VALUE)
     }
@@ -281,12 +318,14 @@ impl BlocksSquare {
 /// 6 #......
 ///   #######
 /// ```
-#[derive(Clone, Debug, Eq, PartialEq)]
+///
+/// 順序は各マスを上の行から順に (各行は左から順に) 並べたものの辞書式順序 (空白が最小)。
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Blocks([Option<Block>; 7 * 7]);
 
 impl Default for Blocks {
     fn default() -> Self {
-        Self(std::array::from_fn(|_| None))
+        Self(core::array::from_fn(|_| None))
     }
 }
 
@@ -309,16 +348,34 @@ impl Blocks {
         self.0.iter().copied().filter(Option::is_some).count()
     }
 
+    /// 列と行の値 (`BlocksCol`, `BlocksRow` の内部値。いずれも 1..=6) で指定したマスを返す。
+    /// 範囲外なら `None` を返す。
+    pub fn get(&self, col: u8, row: u8) -> Option<&Option<Block>> {
+        let col = BlocksCol::from_inner(col)?;
+        let row = BlocksRow::from_inner(row)?;
+
+        Some(&self[(col, row)])
+    }
+
+    /// `get` の可変版。
+    pub fn get_mut(&mut self, col: u8, row: u8) -> Option<&mut Option<Block>> {
+        let col = BlocksCol::from_inner(col)?;
+        let row = BlocksRow::from_inner(row)?;
+
+        Some(&mut self[(col, row)])
+    }
+
     /// ブロックを横方向に投げ込み、(結果, 次の保持ブロック, 置換前に最後にブロックが通った位置) を返す。
     /// 着手が無効(盤面が変化しない)なら `None` を返す。
     pub fn do_move_hori(
         &self,
         row: BlocksRow,
         block_move: Block,
+        ruleset: Ruleset,
     ) -> Option<(Self, Block, BlocksSquare)> {
         let start = Self::cr2idx(BLOCKS_COL_F, row);
 
-        self.do_move_impl(start, Self::DIR_L, block_move)
+        self.do_move_impl(start, Self::DIR_L, block_move, ruleset)
     }
 
     /// ブロックを縦方向に投げ込み、(結果, 次の保持ブロック, 置換前に最後にブロックが通った位置) を返す。
@@ -327,10 +384,11 @@ impl Blocks {
         &self,
         col: BlocksCol,
         block_move: Block,
+        ruleset: Ruleset,
     ) -> Option<(Self, Block, BlocksSquare)> {
         let start = Self::cr2idx(col, BLOCKS_ROW_1);
 
-        self.do_move_impl(start, Self::DIR_D, block_move)
+        self.do_move_impl(start, Self::DIR_D, block_move, ruleset)
     }
 
     fn do_move_impl(
@@ -338,60 +396,73 @@ impl Blocks {
         start: usize,
         dir: isize,
         block_move: Block,
+        ruleset: Ruleset,
     ) -> Option<(Self, Block, BlocksSquare)> {
         let vert = dir == Self::DIR_D;
 
-        let mut idxs = Self::idx_iter(start, dir);
+        let (blocks_res, block_holding_nxt, idx_last) =
+            throw_block(self, Self::idx_iter(start, dir), vert, block_move, ruleset)?;
 
-        // 最初に当たるブロックとその位置を得る。当たらないなら着手は無効。
-        let (idx_first, block_first) = idxs
-            .by_ref()
-            .find_map(|idx| self.0[idx].map(|block| (idx, block)))?;
+        Some((blocks_res, block_holding_nxt, Self::idx2sq(idx_last)))
+    }
 
-        // 最初に当たったブロックが消せないなら着手は無効。
-        if !block_move.can_erase(block_first) {
-            return None;
+    /// 投げ込み先 `dst` に投げたブロックが最初に通るマスと進む向き。
+    fn dst_start(dst: MoveDst) -> (usize, isize) {
+        match dst {
+            MoveDst::Horizontal(row) => (Self::cr2idx(BLOCKS_COL_F, row), Self::DIR_L),
+            MoveDst::Vertical(col) => (Self::cr2idx(col, BLOCKS_ROW_1), Self::DIR_D),
         }
+    }
 
-        let mut blocks_res = self.clone();
-        let mut block_holding_nxt = block_first;
-        let mut idx_last = idx_first;
+    /// ブロック `block_move` を `dst` に投げ込んだときの盤面上のイベント (消去、置換、落下) を起きた順に返す。
+    /// 着手が無効なら `None` を返す。
+    pub(crate) fn throw_events(
+        &self,
+        dst: MoveDst,
+        block_move: Block,
+        ruleset: Ruleset,
+    ) -> Option<Vec<MoveEvent>> {
+        let (start, dir) = Self::dst_start(dst);
+        let vert = dir == Self::DIR_D;
 
-        macro_rules! erase {
-            ($idx:expr) => {{
-                if vert {
-                    blocks_res.0[$idx] = None;
-                } else {
-                    blocks_res.erase_shift($idx);
+        let mut events = Vec::<MoveEvent>::new();
+        throw_block_observed(
+            self,
+            Self::idx_iter(start, dir),
+            vert,
+            block_move,
+            ruleset,
+            |grid, event| match event {
+                ThrowEvent::Erase(idx) => {
+                    let sq = Self::idx2sq(idx);
+                    let block = grid.0[idx].unwrap();
+                    events.push(MoveEvent::BlockErased { sq, block });
+                    // 横方向の場合、消したブロックの上のブロックたちが 1 マスずつ落ちる。
+                    if !vert {
+                        let mut idx_to = idx;
+                        while let Some(idx_from) = idx_to.checked_add_signed(Self::DIR_U) {
+                            if let Some(block) = grid.0[idx_from] {
+                                events.push(MoveEvent::BlockFell {
+                                    from: Self::idx2sq(idx_from),
+                                    to: Self::idx2sq(idx_to),
+                                    block,
+                                });
+                            }
+                            idx_to = idx_from;
+                        }
+                    }
                 }
-            }};
-        }
-
-        // 最初に当たったブロックを消す。
-        erase!(idx_first);
-
-        // その後の移動の処理。
-        for idx in idxs {
-            if let Some(block) = self.0[idx] {
-                if block_first == block {
-                    // 当たったブロックが block_first と同種なら単に消す。
-                    erase!(idx);
-                } else {
-                    // block_first と違う種類のブロックに当たったら置換を行い、そこで止まる。
-                    // ADVANCE モードでは盤面にワイルドカードは現れないことに注意。
-                    blocks_res.0[idx] = Some(block_first);
-                    block_holding_nxt = block;
-                    break;
+                ThrowEvent::Replace(idx, new) => {
+                    events.push(MoveEvent::BlockReplaced {
+                        sq: Self::idx2sq(idx),
+                        old: grid.0[idx].unwrap(),
+                        new,
+                    });
                 }
-            } else {
-                // ブロックに当たらないなら素通り。
-            }
-            idx_last = idx;
-        }
-
-        let sq_last = Self::idx2sq(idx_last);
+            },
+        )?;
 
-        Some((blocks_res, block_holding_nxt, sq_last))
+        Some(events)
     }
 
     fn erase_shift(&mut self, mut idx: usize) {
@@ -414,7 +485,7 @@ impl Blocks {
             true, true,  true,  true,  true,  true,  true,
         ];
 
-        std::iter::successors(Some((start, dir)), |&(idx, dir)| {
+        core::iter::successors(Some((start, dir)), |&(idx, dir)| {
             let idx_nxt = idx.checked_add_signed(dir).unwrap();
 
             if SENTINELS[idx_nxt] {
@@ -435,7 +506,8 @@ impl Blocks {
         7 * row.to_index() + col.to_index() + 1
     }
 
-    fn sq2idx(sq: BlocksSquare) -> usize {
+    /// マス `sq` の内部インデックス。
+    pub(crate) fn sq2idx(sq: BlocksSquare) -> usize {
         Self::cr2idx(sq.col(), sq.row())
     }
 
@@ -460,19 +532,144 @@ impl Blocks {
         }
     }
 
-    fn char_to_block(ch: char) -> anyhow::Result<Option<Block>> {
+    fn char_to_block(ch: char) -> Result<Option<Block>, ParseErrorKind> {
         match ch {
             Self::CHAR_NONE => Ok(None),
             Self::CHAR_BLOCK_1 => Ok(Some(Block::Normal1)),
             Self::CHAR_BLOCK_2 => Ok(Some(Block::Normal2)),
             Self::CHAR_BLOCK_3 => Ok(Some(Block::Normal3)),
             Self::CHAR_BLOCK_4 => Ok(Some(Block::Normal4)),
-            _ => Err(anyhow!("無効な Blocks 内ブロック文字: '{ch}'")),
+            _ => Err(ParseErrorKind::InvalidChar(ch)),
+        }
+    }
+}
+
+/// ブロックを投げ込む処理を共有するための盤面の抽象。
+pub(crate) trait ThrowGrid: Clone {
+    type Index: Copy;
+
+    fn cell(&self, idx: Self::Index) -> Option<Block>;
+
+    fn set_cell(&mut self, idx: Self::Index, block: Option<Block>);
+
+    /// 指定したマスのブロックを消し、その上のブロックたちを 1 マスずつ落とす。
+    fn erase_and_drop(&mut self, idx: Self::Index);
+}
+
+impl ThrowGrid for Blocks {
+    type Index = usize;
+
+    fn cell(&self, idx: usize) -> Option<Block> {
+        self.0[idx]
+    }
+
+    fn set_cell(&mut self, idx: usize, block: Option<Block>) {
+        self.0[idx] = block;
+    }
+
+    fn erase_and_drop(&mut self, idx: usize) {
+        self.erase_shift(idx);
+    }
+}
+
+/// `throw_block_observed` が報告する盤面の変化。
+pub(crate) enum ThrowEvent<I> {
+    /// マスのブロックを消す (横方向なら上のブロックが落ちてくる)。
+    Erase(I),
+    /// マスのブロックを指定したブロックで置換する。
+    Replace(I, Block),
+}
+
+/// ブロックを経路 `path` に沿って投げ込み、(結果, 次の保持ブロック, 置換前に最後にブロックが通った位置) を返す。
+/// 着手が無効(盤面が変化しない)なら `None` を返す。
+///
+/// `vert` が true なら消したブロックの跡は空白になり、false なら上のブロックが落ちてくる。
+#[inline]
+pub(crate) fn throw_block<G: ThrowGrid>(
+    grid: &G,
+    path: impl IntoIterator<Item = G::Index>,
+    vert: bool,
+    block_move: Block,
+    ruleset: Ruleset,
+) -> Option<(G, Block, G::Index)> {
+    throw_block_observed(grid, path, vert, block_move, ruleset, |_, _| {})
+}
+
+/// `throw_block` と同じだが、盤面を変化させる直前に (変化前の盤面, 変化) を `on_event` に渡す。
+#[inline]
+pub(crate) fn throw_block_observed<G: ThrowGrid>(
+    grid: &G,
+    path: impl IntoIterator<Item = G::Index>,
+    vert: bool,
+    block_move: Block,
+    ruleset: Ruleset,
+    mut on_event: impl FnMut(&G, ThrowEvent<G::Index>),
+) -> Option<(G, Block, G::Index)> {
+    let mut idxs = path.into_iter();
+
+    // 最初に当たるブロックとその位置を得る。当たらないなら着手は無効。
+    let (idx_first, block_first) = idxs
+        .by_ref()
+        .find_map(|idx| grid.cell(idx).map(|block| (idx, block)))?;
+
+    // 最初に当たったブロックが消せない場合、入れ替え着手が許されていれば入れ替えを行う。
+    // 許されていなければ着手は無効。
+    if !block_move.can_erase(block_first) {
+        if !ruleset.allow_swap() {
+            return None;
         }
+
+        // 当たったブロックを投げたブロックで置換し、当たったブロックを保持する。
+        // 実際のタイミングは未調査なので、コストは当たった位置までブロックが通ったものとして近似する。
+        // 入れ替え着手を含む解のコストは近似になる (`Position::is_swap_move()`, `Solution::swap_count()`)。
+        let mut grid_res = grid.clone();
+        on_event(&grid_res, ThrowEvent::Replace(idx_first, block_move));
+        grid_res.set_cell(idx_first, Some(block_move));
+        return Some((grid_res, block_first, idx_first));
     }
+
+    let mut grid_res = grid.clone();
+    let mut block_holding_nxt = block_first;
+    let mut idx_last = idx_first;
+
+    macro_rules! erase {
+        ($idx:expr) => {{
+            on_event(&grid_res, ThrowEvent::Erase($idx));
+            if vert {
+                grid_res.set_cell($idx, None);
+            } else {
+                grid_res.erase_and_drop($idx);
+            }
+        }};
+    }
+
+    // 最初に当たったブロックを消す。
+    erase!(idx_first);
+
+    // その後の移動の処理。
+    for idx in idxs {
+        if let Some(block) = grid.cell(idx) {
+            if block_first == block {
+                // 当たったブロックが block_first と同種なら単に消す。
+                erase!(idx);
+            } else {
+                // block_first と違う種類のブロックに当たったら置換を行い、そこで止まる。
+                // ADVANCE モードでは盤面にワイルドカードは現れないことに注意。
+                on_event(&grid_res, ThrowEvent::Replace(idx, block_first));
+                grid_res.set_cell(idx, Some(block_first));
+                block_holding_nxt = block;
+                break;
+            }
+        } else {
+            // ブロックに当たらないなら素通り。
+        }
+        idx_last = idx;
+    }
+
+    Some((grid_res, block_holding_nxt, idx_last))
 }
 
-impl std::ops::Index<(BlocksCol, BlocksRow)> for Blocks {
+impl core::ops::Index<(BlocksCol, BlocksRow)> for Blocks {
     type Output = Option<Block>;
 
     fn index(&self, (col, row): (BlocksCol, BlocksRow)) -> &Self::Output {
@@ -480,13 +677,13 @@ impl std::ops::Index<(BlocksCol, BlocksRow)> for Blocks {
     }
 }
 
-impl std::ops::IndexMut<(BlocksCol, BlocksRow)> for Blocks {
+impl core::ops::IndexMut<(BlocksCol, BlocksRow)> for Blocks {
     fn index_mut(&mut self, (col, row): (BlocksCol, BlocksRow)) -> &mut Self::Output {
         &mut self.0[Self::cr2idx(col, row)]
     }
 }
 
-impl std::ops::Index<BlocksSquare> for Blocks {
+impl core::ops::Index<BlocksSquare> for Blocks {
     type Output = Option<Block>;
 
     fn index(&self, sq: BlocksSquare) -> &Self::Output {
@@ -494,33 +691,35 @@ impl std::ops::Index<BlocksSquare> for Blocks {
     }
 }
 
-impl std::ops::IndexMut<BlocksSquare> for Blocks {
+impl core::ops::IndexMut<BlocksSquare> for Blocks {
     fn index_mut(&mut self, sq: BlocksSquare) -> &mut Self::Output {
         &mut self.0[Self::sq2idx(sq)]
     }
 }
 
-impl std::str::FromStr for Blocks {
-    type Err = anyhow::Error;
+impl core::str::FromStr for Blocks {
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let lines: Vec<_> = s.lines().collect();
-        ensure!(
-            lines.len() == 6,
-            "Blocks 文字列はちょうど 6 行でなければならない"
-        );
+        ensure_line_count(&lines, 6)?;
 
         let mut this = Self::new();
 
-        for (row, line) in std::iter::zip(BlocksRow::all(), lines) {
+        for (row, line) in core::iter::zip(BlocksRow::all(), lines) {
+            let err = |kind| ParseError::new(row.to_index() + 1, kind);
+
             let chars: Vec<_> = line.chars().collect();
-            ensure!(
-                chars.len() == 6,
-                "Blocks 文字列の行はちょうど 6 文字でなければならない"
-            );
+            if chars.len() != 6 {
+                return Err(err(ParseErrorKind::LineLength {
+                    expected: 6,
+                    actual: chars.len(),
+                }));
+            }
 
-            for (col, ch) in std::iter::zip(BlocksCol::all(), chars) {
-                let block = Self::char_to_block(ch)?;
+            for (col, ch) in core::iter::zip(BlocksCol::all(), chars) {
+                let block = Self::char_to_block(ch)
+                    .map_err(|kind| err(kind).with_col(col.to_index() + 1))?;
                 this[(col, row)] = block;
             }
         }
@@ -529,8 +728,8 @@ impl std::str::FromStr for Blocks {
     }
 }
 
-impl std::fmt::Display for Blocks {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Blocks {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         for row in BlocksRow::all() {
             for col in BlocksCol::all() {
                 let block = self[(col, row)];
@@ -581,10 +780,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_blocks_square_neighbors() {
+        let all = BlocksSquare::all();
+        assert_eq!(all[0], BlocksSquare::SqA1);
+        assert_eq!(all[7], BlocksSquare::SqB2);
+        assert_eq!(all[35], BlocksSquare::SqF6);
+
+        let sq = BlocksSquare::SqB2;
+        assert_eq!(sq.up(), Some(BlocksSquare::SqB1));
+        assert_eq!(sq.down(), Some(BlocksSquare::SqB3));
+        assert_eq!(sq.left(), Some(BlocksSquare::SqA2));
+        assert_eq!(sq.right(), Some(BlocksSquare::SqC2));
+        assert_eq!(sq.offset(4, 4), Some(BlocksSquare::SqF6));
+        assert_eq!(sq.offset(5, 0), None);
+        assert_eq!(sq.offset(0, -2), None);
+
+        assert_eq!(BlocksSquare::SqA1.up(), None);
+        assert_eq!(BlocksSquare::SqA1.left(), None);
+        assert_eq!(BlocksSquare::SqF6.down(), None);
+        assert_eq!(BlocksSquare::SqF6.right(), None);
+    }
+
+    #[test]
+    fn test_blocks_get() {
+        let mut blocks = parse_blocks(indoc! {"
+            1.....
+            ......
+            ......
+            ......
+            ......
+            .....4
+        "});
+
+        assert_eq!(blocks.get(1, 1), Some(&Some(Block::Normal1)));
+        assert_eq!(blocks.get(6, 6), Some(&Some(Block::Normal4)));
+        assert_eq!(blocks.get(2, 1), Some(&None));
+        assert_eq!(blocks.get(0, 1), None);
+        assert_eq!(blocks.get(1, 7), None);
+
+        *blocks.get_mut(2, 1).unwrap() = Some(Block::Normal2);
+        assert_eq!(blocks[(BLOCKS_COL_B, BLOCKS_ROW_1)], Some(Block::Normal2));
+        assert!(blocks.get_mut(7, 1).is_none());
+    }
+
     #[test]
     fn test_blocks_do_move_hori() {
         assert_eq!(
-            Blocks::new().do_move_hori(BLOCKS_ROW_1, Block::Normal1),
+            Blocks::new().do_move_hori(BLOCKS_ROW_1, Block::Normal1, Ruleset::default()),
             None
         );
 
@@ -661,7 +904,7 @@ mod tests {
             let before = parse_blocks(before);
             let after = parse_blocks(after);
             assert_eq!(
-                before.do_move_hori(row, block).unwrap(),
+                before.do_move_hori(row, block, Ruleset::default()).unwrap(),
                 (after, block_res, sq_res)
             );
         }
@@ -670,7 +913,7 @@ mod tests {
     #[test]
     fn test_blocks_do_move_vert() {
         assert_eq!(
-            Blocks::new().do_move_vert(BLOCKS_COL_A, Block::Normal1),
+            Blocks::new().do_move_vert(BLOCKS_COL_A, Block::Normal1, Ruleset::default()),
             None
         );
 
@@ -725,9 +968,66 @@ mod tests {
             let before = parse_blocks(before);
             let after = parse_blocks(after);
             assert_eq!(
-                before.do_move_vert(col, block).unwrap(),
+                before.do_move_vert(col, block, Ruleset::default()).unwrap(),
                 (after, block_res, sq_res)
             );
         }
     }
+
+    #[test]
+    fn test_blocks_do_move_swap() {
+        let ruleset = Ruleset::new().with_allow_swap(true);
+
+        let before = parse_blocks(indoc! {"
+            ......
+            ......
+            ......
+            ......
+            ...122
+            ..3411
+        "});
+
+        // 入れ替え着手が許されていなければ無効。
+        assert_eq!(
+            before.do_move_hori(BLOCKS_ROW_5, Block::Normal1, Ruleset::default()),
+            None
+        );
+
+        assert_eq!(
+            before.do_move_hori(BLOCKS_ROW_5, Block::Normal1, ruleset),
+            Some((
+                parse_blocks(indoc! {"
+                    ......
+                    ......
+                    ......
+                    ......
+                    ...121
+                    ..3411
+                "}),
+                Block::Normal2,
+                BlocksSquare::SqF5,
+            ))
+        );
+        assert_eq!(
+            before.do_move_vert(BLOCKS_COL_C, Block::Normal4, ruleset),
+            Some((
+                parse_blocks(indoc! {"
+                    ......
+                    ......
+                    ......
+                    ......
+                    ...122
+                    ..4411
+                "}),
+                Block::Normal3,
+                BlocksSquare::SqC6,
+            ))
+        );
+
+        // 消せる場合は通常の着手となる。
+        assert_eq!(
+            before.do_move_hori(BLOCKS_ROW_6, Block::Normal1, ruleset),
+            before.do_move_hori(BLOCKS_ROW_6, Block::Normal1, Ruleset::default())
+        );
+    }
 }
@@ -0,0 +1,268 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write as _;
+
+use crate::block::{throw_block, Block, Blocks, BlocksCol, BlocksRow, ThrowGrid};
+use crate::error::{ParseError, ParseErrorKind};
+use crate::ruleset::Ruleset;
+
+/// 大きさを実行時に決めるブロック領域。
+///
+/// 投げ込みと消去の処理は `Blocks` と共有しており、6x6 以外の大きさの盤面 (他の Flipull の変種や実験用の大きな問題) で使う。
+/// 座標は列、行とも 0-based で、行は上から数える。ブロックを横に投げると左端で折り返して左端の列を下に進む。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlocksDyn {
+    width: usize,
+    height: usize,
+    cells: Vec<Option<Block>>,
+}
+
+impl BlocksDyn {
+    /// 空のブロック領域を作る。
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(width > 0 && height > 0);
+
+        Self {
+            width,
+            height,
+            cells: vec![None; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.cells.iter().filter(|cell| cell.is_some()).count()
+    }
+
+    /// 指定したマスを返す。範囲外なら `None` を返す。
+    pub fn get(&self, col: usize, row: usize) -> Option<&Option<Block>> {
+        self.in_bounds(col, row)
+            .then(|| &self.cells[self.cr2idx(col, row)])
+    }
+
+    /// `get` の可変版。
+    pub fn get_mut(&mut self, col: usize, row: usize) -> Option<&mut Option<Block>> {
+        self.in_bounds(col, row).then(|| {
+            let idx = self.cr2idx(col, row);
+            &mut self.cells[idx]
+        })
+    }
+
+    /// ブロックを行 `row` に横方向に投げ込み、(結果, 次の保持ブロック, 置換前に最後にブロックが通った位置) を返す。
+    /// 着手が無効(盤面が変化しない)なら `None` を返す。
+    pub fn do_move_hori(
+        &self,
+        row: usize,
+        block_move: Block,
+        ruleset: Ruleset,
+    ) -> Option<(Self, Block, (usize, usize))> {
+        assert!(row < self.height);
+
+        let path = (0..self.width)
+            .rev()
+            .map(move |col| (col, row))
+            .chain((row + 1..self.height).map(|row| (0, row)));
+
+        throw_block(self, path, false, block_move, ruleset)
+    }
+
+    /// ブロックを列 `col` に縦方向に投げ込み、(結果, 次の保持ブロック, 置換前に最後にブロックが通った位置) を返す。
+    /// 着手が無効(盤面が変化しない)なら `None` を返す。
+    pub fn do_move_vert(
+        &self,
+        col: usize,
+        block_move: Block,
+        ruleset: Ruleset,
+    ) -> Option<(Self, Block, (usize, usize))> {
+        assert!(col < self.width);
+
+        let path = (0..self.height).map(move |row| (col, row));
+
+        throw_block(self, path, true, block_move, ruleset)
+    }
+
+    fn in_bounds(&self, col: usize, row: usize) -> bool {
+        col < self.width && row < self.height
+    }
+
+    fn cr2idx(&self, col: usize, row: usize) -> usize {
+        self.width * row + col
+    }
+}
+
+impl ThrowGrid for BlocksDyn {
+    type Index = (usize, usize);
+
+    fn cell(&self, (col, row): (usize, usize)) -> Option<Block> {
+        self.cells[self.cr2idx(col, row)]
+    }
+
+    fn set_cell(&mut self, (col, row): (usize, usize), block: Option<Block>) {
+        let idx = self.cr2idx(col, row);
+        self.cells[idx] = block;
+    }
+
+    fn erase_and_drop(&mut self, (col, row): (usize, usize)) {
+        for row in (1..=row).rev() {
+            let idx = self.cr2idx(col, row);
+            self.cells[idx] = self.cells[idx - self.width];
+        }
+        let idx = self.cr2idx(col, 0);
+        self.cells[idx] = None;
+    }
+}
+
+impl From<&Blocks> for BlocksDyn {
+    fn from(blocks: &Blocks) -> Self {
+        let mut this = Self::new(BlocksCol::NUM, BlocksRow::NUM);
+
+        for (r, row) in BlocksRow::all().into_iter().enumerate() {
+            for (c, col) in BlocksCol::all().into_iter().enumerate() {
+                *this.get_mut(c, r).unwrap() = blocks[(col, row)];
+            }
+        }
+
+        this
+    }
+}
+
+impl core::str::FromStr for BlocksDyn {
+    type Err = ParseError;
+
+    /// `Blocks` と同じ文字で表した長方形の盤面をパースする。大きさは行数と最初の行の文字数から決める。
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<_> = s.lines().collect();
+        let height = lines.len();
+        let width = lines.first().map_or(0, |line| line.chars().count());
+        if height == 0 || width == 0 {
+            return Err(ParseError::new(
+                1,
+                ParseErrorKind::MissingLine { expected: 1 },
+            ));
+        }
+
+        let mut this = Self::new(width, height);
+
+        for (row, line) in lines.into_iter().enumerate() {
+            let err = |kind| ParseError::new(row + 1, kind);
+
+            let chars: Vec<_> = line.chars().collect();
+            if chars.len() != width {
+                return Err(err(ParseErrorKind::LineLength {
+                    expected: width,
+                    actual: chars.len(),
+                }));
+            }
+
+            for (col, ch) in chars.into_iter().enumerate() {
+                let block = match ch {
+                    '.' => None,
+                    '1'..='4' => Block::from_inner(ch as u8 - b'0'),
+                    _ => return Err(err(ParseErrorKind::InvalidChar(ch)).with_col(col + 1)),
+                };
+                *this.get_mut(col, row).unwrap() = block;
+            }
+        }
+
+        Ok(this)
+    }
+}
+
+impl core::fmt::Display for BlocksDyn {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let ch = match self.cells[self.cr2idx(col, row)] {
+                    None => '.',
+                    Some(block) => char::from(b'0' + block.to_inner()),
+                };
+                f.write_char(ch)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn parse_blocks_dyn(s: impl AsRef<str>) -> BlocksDyn {
+        s.as_ref().parse().unwrap()
+    }
+
+    #[test]
+    fn test_blocks_dyn_matches_blocks() {
+        let s = indoc! {"
+            ......
+            ......
+            2444..
+            1222..
+            3333..
+            4111..
+        "};
+        let blocks: Blocks = s.parse().unwrap();
+        let blocks_dyn = BlocksDyn::from(&blocks);
+        assert_eq!(blocks_dyn, parse_blocks_dyn(s));
+        assert_eq!(blocks_dyn.to_string(), s);
+
+        let ruleset = Ruleset::new();
+        for block in [Block::Normal1, Block::Normal3, Block::Wild] {
+            for (i, row) in BlocksRow::all().into_iter().enumerate() {
+                let expected = blocks
+                    .do_move_hori(row, block, ruleset)
+                    .map(|(res, holding, _)| (BlocksDyn::from(&res), holding));
+                let actual = blocks_dyn
+                    .do_move_hori(i, block, ruleset)
+                    .map(|(res, holding, _)| (res, holding));
+                assert_eq!(actual, expected);
+            }
+            for (i, col) in BlocksCol::all().into_iter().enumerate() {
+                let expected = blocks
+                    .do_move_vert(col, block, ruleset)
+                    .map(|(res, holding, _)| (BlocksDyn::from(&res), holding));
+                let actual = blocks_dyn
+                    .do_move_vert(i, block, ruleset)
+                    .map(|(res, holding, _)| (res, holding));
+                assert_eq!(actual, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_blocks_dyn_oversized() {
+        let blocks = parse_blocks_dyn(indoc! {"
+            ........
+            2.......
+            1111111.
+            3.......
+        "});
+
+        let (res, holding, sq_last) = blocks
+            .do_move_hori(2, Block::Normal1, Ruleset::new())
+            .unwrap();
+        assert_eq!(
+            res.to_string(),
+            indoc! {"
+                ........
+                ........
+                2.......
+                1.......
+            "}
+        );
+        assert_eq!(holding, Block::Normal3);
+        assert_eq!(sq_last, (0, 2));
+    }
+}
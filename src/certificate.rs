@@ -0,0 +1,418 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::Itertools as _;
+
+use crate::config::SolverConfig;
+use crate::cost::{calc_min_move_cost, Cost};
+use crate::error::{CertificateError, Error};
+use crate::move_::{Move, MoveSrc};
+use crate::objective::Objective;
+use crate::position::Position;
+use crate::problem::Problem;
+use crate::solver::{solve_problem, solve_problem_with, Solution};
+
+/// 最適性証明書。`solve_problem_certified()` が作り、`verify_optimality()` で検証できる。
+///
+/// 最善解とそのコストに加えて、合法な初手それぞれについて「その着手から始まる解のコストの下界」を持つ。
+/// 全ての初手の下界が最善解のコスト以上であることを独立に確かめれば、最善解が最適であることがわかる。
+/// 公開された TAS のルートを第三者が監査するのに使う。
+///
+/// テキスト形式は 1 行 1 項目で、空白区切りの「項目名 値」:
+///
+/// * `problem`: 問題の ROM バイト列の 16 進数
+/// * `last_stage`: 最終面フラグ (0 または 1)
+/// * `ruleset`: ルール設定 (`Ruleset::to_flags()` の 10 進数)
+/// * `best`: 最善解のコストと着手列 (解がなければ `-`)
+/// * `bound`: 初手とその下界 (その初手から解がなければ `-`)。合法な初手 1 つにつき 1 行
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OptimalityCertificate {
+    rom: Vec<u8>,
+    last_stage: bool,
+    ruleset_flags: u16,
+    best: Option<(Solution, Cost)>,
+    bounds: Vec<(MoveSrc, Option<Cost>)>,
+}
+
+impl OptimalityCertificate {
+    /// 問題 `problem` (最終面フラグ `last_stage`) のものかどうか。
+    pub fn matches(&self, problem: &Problem, last_stage: bool) -> bool {
+        self.rom == problem.to_rom_bytes()
+            && self.last_stage == last_stage
+            && self.ruleset_flags == problem.ruleset().to_flags()
+    }
+
+    /// 最善解とそのコスト。解がなければ `None`。
+    pub fn best(&self) -> Option<&(Solution, Cost)> {
+        self.best.as_ref()
+    }
+
+    /// 初手ごとの、その着手から始まる解のコストの下界 (解がなければ `None`)。
+    pub fn bounds(&self) -> &[(MoveSrc, Option<Cost>)] {
+        &self.bounds
+    }
+
+    /// ファイルから読み込む。
+    #[cfg(feature = "std")]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// ファイルに書き込む。
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        std::fs::write(path, alloc::string::ToString::to_string(self))?;
+        Ok(())
+    }
+}
+
+/// 問題を解き、最適性証明書を作る。
+///
+/// 合法な初手ごとに、その着手を先頭に固定して最適解を求め、そのコストを下界として記録する。
+/// 初手の数だけ探索するので、`solve_problem()` より数倍遅い。
+pub fn solve_problem_certified(problem: &Problem, last_stage: bool) -> OptimalityCertificate {
+    let (pos, moves) = problem.to_position_and_moves();
+
+    let mut best: Option<(Solution, Cost)> = None;
+    let mut bounds = vec![];
+    let roots = root_moves(&pos, &moves);
+    if roots.is_empty() {
+        // 初期局面で面が終了しているなら、空の着手列が唯一の候補。
+        best = solve_problem(problem, last_stage);
+    }
+    for mv in roots {
+        let config = SolverConfig::new().with_prefix(vec![mv.src()]);
+        let res = solve_problem_with(problem, last_stage, &config).into_best();
+        bounds.push((mv.src(), res.as_ref().map(|&(_, cost)| cost)));
+        if let Some((solution, cost)) = res {
+            if best.as_ref().map_or(true, |&(_, best)| cost < best) {
+                best = Some((solution, cost));
+            }
+        }
+    }
+
+    OptimalityCertificate {
+        rom: problem.to_rom_bytes().to_vec(),
+        last_stage,
+        ruleset_flags: problem.ruleset().to_flags(),
+        best,
+        bounds,
+    }
+}
+
+/// 最適性証明書 `cert` を問題 `problem` (最終面フラグ `last_stage`) に対して検証し、最適解のコストを返す (解がなければ `None`)。
+///
+/// 最善解を実際に検証した上で、初手ごとにその下界より安い解がないことを単純な深さ優先探索で確かめる。
+/// この探索は置換表や枝刈りの工夫を使わず、総コストの下界 (最小の投げ込みコスト × 残りの必要手数) でのみ枝刈りするので、
+/// ソルバー本体とは独立した検証になる。その分ソルバーより遅い。
+pub fn verify_optimality(
+    problem: &Problem,
+    last_stage: bool,
+    cert: &OptimalityCertificate,
+) -> Result<Option<Cost>, CertificateError> {
+    if !cert.matches(problem, last_stage) {
+        return Err(CertificateError::Mismatch);
+    }
+
+    let (pos, moves) = problem.to_position_and_moves();
+    let roots = root_moves(&pos, &moves);
+
+    // 下界は合法な初手をちょうど 1 回ずつ覆っていなければならない。
+    for (i, &(src, _)) in cert.bounds.iter().enumerate() {
+        let duplicated = cert.bounds[..i].iter().any(|&(prev, _)| prev == src);
+        if duplicated || roots.iter().all(|mv| mv.src() != src) {
+            return Err(CertificateError::UnexpectedMove { src });
+        }
+    }
+    if let Some(mv) = roots
+        .iter()
+        .find(|mv| cert.bounds.iter().all(|&(src, _)| src != mv.src()))
+    {
+        return Err(CertificateError::MissingMove { src: mv.src() });
+    }
+
+    let best = match &cert.best {
+        Some((solution, claimed)) => {
+            let actual = solution
+                .verify(problem, last_stage)
+                .map_err(CertificateError::Verify)?;
+            if actual != *claimed {
+                return Err(CertificateError::CostMismatch {
+                    claimed: *claimed,
+                    actual,
+                });
+            }
+            Some(actual)
+        }
+        None => None,
+    };
+
+    if roots.is_empty() {
+        // 初期局面で面が終了しているなら、空の着手列が解かどうかだけで決まる。
+        if best.is_none() && Solution::new(vec![]).verify(problem, last_stage).is_ok() {
+            return Err(CertificateError::SolutionMissed);
+        }
+        return Ok(best);
+    }
+
+    let auditor = Auditor {
+        moves: &moves,
+        last_stage,
+        min_throw_cost: moves
+            .iter()
+            .map(|mv| calc_min_move_cost(mv.src()))
+            .min()
+            .unwrap_or(0),
+    };
+    for &(src, bound) in &cert.bounds {
+        match (bound, best) {
+            (Some(bound), Some(best)) if bound < best => {
+                return Err(CertificateError::BoundBelowBest { src, bound, best });
+            }
+            (Some(_), None) => return Err(CertificateError::SolutionMissed),
+            _ => {}
+        }
+
+        let mv = roots.iter().copied().find(|mv| mv.src() == src).unwrap();
+        let (pos_nxt, cost, cost_throw) = pos.do_move(mv).unwrap();
+        let threshold = bound.unwrap_or(Cost::MAX);
+        if auditor.exists_below(&pos_nxt, cost, cost_throw, threshold) {
+            return Err(CertificateError::BoundViolated { src, bound });
+        }
+    }
+
+    Ok(best)
+}
+
+/// 局面 `pos` の合法な初手。面が終了していれば空。
+fn root_moves(pos: &Position, moves: &[Move]) -> Vec<Move> {
+    if pos.is_stuck(moves) || pos.move_remain() == 0 {
+        return vec![];
+    }
+
+    moves
+        .iter()
+        .copied()
+        .filter(|&mv| pos.do_move(mv).is_some())
+        .collect()
+}
+
+/// `verify_optimality()` 用の単純な探索。
+#[derive(Debug)]
+struct Auditor<'a> {
+    moves: &'a [Move],
+    last_stage: bool,
+    min_throw_cost: Cost,
+}
+
+impl Auditor<'_> {
+    /// 局面 `pos` (ここまでのコスト `cost`、最後の投げ込みのコスト `cost_last_throw`) から、
+    /// 総コストが `threshold` 未満の解があるかどうか。
+    fn exists_below(
+        &self,
+        pos: &Position,
+        cost: Cost,
+        cost_last_throw: Cost,
+        threshold: Cost,
+    ) -> bool {
+        if pos.is_stuck(self.moves) {
+            if !pos.is_cleared() {
+                return false;
+            }
+            let cost_total = if self.last_stage {
+                cost - cost_last_throw + 1
+            } else {
+                cost + Objective::Frames.clear_cost(pos.block_count())
+            };
+            return cost_total < threshold;
+        }
+        if pos.move_remain() == 0 {
+            return false;
+        }
+
+        let move_count = pos.min_moves_to_solve().max(1);
+        if move_count > usize::from(pos.move_remain()) {
+            return false;
+        }
+        let cost_lower = if self.last_stage {
+            cost + self.min_throw_cost * (move_count - 1) as Cost + 1
+        } else {
+            cost + self.min_throw_cost * move_count as Cost
+        };
+        if cost_lower >= threshold {
+            return false;
+        }
+
+        self.moves.iter().any(|&mv| {
+            pos.do_move(mv)
+                .is_some_and(|(pos_nxt, cost_mv, cost_throw)| {
+                    self.exists_below(&pos_nxt, cost + cost_mv, cost_throw, threshold)
+                })
+        })
+    }
+}
+
+fn parse_src(token: &str) -> Result<MoveSrc, Error> {
+    token
+        .parse()
+        .ok()
+        .and_then(MoveSrc::from_inner)
+        .ok_or(Error::InvalidCertificateFile)
+}
+
+impl core::str::FromStr for OptimalityCertificate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rom = None;
+        let mut last_stage = None;
+        let mut ruleset_flags = None;
+        let mut best = None;
+        let mut bounds = vec![];
+
+        let parse_cost = |token: &str| -> Result<Cost, Error> {
+            token.parse().map_err(|_| Error::InvalidCertificateFile)
+        };
+
+        for line in s.lines() {
+            let tokens: Vec<_> = line.split_ascii_whitespace().collect();
+            let Some((&name, values)) = tokens.split_first() else {
+                continue;
+            };
+            match (name, values) {
+                ("problem", [value]) => {
+                    if value.len() != 2 * Problem::ROM_BYTES_LEN {
+                        return Err(Error::InvalidCertificateFile);
+                    }
+                    let bytes = (0..value.len())
+                        .step_by(2)
+                        .map(|i| u8::from_str_radix(&value[i..i + 2], 16))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| Error::InvalidCertificateFile)?;
+                    rom = Some(bytes);
+                }
+                ("last_stage", [value]) => {
+                    last_stage = Some(match *value {
+                        "0" => false,
+                        "1" => true,
+                        _ => return Err(Error::InvalidCertificateFile),
+                    });
+                }
+                ("ruleset", [value]) => {
+                    ruleset_flags = Some(value.parse().map_err(|_| Error::InvalidCertificateFile)?);
+                }
+                ("best", ["-"]) => best = Some(None),
+                ("best", [cost, srcs @ ..]) => {
+                    let srcs = srcs
+                        .iter()
+                        .map(|token| parse_src(token))
+                        .collect::<Result<_, _>>()?;
+                    best = Some(Some((Solution::new(srcs), parse_cost(cost)?)));
+                }
+                ("bound", [src, "-"]) => bounds.push((parse_src(src)?, None)),
+                ("bound", [src, cost]) => bounds.push((parse_src(src)?, Some(parse_cost(cost)?))),
+                _ => return Err(Error::InvalidCertificateFile),
+            }
+        }
+
+        let missing = || Error::InvalidCertificateFile;
+        Ok(Self {
+            rom: rom.ok_or_else(missing)?,
+            last_stage: last_stage.ok_or_else(missing)?,
+            ruleset_flags: ruleset_flags.ok_or_else(missing)?,
+            best: best.ok_or_else(missing)?,
+            bounds,
+        })
+    }
+}
+
+impl core::fmt::Display for OptimalityCertificate {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(
+            f,
+            "problem {}",
+            self.rom.iter().map(|b| alloc::format!("{b:02x}")).join("")
+        )?;
+        writeln!(f, "last_stage {}", u8::from(self.last_stage))?;
+        writeln!(f, "ruleset {}", self.ruleset_flags)?;
+        match &self.best {
+            Some((solution, cost)) => {
+                let srcs: String = solution
+                    .moves()
+                    .iter()
+                    .map(|src| alloc::format!(" {}", src.to_inner()))
+                    .collect();
+                writeln!(f, "best {cost}{srcs}")?;
+            }
+            None => writeln!(f, "best -")?,
+        }
+        for &(src, bound) in &self.bounds {
+            match bound {
+                Some(bound) => writeln!(f, "bound {} {bound}", src.to_inner())?,
+                None => writeln!(f, "bound {} -", src.to_inner())?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_optimality() {
+        for (problem, last_stage) in [
+            (include_str!("../problem/01.in"), false),
+            (include_str!("../problem/02.in"), true),
+        ] {
+            let problem: Problem = problem.parse().unwrap();
+            let (_, cost_opt) = solve_problem(&problem, last_stage).unwrap();
+
+            let cert = solve_problem_certified(&problem, last_stage);
+            assert_eq!(cert.best().unwrap().1, cost_opt);
+            let cert: OptimalityCertificate = cert.to_string().parse().unwrap();
+            assert_eq!(
+                verify_optimality(&problem, last_stage, &cert),
+                Ok(Some(cost_opt))
+            );
+            assert_eq!(
+                verify_optimality(&problem, !last_stage, &cert),
+                Err(CertificateError::Mismatch)
+            );
+
+            // 最善解の初手の下界を水増しした証明書は、最善解自体が反例になるので通らない。
+            let mut forged = cert.clone();
+            let src = cert.best().unwrap().0.moves()[0];
+            let (_, bound) = forged.bounds.iter_mut().find(|(s, _)| *s == src).unwrap();
+            *bound = Some(cost_opt + 1);
+            assert_eq!(
+                verify_optimality(&problem, last_stage, &forged),
+                Err(CertificateError::BoundViolated {
+                    src,
+                    bound: Some(cost_opt + 1)
+                })
+            );
+
+            // 初手の欠けた証明書は通らない。
+            let mut forged = cert.clone();
+            let (src, _) = forged.bounds.pop().unwrap();
+            assert_eq!(
+                verify_optimality(&problem, last_stage, &forged),
+                Err(CertificateError::MissingMove { src })
+            );
+
+            // 最善解のコストを偽った証明書は通らない。
+            let mut forged = cert.clone();
+            forged.best.as_mut().unwrap().1 -= 1;
+            assert!(matches!(
+                verify_optimality(&problem, last_stage, &forged),
+                Err(CertificateError::CostMismatch { .. })
+            ));
+        }
+    }
+}
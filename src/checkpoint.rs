@@ -0,0 +1,342 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use itertools::Itertools as _;
+
+use crate::cost::Cost;
+use crate::error::Error;
+use crate::move_::MoveSrc;
+use crate::objective::Objective;
+use crate::problem::Problem;
+use crate::solver::Solution;
+
+/// 中断した探索を後で再開するためのスナップショット。`solve_problem_resumable()` が作る。
+///
+/// 深さ優先探索の途中の局面への経路と、その時点の暫定解を持つ。経路より前の部分木は探索済みなので、
+/// 再開時は経路に沿って降りて残りだけを探索する。探索を完了していれば経路はなく、再開すると直ちに暫定解を返す。
+///
+/// テキスト形式は 1 行 1 項目で、空白区切りの「項目名 値」:
+///
+/// * `problem`: 問題の ROM バイト列の 16 進数
+/// * `last_stage`: 最終面フラグ (0 または 1)
+/// * `ruleset`: ルール設定 (`Ruleset::to_flags()` の 10 進数)
+/// * `objective`: 目的関数 (`frames`, `throw-only`, `move-count`)
+/// * `nodes`: それまでに探索した局面数
+/// * `best`: 暫定解の目的関数の値と着手列 (なければ `-`)
+/// * `path`: 探索中の局面への着手列 (探索を完了していれば `-`)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SearchCheckpoint {
+    rom: Vec<u8>,
+    last_stage: bool,
+    ruleset_flags: u16,
+    objective: Objective,
+    node_count: u64,
+    best: Option<(Solution, Cost)>,
+    path: Option<Vec<MoveSrc>>,
+}
+
+impl SearchCheckpoint {
+    pub(crate) fn new(problem: &Problem, last_stage: bool, objective: Objective) -> Self {
+        Self {
+            rom: problem.to_rom_bytes().to_vec(),
+            last_stage,
+            ruleset_flags: problem.ruleset().to_flags(),
+            objective,
+            node_count: 0,
+            best: None,
+            path: None,
+        }
+    }
+
+    /// 局面数 `node_count`、暫定解 `best`、探索中の局面への経路 `path` (完了していれば `None`) を記録したものを返す。
+    pub(crate) fn with_state(
+        &self,
+        node_count: u64,
+        best: Option<(Solution, Cost)>,
+        path: Option<Vec<MoveSrc>>,
+    ) -> Self {
+        Self {
+            node_count,
+            best,
+            path,
+            ..self.clone()
+        }
+    }
+
+    /// 問題 `problem` (最終面フラグ `last_stage`、目的関数 `objective`) の探索のものかどうか。
+    pub fn matches(&self, problem: &Problem, last_stage: bool, objective: Objective) -> bool {
+        self.rom == problem.to_rom_bytes()
+            && self.last_stage == last_stage
+            && self.ruleset_flags == problem.ruleset().to_flags()
+            && self.objective == objective
+    }
+
+    /// スナップショットまでに探索した局面数。
+    pub fn node_count(&self) -> u64 {
+        self.node_count
+    }
+
+    /// スナップショットの時点の暫定解とその目的関数の値。
+    pub fn best(&self) -> Option<&(Solution, Cost)> {
+        self.best.as_ref()
+    }
+
+    /// 探索中の局面への着手列。探索を完了していれば `None`。
+    pub fn path(&self) -> Option<&[MoveSrc]> {
+        self.path.as_deref()
+    }
+
+    /// 探索を完了したかどうか。
+    pub fn is_complete(&self) -> bool {
+        self.path.is_none()
+    }
+
+    /// ファイルから問題 `problem` (最終面フラグ `last_stage`、目的関数 `objective`) 用のスナップショットを読み込む。
+    /// ファイルが存在しなければ `None` を返す。別の問題用のスナップショットだった場合はエラーを返す。
+    #[cfg(feature = "std")]
+    pub fn load(
+        path: impl AsRef<std::path::Path>,
+        problem: &Problem,
+        last_stage: bool,
+        objective: Objective,
+    ) -> Result<Option<Self>, Error> {
+        match std::fs::read_to_string(path) {
+            Ok(s) => {
+                let this: Self = s.parse()?;
+                if !this.matches(problem, last_stage, objective) {
+                    return Err(Error::CheckpointMismatch);
+                }
+                Ok(Some(this))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// ファイルに書き込む。途中で中断してもファイルが壊れることはない (`atomic_write` 参照)。
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        use std::io::Write as _;
+
+        crate::fs::atomic_write(path, |w| {
+            w.write_all(alloc::string::ToString::to_string(self).as_bytes())
+        })?;
+
+        Ok(())
+    }
+}
+
+fn objective_name(objective: Objective) -> &'static str {
+    match objective {
+        Objective::Frames => "frames",
+        Objective::ThrowOnly => "throw-only",
+        Objective::MoveCount => "move-count",
+    }
+}
+
+fn parse_srcs(tokens: &[&str]) -> Result<Vec<MoveSrc>, Error> {
+    tokens
+        .iter()
+        .map(|token| {
+            token
+                .parse()
+                .ok()
+                .and_then(MoveSrc::from_inner)
+                .ok_or(Error::InvalidCheckpointFile)
+        })
+        .collect()
+}
+
+impl core::str::FromStr for SearchCheckpoint {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rom = None;
+        let mut last_stage = None;
+        let mut ruleset_flags = None;
+        let mut objective = None;
+        let mut node_count = None;
+        let mut best = None;
+        let mut path = None;
+
+        for line in s.lines() {
+            let tokens: Vec<_> = line.split_ascii_whitespace().collect();
+            let Some((&name, values)) = tokens.split_first() else {
+                continue;
+            };
+            let value = || match values {
+                [value] => Ok(*value),
+                _ => Err(Error::InvalidCheckpointFile),
+            };
+            match name {
+                "problem" => {
+                    let value = value()?;
+                    if value.len() != 2 * Problem::ROM_BYTES_LEN {
+                        return Err(Error::InvalidCheckpointFile);
+                    }
+                    let bytes = (0..value.len())
+                        .step_by(2)
+                        .map(|i| u8::from_str_radix(&value[i..i + 2], 16))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| Error::InvalidCheckpointFile)?;
+                    rom = Some(bytes);
+                }
+                "last_stage" => {
+                    last_stage = Some(match value()? {
+                        "0" => false,
+                        "1" => true,
+                        _ => return Err(Error::InvalidCheckpointFile),
+                    });
+                }
+                "ruleset" => {
+                    ruleset_flags =
+                        Some(value()?.parse().map_err(|_| Error::InvalidCheckpointFile)?);
+                }
+                "objective" => {
+                    let value = value()?;
+                    objective = Some(
+                        [
+                            Objective::Frames,
+                            Objective::ThrowOnly,
+                            Objective::MoveCount,
+                        ]
+                        .into_iter()
+                        .find(|&objective| objective_name(objective) == value)
+                        .ok_or(Error::InvalidCheckpointFile)?,
+                    );
+                }
+                "nodes" => {
+                    node_count = Some(value()?.parse().map_err(|_| Error::InvalidCheckpointFile)?);
+                }
+                "best" => {
+                    best = Some(match values {
+                        ["-"] => None,
+                        [cost, srcs @ ..] => {
+                            let cost = cost.parse().map_err(|_| Error::InvalidCheckpointFile)?;
+                            Some((Solution::new(parse_srcs(srcs)?), cost))
+                        }
+                        [] => return Err(Error::InvalidCheckpointFile),
+                    });
+                }
+                "path" => {
+                    path = Some(match values {
+                        ["-"] => None,
+                        srcs => Some(parse_srcs(srcs)?),
+                    });
+                }
+                _ => return Err(Error::InvalidCheckpointFile),
+            }
+        }
+
+        let missing = || Error::InvalidCheckpointFile;
+        Ok(Self {
+            rom: rom.ok_or_else(missing)?,
+            last_stage: last_stage.ok_or_else(missing)?,
+            ruleset_flags: ruleset_flags.ok_or_else(missing)?,
+            objective: objective.ok_or_else(missing)?,
+            node_count: node_count.ok_or_else(missing)?,
+            best: best.ok_or_else(missing)?,
+            path: path.ok_or_else(missing)?,
+        })
+    }
+}
+
+impl core::fmt::Display for SearchCheckpoint {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let srcs = |srcs: &[MoveSrc]| -> String {
+            srcs.iter()
+                .map(|src| alloc::format!(" {}", src.to_inner()))
+                .join("")
+        };
+
+        writeln!(
+            f,
+            "problem {}",
+            self.rom.iter().map(|b| alloc::format!("{b:02x}")).join("")
+        )?;
+        writeln!(f, "last_stage {}", u8::from(self.last_stage))?;
+        writeln!(f, "ruleset {}", self.ruleset_flags)?;
+        writeln!(f, "objective {}", objective_name(self.objective))?;
+        writeln!(f, "nodes {}", self.node_count)?;
+        match &self.best {
+            Some((solution, cost)) => writeln!(f, "best {cost}{}", srcs(solution.moves()))?,
+            None => writeln!(f, "best -")?,
+        }
+        match &self.path {
+            Some(path) => writeln!(f, "path{}", srcs(path))?,
+            None => writeln!(f, "path -")?,
+        }
+
+        Ok(())
+    }
+}
+
+/// `solve_problem_resumable()` の、スナップショットからの再開と定期的なスナップショットの設定。
+pub(crate) struct CheckpointHook<'a> {
+    pub(crate) base: SearchCheckpoint,
+    pub(crate) resume: Option<&'a SearchCheckpoint>,
+    pub(crate) interval: u64,
+    pub(crate) save: &'a mut (dyn FnMut(&SearchCheckpoint) + Send),
+}
+
+impl core::fmt::Debug for CheckpointHook<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("CheckpointHook")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::config::SolverConfig;
+    use crate::solver::{solve_problem, solve_problem_resumable};
+
+    use super::*;
+
+    #[test]
+    fn test_solve_problem_resumable() {
+        let problem: Problem = include_str!("../problem/10.in").parse().unwrap();
+        let (_, cost_opt) = solve_problem(&problem, false).unwrap();
+        let config = SolverConfig::new();
+
+        let mut snapshots = vec![];
+        let outcome = solve_problem_resumable(&problem, false, &config, None, 100, |snapshot| {
+            snapshots.push(snapshot.clone());
+        });
+        assert_eq!(outcome.best().unwrap().1, cost_opt);
+        assert!(snapshots.len() > 2);
+        assert!(snapshots.last().unwrap().is_complete());
+
+        // どのスナップショットから再開しても最適解が求まる。
+        for snapshot in &snapshots {
+            let snapshot: SearchCheckpoint = snapshot.to_string().parse().unwrap();
+            assert!(snapshot.matches(&problem, false, Objective::Frames));
+            assert!(!snapshot.matches(&problem, true, Objective::Frames));
+
+            let outcome =
+                solve_problem_resumable(&problem, false, &config, Some(&snapshot), 100, |_| {});
+            assert!(outcome.is_complete());
+            let (solution, cost) = outcome.best().unwrap();
+            assert_eq!(*cost, cost_opt);
+            assert_eq!(solution.verify(&problem, false), Ok(cost_opt));
+        }
+
+        // 局面数の上限で打ち切っても、最後のスナップショットから続きを探索できる。
+        let mut last = None;
+        let outcome = solve_problem_resumable(
+            &problem,
+            false,
+            &config.clone().with_node_limit(150),
+            None,
+            100,
+            |snapshot| last = Some(snapshot.clone()),
+        );
+        assert!(!outcome.is_complete());
+        let last = last.unwrap();
+        assert!(!last.is_complete());
+        let outcome = solve_problem_resumable(&problem, false, &config, Some(&last), 100, |_| {});
+        assert!(outcome.is_complete());
+        assert_eq!(outcome.best().unwrap().1, cost_opt);
+    }
+}
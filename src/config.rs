@@ -0,0 +1,646 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::constraint::Constraint;
+use crate::cost::Cost;
+use crate::move_::{Move, MoveSrc};
+use crate::objective::{Objective, TieBreak};
+#[cfg(feature = "std")]
+use crate::parallel::SharedTranspositionTable;
+use crate::pattern::PatternLibrary;
+use crate::position::Position;
+use crate::search_tree::SearchTree;
+use crate::solver::Solution;
+use crate::zobrist::splitmix64;
+
+/// `solve_problem_with()` の探索の設定。
+///
+/// デフォルトは `solve_problem()` と同じ (制限なし、下界による枝刈りあり、1 スレッド)。
+#[derive(Clone, Debug)]
+pub struct SolverConfig {
+    node_limit: u64,
+    #[cfg(feature = "std")]
+    time_limit: Option<core::time::Duration>,
+    max_depth: usize,
+    lower_bound: bool,
+    dominance: bool,
+    warm_start: bool,
+    memory_limit: Option<usize>,
+    #[cfg(feature = "std")]
+    threads: usize,
+    objective: Objective,
+    tie_break: Option<TieBreak>,
+    move_ordering: MoveOrdering,
+    prefix: Vec<MoveSrc>,
+    constraints: Vec<Constraint>,
+    cost_bound: Option<Cost>,
+    weight: f64,
+    patterns: Option<PatternLibrary>,
+    incumbent: Option<(Solution, Cost)>,
+    cancel: Option<CancelToken>,
+    #[cfg(feature = "std")]
+    shared_tt: Option<SharedTranspositionTable>,
+    search_tree_depth: Option<usize>,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self {
+            node_limit: u64::MAX,
+            #[cfg(feature = "std")]
+            time_limit: None,
+            max_depth: usize::MAX,
+            lower_bound: true,
+            dominance: true,
+            warm_start: true,
+            memory_limit: None,
+            #[cfg(feature = "std")]
+            threads: 1,
+            objective: Objective::Frames,
+            tie_break: None,
+            move_ordering: MoveOrdering::default(),
+            prefix: Vec::new(),
+            constraints: Vec::new(),
+            cost_bound: None,
+            weight: 1.0,
+            patterns: None,
+            incumbent: None,
+            cancel: None,
+            #[cfg(feature = "std")]
+            shared_tt: None,
+            search_tree_depth: None,
+        }
+    }
+}
+
+impl SolverConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 探索する局面数の上限。達したら探索を打ち切る。
+    pub fn node_limit(&self) -> u64 {
+        self.node_limit
+    }
+
+    pub fn with_node_limit(mut self, node_limit: u64) -> Self {
+        self.node_limit = node_limit;
+        self
+    }
+
+    /// 探索時間の上限。過ぎたら探索を打ち切る。
+    #[cfg(feature = "std")]
+    pub fn time_limit(&self) -> Option<core::time::Duration> {
+        self.time_limit
+    }
+
+    #[cfg(feature = "std")]
+    pub fn with_time_limit(mut self, time_limit: Option<core::time::Duration>) -> Self {
+        self.time_limit = time_limit;
+        self
+    }
+
+    /// 解の手数の上限。これより長い解は探さない。
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// 残りに必要な着手数の下界による枝刈りを行うかどうか。結果は変わらない (比較や検証用)。
+    pub fn lower_bound(&self) -> bool {
+        self.lower_bound
+    }
+
+    pub fn with_lower_bound(mut self, lower_bound: bool) -> Self {
+        self.lower_bound = lower_bound;
+        self
+    }
+
+    /// 既に訪れた局面に支配される局面 (同じ盤面でコストが大きく残り手数が少ないもの) の枝刈りを行うかどうか。
+    /// 結果は変わらない (比較や検証用)。並列探索と、入れ替え着手を許すルールでは使わない。
+    pub fn dominance(&self) -> bool {
+        self.dominance
+    }
+
+    pub fn with_dominance(mut self, dominance: bool) -> Self {
+        self.dominance = dominance;
+        self
+    }
+
+    /// 探索の前に貪欲法で解を求め、最初からその総コストで枝刈りを行うかどうか。結果は変わらない (比較や検証用)。
+    /// 第 2 の基準 (`tie_break`) がある場合や並列探索では使わない。
+    pub fn warm_start(&self) -> bool {
+        self.warm_start
+    }
+
+    pub fn with_warm_start(mut self, warm_start: bool) -> Self {
+        self.warm_start = warm_start;
+        self
+    }
+
+    /// メモリ使用量の上限 (バイト、概算)。指定すると置換表を使って探索し、上限に達したら最も長く参照されていない
+    /// エントリから捨てる (SMA* と同様)。支配による枝刈りの表もこの上限に収める。結果は変わらない。
+    /// `None` (デフォルト) なら置換表を使わない。
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    pub fn with_memory_limit(mut self, memory_limit: Option<usize>) -> Self {
+        self.memory_limit = memory_limit;
+        self
+    }
+
+    /// 探索に使うスレッド数 (1 以上)。2 以上なら `parallel` モジュールの並列探索を使う。
+    #[cfg(feature = "std")]
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    #[cfg(feature = "std")]
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0);
+        self.threads = threads;
+        self
+    }
+
+    /// 解の先頭に固定する着手列。探索はこれを行った後の局面からで、残りの部分だけを探す。
+    /// 不正な着手列 (着手できない行や、面が終了した後の着手を含む) なら解はない。
+    pub fn prefix(&self) -> &[MoveSrc] {
+        &self.prefix
+    }
+
+    pub fn with_prefix(mut self, prefix: Vec<MoveSrc>) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// 解に課す追加の制約 (`solve_problem_with_constraints()`)。制約を満たす解のうち目的関数の値が最小のものを探す。
+    /// 制約があると、支配による枝刈り、貪欲法、置換表、終盤の着手列のライブラリは使わない (経路に依存するため)。
+    /// 並列探索では使えない。
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    pub fn with_constraints(mut self, constraints: Vec<Constraint>) -> Self {
+        self.constraints = constraints;
+        self
+    }
+
+    /// 目的関数の値の上限。これより大きい解は探さない (`solve_problem_ida()` の閾値)。並列探索では使えない。
+    pub fn cost_bound(&self) -> Option<Cost> {
+        self.cost_bound
+    }
+
+    pub fn with_cost_bound(mut self, cost_bound: Option<Cost>) -> Self {
+        self.cost_bound = cost_bound;
+        self
+    }
+
+    /// 下界による枝刈りで下界に掛ける重み (1 以上、`solve_problem_weighted()`)。
+    /// 1 より大きければ、解は最適解の目的関数の値の `weight` 倍以内であることだけが保証される。並列探索では使えない。
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        assert!(weight >= 1.0);
+        self.weight = weight;
+        self
+    }
+
+    /// 終盤の着手列のライブラリ (`solve_problem_with_patterns()`)。登録された局面に達したら、登録された着手列で面を終える。
+    /// 目的関数が `Objective::Frames` でない場合、制約、第 2 の基準、手数の上限がある場合は使わない。
+    /// 並列探索では使えない。
+    pub fn patterns(&self) -> Option<&PatternLibrary> {
+        self.patterns.as_ref()
+    }
+
+    pub fn with_patterns(mut self, patterns: Option<PatternLibrary>) -> Self {
+        self.patterns = patterns;
+        self
+    }
+
+    /// 最小化する目的関数。
+    pub fn objective(&self) -> Objective {
+        self.objective
+    }
+
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    /// 目的関数の値が等しい解どうしを比べる第 2 の基準。並列探索では使えない。
+    pub fn tie_break(&self) -> Option<TieBreak> {
+        self.tie_break
+    }
+
+    pub fn with_tie_break(mut self, tie_break: Option<TieBreak>) -> Self {
+        self.tie_break = tie_break;
+        self
+    }
+
+    /// 各局面で着手を試す順序。
+    pub fn move_ordering(&self) -> MoveOrdering {
+        self.move_ordering
+    }
+
+    pub fn with_move_ordering(mut self, move_ordering: MoveOrdering) -> Self {
+        self.move_ordering = move_ordering;
+        self
+    }
+
+    /// 既知の解とその目的関数の値。指定すると最初からこれを暫定解とし、これより目的関数の値が小さい解だけを探す。
+    /// 見つからなければ (打ち切った場合も) これを返す。長い探索を中断した後に、それまでの最善の解を引き継ぐのに使う。
+    /// 解は問題の解でなければならない (検証しない)。第 2 の基準 (`tie_break`) がある場合は使わない。
+    pub fn incumbent(&self) -> Option<&(Solution, Cost)> {
+        self.incumbent.as_ref()
+    }
+
+    pub fn with_incumbent(mut self, incumbent: Option<(Solution, Cost)>) -> Self {
+        self.incumbent = incumbent;
+        self
+    }
+
+    /// 探索を外部から打ち切るためのトークン。
+    pub fn cancel_token(&self) -> Option<&CancelToken> {
+        self.cancel.as_ref()
+    }
+
+    pub fn with_cancel_token(mut self, cancel: Option<CancelToken>) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// 複数の探索で共有する置換表。指定すると、`memory_limit()` による探索ごとの置換表の代わりにこれを使い、
+    /// 同じハンドルを渡した他の探索 (他のスレッドのものを含む) と下界を共有する。
+    /// 第 2 の基準 (`tie_break`) や手数の上限がある場合は使わない (下界が経路に依存するため)。
+    #[cfg(feature = "std")]
+    pub fn shared_tt(&self) -> Option<&SharedTranspositionTable> {
+        self.shared_tt.as_ref()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn with_shared_tt(mut self, shared_tt: Option<SharedTranspositionTable>) -> Self {
+        self.shared_tt = shared_tt;
+        self
+    }
+
+    /// 探索した木を記録する手数の上限。指定すると、面の最初からこの手数までに訪れた局面を目的関数の値と
+    /// 枝刈りの理由付きで記録し、`SearchOutcome::search_tree()` で返す。`None` (デフォルト) なら記録しない。
+    /// 並列探索では使えない。
+    pub fn search_tree_depth(&self) -> Option<usize> {
+        self.search_tree_depth
+    }
+
+    pub fn with_search_tree_depth(mut self, search_tree_depth: Option<usize>) -> Self {
+        self.search_tree_depth = search_tree_depth;
+        self
+    }
+}
+
+/// 探索の各局面で着手を試す順序。結果の総コストは変わらないが、総コストの等しい解が複数あるときにどれを返すかは変わりうる。
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum MoveOrdering {
+    /// 着手位置の固定の順 (`Problem::to_position_and_moves()` の順)。
+    #[default]
+    Fixed,
+
+    /// 着手後のブロック数が少ない (多く消せる) 順、同数ならコストの小さい順。
+    /// よい解が早く見つかって枝刈りが効きやすくなる。
+    MostErased,
+
+    /// 履歴ヒューリスティック。同じ手数で暫定解の更新につながった回数の多い着手位置から順に試す
+    /// (チェスエンジンのキラー手と同様)。回数が等しければ `MostErased` の順。
+    /// 探索が進むにつれて、兄弟の部分木でもよい解につながった着手を先に試すようになる。
+    History,
+
+    /// シードから決まるランダムな順。同じシードなら同じ局面で同じ順になる。
+    /// ランダムリスタート (`restart::solve_problem_restarts()`) で探索ごとに異なる順を試すのに使う。
+    Random(u64),
+}
+
+impl MoveOrdering {
+    /// 手数 `depth` の局面の子局面の列 `nexts` (着手, 着手後の局面, 総所要コスト, ブロック投げコスト) を
+    /// この順序に並べ替える。`History` では `history` を使う。
+    pub(crate) fn sort(
+        self,
+        objective: Objective,
+        history: Option<&HistoryTable>,
+        depth: usize,
+        nexts: &mut [(Move, Position, Cost, Cost)],
+    ) {
+        match self {
+            Self::Fixed => {}
+            Self::MostErased => {
+                nexts.sort_by_key(|(_, pos_nxt, cost_mv, cost_throw)| {
+                    (
+                        pos_nxt.block_count(),
+                        objective.move_cost(*cost_mv, *cost_throw),
+                    )
+                });
+            }
+            Self::History => {
+                let history = history.unwrap();
+                nexts.sort_by_key(|(mv, pos_nxt, cost_mv, cost_throw)| {
+                    (
+                        core::cmp::Reverse(history.score(depth, mv.src())),
+                        pos_nxt.block_count(),
+                        objective.move_cost(*cost_mv, *cost_throw),
+                    )
+                });
+            }
+            Self::Random(seed) => {
+                // 子局面のハッシュ値とシードを混ぜた値を乱数として並べる。
+                nexts.sort_by_key(|(_, pos_nxt, ..)| {
+                    splitmix64(&mut (seed ^ pos_nxt.zobrist_hash()))
+                });
+            }
+        }
+    }
+}
+
+/// 履歴表に記録する手数の上限。
+const HISTORY_DEPTH: usize = 64;
+
+/// 履歴ヒューリスティックの表。手数と着手位置ごとに、暫定解の更新につながった回数を数える。
+/// 並列探索のスレッド間で共有できるよう、アトミック変数で持つ。
+#[derive(Debug)]
+pub(crate) struct HistoryTable(Vec<[AtomicU32; MoveSrc::NUM]>);
+
+impl HistoryTable {
+    pub(crate) fn new() -> Self {
+        Self(
+            (0..HISTORY_DEPTH)
+                .map(|_| core::array::from_fn(|_| AtomicU32::new(0)))
+                .collect(),
+        )
+    }
+
+    /// 暫定解 `srcs` の各着手を記録する。
+    pub(crate) fn record(&self, srcs: impl IntoIterator<Item = MoveSrc>) {
+        for (row, src) in self.0.iter().zip(srcs) {
+            row[usize::from(src.to_inner())].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn score(&self, depth: usize, src: MoveSrc) -> u32 {
+        self.0.get(depth).map_or(0, |row| {
+            row[usize::from(src.to_inner())].load(Ordering::Relaxed)
+        })
+    }
+}
+
+/// 探索を別のスレッドから打ち切るためのトークン。複製したトークンは状態を共有する。
+///
+/// ソルバーは一定の局面数ごとにトークンを調べ、打ち切られていればそれまでの最善の解を返す。
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 探索の打ち切りを要求する。
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// 設定付きの探索の結果。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SearchOutcome {
+    best: Option<(Solution, Cost)>,
+    complete: bool,
+    stats: SearchStats,
+    search_tree: Option<SearchTree>,
+    cost_cut_min: Cost,
+}
+
+impl SearchOutcome {
+    pub(crate) fn new(best: Option<(Solution, Cost)>, complete: bool) -> Self {
+        Self {
+            best,
+            complete,
+            stats: SearchStats::default(),
+            search_tree: None,
+            cost_cut_min: Cost::MAX,
+        }
+    }
+
+    pub(crate) fn with_stats(mut self, stats: SearchStats) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    pub(crate) fn with_search_tree(mut self, search_tree: Option<SearchTree>) -> Self {
+        self.search_tree = search_tree;
+        self
+    }
+
+    pub(crate) fn with_cost_cut_min(mut self, cost_cut_min: Cost) -> Self {
+        self.cost_cut_min = cost_cut_min;
+        self
+    }
+
+    /// 暫定解による枝刈りで切り捨てた部分木の目的関数の値の下界の最小値。切り捨てなければ `Cost::MAX`。
+    pub(crate) fn cost_cut_min(&self) -> Cost {
+        self.cost_cut_min
+    }
+
+    /// 見つかった最善の解とその目的関数の値。
+    pub fn best(&self) -> Option<&(Solution, Cost)> {
+        self.best.as_ref()
+    }
+
+    pub fn into_best(self) -> Option<(Solution, Cost)> {
+        self.best
+    }
+
+    /// 探索を完了したかどうか。真なら `best()` は (手数の上限の範囲で) 最適で、`None` なら解はない。
+    /// 局面数や時間の上限、またはトークンで打ち切った場合は偽。
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// 探索の統計。ヒューリスティックの変更の効果を比べるのに使う。
+    pub fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
+    /// 探索した木。`SolverConfig::with_search_tree_depth()` を指定した場合のみ記録する。
+    pub fn search_tree(&self) -> Option<&SearchTree> {
+        self.search_tree.as_ref()
+    }
+}
+
+/// 探索の統計 (展開した局面数、到達した最大の手数、種類ごとの枝刈り回数、置換表のヒット数、経過時間)。
+///
+/// 並列探索では全スレッドの合計 (最大の手数は最大値)。
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SearchStats {
+    pub(crate) node_count: u64,
+    pub(crate) max_depth: usize,
+    pub(crate) prune_cost: u64,
+    pub(crate) prune_lower_bound: u64,
+    pub(crate) prune_dominance: u64,
+    pub(crate) prune_tt: u64,
+    pub(crate) tt_hits: u64,
+    #[cfg(feature = "std")]
+    pub(crate) elapsed: core::time::Duration,
+}
+
+impl SearchStats {
+    /// 展開した局面数。
+    pub fn node_count(&self) -> u64 {
+        self.node_count
+    }
+
+    /// 到達した最大の手数。
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// 現局面で解けたとしても総コストが暫定解以上になるための枝刈りの回数。
+    pub fn prune_cost(&self) -> u64 {
+        self.prune_cost
+    }
+
+    /// 残りに必要な着手数の下界による枝刈りの回数 (手数が足りない場合を含む)。
+    pub fn prune_lower_bound(&self) -> u64 {
+        self.prune_lower_bound
+    }
+
+    /// 既に訪れた局面に支配されるための枝刈りの回数。
+    pub fn prune_dominance(&self) -> u64 {
+        self.prune_dominance
+    }
+
+    /// 置換表の下界による枝刈りの回数。
+    pub fn prune_tt(&self) -> u64 {
+        self.prune_tt
+    }
+
+    /// 置換表を引いて局面が見つかった回数。
+    pub fn tt_hits(&self) -> u64 {
+        self.tt_hits
+    }
+
+    /// 探索にかかった時間。
+    #[cfg(feature = "std")]
+    pub fn elapsed(&self) -> core::time::Duration {
+        self.elapsed
+    }
+
+    /// 別のスレッド (または別の探索) の統計を足し合わせる (経過時間は除く)。
+    #[cfg(feature = "std")]
+    pub(crate) fn merge(&mut self, other: &Self) {
+        self.node_count += other.node_count;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.prune_cost += other.prune_cost;
+        self.prune_lower_bound += other.prune_lower_bound;
+        self.prune_dominance += other.prune_dominance;
+        self.prune_tt += other.prune_tt;
+        self.tt_hits += other.tt_hits;
+    }
+}
+
+/// 進捗を報告する間隔 (探索局面数)。
+pub(crate) const PROGRESS_INTERVAL: u64 = 1 << 16;
+
+/// 探索の途中経過。`solve_problem_with_progress()` のコールバックに一定の局面数ごとと探索終了時に渡される。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SearchProgress {
+    node_count: u64,
+    depth: usize,
+    best_cost: Option<Cost>,
+    #[cfg(feature = "std")]
+    elapsed: core::time::Duration,
+}
+
+impl SearchProgress {
+    pub(crate) fn new(
+        node_count: u64,
+        depth: usize,
+        best_cost: Option<Cost>,
+        #[cfg(feature = "std")] elapsed: core::time::Duration,
+    ) -> Self {
+        Self {
+            node_count,
+            depth,
+            best_cost,
+            #[cfg(feature = "std")]
+            elapsed,
+        }
+    }
+
+    /// これまでに探索した局面数。
+    pub fn node_count(&self) -> u64 {
+        self.node_count
+    }
+
+    /// 報告時点で探索している局面の手数。
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// 暫定解の目的関数の値。まだ解がなければ `None`。
+    pub fn best_cost(&self) -> Option<Cost> {
+        self.best_cost
+    }
+
+    /// 探索開始からの経過時間。
+    #[cfg(feature = "std")]
+    pub fn elapsed(&self) -> core::time::Duration {
+        self.elapsed
+    }
+}
+
+/// 進捗のコールバック。
+pub(crate) struct ProgressHook<'a>(pub(crate) &'a mut (dyn FnMut(&SearchProgress) + Send));
+
+impl core::fmt::Debug for ProgressHook<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("ProgressHook")
+    }
+}
+
+/// 暫定解が更新されたときのコールバック。
+pub(crate) struct ImproveHook<'a>(pub(crate) &'a mut (dyn FnMut(&Solution, Cost) + Send));
+
+impl core::fmt::Debug for ImproveHook<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("ImproveHook")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::move_::*;
+
+    use super::*;
+
+    #[test]
+    fn test_history_table() {
+        let history = HistoryTable::new();
+        history.record([MOVE_SRC_ROW_9, MOVE_SRC_ROW_3]);
+        history.record([MOVE_SRC_ROW_9]);
+
+        assert_eq!(history.score(0, MOVE_SRC_ROW_9), 2);
+        assert_eq!(history.score(1, MOVE_SRC_ROW_3), 1);
+        assert_eq!(history.score(1, MOVE_SRC_ROW_9), 0);
+        assert_eq!(history.score(HISTORY_DEPTH, MOVE_SRC_ROW_9), 0);
+    }
+}
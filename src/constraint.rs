@@ -0,0 +1,132 @@
+use crate::cost::Cost;
+use crate::move_::MoveSrc;
+
+/// 解に課す追加の制約。魅せプレイ用の TAS で、制約を満たす範囲でコスト最小の解を求めるのに使う。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Constraint {
+    /// 同じ行から 2 回連続でブロックを投げない。
+    NoRepeatSrc,
+
+    /// 最後の着手を指定した行から行う。
+    EndAt(MoveSrc),
+
+    /// 指定した行からは投げない。複数指定すると、そのどの行からも投げない。
+    /// 特定の行を使わない魅せプレイや、バグ技の条件を満たす手順を探すのに使う。
+    BanSrc(MoveSrc),
+
+    /// 自機の総移動歩数を指定した値以上にする。
+    /// 値を大きくしながら解き直すことで、自機の移動を最大化した解も求められる。
+    MinHeroTravel(u32),
+
+    /// 1 手の所要コスト (自機の移動とブロック投げの合計) を指定した値以下にする。
+    /// RTA の予備の手順として、全ての入力の猶予を人間が扱える長さに収めるのに使う。
+    MaxMoveCost(Cost),
+
+    /// 総コスト (面クリア時のコストを含む) を指定した値以下にする。
+    /// タイマーのあるモードで、人間が操作しても時間切れにならない手順を求めるのに使う。
+    TimeLimit(Cost),
+}
+
+impl Constraint {
+    /// 直前の着手位置が `prev` のとき、次に `src` から投げてよいかどうかを返す。
+    /// 着手列の途中で判定できる制約のみを見る (探索の枝刈りに使う)。
+    pub(crate) fn allows_next(self, prev: Option<MoveSrc>, src: MoveSrc) -> bool {
+        match self {
+            Self::NoRepeatSrc => prev != Some(src),
+            Self::BanSrc(src_banned) => src != src_banned,
+            Self::EndAt(_) | Self::MinHeroTravel(_) | Self::MaxMoveCost(_) | Self::TimeLimit(_) => {
+                true
+            }
+        }
+    }
+
+    /// 所要コスト `cost_mv` の着手を行ってよいかどうかを返す。
+    pub(crate) fn allows_move_cost(self, cost_mv: Cost) -> bool {
+        match self {
+            Self::MaxMoveCost(cost_max) => cost_mv <= cost_max,
+            Self::NoRepeatSrc
+            | Self::EndAt(_)
+            | Self::BanSrc(_)
+            | Self::MinHeroTravel(_)
+            | Self::TimeLimit(_) => true,
+        }
+    }
+
+    /// 総コストが `cost_total` の解を許すかどうかを返す。
+    pub(crate) fn allows_cost_total(self, cost_total: Cost) -> bool {
+        match self {
+            Self::TimeLimit(cost_max) => cost_total <= cost_max,
+            Self::NoRepeatSrc
+            | Self::EndAt(_)
+            | Self::BanSrc(_)
+            | Self::MinHeroTravel(_)
+            | Self::MaxMoveCost(_) => true,
+        }
+    }
+
+    /// 自機の初期位置が `hero_row` のとき、着手位置の列 `srcs` (面が終了するまでの全着手) が制約を満たすかどうかを返す。
+    ///
+    /// 着手位置だけでは判定できない `MaxMoveCost`, `TimeLimit` は常に満たすとみなす
+    /// (`Solution::verify_with_constraints()` で判定する)。
+    pub fn is_satisfied(self, hero_row: MoveSrc, srcs: &[MoveSrc]) -> bool {
+        match self {
+            Self::NoRepeatSrc => srcs.windows(2).all(|pair| pair[0] != pair[1]),
+            Self::EndAt(src) => srcs.last() == Some(&src),
+            Self::BanSrc(src) => !srcs.contains(&src),
+            Self::MinHeroTravel(travel_min) => hero_travel(hero_row, srcs) >= travel_min,
+            Self::MaxMoveCost(_) | Self::TimeLimit(_) => true,
+        }
+    }
+}
+
+/// 自機の初期位置が `hero_row` のとき、着手位置の列 `srcs` に対する自機の総移動歩数を返す。
+pub fn hero_travel(hero_row: MoveSrc, srcs: &[MoveSrc]) -> u32 {
+    let mut travel = 0;
+    let mut row = hero_row;
+
+    for &src in srcs {
+        travel += u32::from(row.to_inner().abs_diff(src.to_inner()));
+        row = src;
+    }
+
+    travel
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::move_::*;
+
+    use super::*;
+
+    #[test]
+    fn test_constraint() {
+        let srcs = [MOVE_SRC_ROW_9, MOVE_SRC_ROW_9, MOVE_SRC_ROW_2];
+
+        assert_eq!(hero_travel(MOVE_SRC_ROW_11, &srcs), 9);
+
+        assert!(!Constraint::NoRepeatSrc.is_satisfied(MOVE_SRC_ROW_11, &srcs));
+        assert!(Constraint::NoRepeatSrc.is_satisfied(MOVE_SRC_ROW_11, &srcs[1..]));
+        assert!(!Constraint::NoRepeatSrc.allows_next(Some(MOVE_SRC_ROW_9), MOVE_SRC_ROW_9));
+        assert!(Constraint::NoRepeatSrc.allows_next(None, MOVE_SRC_ROW_9));
+
+        assert!(Constraint::EndAt(MOVE_SRC_ROW_2).is_satisfied(MOVE_SRC_ROW_11, &srcs));
+        assert!(!Constraint::EndAt(MOVE_SRC_ROW_11).is_satisfied(MOVE_SRC_ROW_11, &srcs));
+
+        assert!(!Constraint::BanSrc(MOVE_SRC_ROW_2).is_satisfied(MOVE_SRC_ROW_11, &srcs));
+        assert!(Constraint::BanSrc(MOVE_SRC_ROW_11).is_satisfied(MOVE_SRC_ROW_11, &srcs));
+        assert!(!Constraint::BanSrc(MOVE_SRC_ROW_9).allows_next(None, MOVE_SRC_ROW_9));
+        assert!(
+            Constraint::BanSrc(MOVE_SRC_ROW_9).allows_next(Some(MOVE_SRC_ROW_9), MOVE_SRC_ROW_2)
+        );
+
+        assert!(Constraint::MinHeroTravel(9).is_satisfied(MOVE_SRC_ROW_11, &srcs));
+        assert!(!Constraint::MinHeroTravel(10).is_satisfied(MOVE_SRC_ROW_11, &srcs));
+
+        assert!(Constraint::MaxMoveCost(100).allows_move_cost(100));
+        assert!(!Constraint::MaxMoveCost(100).allows_move_cost(101));
+        assert!(Constraint::NoRepeatSrc.allows_move_cost(1000));
+
+        assert!(Constraint::TimeLimit(3600).allows_cost_total(3600));
+        assert!(!Constraint::TimeLimit(3600).allows_cost_total(3601));
+    }
+}
@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+
+use crate::block::Block;
+use crate::error::Error;
+use crate::move_::MoveSrc;
+use crate::problem::{parse_problems, Problem, ProblemBoard};
+use crate::solver::Solution;
+
+/// 問題のファイル形式。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProblemFormat {
+    /// `Problem` の文字列表現 (1 問のみ)。
+    Text,
+    /// JSON。1 問ならオブジェクト、複数ならオブジェクトの配列。
+    Json,
+    /// 原作 ROM 内のエンコーディング (`Problem::ROM_BYTES_LEN` バイトずつ連結)。
+    Rom,
+    /// 問題集。`Problem` の文字列表現を `---` だけの行 (または空行) で区切って並べたもの。
+    Collection,
+}
+
+/// 解のファイル形式。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SolutionFormat {
+    /// `Solution` の文字列表現 (1 個のみ)。
+    Text,
+    /// JSON。1 個なら着手位置の配列、複数なら配列の配列。
+    Json,
+    /// 解集。1 行に 1 個の `Solution` の文字列表現を並べたもの。
+    Collection,
+}
+
+/// 問題集の区切り行。
+const COLLECTION_SEPARATOR: &str = "---";
+
+/// JSON での問題の表現。
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct ProblemJson {
+    block_holding: u8,
+    move_remain: u8,
+    /// 盤面の各行の文字列表現。
+    board: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    fn new(mut items: Vec<T>) -> Self {
+        if items.len() == 1 {
+            Self::One(items.pop().unwrap())
+        } else {
+            Self::Many(items)
+        }
+    }
+
+    fn into_vec(self) -> Vec<T> {
+        match self {
+            Self::One(item) => vec![item],
+            Self::Many(items) => items,
+        }
+    }
+}
+
+pub fn read_problems(buf: &[u8], format: ProblemFormat) -> Result<Vec<Problem>, Error> {
+    match format {
+        ProblemFormat::Text => Ok(vec![as_str(buf)?.parse()?]),
+        ProblemFormat::Json => {
+            let jsons: OneOrMany<ProblemJson> = from_json(buf)?;
+            jsons
+                .into_vec()
+                .into_iter()
+                .map(problem_from_json)
+                .collect()
+        }
+        ProblemFormat::Rom => {
+            if buf.len() % Problem::ROM_BYTES_LEN != 0 {
+                return Err(Error::RomLength { len: buf.len() });
+            }
+            buf.chunks_exact(Problem::ROM_BYTES_LEN)
+                .map(|chunk| Problem::from_rom_bytes(chunk.try_into().unwrap()))
+                .collect()
+        }
+        ProblemFormat::Collection => parse_problems(as_str(buf)?),
+    }
+}
+
+pub fn write_problems(problems: &[Problem], format: ProblemFormat) -> Result<Vec<u8>, Error> {
+    match format {
+        ProblemFormat::Text => Ok(single(problems)?.to_string().into_bytes()),
+        ProblemFormat::Json => {
+            let jsons: Vec<_> = problems.iter().map(problem_to_json).collect();
+            to_json(&OneOrMany::new(jsons))
+        }
+        ProblemFormat::Rom => Ok(problems.iter().flat_map(Problem::to_rom_bytes).collect()),
+        ProblemFormat::Collection => {
+            let texts: Vec<_> = problems.iter().map(Problem::to_string).collect();
+            Ok(texts
+                .join(&format!("{COLLECTION_SEPARATOR}\n"))
+                .into_bytes())
+        }
+    }
+}
+
+pub fn read_solutions(buf: &[u8], format: SolutionFormat) -> Result<Vec<Solution>, Error> {
+    match format {
+        SolutionFormat::Text => Ok(vec![as_str(buf)?.parse()?]),
+        SolutionFormat::Json => {
+            let jsons: OneOrMany<Vec<u8>> = from_json(buf)?;
+            jsons
+                .into_vec()
+                .into_iter()
+                .map(solution_from_json)
+                .collect()
+        }
+        SolutionFormat::Collection => {
+            let s = as_str(buf)?;
+            s.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| Ok(line.parse()?))
+                .collect()
+        }
+    }
+}
+
+pub fn write_solutions(solutions: &[Solution], format: SolutionFormat) -> Result<Vec<u8>, Error> {
+    match format {
+        SolutionFormat::Text => Ok(format!("{}\n", single(solutions)?).into_bytes()),
+        SolutionFormat::Json => {
+            let jsons: Vec<Vec<u8>> = solutions
+                .iter()
+                .map(|solution| solution.moves().iter().map(|src| src.to_inner()).collect())
+                .collect();
+            to_json(&OneOrMany::new(jsons))
+        }
+        SolutionFormat::Collection => Ok(solutions
+            .iter()
+            .map(|solution| format!("{solution}\n"))
+            .collect::<String>()
+            .into_bytes()),
+    }
+}
+
+pub(crate) fn problem_to_json(problem: &Problem) -> ProblemJson {
+    ProblemJson {
+        block_holding: problem.block_holding().to_inner(),
+        move_remain: problem.move_remain(),
+        board: problem
+            .board()
+            .to_string()
+            .lines()
+            .map(str::to_owned)
+            .collect(),
+    }
+}
+
+fn problem_from_json(json: ProblemJson) -> Result<Problem, Error> {
+    let block_holding = Block::from_inner(json.block_holding)
+        .ok_or_else(|| Error::Json(format!("invalid block_holding: {}", json.block_holding)))?;
+
+    let board: ProblemBoard = json
+        .board
+        .iter()
+        .map(|line| format!("{line}\n"))
+        .collect::<String>()
+        .parse()?;
+
+    Ok(Problem::new(board, block_holding, json.move_remain)?)
+}
+
+fn solution_from_json(json: Vec<u8>) -> Result<Solution, Error> {
+    let srcs = json
+        .into_iter()
+        .map(|inner| {
+            MoveSrc::from_inner(inner).ok_or_else(|| Error::Json(format!("invalid move: {inner}")))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Solution::new(srcs))
+}
+
+fn single<T>(items: &[T]) -> Result<&T, Error> {
+    match items {
+        [item] => Ok(item),
+        _ => Err(Error::NotSingle { count: items.len() }),
+    }
+}
+
+fn as_str(buf: &[u8]) -> Result<&str, Error> {
+    std::str::from_utf8(buf).map_err(|_| Error::NotUtf8)
+}
+
+fn from_json<'de, T: Deserialize<'de>>(buf: &'de [u8]) -> Result<T, Error> {
+    serde_json::from_slice(buf).map_err(|e| Error::Json(e.to_string()))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    let mut buf = serde_json::to_vec_pretty(value).map_err(|e| Error::Json(e.to_string()))?;
+    buf.push(b'\n');
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_convert_problems() {
+        let text = indoc! {"
+            2 33
+            #####...
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            311432..
+            222242|.
+            334422..
+            422224|.
+            344244..
+            133344..
+        "};
+        let problems = read_problems(text.as_bytes(), ProblemFormat::Text).unwrap();
+        let problems = [problems[0].clone(), problems[0].clone()];
+
+        for format in [
+            ProblemFormat::Json,
+            ProblemFormat::Rom,
+            ProblemFormat::Collection,
+        ] {
+            let buf = write_problems(&problems, format).unwrap();
+            assert_eq!(read_problems(&buf, format).unwrap(), problems);
+
+            let buf = write_problems(&problems[..1], format).unwrap();
+            assert_eq!(read_problems(&buf, format).unwrap(), problems[..1]);
+        }
+
+        let buf = write_problems(&problems[..1], ProblemFormat::Text).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), text);
+
+        assert_eq!(
+            write_problems(&problems, ProblemFormat::Text),
+            Err(Error::NotSingle { count: 2 })
+        );
+        assert_eq!(
+            read_problems(&[0; 3], ProblemFormat::Rom),
+            Err(Error::RomLength { len: 3 })
+        );
+    }
+
+    #[test]
+    fn test_convert_solutions() {
+        let solutions: Vec<Solution> = vec!["10 9 10 11".parse().unwrap(), "0 1".parse().unwrap()];
+
+        for format in [SolutionFormat::Json, SolutionFormat::Collection] {
+            let buf = write_solutions(&solutions, format).unwrap();
+            assert_eq!(read_solutions(&buf, format).unwrap(), solutions);
+        }
+
+        let buf = write_solutions(&solutions[..1], SolutionFormat::Json).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "[\n  10,\n  9,\n  10,\n  11\n]\n"
+        );
+        assert_eq!(
+            read_solutions(b"10 9 10 11\n", SolutionFormat::Text).unwrap(),
+            solutions[..1]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use crate::cost::Cost;
+use crate::problem::Problem;
+use crate::solver::Solution;
+use crate::stage::{Stage, STAGE_COUNT};
+
+/// 原作 ADVANCE モードの 1 面分のデータ。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CorpusStage {
+    stage: Stage,
+    problem: &'static str,
+    solution: &'static str,
+    cost: Cost,
+}
+
+impl CorpusStage {
+    /// 面 (1..=50)。
+    pub fn stage(self) -> Stage {
+        self.stage
+    }
+
+    /// 最終面かどうか。
+    pub fn is_last_stage(self) -> bool {
+        self.stage == STAGE_COUNT
+    }
+
+    pub fn problem(self) -> Problem {
+        self.problem.parse().expect("同梱の問題は正しいはず")
+    }
+
+    /// 問題の文字列表現。
+    pub fn problem_text(self) -> &'static str {
+        self.problem
+    }
+
+    /// 最適解。
+    pub fn solution(self) -> Solution {
+        self.solution.parse().expect("同梱の解は正しいはず")
+    }
+
+    /// 最適解のコスト。
+    pub fn cost(self) -> Cost {
+        self.cost
+    }
+}
+
+macro_rules! corpus {
+    ($($stage:literal: $name:literal => $cost:literal),* $(,)?) => {
+        [$(
+            CorpusStage {
+                stage: $stage,
+                problem: include_str!(concat!("../problem/", $name, ".in")),
+                solution: include_str!(concat!("../problem/", $name, ".out")),
+                cost: $cost,
+            },
+        )*]
+    };
+}
+
+static STAGES: [CorpusStage; STAGE_COUNT as usize] = corpus! {
+    1: "01" => 443, 2: "02" => 516, 3: "03" => 727, 4: "04" => 908, 5: "05" => 1708,
+    6: "06" => 1162, 7: "07" => 1281, 8: "08" => 800, 9: "09" => 1410, 10: "10" => 1431,
+    11: "11" => 1159, 12: "12" => 1072, 13: "13" => 1331, 14: "14" => 1137, 15: "15" => 1129,
+    16: "16" => 1302, 17: "17" => 1345, 18: "18" => 1889, 19: "19" => 1503, 20: "20" => 1435,
+    21: "21" => 1826, 22: "22" => 1252, 23: "23" => 1447, 24: "24" => 1297, 25: "25" => 1116,
+    26: "26" => 1408, 27: "27" => 1302, 28: "28" => 1065, 29: "29" => 1574, 30: "30" => 1826,
+    31: "31" => 1829, 32: "32" => 1154, 33: "33" => 1832, 34: "34" => 1834, 35: "35" => 1842,
+    36: "36" => 1621, 37: "37" => 2140, 38: "38" => 1998, 39: "39" => 2300, 40: "40" => 2227,
+    41: "41" => 2683, 42: "42" => 1718, 43: "43" => 2371, 44: "44" => 1957, 45: "45" => 1953,
+    46: "46" => 1429, 47: "47" => 2444, 48: "48" => 2537, 49: "49" => 2372, 50: "50" => 2449,
+};
+
+/// 面 `stage` (1..=50) のデータを返す。
+pub fn stage(stage: Stage) -> Option<CorpusStage> {
+    stage
+        .checked_sub(1)
+        .and_then(|i| STAGES.get(usize::from(i)))
+        .copied()
+}
+
+/// 全ての面のデータを返す。
+pub fn stages() -> &'static [CorpusStage] {
+    &STAGES
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_corpus() {
+        assert_eq!(stage(0), None);
+        assert_eq!(stage(STAGE_COUNT + 1), None);
+
+        for (&corpus, i) in stages().iter().zip(1..) {
+            assert_eq!(corpus.stage(), i);
+            assert_eq!(stage(i), Some(corpus));
+
+            let problem = corpus.problem();
+            assert_eq!(problem.to_string(), corpus.problem_text());
+            assert_eq!(
+                corpus.solution().verify(&problem, corpus.is_last_stage()),
+                Ok(corpus.cost())
+            );
+        }
+    }
+}
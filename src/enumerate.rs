@@ -0,0 +1,185 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::cost::{calc_min_move_cost, Cost};
+use crate::move_::{Move, MoveSrc};
+use crate::objective::Objective;
+use crate::position::Position;
+use crate::precheck::prove_unsolvable;
+use crate::problem::Problem;
+use crate::solver::Solution;
+
+/// 総コストが `max_cost` 以下の解を全て列挙する。最適解だけでなく、ルートの多様性を数えたり練習用の表を作るのに使う。
+///
+/// 深さ優先で探索しながら解を見つけた順に返すので、コスト順ではない。探索は `next()` を呼ぶたびに進む。
+/// 枝刈りは総コストの下界のみで行うため、`max_cost` を最適解より大きくするほど解の個数も探索量も急速に増える。
+/// 個数だけが必要なら `analysis::solution_space_stats()` でもよい。
+pub fn enumerate_solutions(
+    problem: &Problem,
+    last_stage: bool,
+    max_cost: Cost,
+) -> impl Iterator<Item = (Solution, Cost)> {
+    let (pos, moves) = problem.to_position_and_moves();
+    let min_throw_cost = moves
+        .iter()
+        .map(|mv| calc_min_move_cost(mv.src()))
+        .min()
+        .unwrap_or(0);
+    let stack = if prove_unsolvable(problem).is_some() {
+        vec![]
+    } else {
+        vec![Node {
+            pos,
+            srcs: vec![],
+            cost: 0,
+            cost_last_throw: 0,
+        }]
+    };
+
+    SolutionIter {
+        moves,
+        last_stage,
+        max_cost,
+        min_throw_cost,
+        stack,
+    }
+}
+
+#[derive(Debug)]
+struct Node {
+    pos: Position,
+    srcs: Vec<MoveSrc>,
+    cost: Cost,
+    cost_last_throw: Cost,
+}
+
+#[derive(Debug)]
+struct SolutionIter {
+    moves: Vec<Move>,
+    last_stage: bool,
+    max_cost: Cost,
+    min_throw_cost: Cost,
+    stack: Vec<Node>,
+}
+
+impl Iterator for SolutionIter {
+    type Item = (Solution, Cost);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            let Node {
+                pos,
+                srcs,
+                cost,
+                cost_last_throw,
+            } = node;
+
+            // コストの下界が上限を超えたら枝刈り。
+            // 最終面でない場合の面クリア時のブロック自動消去コストは、以後の着手で減りうるので含めない。
+            let cost_lb = if self.last_stage {
+                cost - cost_last_throw + 1
+            } else {
+                cost
+            };
+            if cost_lb > self.max_cost {
+                continue;
+            }
+
+            if pos.is_stuck(&self.moves) {
+                let cost_total = if self.last_stage {
+                    cost_lb
+                } else {
+                    cost + Objective::Frames.clear_cost(pos.block_count())
+                };
+                if pos.is_cleared() && cost_total <= self.max_cost {
+                    return Some((Solution::new(srcs), cost_total));
+                }
+                continue;
+            }
+            if pos.move_remain() == 0 {
+                continue;
+            }
+
+            let move_count = pos.min_moves_to_solve().max(1);
+            if move_count > usize::from(pos.move_remain()) {
+                continue;
+            }
+            let cost_lower = if self.last_stage {
+                cost + self.min_throw_cost * (move_count - 1) as Cost + 1
+            } else {
+                cost + self.min_throw_cost * move_count as Cost
+            };
+            if cost_lower > self.max_cost {
+                continue;
+            }
+
+            // 着手の順に列挙するため、逆順に積む。
+            for &mv in self.moves.iter().rev() {
+                let Some((pos_nxt, cost_mv, cost_throw)) = pos.do_move(mv) else {
+                    continue;
+                };
+                let mut srcs_nxt = srcs.clone();
+                srcs_nxt.push(mv.src());
+                self.stack.push(Node {
+                    pos: pos_nxt,
+                    srcs: srcs_nxt,
+                    cost: cost + cost_mv,
+                    cost_last_throw: cost_throw,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::solver::solve_problem;
+
+    use super::*;
+
+    #[test]
+    fn test_enumerate_solutions() {
+        for (problem, last_stage) in [
+            (include_str!("../problem/01.in"), false),
+            (include_str!("../problem/02.in"), true),
+        ] {
+            let problem: Problem = problem.parse().unwrap();
+            let (_, cost_opt) = solve_problem(&problem, last_stage).unwrap();
+
+            // 上限が最適解未満なら解はない。
+            assert_eq!(
+                enumerate_solutions(&problem, last_stage, cost_opt - 1).count(),
+                0
+            );
+
+            // 上限が最適解なら最適解だけを列挙する。
+            let solutions: Vec<_> = enumerate_solutions(&problem, last_stage, cost_opt).collect();
+            assert!(!solutions.is_empty());
+            assert!(solutions.iter().all(|&(_, cost)| cost == cost_opt));
+
+            // 上限を上げると解が増え、全て正しく、重複もない。
+            let max_cost = cost_opt + 1000;
+            let solutions: Vec<_> = enumerate_solutions(&problem, last_stage, max_cost).collect();
+            assert!(solutions.len() > 1);
+            for (solution, cost) in &solutions {
+                assert!(*cost <= max_cost);
+                assert_eq!(solution.verify(&problem, last_stage), Ok(*cost));
+            }
+            let mut srcs: Vec<_> = solutions
+                .iter()
+                .map(|(solution, _)| solution.moves())
+                .collect();
+            srcs.sort_unstable();
+            srcs.dedup();
+            assert_eq!(srcs.len(), solutions.len());
+            assert_eq!(
+                solutions.len() as u64,
+                crate::analysis::solution_space_stats(&problem, last_stage, max_cost).clear_count()
+            );
+        }
+    }
+}
@@ -0,0 +1,665 @@
+use alloc::format;
+use alloc::string::String;
+
+use crate::block::{BlocksCol, BlocksRow};
+use crate::constraint::Constraint;
+use crate::cost::Cost;
+use crate::locale::{tr, Locale};
+use crate::move_::{MoveDst, MoveSrc};
+use crate::position::Position;
+use crate::stage::Stage;
+
+/// このクレートのエラー型。
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+
+    #[error(transparent)]
+    Verify(#[from] VerifyError),
+
+    #[error(transparent)]
+    Certificate(#[from] CertificateError),
+
+    #[error(
+        "{msg}: {src:?}",
+        src = .0,
+        msg = tr("ブロック領域の行ではない", "not a row of the blocks area")
+    )]
+    NotBlocksRow(MoveSrc),
+
+    #[error("{msg}: '{name}'", name = .0, msg = tr("未知の言語", "unknown locale"))]
+    UnknownLocale(String),
+
+    #[error("{msg}: '{name}'", name = .0, msg = tr("未知のタイミングプロファイル", "unknown timing profile"))]
+    UnknownProfile(String),
+
+    #[error(
+        "{msg}: offset={offset:#04X}, value={value:#04X}",
+        msg = tr("無効な ROM バイト", "invalid ROM byte")
+    )]
+    InvalidRomByte { offset: usize, value: u8 },
+
+    #[error(
+        "{msg}: {len}",
+        msg = tr(
+            "ROM バイト列の長さが 1 問のバイト数の倍数でない",
+            "ROM byte length is not a multiple of one problem"
+        )
+    )]
+    RomLength { len: usize },
+
+    #[error("{msg}", msg = tr("iNES magic がない", "missing iNES magic"))]
+    InesMagic,
+
+    #[error("{msg}", msg = tr("iNES ファイルの途中で EOF に達した", "unexpected EOF in iNES file"))]
+    InesEof,
+
+    #[error(
+        "{msg} (expect={expected:#06X}, actual={actual:#06X})",
+        msg = tr("CHR サイズが一致しない", "CHR size mismatch")
+    )]
+    ChrSize { expected: usize, actual: usize },
+
+    #[error("{msg}", msg = tr("UTF-8 でない", "not UTF-8"))]
+    NotUtf8,
+
+    #[error(
+        "{msg}: {count}",
+        msg = tr(
+            "この形式はちょうど 1 個の要素しか扱えない",
+            "this format holds exactly one item"
+        )
+    )]
+    NotSingle { count: usize },
+
+    #[error("{msg}", msg = tr("置換表ファイルの形式が不正", "invalid transposition table file"))]
+    InvalidTtFile,
+
+    #[error(
+        "{msg}",
+        msg = tr(
+            "置換表が別の問題 (または最終面フラグ、ルール設定) のもの",
+            "transposition table belongs to another problem (or last-stage flag, ruleset)"
+        )
+    )]
+    TtMismatch,
+
+    #[error("{msg}", msg = tr("終盤表ファイルの形式が不正", "invalid tablebase file"))]
+    InvalidTablebaseFile,
+
+    #[error(
+        "{msg}",
+        msg = tr(
+            "終盤表が別の問題 (または最終面フラグ、ルール設定) のもの",
+            "tablebase belongs to another problem (or last-stage flag, ruleset)"
+        )
+    )]
+    TablebaseMismatch,
+
+    #[error("{msg}", msg = tr("スナップショットファイルの形式が不正", "invalid checkpoint file"))]
+    InvalidCheckpointFile,
+
+    #[error(
+        "{msg}",
+        msg = tr(
+            "スナップショットが別の問題 (または最終面フラグ、ルール設定、目的関数) のもの",
+            "checkpoint belongs to another problem (or last-stage flag, ruleset, objective)"
+        )
+    )]
+    CheckpointMismatch,
+
+    #[error("{msg}", msg = tr("最適性証明書ファイルの形式が不正", "invalid certificate file"))]
+    InvalidCertificateFile,
+
+    #[error("JSON: {0}")]
+    Json(String),
+
+    /// 入出力エラー。`std::io::Error` は `Clone` でないので種類とメッセージのみ保持する。
+    #[cfg(feature = "std")]
+    #[error("I/O: {message}")]
+    Io {
+        kind: std::io::ErrorKind,
+        message: String,
+    },
+}
+
+/// 文字列のパースエラー。
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+#[error("{}: {kind}", self.location())]
+pub struct ParseError {
+    line: usize,
+    col: Option<usize>,
+    kind: ParseErrorKind,
+}
+
+impl ParseError {
+    pub fn new(line: usize, kind: ParseErrorKind) -> Self {
+        Self {
+            line,
+            col: None,
+            kind,
+        }
+    }
+
+    /// エラー箇所の列番号 (1-based, 文字単位) を設定する。
+    pub fn with_col(self, col: usize) -> Self {
+        Self {
+            col: Some(col),
+            ..self
+        }
+    }
+
+    /// エラー箇所の行番号 (1-based)。
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// エラー箇所の列番号 (1-based, 文字単位)。行全体に関するエラーでは `None`。
+    pub fn col(&self) -> Option<usize> {
+        self.col
+    }
+
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+
+    /// 行番号を `offset` だけずらす。文字列の一部をパースした結果を全体に対する位置に直すのに使う。
+    pub(crate) fn offset_line(self, offset: usize) -> Self {
+        Self {
+            line: self.line + offset,
+            ..self
+        }
+    }
+
+    fn location(&self) -> String {
+        let line = self.line;
+        match self.col {
+            Some(col) => tr(
+                format!("{line} 行 {col} 列目"),
+                format!("line {line}, column {col}"),
+            ),
+            None => tr(format!("{line} 行目"), format!("line {line}")),
+        }
+    }
+}
+
+/// `line` 内の部分文字列 `token` の列番号 (1-based, 文字単位) を返す。
+pub(crate) fn token_col(line: &str, token: &str) -> usize {
+    let offset = token.as_ptr() as usize - line.as_ptr() as usize;
+    line[..offset].chars().count() + 1
+}
+
+/// 行数がちょうど `expected` であることを確認する。
+pub(crate) fn ensure_line_count(lines: &[&str], expected: usize) -> Result<(), ParseError> {
+    use core::cmp::Ordering;
+
+    match lines.len().cmp(&expected) {
+        Ordering::Less => Err(ParseError::new(
+            lines.len() + 1,
+            ParseErrorKind::MissingLine { expected },
+        )),
+        Ordering::Greater => Err(ParseError::new(
+            expected + 1,
+            ParseErrorKind::ExtraLine { expected },
+        )),
+        Ordering::Equal => Ok(()),
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum ParseErrorKind {
+    MissingLine { expected: usize },
+    ExtraLine { expected: usize },
+    LineLength { expected: usize, actual: usize },
+    TokenCount { expected: usize, actual: usize },
+    InvalidChar(char),
+    NotNumber(String),
+    InvalidHeroRow(u8),
+    InvalidBlock(u8),
+    InvalidMoveSrc(u8),
+    InvalidProofStatus(String),
+    DuplicateStage(Stage),
+    UnknownKey(String),
+    MissingKey(String),
+}
+
+impl core::fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use Locale::*;
+
+        match (self, Locale::current()) {
+            (Self::MissingLine { expected }, Ja) => {
+                write!(f, "行が足りない (ちょうど {expected} 行でなければならない)")
+            }
+            (Self::MissingLine { expected }, En) => {
+                write!(f, "missing line (expected exactly {expected} lines)")
+            }
+            (Self::ExtraLine { expected }, Ja) => {
+                write!(f, "行が多すぎる (ちょうど {expected} 行でなければならない)")
+            }
+            (Self::ExtraLine { expected }, En) => {
+                write!(f, "too many lines (expected exactly {expected} lines)")
+            }
+            (Self::LineLength { expected, actual }, Ja) => write!(
+                f,
+                "行の文字数が不正: {actual} (ちょうど {expected} 文字でなければならない)"
+            ),
+            (Self::LineLength { expected, actual }, En) => write!(
+                f,
+                "invalid line length: {actual} (expected exactly {expected} chars)"
+            ),
+            (Self::TokenCount { expected, actual }, Ja) => write!(
+                f,
+                "行のトークン数が不正: {actual} (ちょうど {expected} 個でなければならない)"
+            ),
+            (Self::TokenCount { expected, actual }, En) => write!(
+                f,
+                "invalid token count: {actual} (expected exactly {expected} tokens)"
+            ),
+            (Self::InvalidChar(ch), Ja) => write!(f, "無効な文字: '{ch}'"),
+            (Self::InvalidChar(ch), En) => write!(f, "invalid char: '{ch}'"),
+            (Self::NotNumber(token), Ja) => write!(f, "数値でない: '{token}'"),
+            (Self::NotNumber(token), En) => write!(f, "not a number: '{token}'"),
+            (Self::InvalidHeroRow(x), Ja) => write!(f, "無効な自機位置: {x}"),
+            (Self::InvalidHeroRow(x), En) => write!(f, "invalid hero row: {x}"),
+            (Self::InvalidBlock(x), Ja) => write!(f, "無効な保持ブロック値: {x}"),
+            (Self::InvalidBlock(x), En) => write!(f, "invalid holding block: {x}"),
+            (Self::InvalidMoveSrc(x), Ja) => write!(f, "無効な着手: {x}"),
+            (Self::InvalidMoveSrc(x), En) => write!(f, "invalid move: {x}"),
+            (Self::InvalidProofStatus(token), Ja) => write!(f, "無効な証明状態: '{token}'"),
+            (Self::InvalidProofStatus(token), En) => write!(f, "invalid proof status: '{token}'"),
+            (Self::DuplicateStage(stage), Ja) => write!(f, "面 {stage} が重複している"),
+            (Self::DuplicateStage(stage), En) => write!(f, "duplicate stage {stage}"),
+            (Self::UnknownKey(key), Ja) => write!(f, "未知のキー: '{key}'"),
+            (Self::UnknownKey(key), En) => write!(f, "unknown key: '{key}'"),
+            (Self::MissingKey(key), Ja) => write!(f, "キー '{key}' がない"),
+            (Self::MissingKey(key), En) => write!(f, "missing key '{key}'"),
+        }
+    }
+}
+
+/// 問題が ADVANCE モードの制約を満たしていないことを表すエラー。
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+#[error(
+    "{}: ({col}, {row}): {kind}",
+    tr(
+        "問題が ADVANCE モードの制約を満たしていない",
+        "problem violates ADVANCE mode constraints"
+    )
+)]
+pub struct ValidationError {
+    col: usize,
+    row: usize,
+    kind: ValidationErrorKind,
+}
+
+impl ValidationError {
+    pub fn new(col: usize, row: usize, kind: ValidationErrorKind) -> Self {
+        Self { col, row, kind }
+    }
+
+    /// 違反箇所の列 (0-based)。
+    pub fn col(&self) -> usize {
+        self.col
+    }
+
+    /// 違反箇所の行 (0-based)。
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn kind(&self) -> ValidationErrorKind {
+        self.kind
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum ValidationErrorKind {
+    InvalidBlocksAreaTile,
+    BlockOutsideBlocksArea,
+    WallWithoutWallAbove,
+}
+
+impl core::fmt::Display for ValidationErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let msg = match self {
+            Self::InvalidBlocksAreaTile => tr(
+                "左下 6x6 は空白または通常ブロックでなければならない",
+                "bottom-left 6x6 must be empty or normal blocks",
+            ),
+            Self::BlockOutsideBlocksArea => tr(
+                "左下 6x6 の範囲外にブロックがあってはならない",
+                "blocks must not be outside bottom-left 6x6",
+            ),
+            Self::WallWithoutWallAbove => tr(
+                "壁の上には壁がなければならない",
+                "a wall must have a wall above it",
+            ),
+        };
+        f.write_str(msg)
+    }
+}
+
+/// 解の検証エラー。
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum VerifyError {
+    MoveExhausted {
+        index: usize,
+    },
+    StageEnded {
+        index: usize,
+    },
+    /// `dst` は着手の投げ込み先 (その行からはどこにも投げ込めない場合は `None`)。
+    IllegalMove {
+        index: usize,
+        src: MoveSrc,
+        pos: Position,
+        dst: Option<MoveDst>,
+    },
+    NotStuck {
+        pos: Position,
+    },
+    NotCleared {
+        pos: Position,
+    },
+    /// 解が追加の制約を満たさない。
+    ConstraintViolated {
+        constraint: Constraint,
+    },
+}
+
+impl VerifyError {
+    /// エラー箇所を示す盤面を描画する。描画すべき盤面がなければ `None` を返す。
+    ///
+    /// 解けていない局面では残っているブロックを、不正な着手ではブロックの通り道と最初に当たったブロックを強調する。
+    /// `color` が true なら ANSI エスケープシーケンスで色付けし、false なら通り道を `-`, `|` で、
+    /// 最初に当たったブロックを `*` で示す。
+    pub fn render(&self, color: bool) -> Option<String> {
+        match self {
+            Self::IllegalMove {
+                pos,
+                dst: Some(dst),
+                ..
+            } => Some(render_board(pos, Some(*dst), color)),
+            Self::NotStuck { pos } | Self::NotCleared { pos } => {
+                Some(render_board(pos, None, color))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn render_board(pos: &Position, dst: Option<MoveDst>, color: bool) -> String {
+    const ESC_BLOCK: &str = "\x1b[1;31m";
+    const ESC_PATH: &str = "\x1b[33m";
+    const ESC_HIT: &str = "\x1b[97;41m";
+    const ESC_RESET: &str = "\x1b[0m";
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        None,
+        Path,
+        Hit,
+    }
+
+    let blocks = pos.blocks();
+    let mut marks = [[Mark::None; 6]; 6];
+
+    // 投げたブロックの通り道を最初に当たるブロックまで辿る。
+    let path: [(usize, usize); 6] = match dst {
+        Some(MoveDst::Horizontal(row)) => {
+            core::array::from_fn(|i| (5 - i, row.to_inner() as usize - 1))
+        }
+        Some(MoveDst::Vertical(col)) => core::array::from_fn(|i| (col.to_inner() as usize - 1, i)),
+        None => [(0, 0); 6],
+    };
+    if dst.is_some() {
+        for (c, r) in path {
+            let col = BlocksCol::all()[c];
+            let row = BlocksRow::all()[r];
+            if blocks[(col, row)].is_some() {
+                marks[r][c] = Mark::Hit;
+                break;
+            }
+            marks[r][c] = Mark::Path;
+        }
+    }
+    let ch_path = match dst {
+        Some(MoveDst::Vertical(_)) => '|',
+        _ => '-',
+    };
+
+    let mut buf = String::new();
+    for (r, row) in BlocksRow::all().into_iter().enumerate() {
+        for (c, col) in BlocksCol::all().into_iter().enumerate() {
+            let block = blocks[(col, row)];
+            let cell = match (marks[r][c], block, color) {
+                (Mark::Path, _, true) => format!("{ESC_PATH}{ch_path}{ESC_RESET}"),
+                (Mark::Path, _, false) => format!("{ch_path}"),
+                (Mark::Hit, Some(block), true) => {
+                    format!("{ESC_HIT}{}{ESC_RESET}", block.to_inner())
+                }
+                (Mark::Hit, _, false) => "*".into(),
+                (_, Some(block), true) if dst.is_none() => {
+                    format!("{ESC_BLOCK}{}{ESC_RESET}", block.to_inner())
+                }
+                (_, Some(block), _) => format!("{}", block.to_inner()),
+                (_, None, _) => ".".into(),
+            };
+            buf.push_str(&cell);
+        }
+        buf.push('\n');
+    }
+
+    buf
+}
+
+impl core::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use Locale::*;
+
+        match (self, Locale::current()) {
+            (Self::MoveExhausted { index }, Ja) => {
+                write!(f, "{index} 番目の着手前に残り手数が尽きた")
+            }
+            (Self::MoveExhausted { index }, En) => {
+                write!(f, "no moves remain before move {index}")
+            }
+            (Self::StageEnded { index }, Ja) => write!(f, "{index} 番目の着手前に面が終了している"),
+            (Self::StageEnded { index }, En) => {
+                write!(f, "stage already ended before move {index}")
+            }
+            (Self::IllegalMove { index, src, .. }, Ja) => {
+                write!(f, "{index} 番目の着手が不正: {src:?}")?;
+                if let Some(board) = self.render(false) {
+                    write!(f, " (* は最初に当たったブロック):\n{board}")?;
+                }
+                Ok(())
+            }
+            (Self::IllegalMove { index, src, .. }, En) => {
+                write!(f, "move {index} is illegal: {src:?}")?;
+                if let Some(board) = self.render(false) {
+                    write!(f, " (* marks the first block hit):\n{board}")?;
+                }
+                Ok(())
+            }
+            (Self::NotStuck { pos }, Ja) => write!(f, "最後の局面でまだ合法手がある:\n{pos}"),
+            (Self::NotStuck { pos }, En) => {
+                write!(f, "last position still has legal moves:\n{pos}")
+            }
+            (Self::NotCleared { pos }, Ja) => write!(f, "最後の局面が解けていない:\n{pos}"),
+            (Self::NotCleared { pos }, En) => write!(f, "last position is not cleared:\n{pos}"),
+            (Self::ConstraintViolated { constraint }, Ja) => {
+                write!(f, "制約を満たさない: {constraint:?}")
+            }
+            (Self::ConstraintViolated { constraint }, En) => {
+                write!(f, "constraint violated: {constraint:?}")
+            }
+        }
+    }
+}
+
+/// 最適性証明書の検証エラー。
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum CertificateError {
+    /// 証明書が別の問題 (または最終面フラグ、ルール設定) のもの。
+    Mismatch,
+    /// 初手 `src` の下界が記録されていない。
+    MissingMove { src: MoveSrc },
+    /// 合法な初手でない、または重複した着手 `src` の下界が記録されている。
+    UnexpectedMove { src: MoveSrc },
+    /// 最善解が不正。
+    Verify(VerifyError),
+    /// 最善解の実際のコストが記録と異なる。
+    CostMismatch { claimed: Cost, actual: Cost },
+    /// 解がないと記録されているが、解がある。
+    SolutionMissed,
+    /// 初手 `src` の下界が最善解のコストより小さい。
+    BoundBelowBest {
+        src: MoveSrc,
+        bound: Cost,
+        best: Cost,
+    },
+    /// 初手 `src` から下界 (`None` なら解なし) より小さいコストの解がある。
+    BoundViolated { src: MoveSrc, bound: Option<Cost> },
+}
+
+impl core::fmt::Display for CertificateError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use Locale::*;
+
+        match (self, Locale::current()) {
+            (Self::Mismatch, Ja) => write!(
+                f,
+                "証明書が別の問題 (または最終面フラグ、ルール設定) のもの"
+            ),
+            (Self::Mismatch, En) => write!(
+                f,
+                "certificate belongs to another problem (or last-stage flag, ruleset)"
+            ),
+            (Self::MissingMove { src }, Ja) => write!(f, "初手 {src:?} の下界がない"),
+            (Self::MissingMove { src }, En) => write!(f, "no bound for first move {src:?}"),
+            (Self::UnexpectedMove { src }, Ja) => {
+                write!(f, "合法な初手でないか重複している: {src:?}")
+            }
+            (Self::UnexpectedMove { src }, En) => {
+                write!(f, "not a legal first move or duplicated: {src:?}")
+            }
+            (Self::Verify(e), Ja) => write!(f, "最善解が不正: {e}"),
+            (Self::Verify(e), En) => write!(f, "best solution is invalid: {e}"),
+            (Self::CostMismatch { claimed, actual }, Ja) => {
+                write!(
+                    f,
+                    "最善解のコストが記録と異なる: 記録 {claimed}, 実際 {actual}"
+                )
+            }
+            (Self::CostMismatch { claimed, actual }, En) => write!(
+                f,
+                "best solution cost mismatch: claimed {claimed}, actual {actual}"
+            ),
+            (Self::SolutionMissed, Ja) => write!(f, "解なしと記録されているが解がある"),
+            (Self::SolutionMissed, En) => write!(f, "claimed unsolvable but a solution exists"),
+            (Self::BoundBelowBest { src, bound, best }, Ja) => write!(
+                f,
+                "初手 {src:?} の下界 {bound} が最善解のコスト {best} より小さい"
+            ),
+            (Self::BoundBelowBest { src, bound, best }, En) => write!(
+                f,
+                "bound {bound} for first move {src:?} is below best cost {best}"
+            ),
+            (Self::BoundViolated { src, bound }, Ja) => {
+                write!(f, "初手 {src:?} から下界 ")?;
+                match bound {
+                    Some(bound) => write!(f, "{bound}"),
+                    None => write!(f, "(解なし)"),
+                }?;
+                write!(f, " より安い解がある")
+            }
+            (Self::BoundViolated { src, bound }, En) => {
+                write!(f, "a solution from first move {src:?} beats bound ")?;
+                match bound {
+                    Some(bound) => write!(f, "{bound}"),
+                    None => write!(f, "(unsolvable)"),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io {
+            kind: e.kind(),
+            message: e.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use crate::block::*;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_error_render() {
+        let pos: Position = indoc! {"
+            9 1 10
+            ......
+            ......
+            2444..
+            1222..
+            3333..
+            4111..
+        "}
+        .parse()
+        .unwrap();
+
+        let err = VerifyError::IllegalMove {
+            index: 0,
+            src: MoveSrc::Row9,
+            pos: pos.clone(),
+            dst: Some(MoveDst::Horizontal(BLOCKS_ROW_4)),
+        };
+        assert_eq!(
+            err.render(false).unwrap(),
+            indoc! {"
+                ......
+                ......
+                2444..
+                122*--
+                3333..
+                4111..
+            "}
+        );
+
+        let err = VerifyError::IllegalMove {
+            index: 0,
+            src: MoveSrc::Row3,
+            pos: pos.clone(),
+            dst: Some(MoveDst::Vertical(BLOCKS_COL_B)),
+        };
+        assert_eq!(
+            err.render(false).unwrap(),
+            indoc! {"
+                .|....
+                .|....
+                2*44..
+                1222..
+                3333..
+                4111..
+            "}
+        );
+
+        let err = VerifyError::NotCleared { pos };
+        assert!(err.render(true).unwrap().contains("\x1b[1;31m4\x1b[0m"));
+    }
+}
@@ -0,0 +1,410 @@
+use alloc::borrow::ToOwned as _;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use itertools::Itertools as _;
+
+use crate::block::{Block, Blocks, BlocksCol, BlocksSquare};
+use crate::cost::Cost;
+use crate::locale::tr;
+use crate::move_::{Move, MoveDst, MoveSrc};
+use crate::position::Position;
+
+/// 着手 1 回の間に起こる出来事。
+///
+/// 描画や GIF 出力、効果音の同期などで、着手の仕組みを再実装せずにアニメーションさせるのに使う。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MoveEvent {
+    /// 自機が 1 歩移動した。
+    HeroStepped { from: MoveSrc, to: MoveSrc },
+
+    /// 自機が保持ブロック `block` を `dst` に投げた。
+    BlockThrown {
+        src: MoveSrc,
+        dst: MoveDst,
+        block: Block,
+    },
+
+    /// マス `sq` のブロック `block` が消えた。
+    BlockErased { sq: BlocksSquare, block: Block },
+
+    /// マス `sq` のブロック `old` が `new` に置き換わった。
+    BlockReplaced {
+        sq: BlocksSquare,
+        old: Block,
+        new: Block,
+    },
+
+    /// ブロック `block` がマス `from` から `to` に落ちた。
+    BlockFell {
+        from: BlocksSquare,
+        to: BlocksSquare,
+        block: Block,
+    },
+
+    /// 保持ブロックが `old` から `new` に変わった。
+    HoldingChanged { old: Block, new: Block },
+}
+
+/// 着手 1 回分のイベント列と結果。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MoveEvents {
+    events: Vec<MoveEvent>,
+    position: Position,
+    cost: Cost,
+    cost_throw: Cost,
+}
+
+impl MoveEvents {
+    /// 起きた順のイベント列。
+    pub fn events(&self) -> &[MoveEvent] {
+        &self.events
+    }
+
+    /// 着手後の局面。
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// 総所要コスト。
+    pub fn cost(&self) -> Cost {
+        self.cost
+    }
+
+    /// ブロック投げコスト (総所要コストから自機の移動コストを引いたもの)。
+    pub fn cost_throw(&self) -> Cost {
+        self.cost_throw
+    }
+}
+
+/// 局面 `pos` で着手 `mv` を行い、その間に起こるイベント列と結果を返す。
+/// 着手が無効、または残り手数が 0 なら `None` を返す。
+pub fn simulate(pos: &Position, mv: Move) -> Option<MoveEvents> {
+    let (position, cost, cost_throw) = pos.do_move(mv)?;
+    let block_events = Blocks::from(pos.blocks())
+        .throw_events(mv.dst(), pos.block_holding(), pos.ruleset())
+        .unwrap();
+
+    let mut events = Vec::<MoveEvent>::new();
+
+    let mut row = pos.hero_row();
+    while row != mv.src() {
+        let inner = if row < mv.src() {
+            row.to_inner() + 1
+        } else {
+            row.to_inner() - 1
+        };
+        let to = MoveSrc::from_inner(inner).unwrap();
+        events.push(MoveEvent::HeroStepped { from: row, to });
+        row = to;
+    }
+
+    events.push(MoveEvent::BlockThrown {
+        src: mv.src(),
+        dst: mv.dst(),
+        block: pos.block_holding(),
+    });
+    events.extend(block_events);
+
+    if position.block_holding() != pos.block_holding() {
+        events.push(MoveEvent::HoldingChanged {
+            old: pos.block_holding(),
+            new: position.block_holding(),
+        });
+    }
+
+    Some(MoveEvents {
+        events,
+        position,
+        cost,
+        cost_throw,
+    })
+}
+
+/// 局面 `pos` での着手 `mv` の狙いを 1 行で説明する (`mv` が無効なら `None`)。
+///
+/// 何をどこで消すか (または入れ替えるか) と、次の保持ブロックで消せる投げ込み先 (`moves` から探す) を述べる。
+/// 例: `clears 3 of 2 in row 3, sets up holding=4 for row 5, column B`。
+pub fn move_rationale(pos: &Position, mv: Move, moves: &[Move]) -> Option<String> {
+    let events = simulate(pos, mv)?;
+    let pos_nxt = events.position();
+
+    let erased: Vec<_> = events
+        .events()
+        .iter()
+        .filter_map(|event| match *event {
+            MoveEvent::BlockErased { sq, block } => Some((sq, block)),
+            _ => None,
+        })
+        .collect();
+
+    let action = if let Some(&(_, block)) = erased.first() {
+        let count = erased.len();
+        let block = block.to_inner();
+        let in_row = erased.iter().all(|(sq, _)| sq.row() == erased[0].0.row());
+        let in_col = erased.iter().all(|(sq, _)| sq.col() == erased[0].0.col());
+        if count >= 2 && in_row && (matches!(mv.dst(), MoveDst::Horizontal(_)) || !in_col) {
+            let row = erased[0].0.row().to_inner();
+            tr(
+                format!("行 {row} の {block} を {count} 個消す"),
+                format!("clears {count} of {block} in row {row}"),
+            )
+        } else if count >= 2 && in_col {
+            let col = col_name(erased[0].0.col());
+            tr(
+                format!("列 {col} の {block} を {count} 個消す"),
+                format!("clears {count} of {block} in column {col}"),
+            )
+        } else {
+            let sqs = erased.iter().map(|&(sq, _)| square_name(sq)).join(" ");
+            tr(
+                format!("{sqs} の {block} を {count} 個消す"),
+                format!("clears {count} of {block} at {sqs}"),
+            )
+        }
+    } else {
+        // 消さない着手は入れ替え着手のみ。
+        let (sq, old, new) = events
+            .events()
+            .iter()
+            .find_map(|event| match *event {
+                MoveEvent::BlockReplaced { sq, old, new } => Some((sq, old, new)),
+                _ => None,
+            })
+            .unwrap();
+        let (sq, old, new) = (square_name(sq), old.to_inner(), new.to_inner());
+        tr(
+            format!("{sq} の {old} を {new} と入れ替える"),
+            format!("swaps the {old} at {sq} for {new}"),
+        )
+    };
+
+    let holding = pos_nxt.block_holding().to_inner();
+    let setup = if pos_nxt.is_stuck(moves) {
+        if pos_nxt.is_cleared() {
+            tr("面クリア".to_owned(), "clears the stage".to_owned())
+        } else {
+            tr(
+                "面終了 (ブロックが残りすぎ)".to_owned(),
+                "ends the stage with too many blocks".to_owned(),
+            )
+        }
+    } else {
+        let mut targets: Vec<_> = moves
+            .iter()
+            .filter(|&&mv_nxt| {
+                simulate(pos_nxt, mv_nxt).is_some_and(|events| {
+                    events
+                        .events()
+                        .iter()
+                        .any(|event| matches!(event, MoveEvent::BlockErased { .. }))
+                })
+            })
+            .map(|mv_nxt| mv_nxt.dst())
+            .collect();
+        targets.sort_unstable();
+        targets.dedup();
+        if targets.is_empty() {
+            tr(
+                format!("保持 {holding} (消せる投げ込み先なし)"),
+                format!("holding={holding} with no erasing follow-up"),
+            )
+        } else {
+            let targets = targets.into_iter().map(target_name).join(", ");
+            tr(
+                format!("保持 {holding} で {targets} を狙える"),
+                format!("sets up holding={holding} for {targets}"),
+            )
+        }
+    };
+
+    Some(tr(
+        format!("{action}、{setup}"),
+        format!("{action}, {setup}"),
+    ))
+}
+
+/// 投げ込み先の表示名 (`行 3`, `列 C` など)。
+pub(crate) fn target_name(dst: MoveDst) -> String {
+    match dst {
+        MoveDst::Horizontal(row) => tr(
+            format!("行 {}", row.to_inner()),
+            format!("row {}", row.to_inner()),
+        ),
+        MoveDst::Vertical(col) => {
+            let col = col_name(col);
+            tr(format!("列 {col}"), format!("column {col}"))
+        }
+    }
+}
+
+fn col_name(col: BlocksCol) -> char {
+    char::from(b'A' + col.to_inner() - 1)
+}
+
+/// マスの表示名 (`C4` など)。
+fn square_name(sq: BlocksSquare) -> String {
+    format!("{}{}", col_name(sq.col()), sq.row().to_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use crate::block::*;
+    use crate::move_::*;
+    use crate::ruleset::Ruleset;
+
+    use super::*;
+
+    fn parse_position(s: impl AsRef<str>) -> Position {
+        s.as_ref().parse().unwrap()
+    }
+
+    #[test]
+    fn test_simulate() {
+        let pos = parse_position(indoc! {"
+            10 1 5
+            ......
+            ......
+            ......
+            ......
+            1.....
+            231...
+        "});
+        let mv = Move::new(MOVE_SRC_ROW_11, MoveDst::Horizontal(BLOCKS_ROW_6));
+        let res = simulate(&pos, mv).unwrap();
+
+        assert_eq!(
+            res.events(),
+            [
+                MoveEvent::HeroStepped {
+                    from: MOVE_SRC_ROW_10,
+                    to: MOVE_SRC_ROW_11
+                },
+                MoveEvent::BlockThrown {
+                    src: MOVE_SRC_ROW_11,
+                    dst: MoveDst::Horizontal(BLOCKS_ROW_6),
+                    block: Block::Normal1
+                },
+                MoveEvent::BlockErased {
+                    sq: BlocksSquare::SqC6,
+                    block: Block::Normal1
+                },
+                MoveEvent::BlockReplaced {
+                    sq: BlocksSquare::SqB6,
+                    old: Block::Normal3,
+                    new: Block::Normal1
+                },
+                MoveEvent::HoldingChanged {
+                    old: Block::Normal1,
+                    new: Block::Normal3
+                },
+            ]
+        );
+        let (pos_nxt, cost, cost_throw) = pos.do_move(mv).unwrap();
+        assert_eq!(res.position(), &pos_nxt);
+        assert_eq!((res.cost(), res.cost_throw()), (cost, cost_throw));
+
+        // 横方向に投げると、消えたブロックの上のブロックが落ちる。
+        let mv = Move::new(MOVE_SRC_ROW_11, MoveDst::Horizontal(BLOCKS_ROW_6));
+        let pos = parse_position(indoc! {"
+            11 2 5
+            ......
+            ......
+            ......
+            ......
+            1.....
+            2.....
+        "});
+        let res = simulate(&pos, mv).unwrap();
+        assert_eq!(
+            &res.events()[1..],
+            [
+                MoveEvent::BlockErased {
+                    sq: BlocksSquare::SqA6,
+                    block: Block::Normal2
+                },
+                MoveEvent::BlockFell {
+                    from: BlocksSquare::SqA5,
+                    to: BlocksSquare::SqA6,
+                    block: Block::Normal1
+                },
+            ]
+        );
+
+        assert_eq!(
+            simulate(
+                &pos,
+                Move::new(MOVE_SRC_ROW_10, MoveDst::Horizontal(BLOCKS_ROW_1))
+            ),
+            None
+        );
+
+        // 残り手数が 0 なら着手できない (panic しない)。
+        let pos = parse_position(indoc! {"
+            11 2 0
+            ......
+            ......
+            ......
+            ......
+            1.....
+            2.....
+        "});
+        assert_eq!(simulate(&pos, mv), None);
+    }
+
+    #[test]
+    fn test_move_rationale() {
+        let pos = parse_position(indoc! {"
+            10 1 5
+            ......
+            ......
+            ......
+            ......
+            1.....
+            231...
+        "});
+        let mv = Move::new(MOVE_SRC_ROW_11, MoveDst::Horizontal(BLOCKS_ROW_6));
+        let mv_vert = Move::new(MOVE_SRC_ROW_0, MoveDst::Vertical(BLOCKS_COL_A));
+
+        // 言語設定は他のテストと共有されるので、どちらの言語でもよいことにする。
+        let rationale = move_rationale(&pos, mv, &[mv]).unwrap();
+        assert!(
+            [
+                "C6 の 1 を 1 個消す、面クリア",
+                "clears 1 of 1 at C6, clears the stage"
+            ]
+            .contains(&rationale.as_str()),
+            "{rationale}"
+        );
+
+        // 次の保持ブロック 3 では列 A を狙えない (A5 の 1 に当たる)。
+        let rationale = move_rationale(&pos, mv, &[mv, mv_vert]).unwrap();
+        assert!(
+            [
+                "C6 の 1 を 1 個消す、面クリア",
+                "clears 1 of 1 at C6, clears the stage"
+            ]
+            .contains(&rationale.as_str()),
+            "{rationale}"
+        );
+
+        let pos_swap = pos
+            .clone()
+            .with_ruleset(Ruleset::new().with_allow_swap(true));
+        let mv_swap = Move::new(MOVE_SRC_ROW_10, MoveDst::Horizontal(BLOCKS_ROW_5));
+        let pos_swap = pos_swap.do_move(mv).unwrap().0;
+        let rationale = move_rationale(&pos_swap, mv_swap, &[mv, mv_swap]).unwrap();
+        assert!(
+            [
+                "A5 の 1 を 3 と入れ替える、保持 1 で 行 6 を狙える",
+                "swaps the 1 at A5 for 3, sets up holding=1 for row 6"
+            ]
+            .contains(&rationale.as_str()),
+            "{rationale}"
+        );
+    }
+}
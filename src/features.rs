@@ -0,0 +1,158 @@
+use crate::block::{Block, BlocksCol, BlocksRow};
+use crate::position::Position;
+
+/// 局面の特徴量。評価関数 (学習したもの、手で調整したもの) の入力として使う安定した数値表現。
+///
+/// 行は上から、列は左から数える。
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PositionFeatures {
+    block_counts: [u8; 4],
+    col_heights: [u8; 6],
+    row_leading_runs: [u8; 6],
+    col_leading_runs: [u8; 6],
+    hero_row: u8,
+    block_holding: u8,
+    move_remain: u8,
+}
+
+impl PositionFeatures {
+    /// 特徴量を数値列にしたときの長さ。
+    pub const LEN: usize = 4 + 6 + 6 + 6 + 3;
+
+    /// 通常ブロックの種類ごとの個数。
+    pub fn block_counts(&self) -> [u8; 4] {
+        self.block_counts
+    }
+
+    /// 各列の高さ (最も上のブロックから最下段までのマス数。空なら 0)。
+    pub fn col_heights(&self) -> [u8; 6] {
+        self.col_heights
+    }
+
+    /// 各行について、横に投げたとき最初に当たるブロック (最も右のブロック) から左に見て
+    /// 同種のブロックが連続する個数 (空白は飛ばす)。空の行なら 0。
+    pub fn row_leading_runs(&self) -> [u8; 6] {
+        self.row_leading_runs
+    }
+
+    /// 各列について、縦に投げたとき最初に当たるブロック (最も上のブロック) から下に見て
+    /// 同種のブロックが連続する個数 (空白は飛ばす)。空の列なら 0。
+    pub fn col_leading_runs(&self) -> [u8; 6] {
+        self.col_leading_runs
+    }
+
+    pub fn hero_row(&self) -> u8 {
+        self.hero_row
+    }
+
+    pub fn block_holding(&self) -> u8 {
+        self.block_holding
+    }
+
+    pub fn move_remain(&self) -> u8 {
+        self.move_remain
+    }
+
+    /// 全ての特徴量を固定順の数値列にする。
+    /// 順序はブロック数、列の高さ、行の先頭連続数、列の先頭連続数、自機の位置、保持ブロック、残り手数。
+    pub fn to_array(&self) -> [u8; Self::LEN] {
+        let mut buf = [0; Self::LEN];
+
+        let values = self
+            .block_counts
+            .into_iter()
+            .chain(self.col_heights)
+            .chain(self.row_leading_runs)
+            .chain(self.col_leading_runs)
+            .chain([self.hero_row, self.block_holding, self.move_remain]);
+        for (dst, value) in buf.iter_mut().zip(values) {
+            *dst = value;
+        }
+
+        buf
+    }
+}
+
+impl Position {
+    /// 局面の特徴量を求める。
+    pub fn features(&self) -> PositionFeatures {
+        let blocks = self.blocks();
+
+        let mut features = PositionFeatures {
+            hero_row: self.hero_row().to_inner(),
+            block_holding: self.block_holding().to_inner(),
+            move_remain: self.move_remain(),
+            ..Default::default()
+        };
+
+        for (col, row) in itertools::iproduct!(BlocksCol::all(), BlocksRow::all()) {
+            if let Some(block) = blocks[(col, row)] {
+                features.block_counts[usize::from(block.to_inner() - Block::MIN_VALUE)] += 1;
+            }
+        }
+
+        for (i, col) in BlocksCol::all().into_iter().enumerate() {
+            let column = BlocksRow::all().map(|row| blocks[(col, row)]);
+            features.col_heights[i] = column
+                .iter()
+                .position(Option::is_some)
+                .map_or(0, |top| (6 - top) as u8);
+            features.col_leading_runs[i] = leading_run(column);
+        }
+
+        for (i, row) in BlocksRow::all().into_iter().enumerate() {
+            let mut line = BlocksCol::all().map(|col| blocks[(col, row)]);
+            line.reverse();
+            features.row_leading_runs[i] = leading_run(line);
+        }
+
+        features
+    }
+}
+
+/// 先頭から見て最初のブロックと同種のブロックが連続する個数を返す (空白は飛ばす)。
+fn leading_run(line: [Option<Block>; 6]) -> u8 {
+    let mut blocks = line.into_iter().flatten();
+
+    let Some(first) = blocks.next() else {
+        return 0;
+    };
+
+    1 + blocks.take_while(|&block| block == first).count() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_features() {
+        let pos: Position = indoc! {"
+            11 3 13
+            ......
+            ......
+            2444..
+            1222..
+            3333..
+            4111..
+        "}
+        .parse()
+        .unwrap();
+
+        let features = pos.features();
+        assert_eq!(features.block_counts(), [4, 4, 4, 4]);
+        assert_eq!(features.col_heights(), [4, 4, 4, 4, 0, 0]);
+        assert_eq!(features.row_leading_runs(), [0, 0, 3, 3, 4, 3]);
+        assert_eq!(features.col_leading_runs(), [1, 1, 1, 1, 0, 0]);
+        assert_eq!(features.hero_row(), 11);
+        assert_eq!(features.block_holding(), 3);
+        assert_eq!(features.move_remain(), 13);
+        assert_eq!(
+            features.to_array(),
+            [4, 4, 4, 4, 4, 4, 4, 4, 0, 0, 0, 0, 3, 3, 4, 3, 1, 1, 1, 1, 0, 0, 11, 3, 13]
+        );
+    }
+}
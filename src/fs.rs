@@ -0,0 +1,57 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// `write` で書いた内容でファイル `path` を置き換える。
+///
+/// 同じディレクトリの一時ファイルに書いて `sync_all()` してから rename するので、途中で中断しても `path`
+/// が壊れることはない。書き込みに失敗したら一時ファイルを消す。
+pub(crate) fn atomic_write(
+    path: impl AsRef<Path>,
+    write: impl FnOnce(&mut File) -> io::Result<()>,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let mut path_tmp = path.as_os_str().to_owned();
+    path_tmp.push(format!(".tmp{}", std::process::id()));
+
+    let res = File::create(&path_tmp).and_then(|mut file| {
+        write(&mut file)?;
+        file.sync_all()
+    });
+    let res = res.and_then(|()| std::fs::rename(&path_tmp, path));
+    if res.is_err() {
+        // 一時ファイルが作られていない場合もあるので、削除の失敗は無視する。
+        let _ = std::fs::remove_file(&path_tmp);
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use super::*;
+
+    #[test]
+    fn test_atomic_write() {
+        let dir = std::env::temp_dir().join(format!("fas-atomic-write-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.txt");
+
+        atomic_write(&path, |w| w.write_all(b"first")).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first");
+
+        // 書き込みに失敗したら元のファイルはそのままで、一時ファイルも残らない。
+        let err = atomic_write(&path, |w| {
+            w.write_all(b"second")?;
+            Err(io::Error::other("fail"))
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,198 @@
+use rand::seq::SliceRandom as _;
+use rand::SeedableRng as _;
+use rand_chacha::ChaCha8Rng;
+
+use crate::block::Block;
+use crate::problem::{Problem, ProblemBoard, ProblemTile};
+
+/// ランダムな問題の生成設定。
+///
+/// 左下 6x6 のうち左下隅の `col_count` x `row_count` の長方形をランダムな通常ブロックで埋め、
+/// 左上隅に `wall_height` 段の階段状の壁を置く。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GeneratorConfig {
+    col_count: usize,
+    row_count: usize,
+    block_kind_count: u8,
+    wall_height: usize,
+    move_remain: u8,
+    wild_holding: bool,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            col_count: 4,
+            row_count: 4,
+            block_kind_count: 4,
+            wall_height: 4,
+            move_remain: 20,
+            wild_holding: false,
+        }
+    }
+}
+
+impl GeneratorConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ブロックを置く列数 (1..=6)。
+    pub fn col_count(self) -> usize {
+        self.col_count
+    }
+
+    /// ブロックを置く行数 (1..=6)。
+    pub fn row_count(self) -> usize {
+        self.row_count
+    }
+
+    /// 使うブロックの種類数 (1..=4)。
+    pub fn block_kind_count(self) -> u8 {
+        self.block_kind_count
+    }
+
+    /// 階段状の壁の段数 (0..=6)。
+    pub fn wall_height(self) -> usize {
+        self.wall_height
+    }
+
+    /// 残り手数。
+    pub fn move_remain(self) -> u8 {
+        self.move_remain
+    }
+
+    /// 初期保持ブロックをワイルドカードにするかどうか。
+    /// しない場合、初期保持ブロックは使うブロックの種類からランダムに選ぶ。
+    pub fn wild_holding(self) -> bool {
+        self.wild_holding
+    }
+
+    pub fn with_col_count(mut self, col_count: usize) -> Self {
+        assert!((1..=6).contains(&col_count));
+        self.col_count = col_count;
+        self
+    }
+
+    pub fn with_row_count(mut self, row_count: usize) -> Self {
+        assert!((1..=6).contains(&row_count));
+        self.row_count = row_count;
+        self
+    }
+
+    pub fn with_block_kind_count(mut self, block_kind_count: u8) -> Self {
+        assert!((1..=4).contains(&block_kind_count));
+        self.block_kind_count = block_kind_count;
+        self
+    }
+
+    pub fn with_wall_height(mut self, wall_height: usize) -> Self {
+        assert!((0..=6).contains(&wall_height));
+        self.wall_height = wall_height;
+        self
+    }
+
+    pub fn with_move_remain(mut self, move_remain: u8) -> Self {
+        self.move_remain = move_remain;
+        self
+    }
+
+    pub fn with_wild_holding(mut self, wild_holding: bool) -> Self {
+        self.wild_holding = wild_holding;
+        self
+    }
+}
+
+/// シードから乱数生成器を作る。
+///
+/// 乱数を使う機能は全てこれを用いる。プラットフォームや rand のバージョンによらず同じシードから同じ乱数列が得られる。
+pub(crate) fn rng_from_seed(seed: u64) -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(seed)
+}
+
+/// 設定に従ってランダムな問題を生成する。同じ設定とシードからは常に同じ問題が生成される。
+pub fn generate_problem(config: &GeneratorConfig, seed: u64) -> Problem {
+    let rng = &mut rng_from_seed(seed);
+
+    let kinds: Vec<_> = (Block::MIN_VALUE..Block::MIN_VALUE + config.block_kind_count)
+        .map(|inner| Block::from_inner(inner).unwrap())
+        .collect();
+
+    let mut board = ProblemBoard::new();
+
+    // 左上隅の階段状の壁。上から順に幅が 1 ずつ狭くなる。
+    for row in 0..config.wall_height {
+        for col in 0..config.wall_height - row {
+            board[(col, row)] = Some(ProblemTile::Wall);
+        }
+    }
+
+    // 左下隅の長方形のブロック。
+    for row in 12 - config.row_count..12 {
+        for col in 0..config.col_count {
+            let block = *kinds.choose(rng).unwrap();
+            board[(col, row)] = Some(ProblemTile::Block(block));
+        }
+    }
+
+    let block_holding = if config.wild_holding {
+        Block::Wild
+    } else {
+        *kinds.choose(rng).unwrap()
+    };
+
+    Problem::new(board, block_holding, config.move_remain)
+        .expect("生成した問題は ADVANCE モードの制約を満たすはず")
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_generate_problem() {
+        let config = GeneratorConfig::new()
+            .with_col_count(3)
+            .with_row_count(2)
+            .with_block_kind_count(2)
+            .with_wall_height(2);
+
+        let problem = generate_problem(&config, 1);
+
+        let (pos, _) = problem.to_position_and_moves();
+        assert_eq!(pos.block_count(), 3 * 2);
+        assert!(matches!(
+            problem.block_holding(),
+            Block::Normal1 | Block::Normal2
+        ));
+        assert!(problem.board()[(1, 0)].is_some_and(ProblemTile::is_wall));
+        assert!(problem.board()[(2, 0)].is_none());
+
+        // 同じシードからは同じ問題が生成される。
+        let problem_again = generate_problem(&config, 1);
+        assert_eq!(problem_again.to_string(), problem.to_string());
+
+        // 生成される問題はプラットフォームや rand のバージョンによらない。
+        assert_eq!(
+            problem.to_string(),
+            indoc! {"
+                2 20
+                ##......
+                #.......
+                ........
+                ........
+                ........
+                ........
+                ........
+                ........
+                ........
+                ........
+                211.....
+                212.....
+            "}
+        );
+    }
+}
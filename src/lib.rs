@@ -1,13 +1,103 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod anytime;
+#[cfg(feature = "std")]
+pub mod assistant;
+#[cfg(feature = "std")]
+pub mod convert;
+#[cfg(feature = "corpus")]
+pub mod corpus;
+#[cfg(feature = "std")]
+pub mod generator;
+#[cfg(feature = "std")]
+pub mod mcts;
+#[cfg(feature = "std")]
+pub mod movie;
+#[cfg(feature = "std")]
+pub mod parallel;
+#[cfg(feature = "std")]
+pub mod ram;
+#[cfg(feature = "std")]
+pub mod records;
+#[cfg(feature = "std")]
+pub mod restart;
+#[cfg(feature = "std")]
+pub mod rom;
+#[cfg(feature = "std")]
+pub mod rpc;
+#[cfg(feature = "std")]
+pub mod run_time;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "std")]
+pub mod stress;
+#[cfg(feature = "std")]
+pub mod training;
+
 mod block;
+mod blocks_bits;
+mod blocks_dyn;
+mod certificate;
+mod checkpoint;
+mod config;
+mod constraint;
 mod cost;
+mod enumerate;
+mod error;
+mod events;
+mod features;
+#[cfg(feature = "std")]
+mod fs;
+mod locale;
 mod move_;
+mod objective;
+mod pattern;
 mod position;
+mod precheck;
 mod problem;
+mod profile;
+mod ruleset;
+mod score;
+mod search_tree;
+mod session;
 mod solver;
+mod stage;
+mod tablebase;
+mod timeline;
+mod tt;
+mod zobrist;
 
 pub use self::block::*;
+pub use self::blocks_bits::*;
+pub use self::blocks_dyn::*;
+pub use self::certificate::*;
+pub use self::checkpoint::*;
+pub use self::config::*;
+pub use self::constraint::*;
 pub use self::cost::*;
+pub use self::enumerate::*;
+pub use self::error::*;
+pub use self::events::*;
+pub use self::features::*;
+pub use self::locale::*;
 pub use self::move_::*;
+pub use self::objective::*;
+pub use self::pattern::*;
 pub use self::position::*;
+pub use self::precheck::*;
 pub use self::problem::*;
+pub use self::profile::*;
+pub use self::ruleset::*;
+pub use self::score::*;
+pub use self::search_tree::*;
+pub use self::session::*;
 pub use self::solver::*;
+pub use self::stage::*;
+pub use self::tablebase::*;
+pub use self::timeline::*;
+pub use self::tt::*;
@@ -0,0 +1,91 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use alloc::borrow::ToOwned as _;
+
+use crate::error::Error;
+
+static CURRENT: AtomicU8 = AtomicU8::new(Locale::Ja as u8);
+
+/// エラーメッセージやレポートの言語。
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Locale {
+    #[default]
+    Ja,
+    En,
+}
+
+impl Locale {
+    /// 現在の言語を返す。デフォルトは日本語。
+    pub fn current() -> Self {
+        match CURRENT.load(Ordering::Relaxed) {
+            0 => Self::Ja,
+            _ => Self::En,
+        }
+    }
+
+    /// 以後のメッセージの言語を設定する (プロセス全体で共有される)。
+    pub fn set_current(self) {
+        CURRENT.store(self as u8, Ordering::Relaxed);
+    }
+
+    /// 言語に応じて `ja` または `en` を返す。
+    pub fn select<T>(self, ja: T, en: T) -> T {
+        match self {
+            Self::Ja => ja,
+            Self::En => en,
+        }
+    }
+}
+
+impl core::str::FromStr for Locale {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ja" => Ok(Self::Ja),
+            "en" => Ok(Self::En),
+            _ => Err(Error::UnknownLocale(s.to_owned())),
+        }
+    }
+}
+
+/// 現在の言語に応じて `ja` または `en` を返す。
+pub fn tr<T>(ja: T, en: T) -> T {
+    Locale::current().select(ja, en)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString as _;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::error::{ParseError, ParseErrorKind};
+
+    use super::*;
+
+    #[test]
+    fn test_locale() {
+        assert_eq!("en".parse::<Locale>(), Ok(Locale::En));
+        assert!("fr".parse::<Locale>().is_err());
+
+        let err = ParseError::new(3, ParseErrorKind::InvalidChar('?'));
+
+        Locale::En.set_current();
+        let msg_en = err.to_string();
+        Locale::Ja.set_current();
+        let msg_ja = err.to_string();
+
+        assert_eq!(msg_en, "line 3: invalid char: '?'");
+        assert_eq!(msg_ja, "3 行目: 無効な文字: '?'");
+
+        let err = err.with_col(5);
+        Locale::En.set_current();
+        let msg_en = err.to_string();
+        Locale::Ja.set_current();
+        let msg_ja = err.to_string();
+
+        assert_eq!(msg_en, "line 3, column 5: invalid char: '?'");
+        assert_eq!(msg_ja, "3 行 5 列目: 無効な文字: '?'");
+    }
+}
@@ -0,0 +1,329 @@
+use log::info;
+use rand::seq::SliceRandom as _;
+use rand::Rng;
+
+use crate::config::SearchOutcome;
+use crate::cost::{calc_min_move_cost, Cost};
+use crate::generator::rng_from_seed;
+use crate::move_::{Move, MoveSrc};
+use crate::objective::Objective;
+use crate::position::Position;
+use crate::problem::Problem;
+use crate::solver::Solution;
+
+/// UCT の探索項の係数。報酬は 0..=1 に正規化する。
+const UCT_EXPLORATION: f64 = 0.5;
+
+/// モンテカルロ木探索 (MCTS) で問題を解く。
+///
+/// 木の葉からランダムにプレイアウトして得た解の総コストを報酬とし、UCT で有望な着手を優先して木を広げる。
+/// 深さ優先探索の着手順が悪い面向けの代替手段。
+/// 面の終了した局面、総コストの下界 (`solve_problem_ida()` と同じ) が暫定解以上の局面、
+/// および子を全て調べ終えた局面は解決済みとして以降選ばないので、プレイアウトが十分多ければ木全体を調べ終え、
+/// 結果は最適解になる。その場合は `SearchOutcome::is_complete()` が真になる。
+/// `playouts` 回のプレイアウトで調べ終えなければ、それまでの最善の解を返す (最適とは限らない)。
+pub fn solve_problem_mcts(
+    problem: &Problem,
+    last_stage: bool,
+    playouts: u64,
+    seed: u64,
+) -> SearchOutcome {
+    let (pos, moves) = problem.to_position_and_moves();
+    let min_throw_cost = moves
+        .iter()
+        .map(|mv| calc_min_move_cost(mv.src()))
+        .min()
+        .unwrap_or(0);
+
+    let mut tree = Tree {
+        moves: &moves,
+        last_stage,
+        min_throw_cost,
+        nodes: vec![],
+        best: None,
+    };
+    tree.push_node(None, None, pos, 0, 0);
+    let rng = &mut rng_from_seed(seed);
+
+    info!("mcts start (playouts: {playouts})");
+    let mut playout_count = 0;
+    while playout_count < playouts && !tree.nodes[0].solved {
+        tree.iterate(rng);
+        playout_count += 1;
+    }
+    let complete = tree.nodes[0].solved;
+    info!(
+        "mcts end (playouts: {playout_count}, nodes: {})",
+        tree.nodes.len()
+    );
+
+    let best = tree.best.map(|(srcs, cost)| (Solution::new(srcs), cost));
+
+    SearchOutcome::new(best, complete)
+}
+
+#[derive(Debug)]
+struct Node {
+    parent: Option<usize>,
+    mv: Option<Move>,
+    pos: Position,
+    cost: Cost,
+    cost_last_throw: Cost,
+    /// 展開済みなら子の添字。
+    children: Option<Vec<usize>>,
+    visits: u64,
+    reward_sum: f64,
+    /// この局面以降に暫定解より良い解がないことが分かっているかどうか。
+    solved: bool,
+}
+
+#[derive(Debug)]
+struct Tree<'a> {
+    moves: &'a [Move],
+    last_stage: bool,
+    min_throw_cost: Cost,
+    nodes: Vec<Node>,
+    best: Option<(Vec<MoveSrc>, Cost)>,
+}
+
+impl Tree<'_> {
+    fn best_cost(&self) -> Cost {
+        self.best.as_ref().map_or(Cost::MAX, |&(_, cost)| cost)
+    }
+
+    /// 選択、展開、プレイアウト、逆伝播を 1 回ずつ行う。
+    fn iterate(&mut self, rng: &mut impl Rng) {
+        // 選択: 未展開の局面に至るまで UCT で子を選ぶ。
+        let mut idx = 0;
+        while let Some(children) = &self.nodes[idx].children {
+            let children = children.clone();
+            match self.select_child(idx, &children) {
+                Some(child) => idx = child,
+                None => {
+                    self.mark_solved(idx);
+                    return;
+                }
+            }
+        }
+
+        // 展開: 子を全て作り、未解決の子からランダムに 1 つ選ぶ。
+        let children = self.expand(idx);
+        let unsolved: Vec<_> = children
+            .iter()
+            .copied()
+            .filter(|&child| !self.nodes[child].solved)
+            .collect();
+        self.nodes[idx].children = Some(children);
+        let Some(&leaf) = unsolved.choose(rng) else {
+            self.mark_solved(idx);
+            return;
+        };
+
+        // プレイアウトと逆伝播。
+        let reward = self.playout(leaf, rng);
+        let mut cur = Some(leaf);
+        while let Some(i) = cur {
+            let node = &mut self.nodes[i];
+            node.visits += 1;
+            node.reward_sum += reward;
+            cur = node.parent;
+        }
+    }
+
+    /// 未解決の子のうち UCT の値が最大のものを返す。下界が暫定解以上の子はここで解決済みにする。
+    fn select_child(&mut self, idx: usize, children: &[usize]) -> Option<usize> {
+        let best_cost = self.best_cost();
+        let ln_visits = (self.nodes[idx].visits.max(1) as f64).ln();
+
+        let mut res = None;
+        let mut value_max = f64::NEG_INFINITY;
+        for &child in children {
+            if !self.nodes[child].solved && self.cost_lower(child) >= best_cost {
+                self.nodes[child].solved = true;
+            }
+            let node = &self.nodes[child];
+            if node.solved {
+                continue;
+            }
+            let value = if node.visits == 0 {
+                f64::INFINITY
+            } else {
+                let visits = node.visits as f64;
+                node.reward_sum / visits + UCT_EXPLORATION * (ln_visits / visits).sqrt()
+            };
+            if value > value_max {
+                value_max = value;
+                res = Some(child);
+            }
+        }
+
+        res
+    }
+
+    /// 局面 `idx` の子を全て作って返す。面の終了した子は解決済みにする。
+    fn expand(&mut self, idx: usize) -> Vec<usize> {
+        let mut children = vec![];
+        for &mv in self.moves {
+            let Some((pos, cost_mv, cost_throw)) = self.nodes[idx].pos.do_move(mv) else {
+                continue;
+            };
+            let cost = self.nodes[idx].cost + cost_mv;
+            children.push(self.push_node(Some(idx), Some(mv), pos, cost, cost_throw));
+        }
+        children
+    }
+
+    fn push_node(
+        &mut self,
+        parent: Option<usize>,
+        mv: Option<Move>,
+        pos: Position,
+        cost: Cost,
+        cost_last_throw: Cost,
+    ) -> usize {
+        let idx = self.nodes.len();
+        self.nodes.push(Node {
+            parent,
+            mv,
+            pos,
+            cost,
+            cost_last_throw,
+            children: None,
+            visits: 0,
+            reward_sum: 0.0,
+            solved: false,
+        });
+
+        let node = &self.nodes[idx];
+        if node.pos.is_stuck(self.moves) {
+            if node.pos.is_cleared() {
+                let cost_total = self.cost_total(&node.pos, node.cost, node.cost_last_throw);
+                let srcs = self.srcs(idx);
+                self.update_best(srcs, cost_total);
+            }
+            self.nodes[idx].solved = true;
+        } else if node.pos.move_remain() == 0 || self.cost_lower(idx) >= self.best_cost() {
+            self.nodes[idx].solved = true;
+        }
+
+        idx
+    }
+
+    /// 局面 `idx` からランダムに着手して面を終え、報酬を返す。
+    /// 報酬は面をクリアしたら (暫定解のコスト / 総コスト)、クリアできなければ 0。
+    fn playout(&mut self, idx: usize, rng: &mut impl Rng) -> f64 {
+        let mut pos = self.nodes[idx].pos.clone();
+        let mut cost = self.nodes[idx].cost;
+        let mut cost_last_throw = self.nodes[idx].cost_last_throw;
+        let mut srcs = self.srcs(idx);
+
+        while !pos.is_stuck(self.moves) && pos.move_remain() > 0 {
+            let nexts: Vec<_> = self
+                .moves
+                .iter()
+                .filter_map(|&mv| pos.do_move(mv).map(|next| (mv, next)))
+                .collect();
+            let Some(&(mv, (ref pos_nxt, cost_mv, cost_throw))) = nexts.choose(rng) else {
+                break;
+            };
+            pos = pos_nxt.clone();
+            cost += cost_mv;
+            cost_last_throw = cost_throw;
+            srcs.push(mv.src());
+        }
+
+        if !pos.is_stuck(self.moves) || !pos.is_cleared() {
+            return 0.0;
+        }
+        let cost_total = self.cost_total(&pos, cost, cost_last_throw);
+        self.update_best(srcs, cost_total);
+
+        f64::from(self.best_cost()) / f64::from(cost_total.max(1))
+    }
+
+    /// 局面 `idx` を解決済みにし、子が全て解決済みになった祖先も解決済みにする。
+    fn mark_solved(&mut self, idx: usize) {
+        let mut cur = Some(idx);
+        while let Some(i) = cur {
+            let all_solved = self.nodes[i].children.as_ref().map_or(true, |children| {
+                children.iter().all(|&c| self.nodes[c].solved)
+            });
+            if i != idx && !all_solved {
+                break;
+            }
+            self.nodes[i].solved = true;
+            cur = self.nodes[i].parent;
+        }
+    }
+
+    fn update_best(&mut self, srcs: Vec<MoveSrc>, cost_total: Cost) {
+        if cost_total < self.best_cost() {
+            info!("improve: {cost_total} {srcs:?}");
+            self.best = Some((srcs, cost_total));
+        }
+    }
+
+    /// 面が終了した局面 `pos` の総コスト。
+    fn cost_total(&self, pos: &Position, cost: Cost, cost_last_throw: Cost) -> Cost {
+        if self.last_stage {
+            cost - cost_last_throw + 1
+        } else {
+            cost + Objective::Frames.clear_cost(pos.block_count())
+        }
+    }
+
+    /// 局面 `idx` 以降の解の総コストの下界。解けないことが分かっていれば `Cost::MAX`。
+    fn cost_lower(&self, idx: usize) -> Cost {
+        let node = &self.nodes[idx];
+        let move_count = node.pos.min_moves_to_solve().max(1);
+        if move_count > usize::from(node.pos.move_remain()) {
+            return Cost::MAX;
+        }
+        if self.last_stage {
+            node.cost + self.min_throw_cost * (move_count - 1) as Cost + 1
+        } else {
+            node.cost + self.min_throw_cost * move_count as Cost
+        }
+    }
+
+    /// 根から局面 `idx` までの着手列。
+    fn srcs(&self, idx: usize) -> Vec<MoveSrc> {
+        let mut srcs = vec![];
+        let mut cur = idx;
+        while let Some(mv) = self.nodes[cur].mv {
+            srcs.push(mv.src());
+            cur = self.nodes[cur].parent.unwrap();
+        }
+        srcs.reverse();
+        srcs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::solver::solve_problem;
+
+    use super::*;
+
+    #[test]
+    fn test_solve_problem_mcts() {
+        let problem: Problem = include_str!("../problem/01.in").parse().unwrap();
+        let (_, cost_opt) = solve_problem(&problem, false).unwrap();
+
+        // プレイアウトが少なければ最適とは限らないが、正しい解を返す。
+        let outcome = solve_problem_mcts(&problem, false, 10, 0);
+        if let Some((solution, cost)) = outcome.best() {
+            assert_eq!(solution.verify(&problem, false), Ok(*cost));
+            assert!(*cost >= cost_opt);
+        }
+
+        // プレイアウトが十分多ければ木全体を調べ終え、最適解を返す。
+        let outcome = solve_problem_mcts(&problem, false, u64::MAX, 0);
+        assert!(outcome.is_complete());
+        let (solution, cost) = outcome.best().unwrap();
+        assert_eq!(*cost, cost_opt);
+        assert_eq!(solution.verify(&problem, false), Ok(cost_opt));
+    }
+}
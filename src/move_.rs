@@ -1,10 +1,9 @@
-use anyhow::anyhow;
-
 use crate::block::{BlocksCol, BlocksRow};
+use crate::error::Error;
 
 /// ブロックをどの行から投げるか。
 #[repr(u8)]
-#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum MoveSrc {
     Row0 = 0,
     Row1,
@@ -49,7 +48,7 @@ impl MoveSrc {
     pub unsafe fn from_inner_unchecked(inner: u8) -> Self {
         assert!(Self::is_valid(inner));
 
-        std::mem::transmute(inner)
+        core::mem::transmute(inner)
     }
 
     pub const fn to_inner(self) -> u8 {
@@ -83,7 +82,7 @@ impl MoveSrc {
 }
 
 impl TryFrom<MoveSrc> for BlocksRow {
-    type Error = anyhow::Error;
+    type Error = Error;
 
     fn try_from(src: MoveSrc) -> Result<Self, Self::Error> {
         match src {
@@ -93,19 +92,22 @@ impl TryFrom<MoveSrc> for BlocksRow {
             MoveSrc::Row9 => Ok(Self::Row4),
             MoveSrc::Row10 => Ok(Self::Row5),
             MoveSrc::Row11 => Ok(Self::Row6),
-            _ => Err(anyhow!("ブロック領域の行ではない: {src:?}")),
+            _ => Err(Error::NotBlocksRow(src)),
         }
     }
 }
 
 /// ブロックをどの行/列に投げるか。
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+///
+/// 順序は横方向の着手が先で、同じ方向の中では行/列の順。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum MoveDst {
     Horizontal(BlocksRow),
     Vertical(BlocksCol),
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// 着手。順序は (着手位置, 投げ込み先) の辞書式順序。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Move {
     src: MoveSrc,
     dst: MoveDst,
@@ -0,0 +1,319 @@
+use crate::cost::{Cost, COST_HERO_STEP};
+use crate::error::{ParseError, ParseErrorKind, VerifyError};
+use crate::move_::MoveSrc;
+use crate::problem::Problem;
+use crate::profile::TimingProfile;
+use crate::solver::Solution;
+use crate::stage::Stage;
+
+/// ムービーの 1 フレーム分の 1P の入力 (このソルバーが扱うボタンのみ)。
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MovieFrame {
+    up: bool,
+    down: bool,
+    a: bool,
+}
+
+impl MovieFrame {
+    pub fn new(up: bool, down: bool, a: bool) -> Self {
+        Self { up, down, a }
+    }
+
+    pub fn up(self) -> bool {
+        self.up
+    }
+
+    pub fn down(self) -> bool {
+        self.down
+    }
+
+    pub fn a(self) -> bool {
+        self.a
+    }
+}
+
+/// FCEUX のムービー (fm2) または BizHawk のムービーの入力ログ (bk2 内の `Input Log.txt`) をパースする。
+///
+/// `|` で始まる行を 1 フレームとみなし、それ以外の行 (ヘッダなど) は無視する。
+/// 形式は行の最初のフィールドが数値 (fm2 のコマンド) かどうかで判定する。
+pub fn parse_movie(s: &str) -> Result<Vec<MovieFrame>, ParseError> {
+    let mut frames = Vec::<MovieFrame>::new();
+
+    for (row, line) in s.lines().enumerate() {
+        let err = |kind| ParseError::new(row + 1, kind);
+
+        let Some(line) = line.strip_prefix('|') else {
+            continue;
+        };
+        let fields: Vec<_> = line.split('|').collect();
+        if fields.len() < 2 {
+            return Err(err(ParseErrorKind::TokenCount {
+                expected: 2,
+                actual: fields.len(),
+            }));
+        }
+
+        let port: Vec<_> = fields[1].chars().collect();
+        if port.len() != 8 {
+            return Err(err(ParseErrorKind::LineLength {
+                expected: 8,
+                actual: port.len(),
+            }));
+        }
+        let pressed = |i: usize| !matches!(port[i], '.' | ' ');
+
+        // fm2 のボタン順は RLDUTSBA、BizHawk は UDLRsSBA。
+        let is_fm2 = !fields[0].is_empty() && fields[0].bytes().all(|b| b.is_ascii_digit());
+        let frame = if is_fm2 {
+            MovieFrame::new(pressed(3), pressed(2), pressed(7))
+        } else {
+            MovieFrame::new(pressed(0), pressed(1), pressed(7))
+        };
+        frames.push(frame);
+    }
+
+    Ok(frames)
+}
+
+/// ムービーの挙動がコストモデルと食い違った箇所。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Divergence {
+    /// モデル上では自機の移動中またはブロックが落ち着く前のフレームに入力があった (その入力は無視した)。
+    InputWhileBusy { frame: usize },
+
+    /// 面が終わる前にムービーが終わった。
+    MovieEnded,
+}
+
+/// ムービーから復元した 1 面分の結果。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StageReport {
+    stage: Stage,
+    solution: Solution,
+    frame_start: usize,
+    frame_end: usize,
+    result: Result<Cost, VerifyError>,
+    divergences: Vec<Divergence>,
+}
+
+impl StageReport {
+    /// 面 (`problems` 中の 1-based の位置)。
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    /// ムービーの入力から復元した解。
+    pub fn solution(&self) -> &Solution {
+        &self.solution
+    }
+
+    /// この面で最初の入力があったフレーム。
+    pub fn frame_start(&self) -> usize {
+        self.frame_start
+    }
+
+    /// モデル上でこの面の最後の手の盤面が落ち着くフレーム。
+    pub fn frame_end(&self) -> usize {
+        self.frame_end
+    }
+
+    /// 復元した解の検証結果 (コストまたはエラー)。
+    pub fn result(&self) -> &Result<Cost, VerifyError> {
+        &self.result
+    }
+
+    pub fn divergences(&self) -> &[Divergence] {
+        &self.divergences
+    }
+
+    /// モデルと食い違わずに面を解いているかどうか。
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok() && self.divergences.is_empty()
+    }
+}
+
+/// 全面通しのムービーの入力から面ごとの解を復元し、それぞれをモデルで検証する。
+///
+/// `problems` は面の順に並べた問題 (最終面かどうかはメタデータの面番号で決める) で、`Stage::MAX` 個まで。
+/// ムービーのフレーム `frame_first` から最初の面が始まるとし、各面の終了後 `transition` フレームの入力は
+/// 面の切り替え演出中のものとして無視する。
+///
+/// 各面では自機が下端 (行 11) から始まるとし、上下ボタンを押したフレームで自機が 1 歩動き、
+/// A ボタンを押したフレームで自機の行からブロックを投げるとみなす。面が終わると次の面に進む。
+pub fn verify_movie(
+    problems: &[Problem],
+    frames: &[MovieFrame],
+    frame_first: usize,
+    transition: usize,
+    profile: TimingProfile,
+) -> Vec<StageReport> {
+    assert!(problems.len() <= usize::from(Stage::MAX));
+
+    let mut reports = Vec::<StageReport>::with_capacity(problems.len());
+    let mut frame = frame_first;
+
+    for (problem, stage) in problems.iter().zip(1..) {
+        let (mut pos, moves) = problem.to_position_and_moves();
+        let mut hero_row = pos.hero_row();
+        let mut srcs = Vec::<MoveSrc>::new();
+        let mut divergences = Vec::<Divergence>::new();
+        let mut frame_start = None;
+        let mut frame_ready = frame;
+
+        while !pos.is_stuck(&moves) && pos.move_remain() > 0 {
+            let Some(&input) = frames.get(frame) else {
+                divergences.push(Divergence::MovieEnded);
+                break;
+            };
+            let input_prev = frame
+                .checked_sub(1)
+                .map_or_else(MovieFrame::default, |i| frames[i]);
+            let up = input.up && !input_prev.up;
+            let down = input.down && !input_prev.down;
+            let a = input.a && !input_prev.a;
+
+            if up || down || a {
+                if frame < frame_ready {
+                    divergences.push(Divergence::InputWhileBusy { frame });
+                } else if a {
+                    frame_start.get_or_insert(frame);
+                    srcs.push(hero_row);
+                    let Some((pos_nxt, _, cost_throw)) = problem
+                        .move_for_src(hero_row)
+                        .and_then(|mv| pos.do_move(mv))
+                    else {
+                        // 不正な着手は検証で報告する。
+                        frame_ready = frame;
+                        break;
+                    };
+                    pos = pos_nxt;
+                    frame_ready = frame + profile.adjust_move_cost(cost_throw) as usize;
+                } else {
+                    frame_start.get_or_insert(frame);
+                    let row = if up {
+                        hero_row.to_inner().saturating_sub(1)
+                    } else {
+                        hero_row.to_inner() + 1
+                    };
+                    hero_row = MoveSrc::from_inner(row).unwrap_or(hero_row);
+                    frame_ready = frame + COST_HERO_STEP as usize;
+                }
+            }
+
+            frame += 1;
+        }
+
+        let solution = Solution::new(srcs);
+        let result = solution.verify(problem, problem.metadata().is_last_stage());
+        let frame_end = frame_ready.max(frame);
+        reports.push(StageReport {
+            stage,
+            solution,
+            frame_start: frame_start.unwrap_or(frame_end),
+            frame_end,
+            result,
+            divergences,
+        });
+
+        frame = frame_end + transition;
+    }
+
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::timeline::solution_timeline;
+
+    use super::*;
+
+    /// 解のタイムラインどおりに入力する fm2 のムービーを作る。
+    fn solution_to_fm2(problem: &Problem, solution: &Solution, frame_origin: usize) -> String {
+        let timings = solution_timeline(problem, solution, frame_origin as Cost).unwrap();
+        let len = timings.last().unwrap().frame_settle() as usize + 10;
+        let mut frames = vec![MovieFrame::default(); len];
+
+        let mut hero_row = MoveSrc::from_inner(11).unwrap();
+        for timing in &timings {
+            let step_count = hero_row.to_inner().abs_diff(timing.src().to_inner());
+            let up = timing.src() < hero_row;
+            for k in 0..usize::from(step_count) {
+                let frame = timing.frame_start() as usize + COST_HERO_STEP as usize * k;
+                frames[frame] = MovieFrame::new(up, !up, false);
+            }
+            frames[timing.frame_press() as usize] = MovieFrame::new(false, false, true);
+            hero_row = timing.src();
+        }
+
+        let mut s = "version 3\n".to_owned();
+        for frame in frames {
+            let ch = |pressed: bool, ch: char| if pressed { ch } else { '.' };
+            s.push_str(&format!(
+                "|0|..{}{}...{}|........||\n",
+                ch(frame.down, 'D'),
+                ch(frame.up, 'U'),
+                ch(frame.a, 'A')
+            ));
+        }
+        s
+    }
+
+    #[test]
+    fn test_parse_movie() {
+        let frames =
+            parse_movie("version 3\n|0|...U...A|........||\n|..|.D......|........|\n").unwrap();
+        assert_eq!(
+            frames,
+            [
+                MovieFrame::new(true, false, true),
+                MovieFrame::new(false, true, false)
+            ]
+        );
+
+        assert_eq!(
+            parse_movie("|0|..U|\n"),
+            Err(ParseError::new(
+                1,
+                ParseErrorKind::LineLength {
+                    expected: 8,
+                    actual: 3
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_verify_movie() {
+        let problem: Problem = include_str!("../problem/01.in").parse().unwrap();
+        let solution: Solution = "10 9 10 11".parse().unwrap();
+        let s = solution_to_fm2(&problem, &solution, 5);
+        let frames = parse_movie(&s).unwrap();
+
+        let reports = verify_movie(
+            std::slice::from_ref(&problem),
+            &frames,
+            0,
+            0,
+            TimingProfile::default(),
+        );
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].is_ok());
+        assert_eq!(reports[0].solution(), &solution);
+        assert_eq!(reports[0].result(), &Ok(443));
+        assert_eq!(reports[0].frame_start(), 5);
+
+        // 2 手目の移動を 1 フレーム早めると、モデル上は入力を受け付けない。
+        let mut frames_early = frames.clone();
+        let timings = solution_timeline(&problem, &solution, 5).unwrap();
+        let frame = timings[1].frame_start() as usize;
+        frames_early.swap(frame - 1, frame);
+        let reports = verify_movie(&[problem], &frames_early, 0, 0, TimingProfile::default());
+        assert_eq!(
+            reports[0].divergences()[0],
+            Divergence::InputWhileBusy { frame: frame - 1 }
+        );
+        assert!(!reports[0].is_ok());
+    }
+}
@@ -0,0 +1,208 @@
+use core::cmp::Ordering;
+
+use crate::cost::{Cost, COST_CLEAR_ERASE_BLOCK};
+use crate::move_::MoveSrc;
+
+/// ソルバーが最小化する目的関数。
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Objective {
+    /// 総所要フレーム数 (実時間)。
+    #[default]
+    Frames,
+
+    /// ブロック投げの所要フレーム数のみ (自機の移動コストを 0 とみなす)。
+    /// 他の演出中に自機を動かせるバージョンや状況に対応する。
+    ThrowOnly,
+
+    /// ブロックを投げる回数。残り手数がボーナス点になるスコアアタック向け。
+    MoveCount,
+}
+
+impl Objective {
+    /// 総所要コスト `cost_mv`、ブロック投げコスト `cost_throw` の着手の、この目的関数でのコストを返す
+    /// (`Position::do_move()` の返り値をそのまま渡す)。
+    pub fn move_cost(self, cost_mv: Cost, cost_throw: Cost) -> Cost {
+        match self {
+            Self::Frames => cost_mv,
+            Self::ThrowOnly => cost_throw,
+            Self::MoveCount => 1,
+        }
+    }
+
+    /// ブロック投げコスト `cost_throw` の着手の、この目的関数でのブロック投げ部分のコストを返す。
+    /// 最終面の最終手はこの部分を 1 に置き換える。
+    pub fn throw_cost(self, cost_throw: Cost) -> Cost {
+        match self {
+            Self::Frames | Self::ThrowOnly => cost_throw,
+            Self::MoveCount => 1,
+        }
+    }
+
+    /// 面クリア時に残った `block_count` 個のブロックの自動消去の、この目的関数でのコストを返す。
+    pub fn clear_cost(self, block_count: usize) -> Cost {
+        match self {
+            Self::Frames | Self::ThrowOnly => COST_CLEAR_ERASE_BLOCK * block_count as Cost,
+            Self::MoveCount => 0,
+        }
+    }
+}
+
+/// 目的関数の値が等しい解どうしを比べる第 2 の基準 (小さいほどよい)。
+///
+/// コンソールでの検証が容易な、きれいな入力列を得るのに使う。
+/// 基準の値も等しい解どうしは着手位置の列の辞書式順序で比べるので、返す解は着手を試す順序によらず決まる。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TieBreak {
+    /// 入力数 (ブロックを投げる回数と自機の歩数の合計)。
+    Inputs,
+
+    /// 自機の総移動歩数。
+    HeroTravel,
+
+    /// 自機の移動方向 (上下) の切り替え回数。
+    DirectionChanges,
+
+    /// 着手位置の列そのもの (辞書式順序で最小の列)。
+    Lexicographic,
+
+    /// 手数。
+    MoveCount,
+}
+
+impl TieBreak {
+    /// 自機の初期位置が `hero_row` のとき、着手位置の列 `srcs` に対するこの基準の値を返す。
+    /// 値を持たず着手位置の列そのものを比べる基準 (`Lexicographic`) なら `None` を返す。
+    ///
+    /// 値は着手を追加しても減らない (探索の枝刈りに使う)。
+    pub fn value(self, hero_row: MoveSrc, srcs: &[MoveSrc]) -> Option<u32> {
+        let mut row = hero_row;
+        let mut dir_prev = None;
+        let mut travel = 0;
+        let mut dir_changes = 0;
+
+        for &src in srcs {
+            travel += u32::from(row.to_inner().abs_diff(src.to_inner()));
+            if src != row {
+                let dir = src < row;
+                if dir_prev.is_some_and(|dir_prev| dir_prev != dir) {
+                    dir_changes += 1;
+                }
+                dir_prev = Some(dir);
+            }
+            row = src;
+        }
+
+        match self {
+            Self::Inputs => Some(srcs.len() as u32 + travel),
+            Self::HeroTravel => Some(travel),
+            Self::DirectionChanges => Some(dir_changes),
+            Self::Lexicographic => None,
+            Self::MoveCount => Some(srcs.len() as u32),
+        }
+    }
+
+    /// 自機の初期位置が `hero_row` のとき、この基準で着手位置の列 `lhs` と `rhs` を比べる。
+    /// 基準の値が等しければ辞書式順序で比べる。
+    pub fn compare(self, hero_row: MoveSrc, lhs: &[MoveSrc], rhs: &[MoveSrc]) -> Ordering {
+        self.value(hero_row, lhs)
+            .cmp(&self.value(hero_row, rhs))
+            .then_with(|| lhs.cmp(rhs))
+    }
+
+    /// 着手位置の列 `prefix` かそれに着手を追加した列に、この基準で `best` より小さいものがありうるかどうか。
+    /// 真のとき、`prefix` 自身も `best` より小さいとは限らない (延長した列だけが小さいこともある)。
+    pub(crate) fn can_improve(
+        self,
+        hero_row: MoveSrc,
+        prefix: &[MoveSrc],
+        best: &[MoveSrc],
+    ) -> bool {
+        match self
+            .value(hero_row, prefix)
+            .cmp(&self.value(hero_row, best))
+        {
+            Ordering::Less => true,
+            Ordering::Greater => false,
+            // 値は減らないので、辞書式順序で小さくなりうるかどうか。
+            // 最初に異なる着手が小さいか、`prefix` が `best` の真の接頭辞なら小さくなりうる。
+            Ordering::Equal => {
+                let k = core::iter::zip(prefix, best)
+                    .take_while(|(lhs, rhs)| lhs == rhs)
+                    .count();
+                match (prefix.get(k), best.get(k)) {
+                    (Some(lhs), Some(rhs)) => lhs < rhs,
+                    (None, Some(_)) => true,
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::move_::*;
+
+    use super::*;
+
+    #[test]
+    fn test_objective() {
+        assert_eq!(Objective::default(), Objective::Frames);
+        assert_eq!(Objective::Frames.move_cost(150, 118), 150);
+        assert_eq!(Objective::ThrowOnly.move_cost(150, 118), 118);
+        assert_eq!(Objective::MoveCount.move_cost(150, 118), 1);
+        assert_eq!(Objective::ThrowOnly.throw_cost(118), 118);
+        assert_eq!(Objective::MoveCount.throw_cost(118), 1);
+        assert_eq!(Objective::Frames.clear_cost(3), 33);
+        assert_eq!(Objective::MoveCount.clear_cost(3), 0);
+    }
+
+    #[test]
+    fn test_tie_break() {
+        let srcs = [
+            MOVE_SRC_ROW_9,
+            MOVE_SRC_ROW_9,
+            MOVE_SRC_ROW_10,
+            MOVE_SRC_ROW_2,
+        ];
+
+        assert_eq!(TieBreak::Inputs.value(MOVE_SRC_ROW_11, &srcs), Some(4 + 11));
+        assert_eq!(TieBreak::HeroTravel.value(MOVE_SRC_ROW_11, &srcs), Some(11));
+        assert_eq!(
+            TieBreak::DirectionChanges.value(MOVE_SRC_ROW_11, &srcs),
+            Some(2)
+        );
+        assert_eq!(
+            TieBreak::DirectionChanges.value(MOVE_SRC_ROW_11, &[]),
+            Some(0)
+        );
+        assert_eq!(TieBreak::MoveCount.value(MOVE_SRC_ROW_11, &srcs), Some(4));
+        assert_eq!(TieBreak::Lexicographic.value(MOVE_SRC_ROW_11, &srcs), None);
+
+        // 値が等しければ辞書式順序で比べる。
+        let other = [
+            MOVE_SRC_ROW_9,
+            MOVE_SRC_ROW_9,
+            MOVE_SRC_ROW_2,
+            MOVE_SRC_ROW_10,
+        ];
+        for tie_break in [TieBreak::MoveCount, TieBreak::Lexicographic] {
+            assert_eq!(
+                tie_break.compare(MOVE_SRC_ROW_11, &other, &srcs),
+                Ordering::Less
+            );
+        }
+        assert_eq!(
+            TieBreak::HeroTravel.compare(MOVE_SRC_ROW_11, &other, &srcs),
+            Ordering::Greater
+        );
+
+        // 延長した列が小さくなりうるかどうか。
+        let lex = TieBreak::Lexicographic;
+        assert!(lex.can_improve(MOVE_SRC_ROW_11, &other[..3], &srcs));
+        assert!(lex.can_improve(MOVE_SRC_ROW_11, &srcs[..2], &srcs));
+        assert!(!lex.can_improve(MOVE_SRC_ROW_11, &srcs, &srcs));
+        assert!(!lex.can_improve(MOVE_SRC_ROW_11, &srcs[..3], &other));
+        assert!(!TieBreak::MoveCount.can_improve(MOVE_SRC_ROW_11, &srcs, &other[..3]));
+    }
+}
@@ -0,0 +1,691 @@
+use std::collections::{HashMap, VecDeque};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Instant;
+
+use log::info;
+
+use crate::config::{
+    CancelToken, HistoryTable, ImproveHook, MoveOrdering, ProgressHook, SearchOutcome,
+    SearchProgress, SearchStats, SolverConfig, PROGRESS_INTERVAL,
+};
+use crate::cost::{calc_min_move_cost, Cost};
+use crate::move_::Move;
+use crate::objective::Objective;
+use crate::position::Position;
+use crate::problem::Problem;
+use crate::ruleset::Ruleset;
+use crate::solver::{play_prefix, solve_problem_with, Solution};
+use crate::tt::LruOrder;
+
+/// 共有置換表のシャード数。ロックの競合を減らすため、局面のキーで振り分ける。
+const TT_SHARD_COUNT: usize = 64;
+
+/// `threads` 個のスレッドで並列に問題を解く。結果のコストは `solve_problem()` と同じ (解は異なりうる)。
+///
+/// 部分木の探索をタスクとして共有キューに積み、暇なスレッドがそれを取って探索する。
+/// 最初は初手で分かれる部分木が暇なスレッドに配られ、以降も探索中のスレッドは暇なスレッドがいると気付いた時点で
+/// 未探索の子局面をキューに譲るので、初手の 1 つに探索の大半が集中する問題でも並列化が効く。
+/// 暫定解のコスト (アトミック変数) と置換表は全スレッドで共有し、ソルバーと同じ下界で枝刈りする。
+pub fn solve_problem_parallel(
+    problem: &Problem,
+    last_stage: bool,
+    threads: NonZeroUsize,
+) -> Option<(Solution, Cost)> {
+    let config = SolverConfig::new().with_threads(threads.get());
+
+    solve_parallel_with(problem, last_stage, &config, None, None).into_best()
+}
+
+/// `solve_problem_with()` の並列版。第 2 の基準 (`tie_break`) には対応しない。
+pub(crate) fn solve_parallel_with<'p>(
+    problem: &Problem,
+    last_stage: bool,
+    config: &SolverConfig,
+    progress: Option<ProgressHook<'p>>,
+    improve: Option<ImproveHook<'p>>,
+) -> SearchOutcome {
+    let (pos, moves) = problem.to_position_and_moves();
+    let pos_root = pos.clone();
+    let threads = config.threads();
+    let Ok((prefix, pos, cost, cost_last_throw)) =
+        play_prefix(pos, &moves, config.prefix(), config.objective())
+    else {
+        info!("invalid prefix");
+        return SearchOutcome::new(None, true);
+    };
+
+    // 共有の置換表が指定されていればそれを使う。手数の上限がある場合の下界は経路に依存するので共有しない。
+    let tt = match config.shared_tt() {
+        Some(shared_tt) if config.max_depth() == usize::MAX => shared_tt.table(TtContext::new(
+            &pos_root,
+            &moves,
+            last_stage,
+            config.objective(),
+        )),
+        _ => Arc::new(TtShards::new(config.memory_limit())),
+    };
+    // 既知の解があれば暫定解とする。
+    let (best_solution, best_cost) =
+        config
+            .incumbent()
+            .map_or((None, Cost::MAX), |(solution, cost)| {
+                let mvs = solution
+                    .moves()
+                    .iter()
+                    .map(|&src| *moves.iter().find(|mv| mv.src() == src).unwrap())
+                    .collect();
+                (Some(mvs), *cost)
+            });
+    let shared = Shared {
+        moves: &moves,
+        last_stage,
+        objective: config.objective(),
+        move_ordering: config.move_ordering(),
+        history: HistoryTable::new(),
+        max_depth: config.max_depth(),
+        lower_bound: config.lower_bound(),
+        node_limit: config.node_limit(),
+        node_count: AtomicU64::new(0),
+        deadline: config
+            .time_limit()
+            .map(|time_limit| Instant::now() + time_limit),
+        cancel: config.cancel_token(),
+        aborted: AtomicBool::new(false),
+        progress: Mutex::new(progress),
+        improve: Mutex::new(improve),
+        started: Instant::now(),
+        min_throw_cost: moves
+            .iter()
+            .map(|mv| config.objective().throw_cost(calc_min_move_cost(mv.src())))
+            .min()
+            .unwrap_or(0),
+        best_cost: AtomicU32::new(best_cost),
+        best_solution: Mutex::new(best_solution),
+        queue: Mutex::new(Queue {
+            tasks: VecDeque::from([Task {
+                prefix,
+                pos,
+                cost,
+                cost_last_throw,
+            }]),
+            active: 1,
+        }),
+        queue_cond: Condvar::new(),
+        queued: AtomicUsize::new(1),
+        idle: AtomicUsize::new(0),
+        tt,
+        stats: Mutex::new(SearchStats::default()),
+    };
+
+    info!("search start (threads: {threads})");
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| shared.work());
+        }
+    });
+    shared.report_progress(0);
+    info!("search end");
+
+    let mut stats = shared.stats.into_inner().unwrap();
+    stats.elapsed = shared.started.elapsed();
+    let complete = !shared.aborted.into_inner();
+    let best_cost = shared.best_cost.into_inner();
+    let best = shared
+        .best_solution
+        .into_inner()
+        .unwrap()
+        .map(|solution| (Solution::from_moves(&solution), best_cost));
+
+    SearchOutcome::new(best, complete).with_stats(stats)
+}
+
+/// 互いに独立な問題 `problems` を最大 `jobs` 問ずつ並列に解き、入力と同じ順に結果を返す。
+///
+/// 局面数や時間の上限など `config` の設定は問題ごとに適用する (`solve_problem_with()` と同じ)。
+/// 上限で打ち切った問題はそれまでに見つかった最善の解を返す (最適とは限らない)。
+/// 生成した問題集や ROM から抽出した問題を一括で解くためのもの。
+pub fn solve_batch(
+    problems: &[Problem],
+    last_stage: bool,
+    config: &SolverConfig,
+    jobs: NonZeroUsize,
+) -> Vec<Option<(Solution, Cost)>> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![None; problems.len()]);
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.get().min(problems.len()) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(problem) = problems.get(i) else {
+                    break;
+                };
+                let outcome = solve_problem_with(problem, last_stage, config);
+                if !outcome.is_complete() {
+                    info!(
+                        "batch: problem {}/{}: search aborted",
+                        i + 1,
+                        problems.len()
+                    );
+                }
+                results.lock().unwrap()[i] = outcome.into_best();
+            });
+        }
+    });
+
+    results.into_inner().unwrap()
+}
+
+/// 部分木の探索タスク。
+#[derive(Debug)]
+struct Task {
+    prefix: Vec<Move>,
+    pos: Position,
+    cost: Cost,
+    cost_last_throw: Cost,
+}
+
+#[derive(Debug)]
+struct Queue {
+    tasks: VecDeque<Task>,
+    /// キューにあるタスクと探索中のタスクの総数。0 になったら探索終了。
+    active: usize,
+}
+
+/// 共有置換表のシャード。メモリ使用量に上限があれば、最も長く参照されていないエントリから捨てる。
+#[derive(Debug)]
+struct TtShard {
+    entries: HashMap<u128, Cost>,
+    lru: Option<LruOrder>,
+}
+
+impl TtShard {
+    fn get(&mut self, key: u128) -> Option<Cost> {
+        let bound = self.entries.get(&key).copied();
+        if let (Some(_), Some(lru)) = (bound, &mut self.lru) {
+            lru.touch(key);
+        }
+        bound
+    }
+
+    fn update(&mut self, key: u128, bound: Cost) {
+        let entry = self.entries.entry(key).or_insert(0);
+        *entry = (*entry).max(bound);
+        if let Some(key_old) = self.lru.as_mut().and_then(|lru| lru.touch(key)) {
+            self.entries.remove(&key_old);
+        }
+    }
+}
+
+/// 局面で `TtShard` に振り分けた置換表。キーは `Position::packed_key()` (衝突しない)。
+#[derive(Debug)]
+pub(crate) struct TtShards(Vec<Mutex<TtShard>>);
+
+impl TtShards {
+    /// メモリ使用量に上限 `memory_limit` (バイト) があれば、シャードごとに均等に割り振る。
+    fn new(memory_limit: Option<usize>) -> Self {
+        Self(
+            (0..TT_SHARD_COUNT)
+                .map(|_| {
+                    Mutex::new(TtShard {
+                        entries: HashMap::new(),
+                        lru: memory_limit
+                            .map(|limit| LruOrder::with_memory_limit(limit / TT_SHARD_COUNT)),
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    fn shard(&self, pos: &Position) -> &Mutex<TtShard> {
+        &self.0[(pos.zobrist_hash() % TT_SHARD_COUNT as u64) as usize]
+    }
+
+    /// 局面 `pos` 以降に必要な最小コストの下界を返す。
+    pub(crate) fn get(&self, pos: &Position) -> Option<Cost> {
+        self.shard(pos).lock().unwrap().get(pos.packed_key())
+    }
+
+    /// 局面 `pos` 以降に必要な最小コストの下界 `bound` を記録する。既存の値より大きい場合のみ更新する。
+    pub(crate) fn update(&self, pos: &Position, bound: Cost) {
+        self.shard(pos)
+            .lock()
+            .unwrap()
+            .update(pos.packed_key(), bound);
+    }
+
+    fn len(&self) -> usize {
+        self.0
+            .iter()
+            .map(|shard| shard.lock().unwrap().entries.len())
+            .sum()
+    }
+}
+
+/// 複数の問題の探索で共有する、スレッドセーフな置換表へのハンドル。複製したハンドルは同じ表を指す。
+///
+/// 同じ面の変種をまとめて解く場合などに、`SolverConfig::with_shared_tt()` で同じハンドルを渡した探索どうしで
+/// 局面以降のコストの下界を共有する。下界は局面だけで決まるが、最終面フラグ、ルール設定、着手できる行の集合、
+/// 目的関数が異なると意味が変わるので、これらの組ごとに別の表に記録する (異なる問題を混ぜて渡してもよい)。
+/// 探索ごとの `TranspositionTable` と違い、メモリ使用量の上限はなく、ファイルにも保存しない。
+#[derive(Clone, Debug, Default)]
+pub struct SharedTranspositionTable(Arc<Mutex<Vec<ContextTable>>>);
+
+/// 探索の条件とその条件での置換表。
+type ContextTable = (TtContext, Arc<TtShards>);
+
+/// 置換表の下界を共有できる探索の条件。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct TtContext {
+    last_stage: bool,
+    ruleset: Ruleset,
+    moves: Vec<Move>,
+    objective: Objective,
+}
+
+impl TtContext {
+    /// 局面 `pos` から着手 `moves` で探索する場合の条件。
+    pub(crate) fn new(
+        pos: &Position,
+        moves: &[Move],
+        last_stage: bool,
+        objective: Objective,
+    ) -> Self {
+        Self {
+            last_stage,
+            ruleset: pos.ruleset(),
+            moves: moves.to_vec(),
+            objective,
+        }
+    }
+}
+
+impl SharedTranspositionTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 記録している局面数 (全ての条件の合計)。
+    pub fn len(&self) -> usize {
+        let tables = self.0.lock().unwrap();
+        tables.iter().map(|(_, table)| table.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 条件 `context` の探索用の表を返す。なければ作る。
+    pub(crate) fn table(&self, context: TtContext) -> Arc<TtShards> {
+        let mut tables = self.0.lock().unwrap();
+        if let Some((_, table)) = tables.iter().find(|(other, _)| *other == context) {
+            return Arc::clone(table);
+        }
+
+        let table = Arc::new(TtShards::new(None));
+        tables.push((context, Arc::clone(&table)));
+        table
+    }
+}
+
+#[derive(Debug)]
+struct Shared<'a, 'p> {
+    moves: &'a [Move],
+    last_stage: bool,
+    /// 着手のブロック投げコストの目的関数での最小値。
+    min_throw_cost: Cost,
+    objective: Objective,
+    move_ordering: MoveOrdering,
+    history: HistoryTable,
+    max_depth: usize,
+    lower_bound: bool,
+    node_limit: u64,
+    node_count: AtomicU64,
+    deadline: Option<Instant>,
+    cancel: Option<&'a CancelToken>,
+    aborted: AtomicBool,
+    progress: Mutex<Option<ProgressHook<'p>>>,
+    improve: Mutex<Option<ImproveHook<'p>>>,
+    started: Instant,
+    /// `best_solution` のコスト。枝刈りで頻繁に読むのでロックの外に置く (更新は `best_solution` のロック中に行う)。
+    best_cost: AtomicU32,
+    best_solution: Mutex<Option<Vec<Move>>>,
+    queue: Mutex<Queue>,
+    queue_cond: Condvar,
+    queued: AtomicUsize,
+    idle: AtomicUsize,
+    tt: Arc<TtShards>,
+    /// 各スレッドの探索の統計の合計。スレッドの終了時に足し合わせる。
+    stats: Mutex<SearchStats>,
+}
+
+impl Shared<'_, '_> {
+    fn work(&self) {
+        let mut stats = SearchStats::default();
+        loop {
+            let task = {
+                let mut queue = self.queue.lock().unwrap();
+                loop {
+                    if let Some(task) = queue.tasks.pop_front() {
+                        self.queued.fetch_sub(1, Ordering::Relaxed);
+                        break task;
+                    }
+                    if queue.active == 0 {
+                        self.stats.lock().unwrap().merge(&stats);
+                        return;
+                    }
+                    self.idle.fetch_add(1, Ordering::Relaxed);
+                    queue = self.queue_cond.wait(queue).unwrap();
+                    self.idle.fetch_sub(1, Ordering::Relaxed);
+                }
+            };
+
+            let mut cur_solution = task.prefix;
+            self.solve(
+                &mut stats,
+                &mut cur_solution,
+                task.pos,
+                task.cost,
+                task.cost_last_throw,
+            );
+
+            let mut queue = self.queue.lock().unwrap();
+            queue.active -= 1;
+            if queue.active == 0 {
+                self.queue_cond.notify_all();
+            }
+        }
+    }
+
+    /// 手数 `depth` の局面を探索中のスレッドから途中経過を報告する。
+    fn report_progress(&self, depth: usize) {
+        let mut progress = self.progress.lock().unwrap();
+        let Some(progress) = progress.as_mut() else {
+            return;
+        };
+        let best_cost = self.best_cost.load(Ordering::Relaxed);
+        (progress.0)(&SearchProgress::new(
+            self.node_count.load(Ordering::Relaxed),
+            depth,
+            (best_cost != Cost::MAX).then_some(best_cost),
+            self.started.elapsed(),
+        ));
+    }
+
+    /// 暇なスレッドがキューのタスク数より多ければ真。
+    fn wants_task(&self) -> bool {
+        self.idle.load(Ordering::Relaxed) > self.queued.load(Ordering::Relaxed)
+    }
+
+    fn push_task(&self, task: Task) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.tasks.push_back(task);
+        queue.active += 1;
+        self.queued.fetch_add(1, Ordering::Relaxed);
+        self.queue_cond.notify_one();
+    }
+
+    /// 局面 `pos` 以下を探索し、部分木を全て自分で探索したかどうかを返す
+    /// (他のスレッドに譲った部分木があれば、この局面の下界は置換表に記録できない)。
+    fn solve(
+        &self,
+        stats: &mut SearchStats,
+        cur_solution: &mut Vec<Move>,
+        pos: Position,
+        cost: Cost,
+        cost_last_throw: Cost,
+    ) -> bool {
+        // 探索局面数または探索時間が上限に達するか、打ち切りを要求されたら全スレッドで打ち切る。
+        if self.aborted.load(Ordering::Relaxed) {
+            return false;
+        }
+        let node_count = self.node_count.fetch_add(1, Ordering::Relaxed);
+        if (node_count + 1) % PROGRESS_INTERVAL == 0 {
+            self.report_progress(cur_solution.len());
+        }
+        if node_count >= self.node_limit
+            || node_count % 1024 == 0
+                && (self
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline)
+                    || self.cancel.is_some_and(CancelToken::is_cancelled))
+        {
+            self.aborted.store(true, Ordering::Relaxed);
+            return false;
+        }
+        stats.node_count += 1;
+        stats.max_depth = stats.max_depth.max(cur_solution.len());
+
+        // 以下の枝刈りと解の更新はソルバーと同じ。
+        let cost_total = if self.last_stage {
+            cost - cost_last_throw + 1
+        } else {
+            cost + self.objective.clear_cost(pos.block_count())
+        };
+
+        if cost_total >= self.best_cost.load(Ordering::Relaxed) {
+            stats.prune_cost += 1;
+            return true;
+        }
+
+        if pos.is_stuck(self.moves) {
+            if pos.is_cleared() {
+                let mut best_solution = self.best_solution.lock().unwrap();
+                // ロックを取るまでに他のスレッドが更新しているかもしれない。
+                if cost_total < self.best_cost.load(Ordering::Relaxed) {
+                    *best_solution = Some(cur_solution.clone());
+                    self.best_cost.store(cost_total, Ordering::Relaxed);
+                    info!("improve: {cost_total} {best_solution:?}");
+                    self.history.record(cur_solution.iter().map(|mv| mv.src()));
+                    // 解の更新順を保つため、best_solution のロック中に呼ぶ。
+                    if let Some(improve) = self.improve.lock().unwrap().as_mut() {
+                        (improve.0)(&Solution::from_moves(cur_solution), cost_total);
+                    }
+                }
+            }
+            return true;
+        }
+
+        if pos.move_remain() == 0 {
+            return true;
+        }
+
+        // 手数の上限による枝刈りは経路に依存するので、置換表には記録しない。
+        if cur_solution.len() >= self.max_depth {
+            return false;
+        }
+
+        let move_count = pos.min_moves_to_solve().max(1);
+        if self.lower_bound && move_count > usize::from(pos.move_remain()) {
+            stats.prune_lower_bound += 1;
+            return true;
+        }
+        let cost_lower = if self.last_stage {
+            cost + self.min_throw_cost * (move_count - 1) as Cost + 1
+        } else {
+            cost + self.min_throw_cost * move_count as Cost
+        };
+        if self.lower_bound && cost_lower >= self.best_cost.load(Ordering::Relaxed) {
+            stats.prune_lower_bound += 1;
+            return true;
+        }
+
+        if let Some(bound) = self.tt.get(&pos) {
+            stats.tt_hits += 1;
+            if cost.saturating_add(bound) >= self.best_cost.load(Ordering::Relaxed) {
+                stats.prune_tt += 1;
+                return true;
+            }
+        }
+
+        let mut complete = true;
+
+        let mut nexts: Vec<_> = self
+            .moves
+            .iter()
+            .filter_map(|&mv| {
+                pos.do_move(mv)
+                    .map(|(pos_nxt, cost_mv, cost_throw)| (mv, pos_nxt, cost_mv, cost_throw))
+            })
+            .collect();
+        self.move_ordering.sort(
+            self.objective,
+            Some(&self.history),
+            cur_solution.len(),
+            &mut nexts,
+        );
+
+        for (mv, pos_nxt, cost_mv, cost_throw) in nexts {
+            cur_solution.push(mv);
+            if self.wants_task() {
+                self.push_task(Task {
+                    prefix: cur_solution.clone(),
+                    pos: pos_nxt,
+                    cost: cost + self.objective.move_cost(cost_mv, cost_throw),
+                    cost_last_throw: self.objective.throw_cost(cost_throw),
+                });
+                complete = false;
+            } else {
+                let cost_nxt = cost + self.objective.move_cost(cost_mv, cost_throw);
+                complete &= self.solve(
+                    stats,
+                    cur_solution,
+                    pos_nxt,
+                    cost_nxt,
+                    self.objective.throw_cost(cost_throw),
+                );
+            }
+            cur_solution.pop().unwrap();
+        }
+
+        if complete {
+            let best_cost = self.best_cost.load(Ordering::Relaxed);
+            let bound = if best_cost == Cost::MAX {
+                Cost::MAX
+            } else {
+                best_cost.saturating_sub(cost)
+            };
+            self.tt.update(&pos, bound);
+        }
+
+        complete
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::solver::{solve_problem, solve_problem_with};
+
+    use super::*;
+
+    #[test]
+    fn test_solve_problem_parallel() {
+        for (problem, last_stage) in [
+            (include_str!("../problem/01.in"), false),
+            (include_str!("../problem/10.in"), false),
+            (include_str!("../problem/20.in"), true),
+        ] {
+            let problem: Problem = problem.parse().unwrap();
+            let expected = solve_problem(&problem, last_stage).map(|(_, cost)| cost);
+
+            for threads in [1, 4] {
+                let res = solve_problem_parallel(
+                    &problem,
+                    last_stage,
+                    NonZeroUsize::new(threads).unwrap(),
+                );
+                assert_eq!(res.as_ref().map(|(_, cost)| *cost), expected);
+                let (solution, cost) = res.unwrap();
+                assert_eq!(solution.verify(&problem, last_stage), Ok(cost));
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_batch() {
+        let problems: Vec<Problem> = [
+            include_str!("../problem/01.in"),
+            include_str!("../problem/02.in"),
+            include_str!("../problem/10.in"),
+            include_str!("../problem/20.in"),
+        ]
+        .into_iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+        let expected: Vec<_> = problems
+            .iter()
+            .map(|problem| solve_problem(problem, false).map(|(_, cost)| cost))
+            .collect();
+
+        for jobs in [1, 3, 8] {
+            let results = solve_batch(
+                &problems,
+                false,
+                &SolverConfig::new(),
+                NonZeroUsize::new(jobs).unwrap(),
+            );
+            let costs: Vec<_> = results
+                .iter()
+                .map(|res| res.as_ref().map(|(_, cost)| *cost))
+                .collect();
+            assert_eq!(costs, expected);
+            for (problem, (solution, cost)) in problems.iter().zip(results.iter().flatten()) {
+                assert_eq!(solution.verify(problem, false), Ok(*cost));
+            }
+        }
+
+        // 上限は問題ごとに適用する。
+        let config = SolverConfig::new().with_node_limit(1);
+        let results = solve_batch(&problems, false, &config, NonZeroUsize::new(2).unwrap());
+        assert_eq!(results.len(), problems.len());
+        for (problem, res) in problems.iter().zip(&results) {
+            if let Some((solution, cost)) = res {
+                assert_eq!(solution.verify(problem, false), Ok(*cost));
+            }
+        }
+    }
+
+    #[test]
+    fn test_shared_tt() {
+        let problems: Vec<Problem> = [
+            include_str!("../problem/10.in"),
+            include_str!("../problem/20.in"),
+            include_str!("../problem/10.in"),
+            include_str!("../problem/20.in"),
+        ]
+        .into_iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+
+        // 2 回目の探索は 1 回目に記録した下界で枝刈りできる。
+        let shared_tt = SharedTranspositionTable::new();
+        let config = SolverConfig::new().with_shared_tt(Some(shared_tt.clone()));
+        let expected = solve_problem(&problems[0], false).map(|(_, cost)| cost);
+        let first = solve_problem_with(&problems[0], false, &config);
+        assert!(!shared_tt.is_empty());
+        let second = solve_problem_with(&problems[0], false, &config);
+        assert_eq!(first.best().map(|(_, cost)| *cost), expected);
+        assert_eq!(second.best().map(|(_, cost)| *cost), expected);
+        assert!(second.stats().node_count() < first.stats().node_count());
+
+        // 並列に解いても、最終面フラグが異なる探索と共有しても結果は変わらない。
+        for last_stage in [false, true] {
+            let results = solve_batch(
+                &problems,
+                last_stage,
+                &config,
+                NonZeroUsize::new(2).unwrap(),
+            );
+            for (problem, res) in problems.iter().zip(&results) {
+                let expected = solve_problem(problem, last_stage).map(|(_, cost)| cost);
+                assert_eq!(res.as_ref().map(|(_, cost)| *cost), expected);
+                let (solution, cost) = res.as_ref().unwrap();
+                assert_eq!(solution.verify(problem, last_stage), Ok(*cost));
+            }
+        }
+    }
+}
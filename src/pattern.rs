@@ -0,0 +1,297 @@
+use alloc::borrow::ToOwned as _;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use itertools::Itertools as _;
+
+use crate::cost::Cost;
+use crate::error::{ParseError, ParseErrorKind, VerifyError};
+use crate::move_::{Move, MoveDst, MoveSrc};
+use crate::problem::Problem;
+use crate::solver::Solution;
+
+/// 終盤の局面から面が終わるまでの最適な着手列のライブラリ。
+///
+/// 壁やパイプの配置が同じ面では、ブロックの少ない終盤に同じ局面がよく現れる。
+/// 最適解が分かっている面から終盤の着手列を集めておくと、ソルバーはその局面以降を探索せずに済む。
+///
+/// テキスト形式は 1 行 1 局面で、タブ区切りの「着手の形 最終面フラグ ルール設定 局面 残りコスト 着手列」。
+/// ルール設定は最下位ビットが入れ替え着手フラグ、上位ビットが途中でワイルドを得る消去個数 (なければ 0) の 10 進数。
+/// 着手の形は行 0..=11 ごとの投げ込み先 (0: なし, 1..=6: 横方向の行, 7..=12: 縦方向の列) の 16 進数、
+/// 局面は `Position::packed_key()` の 16 進数。
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PatternLibrary(BTreeMap<PatternKey, PatternEntry>);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct PatternKey {
+    shape: [u8; MoveSrc::NUM],
+    last_stage: bool,
+    ruleset_flags: u16,
+    position: u128,
+}
+
+/// ライブラリに登録された、ある局面から面が終わるまでの最適な着手列。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct PatternEntry {
+    srcs: Vec<MoveSrc>,
+    cost: Cost,
+}
+
+impl PatternEntry {
+    pub(crate) fn srcs(&self) -> &[MoveSrc] {
+        &self.srcs
+    }
+
+    /// 面が終わるまでのコスト (面クリア時のコストを含む)。
+    pub(crate) fn cost(&self) -> Cost {
+        self.cost
+    }
+}
+
+impl PatternLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// 問題 `problem` の最適解 `solution` の途中で、ブロック数が `max_blocks` 以下の局面から後の着手列を登録し、
+    /// 新たに登録した局面数を返す。
+    ///
+    /// 最適解の途中から後の着手列はその局面からの最適な着手列なので、`solution` は最適解でなければならない
+    /// (最適性が証明されていない解を登録すると、ソルバーが最適でない解を返しうる)。
+    pub fn learn(
+        &mut self,
+        problem: &Problem,
+        last_stage: bool,
+        solution: &Solution,
+        max_blocks: usize,
+    ) -> Result<usize, VerifyError> {
+        let cost_total = solution.verify(problem, last_stage)?;
+
+        let (mut pos, moves) = problem.to_position_and_moves();
+        let shape = move_shape(&moves);
+        let ruleset_flags = problem.ruleset().to_flags();
+        let mut cost = 0;
+        let mut count = 0;
+
+        for (i, &src) in solution.moves().iter().enumerate() {
+            if pos.block_count() <= max_blocks {
+                let key = PatternKey {
+                    shape,
+                    last_stage,
+                    ruleset_flags,
+                    position: pos.packed_key(),
+                };
+                let entry = PatternEntry {
+                    srcs: solution.moves()[i..].to_vec(),
+                    cost: cost_total - cost,
+                };
+                match self.0.get(&key) {
+                    Some(entry_old) if entry_old.cost <= entry.cost => {}
+                    Some(_) => {
+                        self.0.insert(key, entry);
+                    }
+                    None => {
+                        self.0.insert(key, entry);
+                        count += 1;
+                    }
+                }
+            }
+
+            let mv = moves.iter().copied().find(|mv| mv.src() == src).unwrap();
+            let (pos_nxt, cost_mv, _) = pos.do_move(mv).unwrap();
+            pos = pos_nxt;
+            cost += cost_mv;
+        }
+
+        Ok(count)
+    }
+
+    /// 問題 `problem` (最終面フラグ `last_stage`) の探索で使えるエントリを、局面の `packed_key()` をキーとして返す。
+    pub(crate) fn entries_for(
+        &self,
+        problem: &Problem,
+        last_stage: bool,
+    ) -> BTreeMap<u128, PatternEntry> {
+        let (_, moves) = problem.to_position_and_moves();
+        let key_min = PatternKey {
+            shape: move_shape(&moves),
+            last_stage,
+            ruleset_flags: problem.ruleset().to_flags(),
+            position: 0,
+        };
+        let key_max = PatternKey {
+            position: u128::MAX,
+            ..key_min
+        };
+
+        self.0
+            .range(key_min..=key_max)
+            .map(|(key, entry)| (key.position, entry.clone()))
+            .collect()
+    }
+
+    /// ファイルから読み込む。ファイルが存在しなければ空のライブラリを返す。
+    #[cfg(feature = "std")]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, crate::error::Error> {
+        match std::fs::read_to_string(path) {
+            Ok(s) => Ok(s.parse()?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// ファイルに書き込む。途中で中断してもファイルが壊れることはない (`atomic_write` 参照)。
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), crate::error::Error> {
+        use std::io::Write as _;
+
+        crate::fs::atomic_write(path, |w| {
+            w.write_all(alloc::string::ToString::to_string(self).as_bytes())
+        })?;
+
+        Ok(())
+    }
+}
+
+/// 各行からの着手の投げ込み先を返す。
+fn move_shape(moves: &[Move]) -> [u8; MoveSrc::NUM] {
+    let mut shape = [0; MoveSrc::NUM];
+
+    for mv in moves {
+        shape[mv.src().to_index()] = match mv.dst() {
+            MoveDst::Horizontal(row) => row.to_inner(),
+            MoveDst::Vertical(col) => 6 + col.to_inner(),
+        };
+    }
+
+    shape
+}
+
+impl core::str::FromStr for PatternLibrary {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut this = Self::new();
+
+        for (row, line) in s.lines().enumerate() {
+            let err = |kind| ParseError::new(row + 1, kind);
+            let not_number = |token: &str| err(ParseErrorKind::NotNumber(token.to_owned()));
+
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let tokens: Vec<_> = line.split('\t').collect();
+            if tokens.len() != 6 {
+                return Err(err(ParseErrorKind::TokenCount {
+                    expected: 6,
+                    actual: tokens.len(),
+                }));
+            }
+
+            if tokens[0].len() != MoveSrc::NUM {
+                return Err(not_number(tokens[0]));
+            }
+            let mut shape = [0; MoveSrc::NUM];
+            for (dst, ch) in shape.iter_mut().zip(tokens[0].chars()) {
+                *dst = ch
+                    .to_digit(16)
+                    .filter(|&d| d <= 12)
+                    .ok_or_else(|| not_number(tokens[0]))? as u8;
+            }
+            let parse_flag = |token: &str| match token {
+                "0" => Ok(false),
+                "1" => Ok(true),
+                _ => Err(not_number(token)),
+            };
+            let key = PatternKey {
+                shape,
+                last_stage: parse_flag(tokens[1])?,
+                ruleset_flags: tokens[2].parse().map_err(|_| not_number(tokens[2]))?,
+                position: u128::from_str_radix(tokens[3], 16).map_err(|_| not_number(tokens[3]))?,
+            };
+
+            let cost: Cost = tokens[4].parse().map_err(|_| not_number(tokens[4]))?;
+            let solution: Solution = tokens[5]
+                .parse()
+                .map_err(|e: ParseError| e.offset_line(row))?;
+            let entry = PatternEntry {
+                srcs: solution.moves().to_vec(),
+                cost,
+            };
+
+            this.0.insert(key, entry);
+        }
+
+        Ok(this)
+    }
+}
+
+impl core::fmt::Display for PatternLibrary {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (key, entry) in &self.0 {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{:x}\t{}\t{}",
+                key.shape
+                    .iter()
+                    .map(|dst| alloc::format!("{dst:x}"))
+                    .join(""),
+                u8::from(key.last_stage),
+                key.ruleset_flags,
+                key.position,
+                entry.cost,
+                entry.srcs.iter().map(|src| src.to_inner()).join(" ")
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::solver::{solve_problem, solve_problem_with_patterns};
+
+    use super::*;
+
+    #[test]
+    fn test_pattern_library() {
+        let problem: Problem = include_str!("../problem/20.in").parse().unwrap();
+        let (solution, cost) = solve_problem(&problem, false).unwrap();
+
+        let mut library = PatternLibrary::new();
+        let count = library.learn(&problem, false, &solution, 36).unwrap();
+        assert_eq!(count, solution.moves().len());
+        assert_eq!(library.learn(&problem, false, &solution, 36), Ok(0));
+
+        // 初期局面が登録されていれば、探索せずに登録された解を返す。
+        assert_eq!(
+            solve_problem_with_patterns(&problem, false, &library),
+            Some((solution.clone(), cost))
+        );
+        assert!(library.entries_for(&problem, true).is_empty());
+
+        // 終盤だけ登録しても結果は変わらない。
+        let mut library = PatternLibrary::new();
+        library.learn(&problem, false, &solution, 6).unwrap();
+        assert!(library.len() < solution.moves().len());
+        assert_eq!(
+            solve_problem_with_patterns(&problem, false, &library).map(|(_, cost)| cost),
+            Some(cost)
+        );
+
+        let library_restored: PatternLibrary = library.to_string().parse().unwrap();
+        assert_eq!(library_restored, library);
+    }
+}
@@ -1,32 +1,67 @@
-use anyhow::{anyhow, ensure, Context as _};
+use alloc::borrow::ToOwned as _;
+use alloc::vec::Vec;
 
-use crate::block::{Block, Blocks};
+use crate::block::{Block, Blocks, BlocksCol, BlocksRow, BlocksSquare};
+use crate::blocks_bits::{first_hit, BlocksBits};
 use crate::cost::{calc_hero_move_cost, calc_move_cost, Cost};
+use crate::error::{token_col, ParseError, ParseErrorKind};
 use crate::move_::{Move, MoveDst, MoveSrc, MOVE_SRC_ROW_11};
+use crate::ruleset::Ruleset;
+use crate::zobrist::ZOBRIST;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// 局面。
+///
+/// 順序は (自機の位置, ブロック領域, 保持ブロック, 残り手数, ルール設定) の辞書式順序。
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Position {
     hero_row: MoveSrc,
-    blocks: Blocks,
+    blocks: BlocksBits,
     block_holding: Block,
     move_remain: u8,
+    ruleset: Ruleset,
+    // 以下のフィールドは他のフィールドから決まるので、順序や等価性には影響しない。
+    /// Zobrist ハッシュ値。
+    zobrist: u64,
 }
 
 impl Position {
     pub fn new(blocks: Blocks, block_holding: Block, move_remain: u8) -> Self {
+        Self::from_parts(
+            MOVE_SRC_ROW_11,
+            BlocksBits::from(&blocks),
+            block_holding,
+            move_remain,
+        )
+    }
+
+    fn from_parts(
+        hero_row: MoveSrc,
+        blocks: BlocksBits,
+        block_holding: Block,
+        move_remain: u8,
+    ) -> Self {
+        let zobrist = blocks.zobrist() ^ Self::zobrist_rest(hero_row, block_holding, move_remain);
+
         Self {
-            hero_row: MOVE_SRC_ROW_11,
+            hero_row,
             blocks,
             block_holding,
             move_remain,
+            ruleset: Ruleset::default(),
+            zobrist,
         }
     }
 
+    /// ルール設定を変更した局面を返す。ルール設定は文字列表現には含まれない。
+    pub fn with_ruleset(self, ruleset: Ruleset) -> Self {
+        Self { ruleset, ..self }
+    }
+
     pub fn hero_row(&self) -> MoveSrc {
         self.hero_row
     }
 
-    pub fn blocks(&self) -> &Blocks {
+    pub fn blocks(&self) -> &BlocksBits {
         &self.blocks
     }
 
@@ -38,84 +73,253 @@ impl Position {
         self.move_remain
     }
 
+    pub fn ruleset(&self) -> Ruleset {
+        self.ruleset
+    }
+
     pub fn block_count(&self) -> usize {
         self.blocks.block_count()
     }
 
+    /// ルール設定以外の局面の内容の Zobrist ハッシュ値 (64 ビット)。
+    ///
+    /// `do_move()` で変化した部分だけから差分更新するので、取得は定数時間。
+    /// `packed_key()` と異なり異なる局面が同じ値になりうるが、置換表などのハッシュ値として使うには十分。
+    pub fn zobrist_hash(&self) -> u64 {
+        self.zobrist
+    }
+
+    /// ブロック領域以外の要素の Zobrist ハッシュ値。
+    fn zobrist_rest(hero_row: MoveSrc, block_holding: Block, move_remain: u8) -> u64 {
+        ZOBRIST.hero_row[usize::from(hero_row.to_inner())]
+            ^ ZOBRIST.block_holding[usize::from(block_holding.to_inner())]
+            ^ ZOBRIST.move_remain[usize::from(move_remain)]
+    }
+
+    /// ブロックを消す着手が 1 つもないかどうかを返す。このとき面は終了する。
+    ///
+    /// 入れ替え着手は考慮しない (入れ替え着手しかできない局面でも面は終了する)。
+    /// 投げ込みを実際には行わず、各着手で最初に当たるブロックが消せるかどうかだけを調べる。
+    pub fn is_stuck(&self, moves: &[Move]) -> bool {
+        moves.iter().all(|&mv| {
+            first_hit(self.blocks.occupied(), mv.dst()).map_or(true, |sq| {
+                !self.block_holding.can_erase(self.blocks[sq].unwrap())
+            })
+        })
+    }
+
+    /// 着手 `mv` が入れ替え着手 (最初に当たるブロックが消せない着手) かどうかを返す。
+    ///
+    /// 入れ替え着手のコストは実際のタイミングを再現しておらず近似なので、これを含む解のコストも近似になる。
+    /// 入れ替え着手を許さないルールでは常に false。
+    pub fn is_swap_move(&self, mv: Move) -> bool {
+        self.ruleset.allow_swap()
+            && first_hit(self.blocks.occupied(), mv.dst())
+                .is_some_and(|sq| !self.block_holding.can_erase(self.blocks[sq].unwrap()))
+    }
+
     /// 着手を行い、(結果, 総所要コスト, ブロック投げコスト) を返す。
-    /// 着手が無効なら `None` を返す。
+    /// 着手が無効、または残り手数が 0 なら `None` を返す。
     ///
     /// ブロック投げコストは総所要コストから自機の移動コストを引いたもの。
     pub fn do_move(&self, mv: Move) -> Option<(Self, Cost, Cost)> {
-        assert!(self.move_remain > 0);
+        if self.move_remain == 0 {
+            return None;
+        }
+
+        let throw = self.throw(mv.dst())?;
+
+        Some(self.finish_move(mv, throw, self.zobrist_without_rest()))
+    }
+
+    /// 着手 `moves` のうち有効なものを全て行い、(着手, 結果, 総所要コスト, ブロック投げコスト) を `moves` の順に返す。
+    /// 残り手数が 0 なら何も返さない。
+    ///
+    /// `do_move()` を着手ごとに呼ぶのと同じ結果になるが、投げ込み先が同じ着手 (上の方の行から同じ列に落ちる着手など) の
+    /// 投げ込みは一度だけ行い、着手によらない部分のハッシュ値の計算も共有する。
+    pub fn successors<'a>(
+        &'a self,
+        moves: &'a [Move],
+    ) -> impl Iterator<Item = (Move, Self, Cost, Cost)> + 'a {
+        let moves = if self.move_remain == 0 { &[] } else { moves };
+
+        let zobrist_base = self.zobrist_without_rest();
+        // 投げ込み先 (横方向の行、縦方向の列の順) ごとの投げ込みの結果。
+        let mut throws: [Option<Option<Throw>>; BlocksRow::NUM + BlocksCol::NUM] =
+            Default::default();
+
+        moves.iter().filter_map(move |&mv| {
+            let i = match mv.dst() {
+                MoveDst::Horizontal(row) => row.to_index(),
+                MoveDst::Vertical(col) => BlocksRow::NUM + col.to_index(),
+            };
+            let throw = throws[i]
+                .get_or_insert_with(|| self.throw(mv.dst()))
+                .clone()?;
+            let (pos_nxt, cost, cost_throw) = self.finish_move(mv, throw, zobrist_base);
+
+            Some((mv, pos_nxt, cost, cost_throw))
+        })
+    }
+
+    /// ブロックを `dst` に投げ込み、自機の位置によらない着手の結果を返す。着手が無効なら `None` を返す。
+    fn throw(&self, dst: MoveDst) -> Option<Throw> {
+        let (blocks, mut block_holding, sq_last, zobrist) =
+            self.blocks
+                .do_move_incremental(dst, self.block_holding, self.ruleset)?;
+
+        // ルールによっては、たくさん消すと保持ブロックがワイルドになる。
+        let erase_count = self.blocks.block_count() - blocks.block_count();
+        if self
+            .ruleset
+            .wild_rule()
+            .is_some_and(|rule| rule.gives_wild(erase_count))
+        {
+            block_holding = Block::Wild;
+        }
 
+        Some(Throw {
+            blocks,
+            block_holding,
+            sq_last,
+            zobrist,
+        })
+    }
+
+    /// 投げ込みの結果 `throw` に自機の移動を合わせて、着手 `mv` の結果を返す。
+    /// `zobrist_base` は `zobrist_without_rest()` の値。
+    fn finish_move(&self, mv: Move, throw: Throw, zobrist_base: u64) -> (Self, Cost, Cost) {
         let cost_hero_move = calc_hero_move_cost(self.hero_row, mv.src());
+        let cost_throw = calc_move_cost(mv.src(), throw.sq_last);
 
         let hero_row = mv.src();
-        let (blocks, block_holding, sq_last) = match mv.dst() {
-            MoveDst::Horizontal(row) => self.blocks.do_move_hori(row, self.block_holding),
-            MoveDst::Vertical(col) => self.blocks.do_move_vert(col, self.block_holding),
-        }?;
         let move_remain = self.move_remain - 1;
 
-        let cost_throw = calc_move_cost(mv.src(), sq_last);
+        // 変化した部分だけハッシュ値を更新する。
+        let zobrist = zobrist_base
+            ^ throw.zobrist
+            ^ Self::zobrist_rest(hero_row, throw.block_holding, move_remain);
 
         let pos_nxt = Self {
             hero_row,
-            blocks,
-            block_holding,
+            blocks: throw.blocks,
+            block_holding: throw.block_holding,
             move_remain,
+            ruleset: self.ruleset,
+            zobrist,
+        };
+
+        (pos_nxt, cost_hero_move + cost_throw, cost_throw)
+    }
+
+    /// ハッシュ値から、ブロック領域以外の部分を除いたもの。
+    fn zobrist_without_rest(&self) -> u64 {
+        self.zobrist ^ Self::zobrist_rest(self.hero_row, self.block_holding, self.move_remain)
+    }
+
+    /// 面クリアとなるブロック数 (ルール設定の `clear_max_blocks()` 以下) かどうか。
+    /// 面が終了した (`is_stuck()`) ときにこれが真なら面クリア。
+    pub fn is_cleared(&self) -> bool {
+        self.block_count() <= self.ruleset.clear_max_blocks()
+    }
+
+    /// 面を解く (ブロック数を `Ruleset::clear_max_blocks()` 以下にする) のに必要な着手数の下界を返す。
+    ///
+    /// 1 回の着手で減らせるのは最初に当たったブロックの種類だけなので、
+    /// 個数の多い種類から順に減らしていくとして、必要な種類数が下界となる。
+    pub fn min_moves_to_solve(&self) -> usize {
+        let mut counts = [
+            Block::Normal1,
+            Block::Normal2,
+            Block::Normal3,
+            Block::Normal4,
+        ]
+        .map(|block| self.blocks.count_of(block));
+        counts.sort_unstable_by(|lhs, rhs| rhs.cmp(lhs));
+
+        let erase_need = self
+            .block_count()
+            .saturating_sub(self.ruleset.clear_max_blocks());
+        (0..=counts.len())
+            .find(|&n| counts[..n].iter().sum::<usize>() >= erase_need)
+            .unwrap()
+    }
+
+    /// ルール設定以外の局面の内容を 128 ビットに詰めた値を返す。ルール設定が同じなら、異なる局面は異なる値になる。
+    ///
+    /// 下位から順にマス 36 個 (各 3 ビット)、自機の位置 (4 ビット)、保持ブロック (3 ビット)、残り手数 (8 ビット)。
+    pub fn packed_key(&self) -> u128 {
+        let mut key = 0_u128;
+        let mut shift = 0;
+        let mut push = |value: u8, bits: u32| {
+            key |= u128::from(value) << shift;
+            shift += bits;
         };
 
-        let cost = cost_hero_move + cost_throw;
+        for sq in BlocksSquare::all() {
+            push(self.blocks[sq].map_or(0, Block::to_inner), 3);
+        }
+        push(self.hero_row.to_inner(), 4);
+        push(self.block_holding.to_inner(), 3);
+        push(self.move_remain, 8);
+
+        key
+    }
 
-        Some((pos_nxt, cost, cost_throw))
+    /// `packed_key()` から残り手数を除いた値。ブロック領域、自機の位置、保持ブロックが同じ局面は同じ値になる。
+    pub(crate) fn packed_key_without_move_remain(&self) -> u128 {
+        self.packed_key() & ((1 << (3 * 36 + 4 + 3)) - 1)
     }
 }
 
-impl std::str::FromStr for Position {
-    type Err = anyhow::Error;
+impl core::str::FromStr for Position {
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |kind| ParseError::new(1, kind);
+
         let (line, s) = s
             .split_once('\n')
-            .ok_or_else(|| anyhow!("局面文字列の最初の行がない: '{s}'"))?;
+            .ok_or_else(|| ParseError::new(2, ParseErrorKind::MissingLine { expected: 7 }))?;
 
         let tokens: Vec<_> = line.split_ascii_whitespace().collect();
-        ensure!(
-            tokens.len() == 3,
-            "局面文字列の最初の行はちょうど 3 つのトークンを持たねばならない: '{line}'"
-        );
+        if tokens.len() != 3 {
+            return Err(err(ParseErrorKind::TokenCount {
+                expected: 3,
+                actual: tokens.len(),
+            }));
+        }
+        let err_at = |i: usize, kind| err(kind).with_col(token_col(line, tokens[i]));
 
         let hero_row: u8 = tokens[0]
             .parse()
-            .with_context(|| format!("自機位置が数値でない: '{}'", tokens[0]))?;
-        let hero_row =
-            MoveSrc::from_inner(hero_row).ok_or_else(|| anyhow!("無効な自機位置: {hero_row}"))?;
+            .map_err(|_| err_at(0, ParseErrorKind::NotNumber(tokens[0].to_owned())))?;
+        let hero_row = MoveSrc::from_inner(hero_row)
+            .ok_or_else(|| err_at(0, ParseErrorKind::InvalidHeroRow(hero_row)))?;
 
         let block_holding: u8 = tokens[1]
             .parse()
-            .with_context(|| format!("保持ブロックが数値でない: '{}'", tokens[1]))?;
+            .map_err(|_| err_at(1, ParseErrorKind::NotNumber(tokens[1].to_owned())))?;
         let block_holding = Block::from_inner(block_holding)
-            .ok_or_else(|| anyhow!("無効な保持ブロック値: {block_holding}"))?;
+            .ok_or_else(|| err_at(1, ParseErrorKind::InvalidBlock(block_holding)))?;
 
         let move_remain: u8 = tokens[2]
             .parse()
-            .with_context(|| format!("残り手数が数値でない: '{}'", tokens[2]))?;
+            .map_err(|_| err_at(2, ParseErrorKind::NotNumber(tokens[2].to_owned())))?;
 
-        let blocks: Blocks = s.parse()?;
+        let blocks: Blocks = s.parse().map_err(|e: ParseError| e.offset_line(1))?;
 
-        Ok(Self {
+        Ok(Self::from_parts(
             hero_row,
-            blocks,
+            BlocksBits::from(&blocks),
             block_holding,
             move_remain,
-        })
+        ))
     }
 }
 
-impl std::fmt::Display for Position {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Position {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         writeln!(
             f,
             "{} {} {}",
@@ -130,13 +334,26 @@ impl std::fmt::Display for Position {
     }
 }
 
+/// `Position::successors()` で投げ込み先ごとに共有する、自機の位置によらない着手の結果。
+#[derive(Clone, Debug)]
+struct Throw {
+    blocks: BlocksBits,
+    /// ルールによるワイルド化を反映した次の保持ブロック。
+    block_holding: Block,
+    sq_last: BlocksSquare,
+    /// ブロック領域の Zobrist ハッシュ値の変化分。
+    zobrist: u64,
+}
+
 #[cfg(test)]
 mod tests {
     use indoc::indoc;
     use pretty_assertions::assert_eq;
 
     use crate::block::*;
+    use crate::error::ParseErrorKind;
     use crate::move_::*;
+    use crate::ruleset::WildRule;
 
     use super::*;
 
@@ -173,6 +390,203 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ord() {
+        let pos1 = parse_position(indoc! {"
+            9 1 10
+            ......
+            ......
+            ......
+            ......
+            ......
+            1.....
+        "});
+        let pos2 = parse_position(indoc! {"
+            11 1 10
+            ......
+            ......
+            ......
+            ......
+            ......
+            .....1
+        "});
+        let pos3 = parse_position(indoc! {"
+            11 1 10
+            ......
+            ......
+            ......
+            ......
+            ......
+            2.....
+        "});
+
+        // 自機の位置が最優先で、次にブロック領域を上の行から比べる。
+        let set: alloc::collections::BTreeSet<_> =
+            [&pos3, &pos2, &pos1, &pos2].into_iter().collect();
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), [&pos1, &pos2, &pos3]);
+
+        let mut moves = [
+            Move::new(MOVE_SRC_ROW_5, MoveDst::Vertical(BLOCKS_COL_A)),
+            Move::new(MOVE_SRC_ROW_9, MoveDst::Horizontal(BLOCKS_ROW_4)),
+            Move::new(MOVE_SRC_ROW_5, MoveDst::Horizontal(BLOCKS_ROW_1)),
+        ];
+        moves.sort();
+        assert_eq!(
+            moves,
+            [
+                Move::new(MOVE_SRC_ROW_5, MoveDst::Horizontal(BLOCKS_ROW_1)),
+                Move::new(MOVE_SRC_ROW_5, MoveDst::Vertical(BLOCKS_COL_A)),
+                Move::new(MOVE_SRC_ROW_9, MoveDst::Horizontal(BLOCKS_ROW_4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_packed_key() {
+        let pos = parse_position(indoc! {"
+            10 5 3
+            ......
+            ......
+            2444..
+            1222..
+            3333..
+            4111..
+        "});
+        let (pos_nxt, _, _) = pos
+            .do_move(Move::new(
+                MoveSrc::from_inner(10).unwrap(),
+                MoveDst::Horizontal(BlocksRow::from_inner(5).unwrap()),
+            ))
+            .unwrap();
+
+        assert_eq!(pos.packed_key() >> 108, 10 | (5 << 4) | (3 << 7));
+        assert_ne!(pos.packed_key(), pos_nxt.packed_key());
+        assert_eq!(pos.clone().packed_key(), pos.packed_key());
+    }
+
+    #[test]
+    fn test_zobrist_hash() {
+        fn walk(pos: &Position, moves: &[Move], depth: usize) {
+            // 差分更新した値が一から計算した値と一致する。
+            let pos_fresh = Position::from_parts(
+                pos.hero_row(),
+                *pos.blocks(),
+                pos.block_holding(),
+                pos.move_remain(),
+            );
+            assert_eq!(pos.zobrist_hash(), pos_fresh.zobrist_hash());
+            assert_eq!(BlocksBits::from(&Blocks::from(pos.blocks())), *pos.blocks());
+
+            // 最初に当たるブロックだけを見た判定が、実際に投げ込んだ結果と一致する。
+            let ruleset = pos.ruleset().with_allow_swap(false);
+            let stuck = moves.iter().all(|&mv| {
+                match mv.dst() {
+                    MoveDst::Horizontal(row) => {
+                        pos.blocks().do_move_hori(row, pos.block_holding(), ruleset)
+                    }
+                    MoveDst::Vertical(col) => {
+                        pos.blocks().do_move_vert(col, pos.block_holding(), ruleset)
+                    }
+                }
+                .is_none()
+            });
+            assert_eq!(pos.is_stuck(moves), stuck);
+
+            if depth == 0 || pos.move_remain() == 0 {
+                return;
+            }
+            for &mv in moves {
+                // 入れ替え着手は、入れ替えを許さないルールでは無効になる有効な着手。
+                assert_eq!(
+                    pos.is_swap_move(mv),
+                    pos.do_move(mv).is_some()
+                        && pos.clone().with_ruleset(ruleset).do_move(mv).is_none()
+                );
+                if let Some((pos_nxt, _, _)) = pos.do_move(mv) {
+                    walk(&pos_nxt, moves, depth - 1);
+                }
+            }
+        }
+
+        let problem: crate::problem::Problem = include_str!("../problem/50.in").parse().unwrap();
+        let (pos, moves) = problem.to_position_and_moves();
+        for ruleset in [
+            Ruleset::new(),
+            Ruleset::new()
+                .with_allow_swap(true)
+                .with_wild_rule(Some(WildRule::EraseCount(3))),
+        ] {
+            walk(&pos.clone().with_ruleset(ruleset), &moves, 4);
+        }
+
+        let (pos_nxt, _, _) = moves.iter().find_map(|&mv| pos.do_move(mv)).unwrap();
+        assert_ne!(pos.zobrist_hash(), pos_nxt.zobrist_hash());
+    }
+
+    #[test]
+    fn test_successors() {
+        fn walk(pos: &Position, moves: &[Move], depth: usize) {
+            if depth == 0 || pos.move_remain() == 0 {
+                return;
+            }
+
+            let expected: Vec<_> = moves
+                .iter()
+                .filter_map(|&mv| {
+                    let (pos_nxt, cost, cost_throw) = pos.do_move(mv)?;
+                    Some((mv, pos_nxt, cost, cost_throw))
+                })
+                .collect();
+            let actual: Vec<_> = pos.successors(moves).collect();
+            assert_eq!(actual, expected);
+
+            for (_, pos_nxt, _, _) in actual {
+                walk(&pos_nxt, moves, depth - 1);
+            }
+        }
+
+        // 上の方の行から同じ列に落ちる着手がある。
+        let problem: crate::problem::Problem = include_str!("../problem/50.in").parse().unwrap();
+        let (pos, moves) = problem.to_position_and_moves();
+        assert!(moves
+            .iter()
+            .enumerate()
+            .any(|(i, mv)| moves[..i].iter().any(|other| other.dst() == mv.dst())));
+
+        for ruleset in [
+            Ruleset::new(),
+            Ruleset::new()
+                .with_allow_swap(true)
+                .with_wild_rule(Some(WildRule::EraseCount(3))),
+        ] {
+            walk(&pos.clone().with_ruleset(ruleset), &moves, 3);
+        }
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let parse_err = |s: &str| s.parse::<Position>().unwrap_err();
+
+        assert_eq!(
+            parse_err("11  9 10\n"),
+            ParseError::new(1, ParseErrorKind::InvalidBlock(9)).with_col(5)
+        );
+
+        let s = indoc! {"
+            11 1 10
+            ......
+            ......
+            ...x..
+            ......
+            ......
+            ......
+        "};
+        assert_eq!(
+            parse_err(s),
+            ParseError::new(4, ParseErrorKind::InvalidChar('x')).with_col(4)
+        );
+    }
+
     #[test]
     fn test_do_move() {
         let cases = [
@@ -226,5 +640,86 @@ mod tests {
             let (after_actual, _, _) = before.do_move(mv).unwrap();
             assert_eq!(after_actual, after);
         }
+
+        // 残り手数が 0 なら、消せるブロックがあっても着手できない。
+        let pos = parse_position(indoc! {"
+            11 3 0
+            ......
+            ......
+            222222
+            333333
+            344444
+            311111
+        "});
+        let mv = Move::new(MOVE_SRC_ROW_9, MoveDst::Horizontal(BLOCKS_ROW_4));
+        assert_eq!(pos.do_move(mv), None);
+        assert_eq!(pos.successors(&[mv]).count(), 0);
+    }
+
+    #[test]
+    fn test_do_move_wild_rule() {
+        // 8 個消える着手。
+        let before = parse_position(indoc! {"
+            11 3 5
+            ......
+            ......
+            222222
+            333333
+            344444
+            311111
+        "});
+        let mv = Move::new(MOVE_SRC_ROW_9, MoveDst::Horizontal(BLOCKS_ROW_4));
+
+        for (count, expected) in [(8, Block::Wild), (9, Block::Normal3)] {
+            let ruleset = Ruleset::new().with_wild_rule(Some(WildRule::EraseCount(count)));
+            let (after, _, _) = before.clone().with_ruleset(ruleset).do_move(mv).unwrap();
+            assert_eq!(after.block_holding(), expected);
+        }
+    }
+
+    #[test]
+    fn test_min_moves_to_solve() {
+        let cases = [
+            (
+                indoc! {"
+                    11 1 10
+                    ......
+                    ......
+                    ......
+                    ......
+                    ......
+                    12....
+                "},
+                0,
+            ),
+            (
+                indoc! {"
+                    11 1 10
+                    ......
+                    ......
+                    ......
+                    ......
+                    3.....
+                    12244.
+                "},
+                2,
+            ),
+            (
+                indoc! {"
+                    11 5 10
+                    1.1.1.
+                    111111
+                    222222
+                    333333
+                    444444
+                    444444
+                "},
+                4,
+            ),
+        ];
+
+        for (pos, expected) in cases {
+            assert_eq!(parse_position(pos).min_moves_to_solve(), expected);
+        }
     }
 }
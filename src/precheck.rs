@@ -0,0 +1,206 @@
+use alloc::vec::Vec;
+
+use crate::block::{Block, BlocksCol, BlocksRow, BlocksSquare, BLOCKS_COL_A};
+use crate::locale::tr;
+use crate::move_::{Move, MoveDst};
+use crate::position::Position;
+use crate::problem::Problem;
+
+/// 探索せずに分かる、問題が解けない理由。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Unsolvable {
+    /// どのような着手列によっても消せないブロック (死にブロック) が、面クリアとなる個数より多い。
+    DeadBlocks { count: usize },
+
+    /// 最初から着手できない (投げたブロックがどこにも当たらないか、当たったブロックの種類が合わない) のに、
+    /// 面クリアとなる個数より多くのブロックがある。
+    NoLegalMove,
+
+    /// 消せるブロックの種類ごとの個数から求めた必要な着手数の下界が、手数を超える。
+    NotEnoughMoves { needed: usize, move_remain: u8 },
+}
+
+impl core::fmt::Display for Unsolvable {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            Self::DeadBlocks { count } => write!(
+                f,
+                "{}: {count}",
+                tr(
+                    "消せないブロックが多すぎる",
+                    "too many blocks can never be erased"
+                )
+            ),
+            Self::NoLegalMove => f.write_str(tr(
+                "最初から着手できない",
+                "no legal move in the initial position",
+            )),
+            Self::NotEnoughMoves {
+                needed,
+                move_remain,
+            } => write!(
+                f,
+                "{}: {needed} > {move_remain}",
+                tr(
+                    "必要な着手数が手数を超える",
+                    "required throws exceed the move count"
+                )
+            ),
+        }
+    }
+}
+
+/// 問題が解けないことを探索せずに (安価な静的解析で) 示せれば、その理由を返す。
+///
+/// `None` は解けることを意味しない。ソルバーは探索の前にこれを調べ、解けないと分かれば直ちに `None` を返す。
+pub fn prove_unsolvable(problem: &Problem) -> Option<Unsolvable> {
+    let (pos, moves) = problem.to_position_and_moves();
+
+    if pos.is_cleared() {
+        return None;
+    }
+
+    let clear_max_blocks = pos.ruleset().clear_max_blocks();
+    let sqs_dead = dead_blocks(&pos, &moves);
+    if sqs_dead.len() > clear_max_blocks {
+        return Some(Unsolvable::DeadBlocks {
+            count: sqs_dead.len(),
+        });
+    }
+
+    if pos.is_stuck(&moves) {
+        return Some(Unsolvable::NoLegalMove);
+    }
+
+    // 1 回の着手で消せるのは 1 種類のブロックだけなので、消せるブロックの個数の多い種類から順に消すとして
+    // 必要な着手数を下から抑える。
+    let mut counts = [0_usize; 4];
+    for sq in BlocksSquare::all() {
+        if let Some(block) = pos.blocks()[sq] {
+            if !sqs_dead.contains(&sq) {
+                counts[usize::from(block.to_inner() - Block::MIN_VALUE)] += 1;
+            }
+        }
+    }
+    counts.sort_unstable_by(|lhs, rhs| rhs.cmp(lhs));
+    let erase_need = pos.block_count() - clear_max_blocks;
+    let needed = (0..=counts.len())
+        .find(|&n| counts[..n].iter().sum::<usize>() >= erase_need)
+        .unwrap();
+    if needed > usize::from(pos.move_remain()) {
+        return Some(Unsolvable::NotEnoughMoves {
+            needed,
+            move_remain: pos.move_remain(),
+        });
+    }
+
+    None
+}
+
+/// 局面 `pos` で、着手 `moves` のどれによっても消せないブロックの位置を列挙する
+/// (`analysis::find_dead_blocks()` を参照)。
+pub(crate) fn dead_blocks(pos: &Position, moves: &[Move]) -> Vec<BlocksSquare> {
+    let has_vert = |col: BlocksCol| moves.iter().any(|mv| mv.dst() == MoveDst::Vertical(col));
+    let has_hori_below = |row: BlocksRow| {
+        moves.iter().any(|mv| {
+            matches!(mv.dst(), MoveDst::Horizontal(row_mv) if row_mv.to_inner() >= row.to_inner())
+        })
+    };
+    let has_hori = moves
+        .iter()
+        .any(|mv| matches!(mv.dst(), MoveDst::Horizontal(_)));
+
+    BlocksSquare::all()
+        .into_iter()
+        .filter(|&sq| pos.blocks()[sq].is_some())
+        .filter(|&sq| {
+            let (col, row) = (sq.col(), sq.row());
+            let reachable =
+                has_vert(col) || has_hori_below(row) || (col == BLOCKS_COL_A && has_hori);
+            !reachable
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn parse_problem(s: impl AsRef<str>) -> Problem {
+        s.as_ref().parse().unwrap()
+    }
+
+    #[test]
+    fn test_prove_unsolvable() {
+        for s in [
+            include_str!("../problem/01.in"),
+            include_str!("../problem/20.in"),
+            include_str!("../problem/50.in"),
+        ] {
+            assert_eq!(prove_unsolvable(&parse_problem(s)), None);
+        }
+
+        let problem = parse_problem(indoc! {"
+            1 10
+            #######.
+            #######.
+            #######.
+            #######.
+            #######.
+            #######.
+            1.......
+            .2....|.
+            ..3...|.
+            ...4..|.
+            ....1.|.
+            1.....|.
+        "});
+        assert_eq!(
+            prove_unsolvable(&problem),
+            Some(Unsolvable::DeadBlocks { count: 4 })
+        );
+
+        let problem = parse_problem(indoc! {"
+            3 2
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "});
+        assert_eq!(
+            prove_unsolvable(&problem),
+            Some(Unsolvable::NotEnoughMoves {
+                needed: 4,
+                move_remain: 2,
+            })
+        );
+
+        let problem = parse_problem(indoc! {"
+            1 10
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2222....
+            3333....
+            4444....
+            2222....
+        "});
+        assert_eq!(prove_unsolvable(&problem), Some(Unsolvable::NoLegalMove));
+    }
+}
@@ -1,12 +1,20 @@
-use std::fmt::Write as _;
-
-use anyhow::{anyhow, ensure, Context as _};
+use alloc::borrow::ToOwned as _;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write as _;
 
 use crate::block::{Block, Blocks, BlocksCol, BlocksRow, BLOCKS_COL_A};
+use crate::error::{
+    ensure_line_count, token_col, Error, ParseError, ParseErrorKind, ValidationError,
+    ValidationErrorKind,
+};
 use crate::move_::{Move, MoveDst, MoveSrc};
 use crate::position::Position;
+use crate::ruleset::Ruleset;
+use crate::stage::{Stage, STAGE_COUNT};
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// 問題の盤面のタイル。順序はブロック (値の順)、壁、パイプの順。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum ProblemTile {
     Block(Block),
     Wall,
@@ -35,12 +43,13 @@ impl ProblemTile {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// 問題の盤面。順序は各マスを上の行から順に (各行は左から順に) 並べたものの辞書式順序 (空白が最小)。
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct ProblemBoard([Option<ProblemTile>; Self::WIDTH * Self::HEIGHT]);
 
 impl Default for ProblemBoard {
     fn default() -> Self {
-        Self(std::array::from_fn(|_| None))
+        Self(core::array::from_fn(|_| None))
     }
 }
 
@@ -61,6 +70,20 @@ impl ProblemBoard {
         Self::default()
     }
 
+    /// 指定したマス (0-based) を返す。範囲外なら `None` を返す。
+    pub fn get(&self, col: usize, row: usize) -> Option<&Option<ProblemTile>> {
+        Self::in_bounds(col, row).then(|| &self[(col, row)])
+    }
+
+    /// `get` の可変版。
+    pub fn get_mut(&mut self, col: usize, row: usize) -> Option<&mut Option<ProblemTile>> {
+        Self::in_bounds(col, row).then(|| &mut self[(col, row)])
+    }
+
+    fn in_bounds(col: usize, row: usize) -> bool {
+        col < Self::WIDTH && row < Self::HEIGHT
+    }
+
     fn cr2idx(col: usize, row: usize) -> usize {
         Self::WIDTH * row + col
     }
@@ -78,7 +101,7 @@ impl ProblemBoard {
         }
     }
 
-    fn char_to_tile(ch: char) -> anyhow::Result<Option<ProblemTile>> {
+    fn char_to_tile(ch: char) -> Result<Option<ProblemTile>, ParseErrorKind> {
         match ch {
             Self::CHAR_TILE_NONE => Ok(None),
             Self::CHAR_TILE_BLOCK_NORMAL1 => Ok(Some(ProblemTile::Block(Block::Normal1))),
@@ -88,12 +111,12 @@ impl ProblemBoard {
             Self::CHAR_TILE_BLOCK_WILD => Ok(Some(ProblemTile::Block(Block::Wild))),
             Self::CHAR_TILE_WALL => Ok(Some(ProblemTile::Wall)),
             Self::CHAR_TILE_PIPE => Ok(Some(ProblemTile::Pipe)),
-            _ => Err(anyhow!("無効な盤面タイル文字: '{ch}'")),
+            _ => Err(ParseErrorKind::InvalidChar(ch)),
         }
     }
 }
 
-impl std::ops::Index<(usize, usize)> for ProblemBoard {
+impl core::ops::Index<(usize, usize)> for ProblemBoard {
     type Output = Option<ProblemTile>;
 
     fn index(&self, (col, row): (usize, usize)) -> &Self::Output {
@@ -101,35 +124,34 @@ impl std::ops::Index<(usize, usize)> for ProblemBoard {
     }
 }
 
-impl std::ops::IndexMut<(usize, usize)> for ProblemBoard {
+impl core::ops::IndexMut<(usize, usize)> for ProblemBoard {
     fn index_mut(&mut self, (col, row): (usize, usize)) -> &mut Self::Output {
         &mut self.0[Self::cr2idx(col, row)]
     }
 }
 
-impl std::str::FromStr for ProblemBoard {
-    type Err = anyhow::Error;
+impl core::str::FromStr for ProblemBoard {
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let lines: Vec<_> = s.lines().collect();
-        ensure!(
-            lines.len() == Self::HEIGHT,
-            "盤面文字列はちょうど {} 行でなければならない",
-            Self::HEIGHT
-        );
+        ensure_line_count(&lines, Self::HEIGHT)?;
 
         let mut this = Self::new();
 
         for (row, line) in lines.into_iter().enumerate() {
+            let err = |kind| ParseError::new(row + 1, kind);
+
             let chars: Vec<_> = line.chars().collect();
-            ensure!(
-                chars.len() == Self::WIDTH,
-                "盤面文字列の行はちょうど {} 文字でなければならない",
-                Self::WIDTH
-            );
+            if chars.len() != Self::WIDTH {
+                return Err(err(ParseErrorKind::LineLength {
+                    expected: Self::WIDTH,
+                    actual: chars.len(),
+                }));
+            }
 
             for (col, ch) in chars.into_iter().enumerate() {
-                let tile = Self::char_to_tile(ch)?;
+                let tile = Self::char_to_tile(ch).map_err(|kind| err(kind).with_col(col + 1))?;
                 this[(col, row)] = tile;
             }
         }
@@ -138,8 +160,8 @@ impl std::str::FromStr for ProblemBoard {
     }
 }
 
-impl std::fmt::Display for ProblemBoard {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for ProblemBoard {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         for row in 0..Self::HEIGHT {
             for col in 0..Self::WIDTH {
                 let tile = self[(col, row)];
@@ -153,15 +175,145 @@ impl std::fmt::Display for ProblemBoard {
     }
 }
 
-#[derive(Debug)]
+/// 問題ファイル先頭のコメント行とメタデータ行。求解には影響しない。
+///
+/// 文字列表現では、保持ブロックと残り手数の行より前に `# コメント` または `キー: 値` の形で書く。
+/// 行の順序は保存される。
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ProblemMetadata(Vec<MetadataLine>);
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum MetadataLine {
+    Comment(String),
+    Entry { key: String, value: String },
+}
+
+impl ProblemMetadata {
+    /// 面番号のキー。
+    pub const KEY_STAGE: &'static str = "stage";
+    /// 抽出元 ROM のハッシュのキー。
+    pub const KEY_ROM_HASH: &'static str = "rom_hash";
+    /// 作者のキー。
+    pub const KEY_AUTHOR: &'static str = "author";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// コメント行たち (先頭の `#` とそれに続く空白を除いたもの)。
+    pub fn comments(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().filter_map(|line| match line {
+            MetadataLine::Comment(comment) => Some(comment.as_str()),
+            MetadataLine::Entry { .. } => None,
+        })
+    }
+
+    /// キーに対応する値を返す。
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.iter().find_map(|line| match line {
+            MetadataLine::Entry { key: k, value } if k == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn stage(&self) -> Option<Stage> {
+        self.get(Self::KEY_STAGE)?.parse().ok()
+    }
+
+    /// 面番号が原作の最終面かどうか。
+    pub fn is_last_stage(&self) -> bool {
+        self.stage() == Some(STAGE_COUNT)
+    }
+
+    pub fn rom_hash(&self) -> Option<&str> {
+        self.get(Self::KEY_ROM_HASH)
+    }
+
+    pub fn author(&self) -> Option<&str> {
+        self.get(Self::KEY_AUTHOR)
+    }
+
+    pub fn push_comment(&mut self, comment: impl Into<String>) {
+        self.0.push(MetadataLine::Comment(comment.into()));
+    }
+
+    /// キーに値を設定する。既にあれば置き換え、なければ末尾に追加する。
+    ///
+    /// キーは空白と `:` を含んではならない。
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        let key = key.into();
+        let value = value.into();
+        assert!(Self::is_valid_key(&key), "invalid metadata key: '{key}'");
+
+        for line in &mut self.0 {
+            if let MetadataLine::Entry { key: k, value: v } = line {
+                if *k == key {
+                    *v = value;
+                    return;
+                }
+            }
+        }
+
+        self.0.push(MetadataLine::Entry { key, value });
+    }
+
+    fn is_valid_key(key: &str) -> bool {
+        !key.is_empty() && !key.contains(|c: char| c == ':' || c.is_whitespace())
+    }
+
+    /// 1 行をコメント行またはメタデータ行として解釈する。どちらでもなければ `None` を返す。
+    fn parse_line(line: &str) -> Option<MetadataLine> {
+        if let Some(comment) = line.strip_prefix('#') {
+            return Some(MetadataLine::Comment(comment.trim_start().to_owned()));
+        }
+
+        let (key, value) = line.split_once(':')?;
+        Self::is_valid_key(key).then(|| MetadataLine::Entry {
+            key: key.to_owned(),
+            value: value.trim().to_owned(),
+        })
+    }
+}
+
+impl core::fmt::Display for ProblemMetadata {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for line in &self.0 {
+            match line {
+                MetadataLine::Comment(comment) if comment.is_empty() => writeln!(f, "#")?,
+                MetadataLine::Comment(comment) => writeln!(f, "# {comment}")?,
+                MetadataLine::Entry { key, value } => writeln!(f, "{key}: {value}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 問題。
+///
+/// 順序は (盤面, 保持ブロック, 残り手数, ルール設定, メタデータ) の辞書式順序。
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Problem {
     board: ProblemBoard,
     block_holding: Block,
     move_remain: u8,
+    ruleset: Ruleset,
+    metadata: ProblemMetadata,
 }
 
 impl Problem {
-    pub fn new(board: ProblemBoard, block_holding: Block, move_remain: u8) -> anyhow::Result<Self> {
+    /// 原作 ROM 内のエンコーディングでの 1 問のバイト数。
+    pub const ROM_BYTES_LEN: usize = 50 + 24;
+
+    pub fn new(
+        board: ProblemBoard,
+        block_holding: Block,
+        move_remain: u8,
+    ) -> Result<Self, ValidationError> {
         /// 与えられたマスが左下 6x6 の範囲内かどうかを返す。
         fn is_blocks_area(col: usize, row: usize) -> bool {
             (0..6).contains(&col) && (6..12).contains(&row)
@@ -169,22 +321,20 @@ impl Problem {
 
         // 問題が ADVANCE モードの制約を満たしているかどうかチェック。
         for (row, col) in itertools::iproduct!(0..ProblemBoard::HEIGHT, 0..ProblemBoard::WIDTH) {
+            let err = |kind| Err(ValidationError::new(col, row, kind));
             let tile = board[(col, row)];
             if is_blocks_area(col, row) {
-                ensure!(
-                    tile.map_or(true, |tile| tile.is_normal_block()),
-                    "左下 6x6 は空白または通常ブロックでなければならない"
-                );
+                if !tile.map_or(true, |tile| tile.is_normal_block()) {
+                    return err(ValidationErrorKind::InvalidBlocksAreaTile);
+                }
             } else {
-                ensure!(
-                    !tile.is_some_and(ProblemTile::is_block),
-                    "左下 6x6 の範囲外にブロックがあってはならない"
-                );
-                if tile.is_some_and(ProblemTile::is_wall) {
-                    ensure!(
-                        row == 0 || board[(col, row - 1)].is_some_and(ProblemTile::is_wall),
-                        "壁の上には壁がなければならない"
-                    );
+                if tile.is_some_and(ProblemTile::is_block) {
+                    return err(ValidationErrorKind::BlockOutsideBlocksArea);
+                }
+                if tile.is_some_and(ProblemTile::is_wall)
+                    && !(row == 0 || board[(col, row - 1)].is_some_and(ProblemTile::is_wall))
+                {
+                    return err(ValidationErrorKind::WallWithoutWallAbove);
                 }
             }
         }
@@ -193,9 +343,21 @@ impl Problem {
             board,
             block_holding,
             move_remain,
+            ruleset: Ruleset::default(),
+            metadata: ProblemMetadata::default(),
         })
     }
 
+    /// コメントとメタデータを変更した問題を返す。
+    pub fn with_metadata(self, metadata: ProblemMetadata) -> Self {
+        Self { metadata, ..self }
+    }
+
+    /// ルール設定を変更した問題を返す。ルール設定は文字列表現には含まれない。
+    pub fn with_ruleset(self, ruleset: Ruleset) -> Self {
+        Self { ruleset, ..self }
+    }
+
     pub fn board(&self) -> &ProblemBoard {
         &self.board
     }
@@ -208,11 +370,119 @@ impl Problem {
         self.move_remain
     }
 
+    pub fn ruleset(&self) -> Ruleset {
+        self.ruleset
+    }
+
+    pub fn metadata(&self) -> &ProblemMetadata {
+        &self.metadata
+    }
+
+    /// 原作 ROM 内のエンコーディング (ブロック配置 50 バイト + 壁/パイプ配置 24 バイト) から問題を作る。
+    ///
+    /// ブロック配置は左下 8x6 のタイル 48 バイト、残り手数、初期保持ブロックの順。
+    /// 壁/パイプ配置は各行の壁のビットマスク 12 バイト、各行のパイプのビットマスク 12 バイトの順 (MSB が左端)。
+    pub fn from_rom_bytes(buf: &[u8; Self::ROM_BYTES_LEN]) -> Result<Self, Error> {
+        let mut board = ProblemBoard::new();
+
+        for (row, col) in itertools::iproduct!(0..6, 0..8) {
+            let offset = 8 * row + col;
+            let block = match buf[offset] {
+                0 => None,
+                // ADVANCE モードでは盤面にワイルドカードが現れることはない。
+                value @ 1..=4 => Block::from_inner(value),
+                value => return Err(Error::InvalidRomByte { offset, value }),
+            };
+            board[(col, row + 6)] = block.map(ProblemTile::Block);
+        }
+
+        let move_remain = buf[48];
+
+        let block_holding = Block::from_inner(buf[49]).ok_or(Error::InvalidRomByte {
+            offset: 49,
+            value: buf[49],
+        })?;
+
+        for (i, tile) in [ProblemTile::Wall, ProblemTile::Pipe]
+            .into_iter()
+            .enumerate()
+        {
+            for (row, &value) in buf[50 + 12 * i..][..12].iter().enumerate() {
+                for col in 0..8 {
+                    if (value & (1 << (7 - col))) != 0 {
+                        board[(col, row)] = Some(tile);
+                    }
+                }
+            }
+        }
+
+        Ok(Self::new(board, block_holding, move_remain)?)
+    }
+
+    /// 原作 ROM 内のエンコーディングに変換する (`from_rom_bytes` の逆)。
+    pub fn to_rom_bytes(&self) -> [u8; Self::ROM_BYTES_LEN] {
+        let mut buf = [0; Self::ROM_BYTES_LEN];
+
+        for (row, col) in itertools::iproduct!(0..6, 0..8) {
+            if let Some(ProblemTile::Block(block)) = self.board[(col, row + 6)] {
+                buf[8 * row + col] = block.to_inner();
+            }
+        }
+
+        buf[48] = self.move_remain;
+        buf[49] = self.block_holding.to_inner();
+
+        for (row, col) in itertools::iproduct!(0..12, 0..8) {
+            let bit = 1 << (7 - col);
+            match self.board[(col, row)] {
+                Some(ProblemTile::Wall) => buf[50 + row] |= bit,
+                Some(ProblemTile::Pipe) => buf[50 + 12 + row] |= bit,
+                _ => {}
+            }
+        }
+
+        buf
+    }
+
+    /// 行 `src` からブロックを投げたときの着手 (どこに投げ込まれるか) を返す。
+    /// その行からはどのブロックにも当たらない場合は `None` を返す。
+    pub fn move_for_src(&self, src: MoveSrc) -> Option<Move> {
+        // この行からブロックを横に投げたときに最初に当たるタイルとその列を求める。
+        let col_tile = (0..ProblemBoard::WIDTH)
+            .rev()
+            .find_map(|col| self.board[(col, src.to_index())].map(|tile| (col, tile)));
+        match col_tile {
+            // 当たるタイルがなければ一番奥まで行って落ちる着手となる。
+            None => {
+                let dst = MoveDst::Vertical(BLOCKS_COL_A);
+                Some(Move::new(src, dst))
+            }
+            // ブロックに当たるなら横に投げる着手となる。
+            Some((_, ProblemTile::Block(_))) => {
+                let brow = BlocksRow::try_from(src).unwrap();
+                let dst = MoveDst::Horizontal(brow);
+                Some(Move::new(src, dst))
+            }
+            // 壁またはパイプに当たるならそこから落ちる着手となる。
+            // ブロックに当たらないなら無効とする。
+            Some((col, _)) => {
+                let col = col + 1;
+                let block_exists = (src.to_index()..ProblemBoard::HEIGHT)
+                    .any(|row| self.board[(col, row)].is_some_and(ProblemTile::is_block));
+                block_exists.then(|| {
+                    let bcol = BlocksCol::from_inner((col + 1) as u8).unwrap();
+                    let dst = MoveDst::Vertical(bcol);
+                    Move::new(src, dst)
+                })
+            }
+        }
+    }
+
     pub fn to_position_and_moves(&self) -> (Position, Vec<Move>) {
         let mut blocks = Blocks::new();
 
-        for (prow, brow) in std::iter::zip(6..12, BlocksRow::all()) {
-            for (pcol, bcol) in std::iter::zip(0..6, BlocksCol::all()) {
+        for (prow, brow) in core::iter::zip(6..12, BlocksRow::all()) {
+            for (pcol, bcol) in core::iter::zip(0..6, BlocksCol::all()) {
                 let block = match self.board[(pcol, prow)] {
                     None => None,
                     Some(ProblemTile::Block(block)) => {
@@ -225,84 +495,113 @@ impl Problem {
             }
         }
 
-        let pos = Position::new(blocks, self.block_holding, self.move_remain);
+        let pos =
+            Position::new(blocks, self.block_holding, self.move_remain).with_ruleset(self.ruleset);
 
         // 各行からブロックを投げたときの着手を求め、有効なもののみを集める。
         let moves: Vec<_> = MoveSrc::all()
             .into_iter()
             .rev()
-            .filter_map(|src| {
-                // この行からブロックを横に投げたときに最初に当たるタイルとその列を求める。
-                let col_tile = (0..ProblemBoard::WIDTH)
-                    .rev()
-                    .find_map(|col| self.board[(col, src.to_index())].map(|tile| (col, tile)));
-                match col_tile {
-                    // 当たるタイルがなければ一番奥まで行って落ちる着手となる。
-                    None => {
-                        let dst = MoveDst::Vertical(BLOCKS_COL_A);
-                        Some(Move::new(src, dst))
-                    }
-                    // ブロックに当たるなら横に投げる着手となる。
-                    Some((_, ProblemTile::Block(_))) => {
-                        let brow = BlocksRow::try_from(src).unwrap();
-                        let dst = MoveDst::Horizontal(brow);
-                        Some(Move::new(src, dst))
-                    }
-                    // 壁またはパイプに当たるならそこから落ちる着手となる。
-                    // ブロックに当たらないなら無効とする。
-                    Some((col, _)) => {
-                        let col = col + 1;
-                        let block_exists = (src.to_index()..ProblemBoard::HEIGHT)
-                            .any(|row| self.board[(col, row)].is_some_and(ProblemTile::is_block));
-                        block_exists.then(|| {
-                            let bcol = BlocksCol::from_inner((col + 1) as u8).unwrap();
-                            let dst = MoveDst::Vertical(bcol);
-                            Move::new(src, dst)
-                        })
-                    }
-                }
-            })
+            .filter_map(|src| self.move_for_src(src))
             .collect();
 
         (pos, moves)
     }
 }
 
-impl std::str::FromStr for Problem {
-    type Err = anyhow::Error;
+impl core::str::FromStr for Problem {
+    type Err = Error;
+
+    fn from_str(mut s: &str) -> Result<Self, Self::Err> {
+        // 先頭のコメント行とメタデータ行を読む。
+        let mut metadata = ProblemMetadata::new();
+        let mut line_offset = 0;
+        while let Some((line, rest)) = s.split_once('\n') {
+            let Some(meta_line) = ProblemMetadata::parse_line(line) else {
+                break;
+            };
+            metadata.0.push(meta_line);
+            s = rest;
+            line_offset += 1;
+        }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (line, s) = s
-            .split_once('\n')
-            .ok_or_else(|| anyhow!("問題文字列の最初の行がない: '{s}'"))?;
+        let err = |kind| ParseError::new(line_offset + 1, kind);
+
+        let (line, s) = s.split_once('\n').ok_or_else(|| {
+            ParseError::new(
+                line_offset + 2,
+                ParseErrorKind::MissingLine {
+                    expected: 1 + ProblemBoard::HEIGHT,
+                },
+            )
+        })?;
 
         let tokens: Vec<_> = line.split_ascii_whitespace().collect();
-        ensure!(
-            tokens.len() == 2,
-            "問題文字列の最初の行はちょうど 2 つのトークンを持たねばならない: '{line}'"
-        );
+        if tokens.len() != 2 {
+            return Err(err(ParseErrorKind::TokenCount {
+                expected: 2,
+                actual: tokens.len(),
+            })
+            .into());
+        }
+
+        let err_at = |i: usize, kind| err(kind).with_col(token_col(line, tokens[i]));
 
         let block_holding: u8 = tokens[0]
             .parse()
-            .with_context(|| format!("保持ブロックが数値でない: '{}'", tokens[0]))?;
+            .map_err(|_| err_at(0, ParseErrorKind::NotNumber(tokens[0].to_owned())))?;
         let block_holding = Block::from_inner(block_holding)
-            .ok_or_else(|| anyhow!("無効な保持ブロック値: {block_holding}"))?;
+            .ok_or_else(|| err_at(0, ParseErrorKind::InvalidBlock(block_holding)))?;
 
         let move_remain: u8 = tokens[1]
             .parse()
-            .with_context(|| format!("残り手数が数値でない: '{}'", tokens[1]))?;
+            .map_err(|_| err_at(1, ParseErrorKind::NotNumber(tokens[1].to_owned())))?;
 
-        let board: ProblemBoard = s.parse()?;
+        let board: ProblemBoard = s
+            .parse()
+            .map_err(|e: ParseError| e.offset_line(line_offset + 1))?;
 
-        let this = Self::new(board, block_holding, move_remain)
-            .context("問題が ADVANCE モードの制約を満たしていない")?;
+        let this = Self::new(board, block_holding, move_remain)?.with_metadata(metadata);
 
         Ok(this)
     }
 }
 
-impl std::fmt::Display for Problem {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// 複数の問題を含む文字列をパースする。
+///
+/// 問題どうしは 1 行以上の空行、または `---` だけの行で区切る。
+/// パースエラーの行番号は文字列全体に対するものとなる。
+pub fn parse_problems(s: &str) -> Result<Vec<Problem>, Error> {
+    const SEPARATOR: &str = "---";
+
+    let mut problems = Vec::<Problem>::new();
+    let mut chunk = String::new();
+    let mut chunk_line = 0;
+
+    for (row, line) in s.lines().chain([SEPARATOR]).enumerate() {
+        if line.trim().is_empty() || line == SEPARATOR {
+            if !chunk.is_empty() {
+                let problem = chunk.parse::<Problem>().map_err(|e| match e {
+                    Error::Parse(e) => Error::Parse(e.offset_line(chunk_line)),
+                    e => e,
+                })?;
+                problems.push(problem);
+                chunk.clear();
+            }
+            chunk_line = row + 1;
+        } else {
+            chunk.push_str(line);
+            chunk.push('\n');
+        }
+    }
+
+    Ok(problems)
+}
+
+impl core::fmt::Display for Problem {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.metadata.fmt(f)?;
+
         writeln!(f, "{} {}", self.block_holding.to_inner(), self.move_remain)?;
 
         self.board.fmt(f)?;
@@ -349,6 +648,197 @@ mod tests {
 
         let problem = parse_problem(s);
         assert_eq!(problem.to_string(), s);
+
+        let board = problem.board();
+        assert_eq!(board.get(0, 0), Some(&Some(ProblemTile::Wall)));
+        assert_eq!(board.get(6, 7), Some(&Some(ProblemTile::Pipe)));
+        assert_eq!(board.get(7, 11), Some(&None));
+        assert_eq!(board.get(8, 0), None);
+        assert_eq!(board.get(0, 12), None);
+    }
+
+    #[test]
+    fn test_metadata() {
+        let s = indoc! {"
+            # ADVANCE 1 面
+            stage: 1
+            author: HAL
+            #
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "};
+
+        let problem = parse_problem(s);
+        assert_eq!(problem.to_string(), s);
+
+        let metadata = problem.metadata();
+        assert_eq!(metadata.stage(), Some(1));
+        assert_eq!(metadata.author(), Some("HAL"));
+        assert_eq!(metadata.rom_hash(), None);
+        assert_eq!(
+            metadata.comments().collect::<Vec<_>>(),
+            ["ADVANCE 1 面", ""]
+        );
+
+        // メタデータは求解に影響しない。
+        let problem_bare = parse_problem(
+            s.lines()
+                .skip(4)
+                .map(|line| format!("{line}\n"))
+                .collect::<String>(),
+        );
+        assert_eq!(
+            problem.to_position_and_moves(),
+            problem_bare.to_position_and_moves()
+        );
+        assert_eq!(
+            problem.clone().with_metadata(ProblemMetadata::new()),
+            problem_bare
+        );
+
+        // エラー位置はメタデータ行を含めた行番号で報告される。
+        let s_err = s.replace("3333....", "3333..?.");
+        assert_eq!(
+            s_err.parse::<Problem>().unwrap_err(),
+            Error::Parse(ParseError::new(16, ParseErrorKind::InvalidChar('?')).with_col(7))
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let parse_err = |s: &str| s.parse::<Problem>().unwrap_err();
+
+        assert_eq!(
+            parse_err("2 x\n"),
+            Error::Parse(ParseError::new(1, ParseErrorKind::NotNumber("x".to_owned())).with_col(3))
+        );
+
+        let s = indoc! {"
+            2 33
+            #####...
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            311432..
+            222242|.
+            33442?..
+            422224|.
+            344244..
+            133344..
+        "};
+        assert_eq!(
+            parse_err(s),
+            Error::Parse(ParseError::new(10, ParseErrorKind::InvalidChar('?')).with_col(6))
+        );
+
+        let s = indoc! {"
+            2 33
+            #####...
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            311432..
+            222242|.
+            334422..
+            422224|.
+            344244..
+        "};
+        assert_eq!(
+            parse_err(s),
+            Error::Parse(ParseError::new(
+                13,
+                ParseErrorKind::MissingLine { expected: 12 }
+            ))
+        );
+
+        let s = indoc! {"
+            2 33
+            #####...
+            ##......
+            #.......
+            ........
+            ..#.....
+            ........
+            311432..
+            222242|.
+            334422..
+            422224|.
+            344244..
+            133344..
+        "};
+        assert_eq!(
+            parse_err(s),
+            Error::Validation(ValidationError::new(
+                2,
+                4,
+                ValidationErrorKind::WallWithoutWallAbove
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_problems() {
+        let s = indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+
+            # 2 問目
+            2 33
+            #####...
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            311432..
+            222242|.
+            334422..
+            422224|.
+            344244..
+            133344..
+            ---
+        "};
+
+        let problems = parse_problems(s).unwrap();
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].move_remain(), 13);
+        assert_eq!(problems[1].move_remain(), 33);
+        assert_eq!(
+            problems[1].metadata().comments().collect::<Vec<_>>(),
+            ["2 問目"]
+        );
+
+        let s_err = s.replace("334422..", "3344?2..");
+        assert_eq!(
+            parse_problems(&s_err).unwrap_err(),
+            Error::Parse(ParseError::new(25, ParseErrorKind::InvalidChar('?')).with_col(5))
+        );
     }
 
     #[test]
@@ -394,5 +884,12 @@ mod tests {
         ];
 
         assert_eq!(problem.to_position_and_moves(), (pos, moves));
+
+        assert_eq!(
+            problem.move_for_src(MOVE_SRC_ROW_2),
+            Some(Move::new(MOVE_SRC_ROW_2, MoveDst::Vertical(BLOCKS_COL_B)))
+        );
+        assert_eq!(problem.move_for_src(MOVE_SRC_ROW_0), None);
+        assert_eq!(problem.move_for_src(MOVE_SRC_ROW_7), None);
     }
 }
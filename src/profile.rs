@@ -0,0 +1,106 @@
+use alloc::borrow::ToOwned as _;
+
+use crate::cost::Cost;
+use crate::error::Error;
+
+/// エミュレータ (または実機) ごとの入力遅延とラグの違い。
+///
+/// コストモデルは BizHawk (NesHawk) で計測したものなので、`NesHawk` は補正なしとなる。
+/// 他のプロファイルは、エクスポートしたムービーを手で修正していた分をまとめたもの。
+/// 値は暫定で、計測し直したら更新する。
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TimingProfile {
+    FceuxOldPpu,
+    FceuxNewPpu,
+    #[default]
+    NesHawk,
+    Mesen,
+    Console,
+}
+
+impl TimingProfile {
+    pub fn all() -> [Self; 5] {
+        [
+            Self::FceuxOldPpu,
+            Self::FceuxNewPpu,
+            Self::NesHawk,
+            Self::Mesen,
+            Self::Console,
+        ]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::FceuxOldPpu => "fceux-old-ppu",
+            Self::FceuxNewPpu => "fceux-new-ppu",
+            Self::NesHawk => "neshawk",
+            Self::Mesen => "mesen",
+            Self::Console => "console",
+        }
+    }
+
+    /// 最初の入力の前に挿入する待ちフレーム数。
+    pub fn input_delay(self) -> Cost {
+        match self {
+            Self::FceuxOldPpu => 1,
+            Self::FceuxNewPpu | Self::NesHawk | Self::Mesen | Self::Console => 0,
+        }
+    }
+
+    /// ブロックを投げるたびに生じる追加のラグフレーム数。
+    pub fn throw_lag(self) -> Cost {
+        match self {
+            Self::Console => 1,
+            Self::FceuxOldPpu | Self::FceuxNewPpu | Self::NesHawk | Self::Mesen => 0,
+        }
+    }
+
+    /// 1 手のコストをこのプロファイル向けに補正する。
+    pub fn adjust_move_cost(self, cost_mv: Cost) -> Cost {
+        cost_mv + self.throw_lag()
+    }
+
+    /// `move_count` 手からなる解の総コストをこのプロファイル向けに補正する。
+    pub fn adjust_total_cost(self, cost: Cost, move_count: usize) -> Cost {
+        cost + self.input_delay() + self.throw_lag() * move_count as Cost
+    }
+}
+
+impl core::str::FromStr for TimingProfile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::all()
+            .into_iter()
+            .find(|profile| profile.name() == s)
+            .ok_or_else(|| Error::UnknownProfile(s.to_owned()))
+    }
+}
+
+impl core::fmt::Display for TimingProfile {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_timing_profile() {
+        for profile in TimingProfile::all() {
+            assert_eq!(profile.name().parse::<TimingProfile>(), Ok(profile));
+        }
+        assert_eq!(
+            "nestopia".parse::<TimingProfile>(),
+            Err(Error::UnknownProfile("nestopia".to_owned()))
+        );
+
+        assert_eq!(TimingProfile::default().adjust_total_cost(443, 4), 443);
+        assert_eq!(TimingProfile::Console.adjust_total_cost(443, 4), 447);
+        assert_eq!(TimingProfile::FceuxOldPpu.adjust_total_cost(443, 4), 444);
+    }
+}
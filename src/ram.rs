@@ -0,0 +1,283 @@
+use crate::block::BlocksSquare;
+use crate::error::{token_col, ParseError, ParseErrorKind};
+use crate::position::Position;
+use crate::stage::Stage;
+
+/// ゲームがプレイ中の面の状態を置いている RAM の配置。
+///
+/// 本クレートは原作の RAM の配置を同梱していないので、利用者が調べて与える。
+/// 各要素は (アドレス, 値のずれ) で、RAM 上の値は本クレートでの値 (面番号、`MoveSrc`、`Block` の内部値、残り手数)
+/// に値のずれを足したもの (256 を法とする)。ブロック領域はアドレスから 36 バイト、上の行から順に (各行は左から順に)
+/// 並んでいるとし、空白は 0、ブロックは内部値として値のずれを足す。
+///
+/// 文字列表現は 1 行に 1 要素の「キー アドレス [値のずれ]」。キーは `stage`, `hero_row`, `block_holding`,
+/// `move_remain`, `blocks` で、全て必要。アドレスは 16 進 (`0x` 付き) または 10 進、値のずれは 10 進 (省略時 0)。
+/// 空行と `#` で始まる行は無視する。
+///
+/// ```text
+/// # 例 (実在のゲームの配置ではない)
+/// stage 0x0040 -1
+/// hero_row 0x0041
+/// block_holding 0x0042
+/// move_remain 0x0043
+/// blocks 0x0300
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RamLayout {
+    stage: RamField,
+    hero_row: RamField,
+    block_holding: RamField,
+    move_remain: RamField,
+    blocks: RamField,
+}
+
+/// `RamLayout` の 1 要素。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct RamField {
+    addr: u16,
+    /// RAM 上の値から本クレートでの値を引いたもの (256 を法とする)。
+    offset: u8,
+}
+
+impl RamField {
+    fn encode(self, value: u8) -> u8 {
+        value.wrapping_add(self.offset)
+    }
+}
+
+impl RamLayout {
+    const KEYS: [&'static str; 5] = [
+        "stage",
+        "hero_row",
+        "block_holding",
+        "move_remain",
+        "blocks",
+    ];
+
+    /// 面 `stage` の局面 `pos` にするための RAM の書き込み (アドレス, 値) をアドレスの順に返す。
+    ///
+    /// 壁/パイプの配置は書き込まないので、`pos` は面 `stage` と同じ壁/パイプの配置の問題のものでなければならない。
+    pub fn writes(&self, stage: Stage, pos: &Position) -> Vec<(u16, u8)> {
+        let mut writes = vec![
+            (self.stage.addr, self.stage.encode(stage)),
+            (
+                self.hero_row.addr,
+                self.hero_row.encode(pos.hero_row().to_inner()),
+            ),
+            (
+                self.block_holding.addr,
+                self.block_holding.encode(pos.block_holding().to_inner()),
+            ),
+            (
+                self.move_remain.addr,
+                self.move_remain.encode(pos.move_remain()),
+            ),
+        ];
+        writes.extend(BlocksSquare::all().into_iter().map(|sq| {
+            let value = pos.blocks()[sq].map_or(0, |block| block.to_inner());
+            (
+                self.blocks.addr.wrapping_add(sq.to_index() as u16),
+                self.blocks.encode(value),
+            )
+        }));
+        writes.sort_unstable();
+
+        writes
+    }
+
+    /// `writes()` の書き込みを行う Lua のコード (FCEUX, BizHawk の `memory.writebyte`)。
+    pub fn to_lua_writes(&self, stage: Stage, pos: &Position) -> String {
+        self.writes(stage, pos)
+            .into_iter()
+            .map(|(addr, value)| format!("memory.writebyte({addr:#06X}, {value:#04X})\n"))
+            .collect()
+    }
+
+    /// `writes()` の書き込みを 1 行に 1 つずつ「アドレス:値」(16 進) の形で並べたもの。
+    /// エミュレータのチートの画面に RAM のコードとして入力できる。
+    pub fn to_raw_codes(&self, stage: Stage, pos: &Position) -> String {
+        self.writes(stage, pos)
+            .into_iter()
+            .map(|(addr, value)| format!("{addr:04X}:{value:02X}\n"))
+            .collect()
+    }
+
+    /// RAM から現在の状態を読み、`assistant::LiveState` の文字列表現で返す Lua の関数 `read_state()` のコード。
+    pub fn to_lua_read_state(&self) -> String {
+        let field = |field: RamField| format!("field({:#06X}, {})", field.addr, field.offset);
+
+        format!(
+            r#"local function read_state()
+  local function field(addr, offset)
+    return (memory.readbyte(addr) - offset) % 256
+  end
+  local rows = {{}}
+  for r = 0, 5 do
+    local row = {{}}
+    for c = 0, 5 do
+      local block = field({blocks_addr:#06X} + 6 * r + c, {blocks_offset})
+      row[#row + 1] = block == 0 and "." or tostring(block)
+    end
+    rows[#rows + 1] = table.concat(row)
+  end
+  return string.format("%d %d %d %d %s", {stage}, {hero_row}, {block_holding}, {move_remain}, table.concat(rows, "/"))
+end
+"#,
+            blocks_addr = self.blocks.addr,
+            blocks_offset = self.blocks.offset,
+            stage = field(self.stage),
+            hero_row = field(self.hero_row),
+            block_holding = field(self.block_holding),
+            move_remain = field(self.move_remain),
+        )
+    }
+}
+
+impl core::str::FromStr for RamLayout {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = [None::<RamField>; Self::KEYS.len()];
+
+        for (i, line) in s.lines().enumerate() {
+            let err = |kind| ParseError::new(i + 1, kind);
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+
+            let tokens: Vec<_> = line.split_ascii_whitespace().collect();
+            if !(2..=3).contains(&tokens.len()) {
+                return Err(err(ParseErrorKind::TokenCount {
+                    expected: 2,
+                    actual: tokens.len(),
+                }));
+            }
+            let err_at = |j: usize, kind| err(kind).with_col(token_col(line, tokens[j]));
+            let not_number = |j: usize| err_at(j, ParseErrorKind::NotNumber(tokens[j].to_owned()));
+
+            let key = Self::KEYS
+                .iter()
+                .position(|&key| key == tokens[0])
+                .ok_or_else(|| err_at(0, ParseErrorKind::UnknownKey(tokens[0].to_owned())))?;
+            let addr = match tokens[1].strip_prefix("0x") {
+                Some(hex) => u16::from_str_radix(hex, 16),
+                None => tokens[1].parse(),
+            }
+            .map_err(|_| not_number(1))?;
+            let offset = match tokens.get(2) {
+                Some(token) => token.parse::<i16>().map_err(|_| not_number(2))? as u8,
+                None => 0,
+            };
+
+            fields[key] = Some(RamField { addr, offset });
+        }
+
+        let line_end = s.lines().count() + 1;
+        let [stage, hero_row, block_holding, move_remain, blocks] =
+            core::array::from_fn(|i| fields[i].ok_or(Self::KEYS[i]));
+        let missing =
+            |key: &str| ParseError::new(line_end, ParseErrorKind::MissingKey(key.to_owned()));
+
+        Ok(Self {
+            stage: stage.map_err(missing)?,
+            hero_row: hero_row.map_err(missing)?,
+            block_holding: block_holding.map_err(missing)?,
+            move_remain: move_remain.map_err(missing)?,
+            blocks: blocks.map_err(missing)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use crate::assistant::LiveState;
+    use crate::problem::Problem;
+
+    use super::*;
+
+    const LAYOUT: &str = indoc! {"
+        # test
+        stage 0x0040 -1
+        hero_row 0x0041
+        block_holding 66 16
+
+        move_remain 0x0043
+        blocks 0x0300
+    "};
+
+    #[test]
+    fn test_ram_layout_writes() {
+        let layout: RamLayout = LAYOUT.parse().unwrap();
+        let problem: Problem = include_str!("../problem/01.in").parse().unwrap();
+        let (pos, _) = problem.to_position_and_moves();
+
+        let writes = layout.writes(1, &pos);
+        assert_eq!(writes.len(), 4 + 36);
+        assert_eq!(
+            writes[..4],
+            [
+                (0x0040, 0),
+                (0x0041, pos.hero_row().to_inner()),
+                (0x0042, pos.block_holding().to_inner() + 16),
+                (0x0043, pos.move_remain()),
+            ]
+        );
+
+        // 書き込んだ RAM を read_state() と同じ規則で読むと、元の状態に戻る。
+        let mut ram = [0_u8; 0x800];
+        for &(addr, value) in &writes {
+            ram[usize::from(addr)] = value;
+        }
+        let board: Vec<String> = (0..6)
+            .map(|r| {
+                (0..6)
+                    .map(|c| match ram[0x0300 + 6 * r + c] {
+                        0 => '.',
+                        block => char::from(b'0' + block),
+                    })
+                    .collect()
+            })
+            .collect();
+        let line = format!(
+            "{} {} {} {} {}",
+            ram[0x0040] + 1,
+            ram[0x0041],
+            ram[0x0042] - 16,
+            ram[0x0043],
+            board.join("/")
+        );
+        assert_eq!(
+            line.parse::<LiveState>(),
+            Ok(LiveState::new(1, pos.clone()))
+        );
+
+        assert_eq!(layout.to_raw_codes(1, &pos).lines().next(), Some("0040:00"));
+        assert_eq!(
+            layout.to_lua_writes(1, &pos).lines().next(),
+            Some("memory.writebyte(0x0040, 0x00)")
+        );
+        assert!(layout.to_lua_read_state().contains("field(0x0042, 16)"));
+    }
+
+    #[test]
+    fn test_ram_layout_parse_error() {
+        assert_eq!(
+            "stage 0x40\nfoo 0x41\n".parse::<RamLayout>(),
+            Err(ParseError::new(2, ParseErrorKind::UnknownKey("foo".to_owned())).with_col(1))
+        );
+        assert_eq!(
+            "stage 0x40\nhero_row x\n".parse::<RamLayout>(),
+            Err(ParseError::new(2, ParseErrorKind::NotNumber("x".to_owned())).with_col(10))
+        );
+        assert_eq!(
+            "stage 0x40\n".parse::<RamLayout>(),
+            Err(ParseError::new(
+                2,
+                ParseErrorKind::MissingKey("hero_row".to_owned())
+            ))
+        );
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::BTreeMap;
+use std::io::Write as _;
+use std::path::Path;
+
+use crate::cost::Cost;
+use crate::error::{Error, ParseError, ParseErrorKind, VerifyError};
+use crate::fs::atomic_write;
+use crate::problem::Problem;
+use crate::solver::Solution;
+use crate::stage::Stage;
+
+/// 記録を作ったソルバーのバージョン (このクレートのバージョン)。
+pub const SOLVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// 記録の解が最適であると証明されているかどうか。
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum ProofStatus {
+    /// 暫定解 (探索が打ち切られたなど、最適とは限らない)。
+    Incumbent,
+    /// 最適解 (探索を完了している)。
+    Optimal,
+}
+
+impl ProofStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Incumbent => "incumbent",
+            Self::Optimal => "optimal",
+        }
+    }
+}
+
+impl core::str::FromStr for ProofStatus {
+    type Err = ParseErrorKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "incumbent" => Ok(Self::Incumbent),
+            "optimal" => Ok(Self::Optimal),
+            _ => Err(ParseErrorKind::InvalidProofStatus(s.to_owned())),
+        }
+    }
+}
+
+impl core::fmt::Display for ProofStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 1 つの面の既知の最善解。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Record {
+    stage: Stage,
+    cost: Cost,
+    status: ProofStatus,
+    solver_version: String,
+    solution: Solution,
+}
+
+impl Record {
+    /// 現在のソルバーのバージョンで記録を作る。
+    pub fn new(stage: Stage, solution: Solution, cost: Cost, status: ProofStatus) -> Self {
+        Self {
+            stage,
+            cost,
+            status,
+            solver_version: SOLVER_VERSION.to_owned(),
+            solution,
+        }
+    }
+
+    pub fn with_solver_version(self, solver_version: impl Into<String>) -> Self {
+        Self {
+            solver_version: solver_version.into(),
+            ..self
+        }
+    }
+
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    pub fn cost(&self) -> Cost {
+        self.cost
+    }
+
+    pub fn status(&self) -> ProofStatus {
+        self.status
+    }
+
+    pub fn solver_version(&self) -> &str {
+        &self.solver_version
+    }
+
+    pub fn solution(&self) -> &Solution {
+        &self.solution
+    }
+
+    /// `self` が `other` より良い記録かどうかを返す。
+    /// コストが小さいか、コストが同じで証明状態が強ければ良いとみなす。
+    pub fn improves_on(&self, other: &Self) -> bool {
+        (self.cost, core::cmp::Reverse(self.status))
+            < (other.cost, core::cmp::Reverse(other.status))
+    }
+}
+
+/// 面ごとの既知の最善解のデータベース。
+///
+/// ファイル形式は 1 行 1 面で、タブ区切りの「面 コスト 証明状態 ソルバーのバージョン 解」。
+/// 空行と `#` で始まる行は無視する。
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Records(BTreeMap<Stage, Record>);
+
+impl Records {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ファイルから読み込む。ファイルが存在しなければ空のデータベースを返す。
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        match std::fs::read_to_string(path) {
+            Ok(s) => Ok(s.parse()?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// ファイルに書き込む。途中で中断してもファイルが壊れることはない (`atomic_write` 参照)。
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        atomic_write(path, |w| w.write_all(self.to_string().as_bytes()))?;
+
+        Ok(())
+    }
+
+    pub fn get(&self, stage: Stage) -> Option<&Record> {
+        self.0.get(&stage)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Record> {
+        self.0.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// 記録が既存の記録より良ければ (または既存の記録がなければ) 置き換え、true を返す。
+    pub fn update(&mut self, record: Record) -> bool {
+        match self.0.get(&record.stage) {
+            Some(old) if !record.improves_on(old) => false,
+            _ => {
+                self.0.insert(record.stage, record);
+                true
+            }
+        }
+    }
+
+    /// ファイル上のデータベースを記録たちで更新し、更新された面たちを返す。
+    /// 読み込み、更新、書き込みを一度に行う。書き込みは `save` と同様にアトミック。
+    pub fn update_file(
+        path: impl AsRef<Path>,
+        records: impl IntoIterator<Item = Record>,
+    ) -> Result<Vec<Stage>, Error> {
+        let path = path.as_ref();
+        let mut this = Self::load(path)?;
+
+        let stages: Vec<_> = records
+            .into_iter()
+            .filter_map(|record| {
+                let stage = record.stage;
+                this.update(record).then_some(stage)
+            })
+            .collect();
+
+        if !stages.is_empty() {
+            this.save(path)?;
+        }
+
+        Ok(stages)
+    }
+}
+
+/// 2 つのデータベースで記録が異なる面。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecordDiff<'a> {
+    stage: Stage,
+    old: Option<&'a Record>,
+    new: Option<&'a Record>,
+}
+
+impl<'a> RecordDiff<'a> {
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    pub fn record_old(&self) -> Option<&'a Record> {
+        self.old
+    }
+
+    pub fn record_new(&self) -> Option<&'a Record> {
+        self.new
+    }
+}
+
+/// 2 つのデータベースを比較し、記録が異なる面たちを面の昇順に返す。
+pub fn diff_records<'a>(old: &'a Records, new: &'a Records) -> Vec<RecordDiff<'a>> {
+    let mut stages: Vec<_> = old.0.keys().chain(new.0.keys()).copied().collect();
+    stages.sort_unstable();
+    stages.dedup();
+
+    stages
+        .into_iter()
+        .map(|stage| RecordDiff {
+            stage,
+            old: old.get(stage),
+            new: new.get(stage),
+        })
+        .filter(|diff| diff.old != diff.new)
+        .collect()
+}
+
+/// (問題, 解) の組たちを並列に検証し、組ごとの結果 (コストまたはエラー) を同じ順に返す。
+///
+/// 最終面かどうかは問題のメタデータの面番号 (`ProblemMetadata::is_last_stage`) で決める。
+/// コストモデルを変更した後にデータベース全体を検証し直すのに使う。
+pub fn verify_many(pairs: &[(Problem, Solution)]) -> Vec<Result<Cost, VerifyError>> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    let thread_count = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(pairs.len());
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![None; pairs.len()]);
+
+    // 各スレッドは未処理の組を 1 つずつ取って検証する。
+    std::thread::scope(|scope| {
+        for _ in 0..thread_count {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some((problem, solution)) = pairs.get(i) else {
+                    break;
+                };
+                let res = solution.verify(problem, problem.metadata().is_last_stage());
+                results.lock().unwrap()[i] = Some(res);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(Option::unwrap)
+        .collect()
+}
+
+impl core::str::FromStr for Records {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut this = Self::new();
+
+        for (row, line) in s.lines().enumerate() {
+            let err = |kind| ParseError::new(row + 1, kind);
+
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let tokens: Vec<_> = line.split('\t').collect();
+            if tokens.len() != 5 {
+                return Err(err(ParseErrorKind::TokenCount {
+                    expected: 5,
+                    actual: tokens.len(),
+                }));
+            }
+
+            let stage: Stage = tokens[0]
+                .parse()
+                .map_err(|_| err(ParseErrorKind::NotNumber(tokens[0].to_owned())))?;
+            let cost: Cost = tokens[1]
+                .parse()
+                .map_err(|_| err(ParseErrorKind::NotNumber(tokens[1].to_owned())))?;
+            let status: ProofStatus = tokens[2].parse().map_err(err)?;
+            let solver_version = tokens[3].to_owned();
+            let solution: Solution = tokens[4]
+                .parse()
+                .map_err(|e: ParseError| e.offset_line(row))?;
+
+            let record = Record {
+                stage,
+                cost,
+                status,
+                solver_version,
+                solution,
+            };
+            if this.0.insert(stage, record).is_some() {
+                return Err(err(ParseErrorKind::DuplicateStage(stage)));
+            }
+        }
+
+        Ok(this)
+    }
+}
+
+impl core::fmt::Display for Records {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for record in self.iter() {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{}",
+                record.stage, record.cost, record.status, record.solver_version, record.solution
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn parse_solution(s: impl AsRef<str>) -> Solution {
+        s.as_ref().parse().unwrap()
+    }
+
+    #[test]
+    fn test_records() {
+        let s = "1\t443\toptimal\t0.1.0\t10 9 10 11\n2\t900\tincumbent\t0.1.0\t6 7\n";
+        let mut records: Records = s.parse().unwrap();
+        assert_eq!(records.to_string(), s);
+        assert_eq!(records.get(1).unwrap().cost(), 443);
+
+        // 悪化する記録、同じコストの暫定解では更新されない。
+        let solution = parse_solution("6 7 8");
+        assert!(!records.update(Record::new(1, solution.clone(), 500, ProofStatus::Optimal)));
+        assert!(!records.update(Record::new(
+            1,
+            solution.clone(),
+            443,
+            ProofStatus::Incumbent
+        )));
+
+        // 同じコストでも最適性が証明されれば更新される。
+        let old = records.clone();
+        assert!(records.update(Record::new(2, solution.clone(), 900, ProofStatus::Optimal)));
+        assert!(records.update(Record::new(3, solution, 700, ProofStatus::Incumbent)));
+
+        let diffs = diff_records(&old, &records);
+        assert_eq!(
+            diffs.iter().map(RecordDiff::stage).collect::<Vec<_>>(),
+            [2, 3]
+        );
+        assert_eq!(diffs[1].record_old(), None);
+
+        assert_eq!(
+            "1\t443\tbest\t0.1.0\t10".parse::<Records>(),
+            Err(ParseError::new(
+                1,
+                ParseErrorKind::InvalidProofStatus("best".to_owned())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_verify_many() {
+        let problem: Problem = include_str!("../problem/01.in").parse().unwrap();
+        let mut metadata = crate::problem::ProblemMetadata::new();
+        metadata.set(
+            crate::problem::ProblemMetadata::KEY_STAGE,
+            crate::stage::STAGE_COUNT.to_string(),
+        );
+        let problem_last = problem.clone().with_metadata(metadata);
+
+        let pairs = [
+            (problem.clone(), parse_solution("10 9 10 11")),
+            (problem, parse_solution("10 9")),
+            (problem_last, parse_solution("10 9 10 11")),
+        ];
+        let results = verify_many(&pairs);
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0], Ok(443));
+        assert!(matches!(results[1], Err(VerifyError::NotStuck { .. })));
+        // 最終面として検証されるので、面クリア時のブロック自動消去コストを含まない。
+        assert!(results[2].clone().unwrap() < 443);
+    }
+
+    #[test]
+    fn test_records_update_file() {
+        let path = std::env::temp_dir().join(format!("records_test_{}.tsv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let record = Record::new(5, parse_solution("10 9"), 300, ProofStatus::Optimal);
+        assert_eq!(Records::update_file(&path, [record.clone()]).unwrap(), [5]);
+        assert!(Records::update_file(&path, [record.clone()])
+            .unwrap()
+            .is_empty());
+        assert_eq!(Records::load(&path).unwrap().get(5), Some(&record));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,155 @@
+use std::num::NonZeroU64;
+use std::time::Instant;
+
+use log::info;
+use rand::Rng as _;
+
+use crate::config::{CancelToken, MoveOrdering, SearchOutcome, SearchStats, SolverConfig};
+use crate::generator::rng_from_seed;
+use crate::problem::Problem;
+use crate::solver::solve_problem_with;
+
+/// ランダムリスタートで問題を解く。
+///
+/// 局面数の上限付きの深さ優先探索を、着手順を変えながら繰り返す。最初の探索は `config` の着手順、
+/// 以降は探索ごとにシード `seed` から決まるランダムな順 (`MoveOrdering::Random`) で行い、
+/// それまでの最善の解を暫定解として引き継ぐ (`SolverConfig::with_incumbent()`)。
+/// 固定の着手順では悪い部分木に長時間はまり込む巨大な面向け。
+///
+/// i 回目の探索の局面数の上限は `restart_nodes` に Luby 列 (1, 1, 2, 1, 1, 2, 4, 1, ...) の第 i 項を掛けたもの。
+/// 上限はいくらでも大きくなるので、いずれ探索が上限内に完了し、結果は最適解になる。
+/// その場合は `SearchOutcome::is_complete()` が真になる。
+/// `config` の局面数や時間の上限、打ち切りのトークンは全ての探索を通して適用し、
+/// 達したらそれまでの最善の解を返す (最適とは限らない)。
+/// 第 2 の基準 (`tie_break`) と探索した木の記録には対応しない。
+pub fn solve_problem_restarts(
+    problem: &Problem,
+    last_stage: bool,
+    config: &SolverConfig,
+    restart_nodes: NonZeroU64,
+    seed: u64,
+) -> SearchOutcome {
+    assert!(config.tie_break().is_none());
+    assert!(config.search_tree_depth().is_none());
+
+    let rng = &mut rng_from_seed(seed);
+    let started = Instant::now();
+    let deadline = config.time_limit().map(|time_limit| started + time_limit);
+
+    let mut best = config.incumbent().cloned();
+    let mut stats = SearchStats::default();
+
+    for i in 1_u64.. {
+        let node_limit = restart_nodes.get().saturating_mul(luby(i));
+        let move_ordering = if i == 1 {
+            config.move_ordering()
+        } else {
+            MoveOrdering::Random(rng.gen())
+        };
+        let config_restart = config
+            .clone()
+            .with_node_limit(node_limit.min(config.node_limit() - stats.node_count))
+            .with_time_limit(
+                deadline.map(|deadline| deadline.saturating_duration_since(Instant::now())),
+            )
+            .with_move_ordering(move_ordering)
+            .with_incumbent(best.clone());
+
+        let outcome = solve_problem_with(problem, last_stage, &config_restart);
+        stats.merge(outcome.stats());
+        let complete = outcome.is_complete();
+        if let Some(res) = outcome.into_best() {
+            best = Some(res);
+        }
+        info!(
+            "restart {i}: {node_limit} nodes, best: {:?}",
+            best.as_ref().map(|(_, cost)| *cost)
+        );
+
+        if complete {
+            stats.elapsed = started.elapsed();
+            return SearchOutcome::new(best, true).with_stats(stats);
+        }
+        if stats.node_count >= config.node_limit()
+            || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+            || config.cancel_token().is_some_and(CancelToken::is_cancelled)
+        {
+            break;
+        }
+    }
+
+    stats.elapsed = started.elapsed();
+    SearchOutcome::new(best, false).with_stats(stats)
+}
+
+/// Luby 列 (1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, ...) の第 `i` 項 (1 始まり)。
+fn luby(mut i: u64) -> u64 {
+    assert!(i >= 1);
+
+    // 2^(k-1) <= i < 2^k として、i = 2^k - 1 なら 2^(k-1)、そうでなければ第 (i - 2^(k-1) + 1) 項と同じ。
+    loop {
+        let k = u64::BITS - i.leading_zeros();
+        if i == (1 << k) - 1 {
+            return 1 << (k - 1);
+        }
+        i -= (1 << (k - 1)) - 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::solver::solve_problem;
+
+    use super::*;
+
+    #[test]
+    fn test_luby() {
+        let seq: Vec<_> = (1..=15).map(luby).collect();
+        assert_eq!(seq, [1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn test_solve_problem_restarts() {
+        let restart_nodes = NonZeroU64::new(64).unwrap();
+
+        for (problem, last_stage) in [
+            (include_str!("../problem/10.in"), false),
+            (include_str!("../problem/20.in"), true),
+        ] {
+            let problem: Problem = problem.parse().unwrap();
+            let (_, cost_opt) = solve_problem(&problem, last_stage).unwrap();
+
+            // 探索を繰り返すうちに上限内に完了し、最適解を返す。シードが同じなら結果も同じ。
+            let outcome = solve_problem_restarts(
+                &problem,
+                last_stage,
+                &SolverConfig::new(),
+                restart_nodes,
+                1,
+            );
+            assert!(outcome.is_complete());
+            let (solution, cost) = outcome.best().unwrap();
+            assert_eq!(*cost, cost_opt);
+            assert_eq!(solution.verify(&problem, last_stage), Ok(cost_opt));
+            let outcome_again = solve_problem_restarts(
+                &problem,
+                last_stage,
+                &SolverConfig::new(),
+                restart_nodes,
+                1,
+            );
+            assert_eq!(outcome_again.best(), outcome.best());
+
+            // 局面数の上限は全ての探索を通して適用する。
+            let config = SolverConfig::new().with_node_limit(300);
+            let outcome = solve_problem_restarts(&problem, last_stage, &config, restart_nodes, 1);
+            assert!(outcome.stats().node_count() <= 300);
+            if let Some((solution, cost)) = outcome.best() {
+                assert_eq!(solution.verify(&problem, last_stage), Ok(*cost));
+                assert!(*cost >= cost_opt);
+            }
+        }
+    }
+}
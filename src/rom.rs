@@ -0,0 +1,177 @@
+use std::path::Path;
+
+use crate::error::Error;
+use crate::problem::Problem;
+use crate::stage::{Stage, STAGE_COUNT};
+
+const HEADER_LEN: usize = 16;
+
+const PRG_LEN: usize = 0x8000;
+
+const CHR_BANK_COUNT: usize = 4;
+const CHR_BANK_LEN: usize = 0x2000;
+const CHR_LEN: usize = CHR_BANK_LEN * CHR_BANK_COUNT;
+
+/// 原作の ROM。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Rom {
+    header: [u8; HEADER_LEN],
+    prg: Box<[u8; PRG_LEN]>,
+    chr: Box<[u8; CHR_LEN]>,
+}
+
+impl Rom {
+    /// iNES 形式のバイト列から読み取る。
+    pub fn from_ines(ines: &[u8]) -> Result<Self, Error> {
+        let (header, body) = ines.split_at_checked(HEADER_LEN).ok_or(Error::InesEof)?;
+
+        if !header.starts_with(b"NES\x1A") {
+            return Err(Error::InesMagic);
+        }
+
+        let (prg, chr) = body.split_at_checked(PRG_LEN).ok_or(Error::InesEof)?;
+        if chr.len() != CHR_LEN {
+            return Err(Error::ChrSize {
+                expected: CHR_LEN,
+                actual: chr.len(),
+            });
+        }
+
+        let header: [u8; HEADER_LEN] = header.try_into().unwrap();
+        let prg: Box<[u8; PRG_LEN]> = prg.to_vec().try_into().unwrap();
+        let chr: Box<[u8; CHR_LEN]> = chr.to_vec().try_into().unwrap();
+
+        Ok(Self { header, prg, chr })
+    }
+
+    /// iNES 形式のファイルから読み取る。
+    pub fn from_ines_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_ines(&std::fs::read(path)?)
+    }
+
+    /// iNES 形式のバイト列に変換する。
+    pub fn to_ines(&self) -> Vec<u8> {
+        [&self.header[..], &self.prg[..], &self.chr[..]].concat()
+    }
+
+    /// 面 `stage` (1..=50) の問題を抽出する。
+    pub fn problem(&self, stage: Stage) -> Result<Problem, Error> {
+        let (offset_blocks, offset_walls) = self.stage_offsets(stage);
+
+        let mut buf = [0; Problem::ROM_BYTES_LEN];
+        buf[..50].copy_from_slice(&self.chr[offset_blocks..][..50]);
+        buf[50..].copy_from_slice(&self.chr[offset_walls..][..24]);
+
+        Problem::from_rom_bytes(&buf)
+    }
+
+    /// 全ての面の問題を面の順に抽出する。
+    pub fn problems(&self) -> Result<Vec<Problem>, Error> {
+        (1..=STAGE_COUNT).map(|stage| self.problem(stage)).collect()
+    }
+
+    /// 面 `stage` (1..=50) のデータを問題 `problem` で上書きする。
+    ///
+    /// 面のデータは CHR-ROM 上にあるので、ゲームを起動し直せばその面から `problem` を練習できる。
+    /// 壁/パイプの配置を他の面と共有している場合、その面の壁/パイプの配置も変わることに注意。
+    pub fn set_problem(&mut self, stage: Stage, problem: &Problem) {
+        let (offset_blocks, offset_walls) = self.stage_offsets(stage);
+        let buf = problem.to_rom_bytes();
+
+        self.chr[offset_blocks..][..50].copy_from_slice(&buf[..50]);
+        self.chr[offset_walls..][..24].copy_from_slice(&buf[50..]);
+    }
+
+    /// 面 `stage` (1..=50) のデータを問題 `problem` にするために書き換える (iNES ファイル上のオフセット, 値) を列挙する。
+    /// 既に同じ値のバイトは含まない。
+    pub fn problem_patch(&self, stage: Stage, problem: &Problem) -> Vec<(usize, u8)> {
+        let mut patched = self.clone();
+        patched.set_problem(stage, problem);
+
+        self.chr
+            .iter()
+            .zip(patched.chr.iter())
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .map(|(i, (_, &new))| (HEADER_LEN + PRG_LEN + i, new))
+            .collect()
+    }
+
+    /// 面 `stage` (1..=50) の (ブロック配置などのデータ, 壁/パイプの配置) の CHR-ROM 上のオフセットを返す。
+    fn stage_offsets(&self, stage: Stage) -> (usize, usize) {
+        assert!((1..=STAGE_COUNT).contains(&stage));
+
+        // 面を 0-based に直す。
+        let stage = stage - 1;
+
+        let (bank, ptrs_offset) = if stage < 25 {
+            (0, 0x0A00 + 4 * usize::from(stage))
+        } else {
+            (2, 0x1A00 + 4 * usize::from(stage - 25))
+        };
+        let base = CHR_BANK_LEN * bank;
+        let chr_bank = &self.chr[base..][..CHR_BANK_LEN];
+
+        // ブロック配置、残り手数、初期保持ブロック。
+        let ptr_blocks = usize::from(read_u16_le(&chr_bank[ptrs_offset..]) & 0x3FFF);
+
+        // 壁/パイプの配置。
+        let ptr_walls = usize::from(read_u16_le(&chr_bank[ptrs_offset + 2..]) & 0x3FFF);
+
+        (base + ptr_blocks, base + ptr_walls)
+    }
+}
+
+fn read_u16_le(buf: &[u8]) -> u16 {
+    let buf: [u8; 2] = buf[..2].try_into().unwrap();
+    u16::from_le_bytes(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_rom() {
+        let problem: Problem = include_str!("../problem/01.in").parse().unwrap();
+        let bytes = problem.to_rom_bytes();
+
+        // 面 1 のポインタとデータだけを置いた ROM を作る。
+        let mut ines = b"NES\x1A".to_vec();
+        ines.resize(16 + PRG_LEN + CHR_LEN, 0);
+        let chr = &mut ines[16 + PRG_LEN..];
+        chr[0x0A00..][..4].copy_from_slice(&[0x00, 0x10, 0x00, 0x11]);
+        chr[0x1000..][..50].copy_from_slice(&bytes[..50]);
+        chr[0x1100..][..24].copy_from_slice(&bytes[50..]);
+
+        let rom = Rom::from_ines(&ines).unwrap();
+        assert_eq!(rom.problem(1).unwrap(), problem);
+        assert_eq!(rom.to_ines(), ines);
+
+        // 面 1 を別の問題で上書きする。
+        let problem_new: Problem = include_str!("../problem/02.in").parse().unwrap();
+        let patch = rom.problem_patch(1, &problem_new);
+        assert!(!patch.is_empty());
+        let mut ines_patched = ines.clone();
+        for &(offset, value) in &patch {
+            ines_patched[offset] = value;
+        }
+        let mut rom_patched = rom.clone();
+        rom_patched.set_problem(1, &problem_new);
+        assert_eq!(rom_patched.to_ines(), ines_patched);
+        assert_eq!(rom_patched.problem(1).unwrap(), problem_new);
+        assert!(rom_patched.problem_patch(1, &problem_new).is_empty());
+
+        assert_eq!(
+            Rom::from_ines(&ines[..16 + PRG_LEN]).unwrap_err(),
+            Error::ChrSize {
+                expected: CHR_LEN,
+                actual: 0
+            }
+        );
+        assert_eq!(Rom::from_ines(&ines[..8]).unwrap_err(), Error::InesEof);
+        assert_eq!(Rom::from_ines(&ines[4..]).unwrap_err(), Error::InesMagic);
+    }
+}
@@ -0,0 +1,277 @@
+use std::io::{BufRead, Write};
+
+use serde_json::{json, Value};
+
+use crate::analysis::find_dead_blocks;
+use crate::config::SolverConfig;
+use crate::convert::problem_to_json;
+use crate::cost::Cost;
+use crate::error::Error;
+use crate::move_::MoveSrc;
+use crate::objective::Objective;
+use crate::problem::Problem;
+use crate::ruleset::Ruleset;
+use crate::solver::{play_prefix, solve_continuation, solve_problem, Solution};
+
+/// JSON-RPC 2.0 のエラーコード。
+const CODE_PARSE_ERROR: i64 = -32700;
+const CODE_INVALID_REQUEST: i64 = -32600;
+const CODE_METHOD_NOT_FOUND: i64 = -32601;
+const CODE_INVALID_PARAMS: i64 = -32602;
+
+/// エディタのプラグインなどから子プロセスとして使うための JSON-RPC 2.0 サーバー。
+///
+/// `input` から 1 行に 1 個のリクエストを読み、レスポンスを 1 行ずつ `output` に書く (通知にはレスポンスを返さない)。
+/// `input` が EOF に達したら終了する。
+///
+/// メソッド (問題 `problem` は文字列表現、解 `solution` は着手位置の配列で渡す):
+///
+/// * `parseProblem {problem}`: 問題を JSON 形式 (`convert` と同じ) に変換する。
+/// * `validate {problem}`: 問題が正しいかどうかと、死にブロックの数を返す。
+/// * `solve {problem, lastStage?, allowSwap?}`: 最適解とコストを返す。解がなければ `null`。
+/// * `verify {problem, solution, lastStage?, allowSwap?}`: 解のコストを返す。
+/// * `hint {problem, solution, lastStage?, allowSwap?}`: 着手列 `solution` に続く最善の次の手と、その場合の総コストを返す。
+/// * `render {problem, solution?, allowSwap?}`: 着手列 `solution` を行った後の局面の文字列表現を返す。
+///
+/// `allowSwap` を取るメソッドは `perfectClear` (パーフェクトクリアのみを面クリアとする) と
+/// `clearMaxBlocks` (面クリアとなる残りブロック数の上限、0..=36) も取る。
+pub fn serve(input: impl BufRead, mut output: impl Write) -> Result<(), Error> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_message(&line) {
+            writeln!(output, "{response}")?;
+            output.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// 1 個のリクエストを処理し、レスポンスを返す。通知 (`id` のないリクエスト) なら `None` を返す。
+pub fn handle_message(line: &str) -> Option<String> {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return Some(error_response(Value::Null, CODE_PARSE_ERROR, e.to_string())),
+    };
+
+    let id = request.get("id").cloned();
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        return Some(error_response(
+            id.unwrap_or(Value::Null),
+            CODE_INVALID_REQUEST,
+            "missing method".to_owned(),
+        ));
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = call(method, &params);
+    let id = id?;
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string(),
+        Err((code, message)) => error_response(id, code, message),
+    })
+}
+
+fn error_response(id: Value, code: i64, message: String) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+    .to_string()
+}
+
+type RpcResult = Result<Value, (i64, String)>;
+
+fn call(method: &str, params: &Value) -> RpcResult {
+    match method {
+        "parseProblem" => {
+            let problem = param_problem(params)?;
+            serde_json::to_value(problem_to_json(&problem)).map_err(invalid_params)
+        }
+        "validate" => Ok(match param_problem(params) {
+            Ok(problem) => json!({
+                "valid": true,
+                "deadBlocks": find_dead_blocks(&problem).len(),
+            }),
+            Err((_, message)) => json!({ "valid": false, "message": message }),
+        }),
+        "solve" => {
+            let problem = param_problem(params)?;
+            Ok(solve_problem(&problem, param_last_stage(params))
+                .map_or(Value::Null, |(solution, cost)| {
+                    solution_json(&problem, &solution, cost)
+                }))
+        }
+        "verify" => {
+            let problem = param_problem(params)?;
+            let solution = param_solution(params)?;
+            let cost = solution
+                .verify(&problem, param_last_stage(params))
+                .map_err(invalid_params)?;
+            Ok(json!({ "cost": cost, "approximate": solution.swap_count(&problem) > 0 }))
+        }
+        "hint" => {
+            let problem = param_problem(params)?;
+            let prefix = param_solution(params)?;
+            let cont = solve_continuation(
+                &problem,
+                &prefix,
+                param_last_stage(params),
+                &SolverConfig::new(),
+            )
+            .map_err(invalid_params)?;
+            Ok(match cont {
+                Some(cont) => json!({
+                    "move": cont.suffix().first().map(|src| src.to_inner()),
+                    "cost": cont.cost_total(),
+                }),
+                None => Value::Null,
+            })
+        }
+        "render" => {
+            let problem = param_problem(params)?;
+            let solution = match params.get("solution") {
+                Some(_) => param_solution(params)?,
+                None => Solution::new(vec![]),
+            };
+            let (pos, moves) = problem.to_position_and_moves();
+            let (_, pos, _, _) = play_prefix(pos, &moves, solution.moves(), Objective::Frames)
+                .map_err(invalid_params)?;
+            Ok(Value::String(pos.to_string()))
+        }
+        _ => Err((CODE_METHOD_NOT_FOUND, format!("unknown method: {method}"))),
+    }
+}
+
+fn invalid_params(e: impl ToString) -> (i64, String) {
+    (CODE_INVALID_PARAMS, e.to_string())
+}
+
+fn param_problem(params: &Value) -> Result<Problem, (i64, String)> {
+    let problem = params
+        .get("problem")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid_params("missing problem"))?;
+    let problem: Problem = problem.parse().map_err(|e: Error| invalid_params(e))?;
+    let flag = |name: &str| params.get(name).and_then(Value::as_bool).unwrap_or(false);
+    let mut ruleset = Ruleset::new()
+        .with_allow_swap(flag("allowSwap"))
+        .with_perfect_clear(flag("perfectClear"));
+    if let Some(clear_max_blocks) = params.get("clearMaxBlocks") {
+        let clear_max_blocks = clear_max_blocks
+            .as_u64()
+            .filter(|&n| n <= 36)
+            .ok_or_else(|| invalid_params("invalid clearMaxBlocks"))?;
+        ruleset = ruleset.with_clear_max_blocks(clear_max_blocks as usize);
+    }
+
+    Ok(problem.with_ruleset(ruleset))
+}
+
+fn param_solution(params: &Value) -> Result<Solution, (i64, String)> {
+    let srcs = params
+        .get("solution")
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid_params("missing solution"))?;
+
+    let srcs = srcs
+        .iter()
+        .map(|src| {
+            src.as_u64()
+                .and_then(|src| u8::try_from(src).ok())
+                .and_then(MoveSrc::from_inner)
+                .ok_or_else(|| invalid_params(format!("invalid move: {src}")))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Solution::new(srcs))
+}
+
+fn param_last_stage(params: &Value) -> bool {
+    params
+        .get("lastStage")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// 解とそのコストを JSON にする。解が入れ替え着手を含みコストが近似なら `approximate` を真にする。
+fn solution_json(problem: &Problem, solution: &Solution, cost: Cost) -> Value {
+    let srcs: Vec<_> = solution.moves().iter().map(|src| src.to_inner()).collect();
+    let approximate = solution.swap_count(problem) > 0;
+    json!({ "solution": srcs, "cost": cost, "approximate": approximate })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::error::VerifyError;
+
+    use super::*;
+
+    fn request(method: &str, params: Value) -> Value {
+        let line = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let response = handle_message(&line.to_string()).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn test_rpc() {
+        let problem = include_str!("../problem/01.in");
+
+        let response = request("parseProblem", json!({ "problem": problem }));
+        assert_eq!(response["result"]["move_remain"], 13);
+
+        let response = request("validate", json!({ "problem": "x" }));
+        assert_eq!(response["result"]["valid"], false);
+
+        let response = request("solve", json!({ "problem": problem }));
+        assert_eq!(response["result"]["cost"], 443);
+        assert_eq!(response["result"]["approximate"], false);
+
+        let response = request(
+            "verify",
+            json!({ "problem": problem, "solution": [10, 9, 10, 11] }),
+        );
+        assert_eq!(response["result"]["cost"], 443);
+        assert_eq!(response["result"]["approximate"], false);
+
+        let response = request("hint", json!({ "problem": problem, "solution": [10] }));
+        assert_eq!(response["result"]["move"], 9);
+        assert_eq!(response["result"]["cost"], 443);
+
+        let response = request("render", json!({ "problem": problem, "solution": [12] }));
+        assert_eq!(response["error"]["code"], CODE_INVALID_PARAMS);
+        // 不正な着手列は `VerifyError` で報告する。
+        let response = request(
+            "render",
+            json!({ "problem": problem, "solution": [10, 9, 10, 11, 10] }),
+        );
+        let err = "10 9 10 11 10"
+            .parse::<Solution>()
+            .unwrap()
+            .verify(&problem.parse().unwrap(), false)
+            .unwrap_err();
+        assert!(matches!(err, VerifyError::StageEnded { index: 4 }));
+        assert_eq!(response["error"]["message"], err.to_string());
+
+        let response = request("foo", json!({}));
+        assert_eq!(response["error"]["code"], CODE_METHOD_NOT_FOUND);
+
+        // 通知にはレスポンスを返さない。
+        assert_eq!(
+            handle_message(r#"{"jsonrpc":"2.0","method":"solve","params":{}}"#),
+            None
+        );
+
+        let mut output = Vec::<u8>::new();
+        serve("{\n\n".as_bytes(), &mut output).unwrap();
+        let response: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(response["error"]["code"], CODE_PARSE_ERROR);
+    }
+}
@@ -0,0 +1,105 @@
+/// ゲームのルール設定。
+///
+/// デフォルトは原作 ADVANCE モードのルール。
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Ruleset {
+    allow_swap: bool,
+    wild_rule: Option<WildRule>,
+    clear_max_blocks: Option<u8>,
+}
+
+/// 面の途中で保持ブロックがワイルドになる条件 (改造版や別モード用)。
+///
+/// 原作 ADVANCE モードではワイルドは初期保持ブロックとしてしか得られないが、
+/// 途中でワイルドを得られるモードや改造版ではこれを指定しないと正しい解が得られない (解が不正と判定される)。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum WildRule {
+    /// 1 回の着手でブロックを指定個数 (1..=36) 以上消すと、次の保持ブロックがワイルドになる。
+    EraseCount(u8),
+}
+
+impl WildRule {
+    /// 1 回の着手で `erase_count` 個のブロックを消したとき、次の保持ブロックがワイルドになるかどうか。
+    pub fn gives_wild(self, erase_count: usize) -> bool {
+        match self {
+            Self::EraseCount(count) => erase_count >= usize::from(count),
+        }
+    }
+}
+
+impl Ruleset {
+    const CLEAR_MAX_BLOCKS_DEFAULT: usize = 3;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 最初に当たったブロックが消せない着手 (入れ替え着手) を許すかどうか。
+    ///
+    /// 入れ替え着手では、最初に当たったブロックが投げたブロックに置換され、
+    /// 当たったブロックが次の保持ブロックとなる。手数は 1 消費する。
+    /// 入れ替え着手のコストは近似なので、これを含む解のコストも近似になる (`Solution::swap_count()`)。
+    pub fn allow_swap(self) -> bool {
+        self.allow_swap
+    }
+
+    pub fn with_allow_swap(mut self, allow_swap: bool) -> Self {
+        self.allow_swap = allow_swap;
+        self
+    }
+
+    /// 面の途中で保持ブロックがワイルドになる条件。`None` なら途中でワイルドを得ることはない。
+    pub fn wild_rule(self) -> Option<WildRule> {
+        self.wild_rule
+    }
+
+    pub fn with_wild_rule(mut self, wild_rule: Option<WildRule>) -> Self {
+        if let Some(WildRule::EraseCount(count)) = wild_rule {
+            assert!((1..=36).contains(&count));
+        }
+        self.wild_rule = wild_rule;
+        self
+    }
+
+    /// ブロックを全て消したときだけ面クリアとするかどうか (パーフェクトクリア)。
+    /// 原作ではブロックが 3 個以下になれば面クリアだが、全て消すと得点が異なる。
+    pub fn perfect_clear(self) -> bool {
+        self.clear_max_blocks() == 0
+    }
+
+    /// `true` なら `with_clear_max_blocks(0)`、`false` なら原作の上限に戻す。
+    pub fn with_perfect_clear(self, perfect_clear: bool) -> Self {
+        self.with_clear_max_blocks(if perfect_clear {
+            0
+        } else {
+            Self::CLEAR_MAX_BLOCKS_DEFAULT
+        })
+    }
+
+    /// 面クリアとなる残りブロック数の上限。原作では 3。
+    pub fn clear_max_blocks(self) -> usize {
+        self.clear_max_blocks
+            .map_or(Self::CLEAR_MAX_BLOCKS_DEFAULT, usize::from)
+    }
+
+    /// 面クリアとなる残りブロック数の上限 (0..=36) を設定する。ノルマの異なる別バージョンや改造版用。
+    pub fn with_clear_max_blocks(mut self, clear_max_blocks: usize) -> Self {
+        assert!(clear_max_blocks <= 36);
+        self.clear_max_blocks =
+            (clear_max_blocks != Self::CLEAR_MAX_BLOCKS_DEFAULT).then_some(clear_max_blocks as u8);
+        self
+    }
+
+    /// ファイルに記録するための 2 バイト表現。
+    /// 最下位ビットが入れ替え着手の可否、次の 6 ビットが `WildRule::EraseCount` の個数 (なければ 0)、
+    /// その次の 6 ビットが面クリアとなる残りブロック数の上限に 1 を足したもの (原作通りなら 0)。
+    pub(crate) fn to_flags(self) -> u16 {
+        let wild = match self.wild_rule {
+            Some(WildRule::EraseCount(count)) => count,
+            None => 0,
+        };
+        let clear = self.clear_max_blocks.map_or(0, |n| n + 1);
+
+        u16::from(self.allow_swap) | (u16::from(wild) << 1) | (u16::from(clear) << 7)
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+
+use crate::cost::Cost;
+use crate::error::{ParseError, ParseErrorKind};
+use crate::movie::StageReport;
+use crate::records::Records;
+use crate::stage::Stage;
+
+/// 1 面分の所要フレーム数。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StageTime {
+    routing: Cost,
+    transition: Cost,
+}
+
+impl StageTime {
+    pub fn new(routing: Cost, transition: Cost) -> Self {
+        Self {
+            routing,
+            transition,
+        }
+    }
+
+    /// 面の最初の入力から面が終わるまでのフレーム数 (着手の選び方で決まる部分)。
+    pub fn routing(&self) -> Cost {
+        self.routing
+    }
+
+    /// 前の面が終わってから (最初の面では電源投入から) この面の最初の入力までのフレーム数
+    /// (メニューや面の切り替え演出)。
+    pub fn transition(&self) -> Cost {
+        self.transition
+    }
+
+    pub fn total(&self) -> Cost {
+        self.routing + self.transition
+    }
+}
+
+/// 全面通しの所要フレーム数の面ごとの内訳。
+///
+/// テキスト形式は 1 行 1 面で、タブ区切りの「面 ルーティング 切り替え」。空行と `#` で始まる行は無視する。
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RunTime(BTreeMap<Stage, StageTime>);
+
+impl RunTime {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 既知の最善解のデータベースから、全面通しの所要フレーム数を見積もる。
+    ///
+    /// 各面のルーティングは記録のコストとし、切り替えは最初の面のみ `frames_menu`、以降の面は `frames_transition` とする。
+    pub fn from_records(records: &Records, frames_menu: Cost, frames_transition: Cost) -> Self {
+        let mut this = Self::new();
+
+        for (i, record) in records.iter().enumerate() {
+            let transition = if i == 0 {
+                frames_menu
+            } else {
+                frames_transition
+            };
+            this.set(record.stage(), StageTime::new(record.cost(), transition));
+        }
+
+        this
+    }
+
+    /// 全面通しのムービーの検証結果 (`movie::verify_movie`) から、実際の所要フレーム数を求める。
+    pub fn from_stage_reports(reports: &[StageReport]) -> Self {
+        let mut this = Self::new();
+        let mut frame_prev = 0;
+
+        for report in reports {
+            let routing = report.frame_end() - report.frame_start();
+            let transition = report.frame_start() - frame_prev;
+            this.set(
+                report.stage(),
+                StageTime::new(routing as Cost, transition as Cost),
+            );
+            frame_prev = report.frame_end();
+        }
+
+        this
+    }
+
+    pub fn get(&self, stage: Stage) -> Option<StageTime> {
+        self.0.get(&stage).copied()
+    }
+
+    pub fn set(&mut self, stage: Stage, time: StageTime) {
+        self.0.insert(stage, time);
+    }
+
+    /// (面, 所要フレーム数) を面の順に列挙する。
+    pub fn iter(&self) -> impl Iterator<Item = (Stage, StageTime)> + '_ {
+        self.0.iter().map(|(&stage, &time)| (stage, time))
+    }
+
+    pub fn routing(&self) -> Cost {
+        self.0.values().map(StageTime::routing).sum()
+    }
+
+    pub fn transition(&self) -> Cost {
+        self.0.values().map(StageTime::transition).sum()
+    }
+
+    pub fn total(&self) -> Cost {
+        self.routing() + self.transition()
+    }
+
+    /// 基準 `reference` (公開されている TAS など) との差を面ごとに求める。
+    /// 一方にしかない面は、もう一方の所要フレーム数を 0 とみなす。
+    pub fn compare(&self, reference: &Self) -> Vec<StageDelta> {
+        let mut stages: Vec<_> = self.0.keys().chain(reference.0.keys()).copied().collect();
+        stages.sort_unstable();
+        stages.dedup();
+
+        stages
+            .into_iter()
+            .map(|stage| {
+                let zero = StageTime::new(0, 0);
+                let mine = self.get(stage).unwrap_or(zero);
+                let theirs = reference.get(stage).unwrap_or(zero);
+                StageDelta {
+                    stage,
+                    routing: i64::from(mine.routing) - i64::from(theirs.routing),
+                    transition: i64::from(mine.transition) - i64::from(theirs.transition),
+                }
+            })
+            .collect()
+    }
+}
+
+/// 1 面分の基準との所要フレーム数の差 (負なら基準より速い)。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StageDelta {
+    stage: Stage,
+    routing: i64,
+    transition: i64,
+}
+
+impl StageDelta {
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    pub fn routing(&self) -> i64 {
+        self.routing
+    }
+
+    pub fn transition(&self) -> i64 {
+        self.transition
+    }
+
+    pub fn total(&self) -> i64 {
+        self.routing + self.transition
+    }
+}
+
+impl core::str::FromStr for RunTime {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut this = Self::new();
+
+        for (row, line) in s.lines().enumerate() {
+            let err = |kind| ParseError::new(row + 1, kind);
+
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let tokens: Vec<_> = line.split('\t').collect();
+            if tokens.len() != 3 {
+                return Err(err(ParseErrorKind::TokenCount {
+                    expected: 3,
+                    actual: tokens.len(),
+                }));
+            }
+            let not_number = |token: &str| err(ParseErrorKind::NotNumber(token.to_owned()));
+            let parse = |token: &str| token.parse::<Cost>().map_err(|_| not_number(token));
+
+            let stage: Stage = tokens[0].parse().map_err(|_| not_number(tokens[0]))?;
+            let time = StageTime::new(parse(tokens[1])?, parse(tokens[2])?);
+            if this.0.insert(stage, time).is_some() {
+                return Err(err(ParseErrorKind::DuplicateStage(stage)));
+            }
+        }
+
+        Ok(this)
+    }
+}
+
+impl core::fmt::Display for RunTime {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (stage, time) in self.iter() {
+            writeln!(f, "{stage}\t{}\t{}", time.routing, time.transition)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::records::{ProofStatus, Record};
+
+    use super::*;
+
+    #[test]
+    fn test_run_time() {
+        let mut records = Records::new();
+        for (stage, cost) in [(1, 443), (2, 900)] {
+            let solution = "10 9".parse().unwrap();
+            records.update(Record::new(stage, solution, cost, ProofStatus::Optimal));
+        }
+
+        let mine = RunTime::from_records(&records, 300, 120);
+        assert_eq!(mine.to_string(), "1\t443\t300\n2\t900\t120\n");
+        assert_eq!(mine.total(), 443 + 900 + 300 + 120);
+
+        let reference: RunTime = "# published TAS\n1\t450\t300\n2\t900\t100\n3\t800\t100\n"
+            .parse()
+            .unwrap();
+        let deltas = mine.compare(&reference);
+        assert_eq!(
+            deltas
+                .iter()
+                .map(|delta| (delta.stage(), delta.routing(), delta.transition()))
+                .collect::<Vec<_>>(),
+            [(1, -7, 0), (2, 0, 20), (3, -800, -100)]
+        );
+        assert_eq!(
+            deltas.iter().map(StageDelta::total).sum::<i64>(),
+            i64::from(mine.total()) - i64::from(reference.total())
+        );
+
+        assert_eq!(
+            "1\t450\n".parse::<RunTime>(),
+            Err(ParseError::new(
+                1,
+                ParseErrorKind::TokenCount {
+                    expected: 3,
+                    actual: 2
+                }
+            ))
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use crate::error::VerifyError;
+use crate::move_::{Move, MoveSrc};
+use crate::position::Position;
+use crate::precheck::prove_unsolvable;
+use crate::problem::Problem;
+use crate::solver::Solution;
+
+/// ゲーム内の得点 (大きいほどよい)。
+pub type Score = u64;
+
+/// 1 回の着手で消せるブロック数の上限。
+///
+/// 横に投げたブロックは行を 6 マス進んだ後 A 列を 5 マス落ちていくので、たかだか 11 個。縦に投げた場合は 6 個。
+const MAX_ERASE_PER_THROW: usize = 11;
+
+/// 面の得点のモデル。原作の得点体系を単純化したもの。
+///
+/// * 1 回の着手でブロックを n 個消すと `erase_base * 2^(n-1)` 点 (連鎖ボーナス)。
+/// * 面クリア時、残り手数 1 手につき `move_bonus` 点。
+/// * ブロックを全て消して面クリアすると、さらに `perfect_bonus` 点。
+///
+/// 面クリアできなかった着手列には得点を与えない (解とみなさない)。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ScoreModel {
+    erase_base: Score,
+    move_bonus: Score,
+    perfect_bonus: Score,
+}
+
+impl Default for ScoreModel {
+    fn default() -> Self {
+        Self {
+            erase_base: 100,
+            move_bonus: 1000,
+            perfect_bonus: 10000,
+        }
+    }
+}
+
+impl ScoreModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_erase_base(mut self, erase_base: Score) -> Self {
+        self.erase_base = erase_base;
+        self
+    }
+
+    pub fn with_move_bonus(mut self, move_bonus: Score) -> Self {
+        self.move_bonus = move_bonus;
+        self
+    }
+
+    pub fn with_perfect_bonus(mut self, perfect_bonus: Score) -> Self {
+        self.perfect_bonus = perfect_bonus;
+        self
+    }
+
+    /// 1 回の着手でブロックを `erase_count` 個消したときの得点。
+    pub fn erase_score(&self, erase_count: usize) -> Score {
+        if erase_count == 0 {
+            return 0;
+        }
+
+        self.erase_base.saturating_mul(1 << (erase_count - 1))
+    }
+
+    /// 面クリアした局面 `pos` での、残り手数とパーフェクトクリアのボーナス。
+    pub fn clear_score(&self, pos: &Position) -> Score {
+        let perfect = if pos.block_count() == 0 {
+            self.perfect_bonus
+        } else {
+            0
+        };
+
+        self.move_bonus * Score::from(pos.move_remain()) + perfect
+    }
+
+    /// 局面 `pos` から面クリアまでに得られる得点の上界を返す。
+    ///
+    /// * 1 回の着手の得点は消す個数について凸なので、消せるだけまとめて消すのが最善。
+    ///   よって残りのブロックを 1 手につき `MAX_ERASE_PER_THROW` 個ずつ、残り手数の範囲で消すとして上から抑える。
+    /// * 面クリアには少なくとも `Position::min_moves_to_solve()` 手が必要なので、残り手数のボーナスはその分減る。
+    pub fn score_upper_bound(&self, pos: &Position) -> Score {
+        let mut block_remain = pos.block_count();
+        let mut score = 0;
+        for _ in 0..pos.move_remain() {
+            if block_remain == 0 {
+                break;
+            }
+            let erase_count = block_remain.min(MAX_ERASE_PER_THROW);
+            score += self.erase_score(erase_count);
+            block_remain -= erase_count;
+        }
+
+        let move_remain = usize::from(pos.move_remain());
+        let move_bonus_count = move_remain - pos.min_moves_to_solve().min(move_remain);
+
+        score + self.move_bonus * move_bonus_count as Score + self.perfect_bonus
+    }
+
+    /// 解 `solution` を検証し、このモデルでの得点を返す。
+    pub fn score(&self, problem: &Problem, solution: &Solution) -> Result<Score, VerifyError> {
+        solution.verify(problem, false)?;
+
+        let (mut pos, moves) = problem.to_position_and_moves();
+        let mut score = 0;
+        for &src in solution.moves() {
+            let mv = moves.iter().copied().find(|mv| mv.src() == src).unwrap();
+            let (pos_nxt, _, _) = pos.do_move(mv).unwrap();
+            score += self.erase_score(pos.block_count() - pos_nxt.block_count());
+            pos = pos_nxt;
+        }
+
+        Ok(score + self.clear_score(&pos))
+    }
+}
+
+/// 総所要フレーム数を最小化する代わりに、得点モデル `model` での得点を最大化する解を求める。
+///
+/// 分岐限定法で探索し、`ScoreModel::score_upper_bound()` で枝刈りする。
+/// 得点の等しい解が複数あれば、消すブロックの多い着手を先に試して最初に見つかったものを返す。
+pub fn solve_problem_max_score(problem: &Problem, model: &ScoreModel) -> Option<(Solution, Score)> {
+    if prove_unsolvable(problem).is_some() {
+        return None;
+    }
+
+    let (pos, moves) = problem.to_position_and_moves();
+    let mut searcher = MaxScoreSearcher {
+        model,
+        moves: &moves,
+        cur_solution: Vec::new(),
+        best: None,
+    };
+    searcher.dfs(&pos, 0);
+
+    searcher.best
+}
+
+#[derive(Debug)]
+struct MaxScoreSearcher<'a> {
+    model: &'a ScoreModel,
+    moves: &'a [Move],
+    cur_solution: Vec<MoveSrc>,
+    best: Option<(Solution, Score)>,
+}
+
+impl MaxScoreSearcher<'_> {
+    fn dfs(&mut self, pos: &Position, score: Score) {
+        if pos.is_stuck(self.moves) {
+            if pos.is_cleared() {
+                let score_total = score + self.model.clear_score(pos);
+                if self
+                    .best
+                    .as_ref()
+                    .map_or(true, |(_, best)| score_total > *best)
+                {
+                    self.best = Some((Solution::new(self.cur_solution.clone()), score_total));
+                }
+            }
+            return;
+        }
+
+        if pos.move_remain() == 0 || pos.min_moves_to_solve() > usize::from(pos.move_remain()) {
+            return;
+        }
+
+        // 得点の上界が暫定解以下なら枝刈り。
+        if let Some((_, best)) = &self.best {
+            if score + self.model.score_upper_bound(pos) <= *best {
+                return;
+            }
+        }
+
+        let mut children: Vec<_> = pos
+            .successors(self.moves)
+            .map(|(mv, pos_nxt, _, _)| {
+                let erase_count = pos.block_count() - pos_nxt.block_count();
+                (mv.src(), pos_nxt, erase_count)
+            })
+            .collect();
+        children.sort_by_key(|&(_, _, erase_count)| Reverse(erase_count));
+
+        for (src, pos_nxt, erase_count) in children {
+            self.cur_solution.push(src);
+            self.dfs(&pos_nxt, score + self.model.erase_score(erase_count));
+            self.cur_solution.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use crate::cost::Cost;
+    use crate::enumerate::enumerate_solutions;
+
+    use super::*;
+
+    #[test]
+    fn test_score_model() {
+        let model = ScoreModel::new();
+        assert_eq!(model.erase_score(0), 0);
+        assert_eq!(model.erase_score(1), 100);
+        assert_eq!(model.erase_score(4), 800);
+        assert_eq!(ScoreModel::new().with_erase_base(10).erase_score(3), 40);
+    }
+
+    #[test]
+    fn test_solve_problem_max_score() {
+        let problem: Problem = indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "}
+        .parse()
+        .unwrap();
+
+        for model in [
+            ScoreModel::new(),
+            ScoreModel::new().with_move_bonus(0),
+            ScoreModel::new().with_erase_base(1).with_move_bonus(10000),
+        ] {
+            let (solution, score) = solve_problem_max_score(&problem, &model).unwrap();
+            assert_eq!(model.score(&problem, &solution), Ok(score));
+
+            // 全ての解の中で得点が最大。
+            let score_max = enumerate_solutions(&problem, false, Cost::MAX)
+                .map(|(solution, _)| model.score(&problem, &solution).unwrap())
+                .max();
+            assert_eq!(score_max, Some(score));
+        }
+    }
+}
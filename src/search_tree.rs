@@ -0,0 +1,276 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write as _;
+
+use crate::cost::Cost;
+use crate::move_::MoveSrc;
+
+/// 探索した木 (の上の方の一部)。ソルバーが特定の面で苦戦する理由を調べるためのもの。
+///
+/// `SolverConfig::with_search_tree_depth()` を指定した探索で記録され、`SearchOutcome::search_tree()` で得られる。
+/// 局面は訪れた順に並び、根 (先頭の着手列を行った後の局面) が先頭。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SearchTree {
+    nodes: Vec<SearchTreeNode>,
+    max_depth: usize,
+    /// 探索中の局面への経路上の、記録した局面のインデックス。
+    path: Vec<usize>,
+}
+
+/// 探索した木の局面。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SearchTreeNode {
+    parent: Option<usize>,
+    src: Option<MoveSrc>,
+    depth: usize,
+    cost: Cost,
+    outcome: NodeOutcome,
+}
+
+/// 局面を訪れた結果。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum NodeOutcome {
+    /// 子局面を展開した。
+    Expanded,
+    /// 面を終えて暫定解を更新した。
+    Improved,
+    /// 面が終了した (または手数が尽きた) が、暫定解は更新しなかった。
+    Ended,
+    /// 枝刈りした。
+    Pruned(PruneReason),
+    /// 局面数や時間の上限などで探索を打ち切った。
+    Aborted,
+}
+
+/// 枝刈りの理由。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PruneReason {
+    /// 総コストが制約を満たさない。
+    Constraint,
+    /// 解けていると仮定した総コストが暫定解以上。
+    Cost,
+    /// 手数の上限に達した。
+    MaxDepth,
+    /// 残りに必要な着手数の下界から求めた総コストの下界が暫定解以上 (または残り手数で解けない)。
+    LowerBound,
+    /// 既に訪れた局面に支配される。
+    Dominance,
+    /// 置換表の下界から求めた総コストが暫定解以上。
+    TranspositionTable,
+}
+
+impl NodeOutcome {
+    /// DOT や JSON に出力する名前。
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Expanded => "expanded",
+            Self::Improved => "improved",
+            Self::Ended => "ended",
+            Self::Pruned(PruneReason::Constraint) => "pruned_constraint",
+            Self::Pruned(PruneReason::Cost) => "pruned_cost",
+            Self::Pruned(PruneReason::MaxDepth) => "pruned_max_depth",
+            Self::Pruned(PruneReason::LowerBound) => "pruned_lower_bound",
+            Self::Pruned(PruneReason::Dominance) => "pruned_dominance",
+            Self::Pruned(PruneReason::TranspositionTable) => "pruned_tt",
+            Self::Aborted => "aborted",
+        }
+    }
+}
+
+impl SearchTreeNode {
+    /// 親局面のインデックス。根なら `None`。
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    /// 親局面からの着手位置。根なら `None`。
+    pub fn src(&self) -> Option<MoveSrc> {
+        self.src
+    }
+
+    /// 面の最初からの手数。
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// この局面までの目的関数の値。
+    pub fn cost(&self) -> Cost {
+        self.cost
+    }
+
+    pub fn outcome(&self) -> NodeOutcome {
+        self.outcome
+    }
+}
+
+impl SearchTree {
+    /// 面の最初から `max_depth` 手までの局面を記録する木を作る。
+    pub(crate) fn new(max_depth: usize) -> Self {
+        Self {
+            nodes: Vec::new(),
+            max_depth,
+            path: Vec::new(),
+        }
+    }
+
+    pub fn nodes(&self) -> &[SearchTreeNode] {
+        &self.nodes
+    }
+
+    /// 手数 `depth` の局面 (着手位置 `src` で到達、目的関数の値 `cost`) を訪れ始める。
+    /// 記録したならインデックスを返す。結果は `leave()` で記録する。
+    pub(crate) fn enter(
+        &mut self,
+        src: Option<MoveSrc>,
+        depth: usize,
+        cost: Cost,
+    ) -> Option<usize> {
+        if depth > self.max_depth {
+            return None;
+        }
+
+        let idx = self.nodes.len();
+        self.nodes.push(SearchTreeNode {
+            parent: self.path.last().copied(),
+            src: self.path.last().and(src),
+            depth,
+            cost,
+            outcome: NodeOutcome::Expanded,
+        });
+        self.path.push(idx);
+
+        Some(idx)
+    }
+
+    /// `enter()` で記録した局面 `idx` を訪れ終え、結果 `outcome` を記録する。
+    pub(crate) fn leave(&mut self, idx: usize, outcome: NodeOutcome) {
+        assert_eq!(self.path.pop(), Some(idx));
+
+        self.nodes[idx].outcome = outcome;
+    }
+
+    /// Graphviz の DOT 形式で出力する。辺のラベルは着手位置 (行番号)、局面のラベルは目的関数の値と結果。
+    pub fn to_dot(&self) -> String {
+        let mut s = String::new();
+
+        s.push_str("digraph search_tree {\n");
+        s.push_str("    node [shape=box, fontname=monospace];\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            let color = match node.outcome {
+                NodeOutcome::Expanded => "black",
+                NodeOutcome::Improved => "green",
+                NodeOutcome::Ended => "gray",
+                NodeOutcome::Pruned(_) => "red",
+                NodeOutcome::Aborted => "orange",
+            };
+            writeln!(
+                s,
+                "    n{i} [label=\"{}\\n{}\", color={color}];",
+                node.cost,
+                node.outcome.name()
+            )
+            .unwrap();
+            if let (Some(parent), Some(src)) = (node.parent, node.src) {
+                writeln!(s, "    n{parent} -> n{i} [label=\"{}\"];", src.to_inner()).unwrap();
+            }
+        }
+        s.push_str("}\n");
+
+        s
+    }
+
+    /// JSON の配列で出力する。要素は局面ごとに
+    /// `{"id", "parent", "src", "depth", "cost", "outcome"}` (根の `parent` と `src` は `null`)。
+    #[cfg(feature = "std")]
+    pub fn to_json(&self) -> String {
+        let nodes: Vec<_> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| {
+                serde_json::json!({
+                    "id": i,
+                    "parent": node.parent,
+                    "src": node.src.map(MoveSrc::to_inner),
+                    "depth": node.depth,
+                    "cost": node.cost,
+                    "outcome": node.outcome.name(),
+                })
+            })
+            .collect();
+
+        serde_json::Value::Array(nodes).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use crate::config::SolverConfig;
+    use crate::problem::Problem;
+    use crate::solver::{solve_problem, solve_problem_with};
+
+    use super::*;
+
+    #[test]
+    fn test_search_tree() {
+        let problem: Problem = indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "}
+        .parse()
+        .unwrap();
+
+        // 記録しても結果は変わらない。
+        let config = SolverConfig::new().with_search_tree_depth(Some(usize::MAX));
+        let outcome = solve_problem_with(&problem, false, &config);
+        assert_eq!(outcome.best(), solve_problem(&problem, false).as_ref());
+
+        let tree = outcome.search_tree().unwrap();
+        let nodes = tree.nodes();
+        assert_eq!(nodes[0].parent(), None);
+        assert_eq!(nodes[0].depth(), 0);
+        for node in &nodes[1..] {
+            // 親は先に訪れていて、子を展開している。
+            let parent = &nodes[node.parent().unwrap()];
+            assert_eq!(parent.depth() + 1, node.depth());
+            assert_eq!(parent.outcome(), NodeOutcome::Expanded);
+            assert!(parent.cost() < node.cost());
+        }
+        // 暫定解を更新した局面がある。
+        assert!(nodes
+            .iter()
+            .any(|node| node.outcome() == NodeOutcome::Improved));
+        assert_eq!(tree.to_dot().matches(" -> ").count(), nodes.len() - 1);
+
+        // 記録する手数の上限を超える局面は記録しない。手数の上限による枝刈りは理由付きで記録する。
+        let config = SolverConfig::new()
+            .with_max_depth(1)
+            .with_search_tree_depth(Some(1));
+        let outcome = solve_problem_with(&problem, false, &config);
+        let nodes = outcome.search_tree().unwrap().nodes();
+        assert!(nodes.iter().all(|node| node.depth() <= 1));
+        assert!(nodes[1..].iter().all(|node| matches!(
+            node.outcome(),
+            NodeOutcome::Pruned(PruneReason::MaxDepth | PruneReason::LowerBound)
+                | NodeOutcome::Ended
+        )));
+
+        // 記録しなければ木はない。
+        let outcome = solve_problem_with(&problem, false, &SolverConfig::new());
+        assert_eq!(outcome.search_tree(), None);
+    }
+}
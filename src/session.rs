@@ -0,0 +1,174 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::cost::Cost;
+use crate::error::VerifyError;
+use crate::move_::{Move, MoveSrc};
+use crate::position::Position;
+use crate::problem::Problem;
+use crate::solver::{play_move, Solution};
+
+/// 1 面分のプレイ状態。着手の履歴を記録し、取り消し (undo) とやり直し (redo) ができる。
+///
+/// GUI/TUI のフロントエンドの状態管理に使う。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GameSession {
+    problem: Problem,
+    moves: Vec<Move>,
+    /// 初期局面と、各着手後の局面。
+    positions: Vec<Position>,
+    srcs: Vec<MoveSrc>,
+    /// 各着手後の累積コスト。
+    costs: Vec<Cost>,
+    /// 取り消した着手 (最後に取り消したものが末尾)。
+    redo_srcs: Vec<MoveSrc>,
+}
+
+impl GameSession {
+    pub fn new(problem: &Problem) -> Self {
+        let (pos, moves) = problem.to_position_and_moves();
+
+        Self {
+            problem: problem.clone(),
+            moves,
+            positions: vec![pos],
+            srcs: vec![],
+            costs: vec![],
+            redo_srcs: vec![],
+        }
+    }
+
+    pub fn problem(&self) -> &Problem {
+        &self.problem
+    }
+
+    /// 現在の局面。
+    pub fn position(&self) -> &Position {
+        self.positions.last().unwrap()
+    }
+
+    /// これまでの着手位置の列。
+    pub fn history(&self) -> &[MoveSrc] {
+        &self.srcs
+    }
+
+    /// これまでの着手の累積コスト (面クリア時のコストは含まない)。
+    pub fn cost(&self) -> Cost {
+        self.costs.last().copied().unwrap_or(0)
+    }
+
+    /// 面が終了している (ブロックを消す着手がない) かどうか。
+    pub fn is_stuck(&self) -> bool {
+        self.position().is_stuck(&self.moves)
+    }
+
+    /// 行 `src` からブロックを投げ、その着手のコストを返す。やり直し用の履歴は破棄する。
+    ///
+    /// 着手できない場合はエラーを返し、状態は変わらない。
+    pub fn do_move(&mut self, src: MoveSrc) -> Result<Cost, VerifyError> {
+        let cost = self.apply(src)?;
+        self.redo_srcs.clear();
+
+        Ok(cost)
+    }
+
+    /// 最後の着手を取り消す。取り消す着手がなければ false を返す。
+    pub fn undo(&mut self) -> bool {
+        let Some(src) = self.srcs.pop() else {
+            return false;
+        };
+        self.positions.pop().unwrap();
+        self.costs.pop().unwrap();
+        self.redo_srcs.push(src);
+
+        true
+    }
+
+    /// 最後に取り消した着手をやり直す。やり直す着手がなければ false を返す。
+    pub fn redo(&mut self) -> bool {
+        let Some(src) = self.redo_srcs.pop() else {
+            return false;
+        };
+        self.apply(src).expect("取り消した着手はやり直せるはず");
+
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.srcs.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_srcs.is_empty()
+    }
+
+    /// これまでの着手列を解として返す。
+    pub fn to_solution(&self) -> Solution {
+        Solution::new(self.srcs.clone())
+    }
+
+    fn apply(&mut self, src: MoveSrc) -> Result<Cost, VerifyError> {
+        let index = self.srcs.len();
+        let (_, pos_nxt, cost_mv, _) = play_move(self.position(), &self.moves, index, src)?;
+
+        let cost = self.cost() + cost_mv;
+        self.positions.push(pos_nxt);
+        self.srcs.push(src);
+        self.costs.push(cost);
+
+        Ok(cost_mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::cost::COST_CLEAR_ERASE_BLOCK;
+    use crate::move_::*;
+
+    use super::*;
+
+    #[test]
+    fn test_game_session() {
+        let problem: Problem = include_str!("../problem/01.in").parse().unwrap();
+        let solution: Solution = "10 9 10 11".parse().unwrap();
+
+        let mut session = GameSession::new(&problem);
+        assert!(!session.can_undo());
+        assert!(!session.undo());
+
+        for &src in solution.moves() {
+            session.do_move(src).unwrap();
+        }
+        assert!(session.is_stuck());
+        assert_eq!(session.to_solution(), solution);
+        let cost = session.cost();
+        assert_eq!(
+            solution.verify(&problem, false).unwrap(),
+            cost + COST_CLEAR_ERASE_BLOCK * session.position().block_count() as Cost
+        );
+        assert!(matches!(
+            session.do_move(MOVE_SRC_ROW_11),
+            Err(VerifyError::StageEnded { index: 4 })
+        ));
+
+        assert!(session.undo());
+        assert!(session.undo());
+        assert_eq!(session.history(), &solution.moves()[..2]);
+        assert!(session.cost() < cost);
+        assert!(session.redo());
+        assert_eq!(session.history(), &solution.moves()[..3]);
+
+        // 新しく着手するとやり直し用の履歴は破棄される。
+        session.undo();
+        session.do_move(MOVE_SRC_ROW_10).unwrap();
+        assert!(!session.can_redo());
+        assert!(!session.redo());
+
+        // 不正な着手では状態は変わらない。
+        let session_before = session.clone();
+        assert!(session.do_move(MOVE_SRC_ROW_0).is_err());
+        assert_eq!(session, session_before);
+    }
+}
@@ -1,80 +1,160 @@
-use anyhow::{anyhow, ensure, Context as _};
+use alloc::borrow::ToOwned as _;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::num::NonZeroUsize;
+
 use itertools::Itertools as _;
 use log::info;
 
-use crate::cost::{Cost, COST_CLEAR_ERASE_BLOCK};
-use crate::move_::{Move, MoveSrc};
+use crate::checkpoint::{CheckpointHook, SearchCheckpoint};
+use crate::config::{
+    CancelToken, HistoryTable, ImproveHook, MoveOrdering, ProgressHook, SearchOutcome,
+    SearchProgress, SearchStats, SolverConfig, PROGRESS_INTERVAL,
+};
+use crate::constraint::Constraint;
+use crate::cost::{calc_min_move_cost, Cost};
+use crate::error::{token_col, ParseError, ParseErrorKind, VerifyError};
+use crate::move_::{Move, MoveSrc, MOVE_SRC_ROW_11};
+use crate::objective::{Objective, TieBreak};
+use crate::pattern::{PatternEntry, PatternLibrary};
 use crate::position::Position;
+use crate::precheck::prove_unsolvable;
 use crate::problem::Problem;
+use crate::search_tree::{NodeOutcome, PruneReason, SearchTree};
+use crate::tablebase::Tablebase;
+use crate::tt::{TranspositionTable, TtKeyMode};
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Solution(Vec<MoveSrc>);
 
 impl Solution {
+    pub fn new(srcs: Vec<MoveSrc>) -> Self {
+        Self(srcs)
+    }
+
     pub fn moves(&self) -> &[MoveSrc] {
         &self.0
     }
 
-    pub fn verify(&self, problem: &Problem, last_stage: bool) -> anyhow::Result<Cost> {
+    /// 着手列 `mvs` の解を作る。
+    pub(crate) fn from_moves(mvs: &[Move]) -> Self {
+        Self(mvs.iter().map(|mv| mv.src()).collect())
+    }
+
+    pub fn verify(&self, problem: &Problem, last_stage: bool) -> Result<Cost, VerifyError> {
+        self.verify_with_objective(problem, last_stage, Objective::Frames)
+    }
+
+    /// 解に含まれる入れ替え着手の数を返す。解は `problem` に対して合法であるとし、不正な着手以降は数えない。
+    ///
+    /// 入れ替え着手のコストは近似なので (`Position::is_swap_move()`)、これが 0 でなければ解のコストも近似になる。
+    pub fn swap_count(&self, problem: &Problem) -> usize {
         let (mut pos, moves) = problem.to_position_and_moves();
-        let mut cost_total = 0;
+        let mut count = 0;
 
-        for (i, &src) in self.0.iter().enumerate() {
-            ensure!(pos.move_remain() > 0, "{i} 番目の着手前に残り手数が尽きた");
-            let mv = moves
-                .iter()
-                .copied()
-                .find(|mv| mv.src() == src)
-                .ok_or_else(|| anyhow!("{i} 番目の着手が不正: {src:?}"))?;
-            let (pos_nxt, cost_mv, cost_throw) = pos
-                .do_move(mv)
-                .ok_or_else(|| anyhow!("{i} 番目の着手が不正: {mv:?}"))?;
+        for &src in &self.0 {
+            let Some(mv) = moves.iter().copied().find(|mv| mv.src() == src) else {
+                break;
+            };
+            count += usize::from(pos.is_swap_move(mv));
+            match pos.do_move(mv) {
+                Some((pos_nxt, _, _)) => pos = pos_nxt,
+                None => break,
+            }
+        }
 
+        count
+    }
+
+    /// 解を検証し、さらに追加の制約 `constraints` を全て満たすかどうかを調べてコストを返す。
+    pub fn verify_with_constraints(
+        &self,
+        problem: &Problem,
+        last_stage: bool,
+        constraints: &[Constraint],
+    ) -> Result<Cost, VerifyError> {
+        let cost = self.verify(problem, last_stage)?;
+
+        let (mut pos, moves) = problem.to_position_and_moves();
+        let hero_row = pos.hero_row();
+        let mut cost_mv_max = 0;
+        for &src in &self.0 {
+            let mv = moves.iter().copied().find(|mv| mv.src() == src).unwrap();
+            let (pos_nxt, cost_mv, _) = pos.do_move(mv).unwrap();
             pos = pos_nxt;
+            cost_mv_max = cost_mv_max.max(cost_mv);
+        }
 
-            // 最終面の最終手のブロック投げコストは 1 とみなす。
-            if i == self.0.len() - 1 && last_stage {
-                cost_total += cost_mv - cost_throw + 1;
-            } else {
-                cost_total += cost_mv;
+        for &constraint in constraints {
+            if !(constraint.is_satisfied(hero_row, &self.0)
+                && constraint.allows_move_cost(cost_mv_max)
+                && constraint.allows_cost_total(cost))
+            {
+                return Err(VerifyError::ConstraintViolated { constraint });
             }
         }
 
-        let stuck = moves.iter().all(|&mv| pos.do_move(mv).is_none());
-        ensure!(stuck, "最後の局面でまだ合法手がある:\n{pos}");
+        Ok(cost)
+    }
+
+    /// 解を検証し、目的関数 `objective` でのコストを返す。
+    pub fn verify_with_objective(
+        &self,
+        problem: &Problem,
+        last_stage: bool,
+        objective: Objective,
+    ) -> Result<Cost, VerifyError> {
+        let (pos, moves) = problem.to_position_and_moves();
+        let (_, pos, mut cost_total, cost_last_throw) =
+            play_prefix(pos, &moves, &self.0, objective)?;
+
+        // 最終面の最終手のブロック投げコストは 1 とみなす。
+        if last_stage && !self.0.is_empty() {
+            cost_total = cost_total - cost_last_throw + 1;
+        }
+
+        if !pos.is_stuck(&moves) {
+            return Err(VerifyError::NotStuck { pos });
+        }
 
-        ensure!(pos.block_count() <= 3, "最後の局面が解けていない:\n{pos}");
+        if !pos.is_cleared() {
+            return Err(VerifyError::NotCleared { pos });
+        }
 
         // 最終面の場合、面クリア時のブロック自動消去コストは 0 とする。
         if !last_stage {
-            cost_total += COST_CLEAR_ERASE_BLOCK * pos.block_count() as Cost;
+            cost_total += objective.clear_cost(pos.block_count());
         }
 
         Ok(cost_total)
     }
 }
 
-impl std::str::FromStr for Solution {
-    type Err = anyhow::Error;
+impl core::str::FromStr for Solution {
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut srcs = Vec::<MoveSrc>::new();
 
-        for (i, token) in s.split_ascii_whitespace().enumerate() {
-            let src: u8 = token
-                .parse()
-                .with_context(|| format!("{i} 番目の着手が数値でない: '{token}'"))?;
-            let src =
-                MoveSrc::from_inner(src).ok_or_else(|| anyhow!("{i} 番目の着手が無効: '{src}'"))?;
-            srcs.push(src);
+        for (row, line) in s.lines().enumerate() {
+            for token in line.split_ascii_whitespace() {
+                let err = |kind| ParseError::new(row + 1, kind).with_col(token_col(line, token));
+                let src: u8 = token
+                    .parse()
+                    .map_err(|_| err(ParseErrorKind::NotNumber(token.to_owned())))?;
+                let src = MoveSrc::from_inner(src)
+                    .ok_or_else(|| err(ParseErrorKind::InvalidMoveSrc(src)))?;
+                srcs.push(src);
+            }
         }
 
         Ok(Solution(srcs))
     }
 }
 
-impl std::fmt::Display for Solution {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Solution {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "{}",
@@ -84,71 +164,2851 @@ impl std::fmt::Display for Solution {
 }
 
 pub fn solve_problem(problem: &Problem, last_stage: bool) -> Option<(Solution, Cost)> {
+    solve_problem_with(problem, last_stage, &SolverConfig::new()).into_best()
+}
+
+/// 設定 `config` に従って問題を解く。
+///
+/// 局面数や時間の上限、または打ち切りトークンで探索を打ち切った場合は、それまでに見つかった最善の解を返す (最適とは限らない)。
+pub fn solve_problem_with(
+    problem: &Problem,
+    last_stage: bool,
+    config: &SolverConfig,
+) -> SearchOutcome {
+    solve_with_config(
+        problem,
+        last_stage,
+        config,
+        None,
+        None,
+        None,
+        SearchTables::default(),
+    )
+}
+
+/// `solve_problem_with()` と同様だが、探索中に一定の局面数ごとと探索終了時に `progress` を呼んで途中経過を報告する。
+pub fn solve_problem_with_progress(
+    problem: &Problem,
+    last_stage: bool,
+    config: &SolverConfig,
+    mut progress: impl FnMut(&SearchProgress) + Send,
+) -> SearchOutcome {
+    solve_with_config(
+        problem,
+        last_stage,
+        config,
+        Some(ProgressHook(&mut progress)),
+        None,
+        None,
+        SearchTables::default(),
+    )
+}
+
+/// `solve_problem_with()` と同様だが、暫定解を更新するたびに `improve` を (解, 目的関数の値) で呼ぶ。
+/// 時間のかかる探索の途中の解を保存するのに使う。
+/// 探索の前に貪欲法で求めた解 (`SolverConfig::with_warm_start()`) も報告する。その次に報告する解だけは同じ値のことがある。
+pub fn solve_problem_with_improvements(
+    problem: &Problem,
+    last_stage: bool,
+    config: &SolverConfig,
+    mut improve: impl FnMut(&Solution, Cost) + Send,
+) -> SearchOutcome {
+    solve_with_config(
+        problem,
+        last_stage,
+        config,
+        None,
+        Some(ImproveHook(&mut improve)),
+        None,
+        SearchTables::default(),
+    )
+}
+
+/// `solve_problem_with()` と同様だが、`interval` 局面ごとと探索終了時 (打ち切った場合を含む) に
+/// 探索のスナップショットを作って `save` を呼ぶ。`resume` を渡すと、そのスナップショットから探索を再開する。
+///
+/// 時間のかかる探索を再起動などで中断しても、保存したスナップショットから続きを探索できる。
+/// 再開後の探索は中断しなかった場合と同じ総コストの解を返す。
+/// 1 スレッドのみで、第 2 の基準 (`tie_break`)、`MoveOrdering::History`、先頭の着手列 (`prefix`) には対応しない
+/// (着手順が探索の経過に依存し、探索済みの部分木を特定できないため)。
+/// `resume` は同じ問題、最終面フラグ、目的関数のものでなければならない。
+pub fn solve_problem_resumable(
+    problem: &Problem,
+    last_stage: bool,
+    config: &SolverConfig,
+    resume: Option<&SearchCheckpoint>,
+    interval: u64,
+    mut save: impl FnMut(&SearchCheckpoint) + Send,
+) -> SearchOutcome {
+    assert!(config.tie_break().is_none());
+    assert_ne!(config.move_ordering(), MoveOrdering::History);
+    assert!(config.prefix().is_empty());
+    assert!(interval > 0);
+    if let Some(resume) = resume {
+        assert!(resume.matches(problem, last_stage, config.objective()));
+    }
+
+    solve_with_config(
+        problem,
+        last_stage,
+        config,
+        None,
+        None,
+        Some(CheckpointHook {
+            base: SearchCheckpoint::new(problem, last_stage, config.objective()),
+            resume,
+            interval,
+            save: &mut save,
+        }),
+        SearchTables::default(),
+    )
+}
+
+/// 面の列 `problems` (原作なら全 50 面) を順に解き、面ごとの最適解と全面の総コストを返す。
+///
+/// 最後の面は自動的に最終面の規則 (`last_stage`) で解く。全面通しの TAS のルートを求めるのに使う。
+/// 総コストはメニューや面の切り替えのフレーム数を含まない (`run_time` モジュールを参照)。
+pub fn solve_campaign(problems: &[Problem]) -> CampaignSolution {
+    let stages = problems
+        .iter()
+        .enumerate()
+        .map(|(i, problem)| {
+            info!("campaign: stage {}/{}", i + 1, problems.len());
+            solve_problem(problem, i + 1 == problems.len())
+        })
+        .collect();
+
+    CampaignSolution(stages)
+}
+
+/// `solve_campaign()` の結果。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CampaignSolution(Vec<Option<(Solution, Cost)>>);
+
+impl CampaignSolution {
+    /// 面ごとの最適解と総コスト (解がなければ `None`)。
+    pub fn stages(&self) -> &[Option<(Solution, Cost)>] {
+        &self.0
+    }
+
+    /// 全面の総コストの合計。解のない面があれば `None`。
+    pub fn total_cost(&self) -> Option<Cost> {
+        self.0
+            .iter()
+            .map(|stage| stage.as_ref().map(|&(_, cost)| cost))
+            .sum()
+    }
+}
+
+/// 最終面でない場合と最終面の場合 (`last_stage` が偽と真) の両方の最適解を 1 回の探索で求める。
+///
+/// 2 つの場合の探索木は同じで、総コストの数え方 (最終手のブロック投げコストと面クリア時のブロック自動消去コスト) だけが違う。
+/// そこで両方の暫定解を持って探索し、どちらの暫定解も改善できない部分木だけを枝刈りする。
+/// 総コストは `solve_problem()` を 2 回呼んだ場合と同じ (解は異なりうる)。ルートの計画で面の候補を比べる場合などに、
+/// 2 回分の探索を省ける。
+pub fn solve_problem_both_stages(problem: &Problem) -> BothStagesSolution {
+    if let Some(reason) = prove_unsolvable(problem) {
+        info!("unsolvable: {reason}");
+        return BothStagesSolution([None, None]);
+    }
+
+    let (pos, moves) = problem.to_position_and_moves();
+    let mut solver = BothStagesSolver {
+        best: [(None, Cost::MAX), (None, Cost::MAX)],
+        cur_solution: vec![],
+        min_throw_cost: moves
+            .iter()
+            .map(|mv| calc_min_move_cost(mv.src()))
+            .min()
+            .unwrap_or(0),
+        dominance: DominanceTable::new(DOMINANCE_CAPACITY),
+    };
+
+    info!("search start");
+    solver.solve(&moves, pos, 0, 0);
+    info!("search end");
+
+    BothStagesSolution(
+        solver.best.map(|(solution, cost)| {
+            solution.map(|solution| (Solution::from_moves(&solution), cost))
+        }),
+    )
+}
+
+/// `solve_problem_both_stages()` の結果。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BothStagesSolution([Option<(Solution, Cost)>; 2]);
+
+impl BothStagesSolution {
+    /// 最終面フラグが `last_stage` の場合の最適解と総コスト (解がなければ `None`)。
+    pub fn get(&self, last_stage: bool) -> Option<&(Solution, Cost)> {
+        self.0[usize::from(last_stage)].as_ref()
+    }
+}
+
+/// 総コスト (フレーム数) と手数のパレート最適な解をすべて求める。
+///
+/// 手数の昇順 (総コストの降順) に (解, 総コスト) を返す。先頭は手数最小の解のうち総コスト最小のもの、
+/// 末尾は `solve_problem()` の最適解と同じ総コストの解。解がなければ空。
+/// 手数の上限を 1 ずつ増やしながら総コスト最小の解を求めるので、通常の探索の数倍の時間がかかる。
+pub fn solve_problem_pareto(problem: &Problem, last_stage: bool) -> Vec<(Solution, Cost)> {
+    let Some((solution_min, _)) =
+        solve_problem_with_objective(problem, last_stage, Objective::MoveCount)
+    else {
+        return vec![];
+    };
+    let (solution_opt, cost_opt) = solve_problem(problem, last_stage).unwrap();
+
+    let mut frontier = Vec::<(Solution, Cost)>::new();
+    for max_depth in solution_min.0.len()..solution_opt.0.len() {
+        let config = SolverConfig::new().with_max_depth(max_depth);
+        let (solution, cost) = solve_problem_with(problem, last_stage, &config)
+            .into_best()
+            .unwrap();
+        // 手数の上限を増やしても総コストが減らなければ、その手数の点は支配されている。
+        if frontier
+            .last()
+            .map_or(true, |&(_, cost_prev)| cost < cost_prev)
+        {
+            frontier.push((solution, cost));
+        }
+    }
+    if frontier
+        .last()
+        .map_or(true, |&(_, cost_prev)| cost_opt < cost_prev)
+    {
+        frontier.push((solution_opt, cost_opt));
+    }
+
+    frontier
+}
+
+/// 探索で引いて追記する、呼び出し側が持つ表。探索の後で書き戻す。
+#[derive(Debug, Default)]
+struct SearchTables<'a> {
+    /// 置換表 (`solve_problem_with_tt()`)。`memory_limit()` や共有の置換表より優先する。
+    tt: Option<&'a mut TranspositionTable>,
+    /// 終盤表 (`solve_problem_with_tablebase()`)。
+    tablebase: Option<&'a mut Tablebase>,
+}
+
+fn solve_with_config<'a>(
+    problem: &Problem,
+    last_stage: bool,
+    config: &SolverConfig,
+    progress: Option<ProgressHook<'a>>,
+    improve: Option<ImproveHook<'a>>,
+    mut checkpoint: Option<CheckpointHook<'a>>,
+    tables: SearchTables<'_>,
+) -> SearchOutcome {
+    if let Some(reason) = prove_unsolvable(problem) {
+        info!("unsolvable: {reason}");
+        if let Some(checkpoint) = &mut checkpoint {
+            (checkpoint.save)(&checkpoint.base.with_state(0, None, None));
+        }
+        return SearchOutcome::new(None, true);
+    }
+
+    #[cfg(feature = "std")]
+    if config.threads() > 1 {
+        assert!(config.tie_break().is_none());
+        assert!(config.search_tree_depth().is_none());
+        assert!(config.constraints().is_empty());
+        assert!(config.cost_bound().is_none());
+        assert!(config.weight() == 1.0);
+        assert!(config.patterns().is_none());
+        assert!(checkpoint.is_none());
+        assert!(tables.tt.is_none() && tables.tablebase.is_none());
+        return crate::parallel::solve_parallel_with(
+            problem, last_stage, config, progress, improve,
+        );
+    }
+
     let (pos, moves) = problem.to_position_and_moves();
+    let hero_row_init = pos.hero_row();
+    let Ok((prefix, pos, cost, cost_last_throw)) =
+        play_prefix(pos, &moves, config.prefix(), config.objective())
+    else {
+        info!("invalid prefix");
+        return SearchOutcome::new(None, true);
+    };
 
     let mut solver = Solver::new(last_stage);
+    solver.cur_solution = prefix;
+    solver.hero_row_init = hero_row_init;
+    solver.node_limit = config.node_limit();
+    solver.max_depth = config.max_depth();
+    solver.lower_bound = config.lower_bound();
+    solver.objective = config.objective();
+    solver.tie_break = config.tie_break();
+    solver.move_ordering = config.move_ordering();
+    solver.constraints = config.constraints().to_vec();
+    solver.weight = config.weight();
+    if let Some(cost_bound) = config.cost_bound() {
+        solver.best_cost = cost_bound.saturating_add(1);
+    }
+    // 制約や第 2 の基準があると、局面以降の最善の解が経路に依存する。
+    let path_dependent = !config.constraints().is_empty() || config.tie_break().is_some();
+    // 置換表の下界は手数の上限があると経路に依存し、重み付きの枝刈りでは正しくない。
+    let tt_usable = !path_dependent && config.max_depth() == usize::MAX && config.weight() == 1.0;
+    // 終盤の着手列のライブラリと終盤表の着手列は、フレーム数で最善のもの。
+    let endgame_usable = !path_dependent
+        && config.max_depth() == usize::MAX
+        && config.objective() == Objective::Frames;
+    if endgame_usable {
+        if let Some(library) = config.patterns() {
+            solver.patterns = library.entries_for(problem, last_stage);
+            info!("patterns: {}", solver.patterns.len());
+        }
+    }
+    let mut tablebase = tables.tablebase.filter(|_| endgame_usable);
+    // 呼び出し側の置換表は問題と最終面フラグだけに対応するので、フレーム数の下界しか記録できない。
+    let mut tt = tables
+        .tt
+        .filter(|_| tt_usable && config.objective() == Objective::Frames);
+    // メモリ使用量に上限があれば、支配による枝刈りの表と置換表で半分ずつ使う。
+    // 入れ替え着手を許す場合、支配による枝刈りは置換表と両立しないので行わない (`DominanceTable` 参照)。
+    let memory_limit = config.memory_limit();
+    if config.dominance() && !path_dependent && !problem.ruleset().allow_swap() {
+        let capacity = memory_limit.map_or(DOMINANCE_CAPACITY, |limit| {
+            (limit / 2 / DOMINANCE_ENTRY_BYTES).min(DOMINANCE_CAPACITY)
+        });
+        solver.dominance = Some(DominanceTable::new(capacity));
+    }
+    // 共有の置換表が指定されていれば、探索ごとの置換表の代わりにそれを使う。
+    #[cfg(feature = "std")]
+    if let Some(shared_tt) = config.shared_tt() {
+        if tt_usable && tt.is_none() {
+            let context =
+                crate::parallel::TtContext::new(&pos, &moves, last_stage, config.objective());
+            solver.shared_tt = Some(shared_tt.table(context));
+        }
+    }
+    if let Some(limit) = memory_limit {
+        if tt_usable && tt.is_none() && !solver.uses_shared_tt() {
+            solver.tt = Some(
+                TranspositionTable::new(problem, last_stage)
+                    .with_key_mode(TtKeyMode::Full128)
+                    .with_memory_limit(Some(limit / 2)),
+            );
+        }
+    }
+    if solver.move_ordering == MoveOrdering::History {
+        solver.history = Some(HistoryTable::new());
+    }
+    solver.cancel = config.cancel_token().cloned();
+    solver.search_tree = config.search_tree_depth().map(SearchTree::new);
+    solver.progress = progress;
+    solver.improve = improve;
+    #[cfg(feature = "std")]
+    {
+        solver.deadline = config
+            .time_limit()
+            .map(|time_limit| std::time::Instant::now() + time_limit);
+    }
+
+    // スナップショットから再開する場合、暫定解と探索中の局面への経路を復元する。
+    let to_moves = |srcs: &[MoveSrc]| -> Vec<Move> {
+        srcs.iter()
+            .map(|&src| *moves.iter().find(|mv| mv.src() == src).unwrap())
+            .collect()
+    };
+    if let Some(resume) = checkpoint.as_ref().and_then(|checkpoint| checkpoint.resume) {
+        if let Some((solution, cost)) = resume.best() {
+            solver.best_solution = Some(to_moves(solution.moves()));
+            solver.best_cost = *cost;
+        }
+        solver.node_count = resume.node_count();
+        match resume.path() {
+            Some(path) => solver.resume = Some(to_moves(path)),
+            None => {
+                info!("resume: search already complete");
+                return SearchOutcome::new(resume.best().cloned(), true);
+            }
+        }
+        info!("resume: {} nodes", resume.node_count());
+    }
+    solver.checkpoint = checkpoint;
+    // 既知の解があれば暫定解とする (再開時は良い方)。
+    if let Some((solution, cost)) = config.incumbent() {
+        if config.tie_break().is_none() && *cost < solver.best_cost {
+            solver.best_solution = Some(to_moves(solution.moves()));
+            solver.best_cost = *cost;
+        }
+    }
+    if config.warm_start() && !path_dependent && solver.best_solution.is_none() {
+        solver.warm_start(&moves, &pos, cost);
+    }
+
+    if let Some(tt) = tt.as_deref_mut() {
+        solver.tt = Some(core::mem::take(tt));
+    }
+    solver.tablebase = tablebase.as_deref_mut().map(core::mem::take);
 
     info!("search start");
-    solver.solve(&moves, pos, 0, 0);
+    solver.solve(&moves, pos, cost, cost_last_throw);
+    solver.report_progress();
     info!("search end");
 
-    solver.best_solution.map(|solution| {
-        let srcs = solution.into_iter().map(Move::src).collect();
-        let solution = Solution(srcs);
-        (solution, solver.best_cost)
+    if let Some(tt) = tt {
+        *tt = solver.tt.take().unwrap();
+    }
+    if let Some(tablebase) = tablebase {
+        *tablebase = solver.tablebase.take().unwrap();
+    }
+
+    if solver.checkpoint.is_some() {
+        let path = solver
+            .aborted
+            .then(|| solver.abort_path.take().unwrap_or_default());
+        solver.save_checkpoint(path);
+    }
+
+    let stats = solver.stats();
+    let best = solver
+        .take_best()
+        .map(|(solution, cost)| (Solution::from_moves(&solution), cost));
+
+    SearchOutcome::new(best, !solver.aborted)
+        .with_stats(stats)
+        .with_search_tree(solver.search_tree)
+        .with_cost_cut_min(solver.cost_cut_min)
+}
+
+/// 局面 `pos` で着手位置 `src` から投げ、(着手, 行った後の局面, 着手のコスト, 投げたコスト) を返す。
+/// 不正な着手ならエラーを返す。`index` はエラーに含める着手列中の位置。
+pub(crate) fn play_move(
+    pos: &Position,
+    moves: &[Move],
+    index: usize,
+    src: MoveSrc,
+) -> Result<(Move, Position, Cost, Cost), VerifyError> {
+    if pos.move_remain() == 0 {
+        return Err(VerifyError::MoveExhausted { index });
+    }
+    if pos.is_stuck(moves) {
+        return Err(VerifyError::StageEnded { index });
+    }
+    let illegal = |dst| VerifyError::IllegalMove {
+        index,
+        src,
+        pos: pos.clone(),
+        dst,
+    };
+    let mv = moves
+        .iter()
+        .copied()
+        .find(|mv| mv.src() == src)
+        .ok_or_else(|| illegal(None))?;
+    let (pos_nxt, cost_mv, cost_throw) = pos.do_move(mv).ok_or_else(|| illegal(Some(mv.dst())))?;
+
+    Ok((mv, pos_nxt, cost_mv, cost_throw))
+}
+
+/// 局面 `pos` から着手列 `prefix` を行い、(着手列, 行った後の局面, 目的関数の値, 最後に投げたコスト) を返す。
+/// 不正な着手列ならエラーを返す。
+pub(crate) fn play_prefix(
+    mut pos: Position,
+    moves: &[Move],
+    prefix: &[MoveSrc],
+    objective: Objective,
+) -> Result<(Vec<Move>, Position, Cost, Cost), VerifyError> {
+    let mut mvs = Vec::<Move>::with_capacity(prefix.len());
+    let mut cost = 0;
+    let mut cost_last_throw = 0;
+
+    for (i, &src) in prefix.iter().enumerate() {
+        let (mv, pos_nxt, cost_mv, cost_throw) = play_move(&pos, moves, i, src)?;
+        pos = pos_nxt;
+        mvs.push(mv);
+        cost += objective.move_cost(cost_mv, cost_throw);
+        cost_last_throw = objective.throw_cost(cost_throw);
+    }
+
+    Ok((mvs, pos, cost, cost_last_throw))
+}
+
+/// 探索の方式。`solve_problem_with_strategy()` に渡す。
+///
+/// 組み込みの方式は `BuiltinStrategy`。`Problem::to_position_and_moves()` で得た局面と着手に
+/// `Position::do_move()` などを使えば、下流のクレートで独自の方式を実装できる。
+pub trait SolverStrategy {
+    /// 問題を解き、解とその総コストを返す。解がなければ (または見つからなければ) `None`。
+    fn solve(&self, problem: &Problem, last_stage: bool) -> Option<(Solution, Cost)>;
+}
+
+/// 組み込みの探索の方式。
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BuiltinStrategy {
+    /// 深さ優先の分岐限定法 (`solve_problem()`)。
+    #[default]
+    DepthFirst,
+
+    /// 反復深化 A* (`solve_problem_ida()`)。
+    Ida,
+
+    /// ビームサーチ (`solve_problem_beam()`)。最適とは限らない。
+    Beam { width: NonZeroUsize },
+
+    /// 下界に重みを掛けた分岐限定法 (`solve_problem_weighted()`)。最適解の総コストの `weight` 倍以内。
+    Weighted { weight: f64 },
+
+    /// モンテカルロ木探索 (`mcts::solve_problem_mcts()`)。プレイアウトが十分多ければ最適。
+    #[cfg(feature = "std")]
+    Mcts { playouts: u64, seed: u64 },
+
+    /// ランダムリスタート (`restart::solve_problem_restarts()`)。最適。
+    #[cfg(feature = "std")]
+    Restarts {
+        restart_nodes: core::num::NonZeroU64,
+        seed: u64,
+    },
+}
+
+impl SolverStrategy for BuiltinStrategy {
+    fn solve(&self, problem: &Problem, last_stage: bool) -> Option<(Solution, Cost)> {
+        match *self {
+            Self::DepthFirst => solve_problem(problem, last_stage),
+            Self::Ida => solve_problem_ida(problem, last_stage),
+            Self::Beam { width } => solve_problem_beam(problem, last_stage, width),
+            Self::Weighted { weight } => solve_problem_weighted(problem, last_stage, weight)
+                .map(|res| (res.solution, res.cost)),
+            #[cfg(feature = "std")]
+            Self::Mcts { playouts, seed } => {
+                crate::mcts::solve_problem_mcts(problem, last_stage, playouts, seed).into_best()
+            }
+            #[cfg(feature = "std")]
+            Self::Restarts {
+                restart_nodes,
+                seed,
+            } => crate::restart::solve_problem_restarts(
+                problem,
+                last_stage,
+                &SolverConfig::new(),
+                restart_nodes,
+                seed,
+            )
+            .into_best(),
+        }
+    }
+}
+
+/// 探索の方式 `strategy` で問題を解く。方式によっては最適とは限らない (`BuiltinStrategy` を参照)。
+pub fn solve_problem_with_strategy<S: SolverStrategy + ?Sized>(
+    problem: &Problem,
+    last_stage: bool,
+    strategy: &S,
+) -> Option<(Solution, Cost)> {
+    strategy.solve(problem, last_stage)
+}
+
+/// 反復深化 A* (IDA*) で問題を解く。結果のコストは `solve_problem()` と同じ (解は異なりうる)。
+///
+/// 総コストの閾値を決め、総コストの下界 (現在のコスト + 残りに必要な着手数の下界 × ブロック投げの最小コスト) が
+/// 閾値を超える局面を切り捨てて探索し、閾値以下の解のうち最適なものを求める。
+/// 解がなければ、切り捨てた局面の下界の最小値と閾値の 1/16 増しの大きい方を次の閾値として探索し直す。
+/// 悪い暫定解のもとで深く探索することはない代わりに、閾値を上げるたびに浅い部分を探索し直す。
+/// どちらが速いかは面による (原作の面では分岐限定法の方が速いことが多い)。
+pub fn solve_problem_ida(problem: &Problem, last_stage: bool) -> Option<(Solution, Cost)> {
+    let mut threshold = 0;
+    loop {
+        // 閾値を超える解は探さない。貪欲法の解で閾値より先に枝刈りを始めることもしない。
+        let config = SolverConfig::new()
+            .with_cost_bound(Some(threshold))
+            .with_warm_start(false);
+        let outcome = solve_problem_with(problem, last_stage, &config);
+        let cost_cut_min = outcome.cost_cut_min();
+
+        if let Some(best) = outcome.into_best() {
+            break Some(best);
+        }
+        // 何も切り捨てずに探索を完了したなら解はない。
+        if cost_cut_min == Cost::MAX {
+            break None;
+        }
+        threshold = cost_cut_min.max(threshold + threshold / 16);
+        info!("threshold: {threshold}");
+    }
+}
+
+/// ビームサーチで問題を解く。結果は最適とは限らない (問題の編集中に素早く目安を得る用途)。
+///
+/// 手数ごとに、全ての子局面のうち総コストの下界 (`solve_problem_ida()` と同じ) が小さい順に
+/// `beam_width` 個だけを残して次の手数に進む。同じ局面に複数の経路で至った場合はコストの小さい方を残す。
+/// 見つかった解のうち総コスト最小のものを返す。ビーム幅を大きくするほど解はよくなるが、遅くなる。
+pub fn solve_problem_beam(
+    problem: &Problem,
+    last_stage: bool,
+    beam_width: NonZeroUsize,
+) -> Option<(Solution, Cost)> {
+    #[derive(Debug)]
+    struct Node {
+        pos: Position,
+        cost: Cost,
+        cost_lower: Cost,
+        srcs: Vec<MoveSrc>,
+    }
+
+    let (pos, moves) = problem.to_position_and_moves();
+    let min_throw_cost = moves
+        .iter()
+        .map(|mv| calc_min_move_cost(mv.src()))
+        .min()
+        .unwrap_or(0);
+
+    info!("beam search start (width: {beam_width})");
+    let mut best: Option<(Vec<MoveSrc>, Cost)> = None;
+    let mut beam = vec![Node {
+        pos,
+        cost: 0,
+        cost_lower: 0,
+        srcs: vec![],
+    }];
+    while !beam.is_empty() {
+        let mut children = BTreeMap::<u128, Node>::new();
+        for node in &beam {
+            for &mv in &moves {
+                let Some((pos, cost_mv, cost_throw)) = node.pos.do_move(mv) else {
+                    continue;
+                };
+                let cost = node.cost + cost_mv;
+                let mut srcs = node.srcs.clone();
+                srcs.push(mv.src());
+
+                if pos.is_stuck(&moves) {
+                    if pos.is_cleared() {
+                        let cost_total = if last_stage {
+                            cost - cost_throw + 1
+                        } else {
+                            cost + Objective::Frames.clear_cost(pos.block_count())
+                        };
+                        if best
+                            .as_ref()
+                            .map_or(true, |&(_, cost_best)| cost_total < cost_best)
+                        {
+                            info!("improve: {cost_total} {srcs:?}");
+                            best = Some((srcs, cost_total));
+                        }
+                    }
+                    continue;
+                }
+                let move_count = pos.min_moves_to_solve().max(1);
+                if move_count > usize::from(pos.move_remain()) {
+                    continue;
+                }
+                let cost_lower = if last_stage {
+                    cost + min_throw_cost * (move_count - 1) as Cost + 1
+                } else {
+                    cost + min_throw_cost * move_count as Cost
+                };
+                if best
+                    .as_ref()
+                    .is_some_and(|&(_, cost_best)| cost_lower >= cost_best)
+                {
+                    continue;
+                }
+
+                let key = pos.packed_key();
+                if children.get(&key).is_some_and(|other| other.cost <= cost) {
+                    continue;
+                }
+                children.insert(
+                    key,
+                    Node {
+                        pos,
+                        cost,
+                        cost_lower,
+                        srcs,
+                    },
+                );
+            }
+        }
+
+        beam = children.into_values().collect();
+        beam.sort_by_key(|node| (node.cost_lower, node.pos.block_count()));
+        beam.truncate(beam_width.get());
+    }
+    info!("beam search end");
+
+    best.map(|(srcs, cost)| (Solution(srcs), cost))
+}
+
+/// `solve_problem_weighted()` の結果。解と、最適解の総コストの (証明された) 下界。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BoundedSolution {
+    solution: Solution,
+    cost: Cost,
+    lower_bound: Cost,
+}
+
+impl BoundedSolution {
+    pub fn solution(&self) -> &Solution {
+        &self.solution
+    }
+
+    pub fn cost(&self) -> Cost {
+        self.cost
+    }
+
+    /// 最適解の総コストの下界。最適解の総コストは `lower_bound()` 以上 `cost()` 以下。
+    pub fn lower_bound(&self) -> Cost {
+        self.lower_bound
+    }
+}
+
+/// 最適解の総コストの `weight` 倍以内であることが保証された解を求める (`weight` は 1 以上)。
+///
+/// `solve_problem()` と同じ分岐限定法で、残りに必要な着手数による総コストの下界を `weight` 倍して枝刈りする。
+/// 厳密な探索とビームサーチの中間で、`weight` が大きいほど速いが解は悪くなりうる。
+/// 枝刈りした部分木の (重みを掛けない) 下界の最小値から、最適解の総コストの下界も求める。
+pub fn solve_problem_weighted(
+    problem: &Problem,
+    last_stage: bool,
+    weight: f64,
+) -> Option<BoundedSolution> {
+    let config = SolverConfig::new().with_weight(weight);
+    let outcome = solve_problem_with(problem, last_stage, &config);
+    let cost_cut_min = outcome.cost_cut_min();
+
+    outcome.into_best().map(|(solution, cost)| BoundedSolution {
+        solution,
+        cost,
+        lower_bound: cost.min(cost_cut_min),
     })
 }
 
+/// 追加の制約 `constraints` を全て満たす解のうち、コスト最小のものを求める。
+pub fn solve_problem_with_constraints(
+    problem: &Problem,
+    last_stage: bool,
+    constraints: &[Constraint],
+) -> Option<(Solution, Cost)> {
+    let config = SolverConfig::new().with_constraints(constraints.to_vec());
+    solve_problem_with(problem, last_stage, &config).into_best()
+}
+
+/// 着手数が `max_depth` 以下の解のうち最適なものを求める。そのような解がなければ `None`。
+///
+/// 「この面は 9 手で解けるか」のような問いに答えるのに使う。残りに必要な着手数の下界で上限までに解けない局面を枝刈りするので、
+/// 上限が小さいほど速く答えが出る。`SolverConfig::with_max_depth()` で解くのと同じ。
+pub fn solve_problem_with_max_depth(
+    problem: &Problem,
+    last_stage: bool,
+    max_depth: usize,
+) -> Option<(Solution, Cost)> {
+    solve_problem_with(
+        problem,
+        last_stage,
+        &SolverConfig::new().with_max_depth(max_depth),
+    )
+    .into_best()
+}
+
+/// 目的関数 `objective` の値が最小の解とその値を求める。
+pub fn solve_problem_with_objective(
+    problem: &Problem,
+    last_stage: bool,
+    objective: Objective,
+) -> Option<(Solution, Cost)> {
+    let config = SolverConfig::new().with_objective(objective);
+    solve_problem_with(problem, last_stage, &config).into_best()
+}
+
+/// 目的関数 `objective` の値が最小の解のうち、第 2 の基準 `tie_break` の値が最小のものとその目的関数の値を求める。
+pub fn solve_problem_lexicographic(
+    problem: &Problem,
+    last_stage: bool,
+    objective: Objective,
+    tie_break: TieBreak,
+) -> Option<(Solution, Cost)> {
+    let config = SolverConfig::new()
+        .with_objective(objective)
+        .with_tie_break(Some(tie_break));
+    solve_problem_with(problem, last_stage, &config).into_best()
+}
+
+/// 人手で作った序盤の着手列 `prefix` に続く最適な残りの着手列を求めた結果。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Continuation {
+    solution: Solution,
+    prefix_len: usize,
+    cost_prefix: Cost,
+    cost_suffix: Cost,
+}
+
+impl Continuation {
+    /// `prefix` とソルバーが求めた残りを連結した解。
+    pub fn solution(&self) -> &Solution {
+        &self.solution
+    }
+
+    /// ソルバーが求めた残りの着手列。
+    pub fn suffix(&self) -> &[MoveSrc] {
+        &self.solution.moves()[self.prefix_len..]
+    }
+
+    /// `prefix` 部分のコスト。
+    pub fn cost_prefix(&self) -> Cost {
+        self.cost_prefix
+    }
+
+    /// ソルバーが求めた残りの部分のコスト (面クリア時のブロック自動消去コストを含む)。
+    pub fn cost_suffix(&self) -> Cost {
+        self.cost_suffix
+    }
+
+    pub fn cost_total(&self) -> Cost {
+        self.cost_prefix + self.cost_suffix
+    }
+}
+
+/// 着手列 `prefix` を行った後の局面から設定 `config` で探索し、`prefix` で始まる最適解を求める。
+/// `config` の `prefix()` は `prefix` で置き換える。コストは `config.objective()` で測る。
+///
+/// `prefix` が不正な場合はエラーを返す。`prefix` に続く解がない (または見つからない) 場合は `Ok(None)` を返す。
+pub fn solve_continuation(
+    problem: &Problem,
+    prefix: &Solution,
+    last_stage: bool,
+    config: &SolverConfig,
+) -> Result<Option<Continuation>, VerifyError> {
+    let (pos, moves) = problem.to_position_and_moves();
+    let (prefix_mvs, _, cost_prefix, _) =
+        play_prefix(pos, &moves, prefix.moves(), config.objective())?;
+
+    let config = config.clone().with_prefix(prefix.moves().to_vec());
+    let res = solve_with_config(
+        problem,
+        last_stage,
+        &config,
+        None,
+        None,
+        None,
+        SearchTables::default(),
+    )
+    .into_best();
+
+    Ok(res.map(|(solution, cost)| {
+        // 最終面で prefix だけで解けている場合、最終手のコスト補正は prefix 側に含める。
+        let cost_prefix = if solution.moves().len() == prefix_mvs.len() && last_stage {
+            cost
+        } else {
+            cost_prefix
+        };
+        Continuation {
+            solution,
+            prefix_len: prefix_mvs.len(),
+            cost_prefix,
+            cost_suffix: cost - cost_prefix,
+        }
+    }))
+}
+
+/// 着手列 `prefix` を行った後の局面から探索し、`prefix` で始まる最適解とそのコストを返す。
+/// `prefix` が不正な場合、または解がない場合は `None` を返す。
+#[cfg(feature = "std")]
+pub(crate) fn solve_with_prefix(
+    problem: &Problem,
+    last_stage: bool,
+    prefix: &[Move],
+) -> Option<(Solution, Cost)> {
+    let config = SolverConfig::new().with_prefix(Solution::from_moves(prefix).0);
+    solve_problem_with(problem, last_stage, &config).into_best()
+}
+
+/// 置換表 `tt` を使って問題を解く。探索中に得た下界は `tt` に追記される。
+///
+/// `tt` は `TranspositionTable::new(problem, last_stage)` で作ったもの、または同じ引数で読み込んだものでなければならない。
+pub fn solve_problem_with_tt(
+    problem: &Problem,
+    last_stage: bool,
+    tt: &mut TranspositionTable,
+) -> Option<(Solution, Cost)> {
+    assert!(tt.matches(problem, last_stage));
+
+    let tables = SearchTables {
+        tt: Some(tt),
+        tablebase: None,
+    };
+    solve_with_config(
+        problem,
+        last_stage,
+        &SolverConfig::new(),
+        None,
+        None,
+        None,
+        tables,
+    )
+    .into_best()
+}
+
+/// 少しずつ編集した問題を続けて解くためのソルバー。自作の面をブロック 1 個ずつ変えながら試す場合などに使う。
+///
+/// 前回の探索の置換表を次の探索に引き継ぐ。置換表の下界は局面だけで決まるので、編集で変わらなかった部分の局面では
+/// 前回の探索結果で枝刈りできる。さらに前回の解が編集後の問題でも解なら、それを暫定解として探索を始める。
+/// 着手できる行の集合 (ステージの形) やルール設定が変わった場合は、置換表を作り直す。
 #[derive(Debug)]
-struct Solver {
-    best_solution: Option<Vec<Move>>,
-    best_cost: Cost,
-    cur_solution: Vec<Move>,
+pub struct IncrementalSolver {
     last_stage: bool,
+    tt: Option<TranspositionTable>,
+    moves: Vec<Move>,
+    prev: Option<Solution>,
 }
 
-impl Solver {
-    fn new(last_stage: bool) -> Self {
+impl IncrementalSolver {
+    pub fn new(last_stage: bool) -> Self {
         Self {
-            best_solution: None,
-            best_cost: Cost::MAX,
-            cur_solution: vec![],
             last_stage,
+            tt: None,
+            moves: vec![],
+            prev: None,
         }
     }
 
-    fn solve(&mut self, moves: &[Move], pos: Position, cost: Cost, cost_last_throw: Cost) {
-        // 現局面が解けていると仮定したときの総コストを求める。
-        let cost_total = if self.last_stage {
-            // 最終面の場合、最終手のブロック投げコストは 1 (A ボタンを押して放置するだけ)とみなし、
-            // 面クリア時のブロック自動消去コストを 0 とする。
-            cost - cost_last_throw + 1
-        } else {
-            // 最終面でない場合、面クリア時のブロック自動消去コストを加算する。
-            cost + COST_CLEAR_ERASE_BLOCK * pos.block_count() as Cost
+    /// 引き継いでいる置換表のエントリ数。
+    pub fn tt_len(&self) -> usize {
+        self.tt.as_ref().map_or(0, TranspositionTable::len)
+    }
+
+    /// 問題を解く。結果は `solve_problem()` と同じコストになる。
+    pub fn solve(&mut self, problem: &Problem) -> Option<(Solution, Cost)> {
+        let (_, moves) = problem.to_position_and_moves();
+
+        let tt = self
+            .tt
+            .take()
+            .filter(|_| self.moves == moves)
+            .and_then(|tt| tt.rebind(problem, self.last_stage));
+        let reused = tt.is_some();
+        let mut tt = tt.unwrap_or_else(|| TranspositionTable::new(problem, self.last_stage));
+        info!(
+            "incremental: tt {} ({} entries)",
+            if reused { "reused" } else { "new" },
+            tt.len()
+        );
+        self.moves = moves;
+
+        // 前回の解が今回も解なら暫定解とする。これより真に良い解だけを探せばよい。
+        let incumbent = self.prev.take().and_then(|prev| {
+            let cost = prev.verify(problem, self.last_stage).ok()?;
+            info!("incremental: previous solution still valid: {cost}");
+            Some((prev, cost))
+        });
+
+        let config = SolverConfig::new().with_incumbent(incumbent);
+        let tables = SearchTables {
+            tt: Some(&mut tt),
+            tablebase: None,
         };
+        let res = solve_with_config(problem, self.last_stage, &config, None, None, None, tables)
+            .into_best();
+        self.tt = Some(tt);
+        self.prev = res.as_ref().map(|(solution, _)| solution.clone());
 
-        // 現局面が解けていると仮定したときの総コストが best_cost 以上ならば枝刈り。
-        if cost_total >= self.best_cost {
-            return;
-        }
+        res
+    }
+}
 
-        let mut has_move = false;
-        for &mv in moves {
-            let Some((pos_nxt, cost_mv, cost_throw)) = pos.do_move(mv) else {
-                continue;
-            };
-            has_move = true;
-            self.cur_solution.push(mv);
-            self.solve(moves, pos_nxt, cost + cost_mv, cost_throw);
-            self.cur_solution.pop().unwrap();
-        }
+/// 終盤の着手列のライブラリ `library` を使って問題を解く。
+/// ライブラリに登録された局面に達したら、それ以降は探索せずに登録された着手列を使う。
+pub fn solve_problem_with_patterns(
+    problem: &Problem,
+    last_stage: bool,
+    library: &PatternLibrary,
+) -> Option<(Solution, Cost)> {
+    let config = SolverConfig::new().with_patterns(Some(library.clone()));
+    solve_problem_with(problem, last_stage, &config).into_best()
+}
 
-        // 現局面が実際に解けていれば最適解を更新(更新されないケースは事前に枝刈りしていることに注意)。
-        if !has_move && pos.block_count() <= 3 {
-            self.best_solution = Some(self.cur_solution.clone());
-            self.best_cost = cost_total;
-            info!("improve: {} {:?}", self.best_cost, self.best_solution);
-        }
+/// 終盤表 `tablebase` を使って問題を解く。終盤表に無い局面は探索中に求めて追記される。
+///
+/// `tablebase` は `Tablebase::new(problem, last_stage, _)` で作ったもの、または同じ引数で読み込んだものでなければならない。
+pub fn solve_problem_with_tablebase(
+    problem: &Problem,
+    last_stage: bool,
+    tablebase: &mut Tablebase,
+) -> Option<(Solution, Cost)> {
+    assert!(tablebase.matches(problem, last_stage));
+
+    let tables = SearchTables {
+        tt: None,
+        tablebase: Some(tablebase),
+    };
+    solve_with_config(
+        problem,
+        last_stage,
+        &SolverConfig::new(),
+        None,
+        None,
+        None,
+        tables,
+    )
+    .into_best()
+}
+
+/// 序盤の着手列 `prefix` を固定し、探索する局面数の上限 `node_limit` 付きで問題を解き、
+/// (解とコスト, 探索を完了したかどうか) を返す。探索を完了しなかった場合、解は最適とは限らない。
+#[cfg(feature = "std")]
+pub(crate) fn solve_with_node_limit(
+    problem: &Problem,
+    last_stage: bool,
+    prefix: &[Move],
+    node_limit: u64,
+) -> (Option<(Solution, Cost)>, bool) {
+    let config = SolverConfig::new()
+        .with_node_limit(node_limit)
+        .with_prefix(Solution::from_moves(prefix).0);
+    let outcome = solve_problem_with(problem, last_stage, &config);
+    let complete = outcome.is_complete();
+
+    (outcome.into_best(), complete)
+}
+
+/// 局面 `pos` (着手 `moves` の問題のもの) での最善の次の手と、その手以降面が終わるまでのコストを返す。
+///
+/// 問題を最初から解き直さずに、プレイ中の局面から面の残りを探索する。対話的なツールでヒントを出すためのもの。
+/// 探索する局面数は `budget` までで、達したらそれまでの最善の解の初手を返す (最善とは限らない)。
+/// 解がない、または面が終了していれば `None` を返す。
+pub fn best_move(
+    pos: &Position,
+    moves: &[Move],
+    last_stage: bool,
+    budget: u64,
+) -> Option<(Move, Cost)> {
+    let (res, _) =
+        solve_position_with_node_limit(moves, pos.clone(), last_stage, budget, None, None);
+
+    res.and_then(|(line, cost)| line.first().map(|&mv| (mv, cost)))
+}
+
+/// 局面 `pos` から、探索する局面数の上限 `node_limit` 付きで面の残りを解き、
+/// (残りの着手列とそのコスト, 探索を完了したかどうか) を返す。
+///
+/// `incumbent` は同じ局面からの既知の解で、これより真に良い解だけを探す (見つからなければこれを返す)。
+/// 置換表 `tt` を渡すと、その下界で枝刈りし、探索中に得た下界を追記する。
+/// 上限を増やしながら同じ局面を探索し直す場合に、前回までの結果を活かすためのもの。
+pub(crate) fn solve_position_with_node_limit(
+    moves: &[Move],
+    pos: Position,
+    last_stage: bool,
+    node_limit: u64,
+    incumbent: Option<(Vec<Move>, Cost)>,
+    mut tt: Option<&mut TranspositionTable>,
+) -> (Option<(Vec<Move>, Cost)>, bool) {
+    let mut solver = Solver::new(last_stage);
+    solver.node_limit = node_limit;
+    if let Some((solution, cost)) = incumbent {
+        solver.best_solution = Some(solution);
+        solver.best_cost = cost;
+    }
+    solver.tt = tt.as_deref_mut().map(core::mem::take);
+    solver.solve(moves, pos, 0, 0);
+    if let Some(tt) = tt {
+        *tt = solver.tt.take().unwrap();
+    }
+
+    let res = solver
+        .best_solution
+        .map(|solution| (solution, solver.best_cost));
+
+    (res, !solver.aborted)
+}
+
+/// `greedy_solution()` で訪れる局面数の上限。
+const GREEDY_NODE_LIMIT: u64 = 1 << 12;
+
+/// 局面 `pos` (目的関数の値 `cost`) から貪欲法で面を終え、(残りの着手列, 総コスト) を返す。
+/// 面クリアできなければ `None` を返す。
+///
+/// 各局面で着手後のブロック数が少ない (同数なら目的関数の値が小さい) 着手から順に深さ優先で試し、
+/// 面クリアできたら、それより総コストの小さい解だけを探し続ける。
+/// 訪れる局面数は `GREEDY_NODE_LIMIT` までなので、厳密な探索に比べて十分速い。
+fn greedy_solution(
+    moves: &[Move],
+    pos: &Position,
+    cost: Cost,
+    last_stage: bool,
+    objective: Objective,
+) -> Option<(Vec<Move>, Cost)> {
+    struct Greedy<'a> {
+        moves: &'a [Move],
+        last_stage: bool,
+        objective: Objective,
+        node_count: u64,
+        line: Vec<Move>,
+        best: Option<(Vec<Move>, Cost)>,
+    }
+
+    impl Greedy<'_> {
+        fn dfs(&mut self, pos: &Position, cost: Cost, cost_last_throw: Cost) {
+            if self.node_count >= GREEDY_NODE_LIMIT {
+                return;
+            }
+            self.node_count += 1;
+
+            let cost_best = self.best.as_ref().map_or(Cost::MAX, |&(_, cost)| cost);
+            if cost >= cost_best {
+                return;
+            }
+
+            if pos.is_stuck(self.moves) {
+                let cost_total = if self.last_stage {
+                    cost - cost_last_throw + 1
+                } else {
+                    cost + self.objective.clear_cost(pos.block_count())
+                };
+                if pos.is_cleared() && !self.line.is_empty() && cost_total < cost_best {
+                    self.best = Some((self.line.clone(), cost_total));
+                }
+                return;
+            }
+            if pos.move_remain() == 0 || pos.min_moves_to_solve() > usize::from(pos.move_remain()) {
+                return;
+            }
+
+            let mut nexts: Vec<_> = pos.successors(self.moves).collect();
+            nexts.sort_by_key(|(_, pos_nxt, cost_mv, cost_throw)| {
+                (
+                    pos_nxt.block_count(),
+                    self.objective.move_cost(*cost_mv, *cost_throw),
+                )
+            });
+            for (mv, pos_nxt, cost_mv, cost_throw) in nexts {
+                self.line.push(mv);
+                self.dfs(
+                    &pos_nxt,
+                    cost + self.objective.move_cost(cost_mv, cost_throw),
+                    self.objective.throw_cost(cost_throw),
+                );
+                self.line.pop();
+            }
+        }
+    }
+
+    let mut greedy = Greedy {
+        moves,
+        last_stage,
+        objective,
+        node_count: 0,
+        line: vec![],
+        best: None,
+    };
+    greedy.dfs(pos, cost, 0);
+
+    greedy.best
+}
+
+/// 支配による枝刈りの表に記録する局面数の上限 (メモリ使用量を抑えるため)。
+const DOMINANCE_CAPACITY: usize = 1 << 20;
+
+/// 支配による枝刈りの表の、1 局面あたりのメモリ使用量の概算。
+const DOMINANCE_ENTRY_BYTES: usize = 96;
+
+/// 支配による枝刈りの表。
+///
+/// ブロック領域、自機の位置、保持ブロックが同じ局面どうしでは、コストが小さく、残り手数が多く、手数が少ない方が
+/// 少なくとも同じくらいよい解を持つ。そこで、そうした局面ごとに訪れた (コスト, 残り手数, 手数) のうち
+/// 互いに支配しないものを記録し、記録済みのものに支配される局面を枝刈りする。
+/// 記録済みの局面は探索済みか現在の経路上にあるので、枝刈りしても最適解は失われない。
+///
+/// ただし入れ替え着手を許すとブロックが減らない着手があり、経路上で同じ局面 (残り手数を除く) が繰り返されうる。
+/// このとき探索中の祖先による枝刈りは、間の局面の部分木の結果を祖先の探索の途中経過に依存させ、
+/// 置換表に記録する下界を誤らせる。そこで `Solver` では入れ替え着手を許す場合は使わない。
+/// 置換表を使わない `BothStagesSolver` では問題ない。
+#[derive(Debug)]
+struct DominanceTable {
+    entries: BTreeMap<u128, Vec<(Cost, u8, usize)>>,
+    len: usize,
+    capacity: usize,
+}
+
+impl DominanceTable {
+    /// 記録する局面数の上限を `capacity` とする。
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            len: 0,
+            capacity,
+        }
+    }
+
+    /// 手数 `depth`、コスト `cost` の局面 `pos` が記録済みの局面に支配されていれば偽を返す。
+    /// そうでなければ記録して (上限に達していなければ) 真を返す。
+    fn check_and_insert(&mut self, pos: &Position, cost: Cost, depth: usize) -> bool {
+        let move_remain = pos.move_remain();
+        let dominates = |&(cost_a, remain_a, depth_a): &(Cost, u8, usize),
+                         (cost_b, remain_b, depth_b): (Cost, u8, usize)| {
+            cost_a <= cost_b && remain_a >= remain_b && depth_a <= depth_b
+        };
+        let state = (cost, move_remain, depth);
+
+        let key = pos.packed_key_without_move_remain();
+        if let Some(list) = self.entries.get_mut(&key) {
+            if list.iter().any(|entry| dominates(entry, state)) {
+                return false;
+            }
+            let len_prev = list.len();
+            list.retain(|&entry| !dominates(&state, entry));
+            self.len -= len_prev - list.len();
+            if self.len < self.capacity {
+                list.push(state);
+                self.len += 1;
+            }
+        } else if self.len < self.capacity {
+            self.entries.insert(key, vec![state]);
+            self.len += 1;
+        }
+
+        true
+    }
+}
+
+/// `solve_problem_both_stages()` の探索。`Solver` の最終面フラグの両方の場合を同時に扱う簡易版。
+#[derive(Debug)]
+struct BothStagesSolver {
+    /// 最終面フラグごとの (暫定解, 総コスト)。添字は `usize::from(last_stage)`。
+    best: [(Option<Vec<Move>>, Cost); 2],
+    cur_solution: Vec<Move>,
+    min_throw_cost: Cost,
+    dominance: DominanceTable,
+}
+
+impl BothStagesSolver {
+    fn solve(&mut self, moves: &[Move], pos: Position, cost: Cost, cost_last_throw: Cost) {
+        // 現局面が解けていると仮定したときの総コスト (`Solver::solve()` と同じ)。
+        let cost_totals = [
+            cost + Objective::Frames.clear_cost(pos.block_count()),
+            cost - cost_last_throw + 1,
+        ];
+        if self.cannot_improve(cost_totals) {
+            return;
+        }
+
+        if pos.is_stuck(moves) {
+            if pos.is_cleared() {
+                for (best, cost_total) in self.best.iter_mut().zip(cost_totals) {
+                    if cost_total < best.1 {
+                        *best = (Some(self.cur_solution.clone()), cost_total);
+                    }
+                }
+            }
+            return;
+        }
+
+        if pos.move_remain() == 0 {
+            return;
+        }
+
+        let move_count = pos.min_moves_to_solve().max(1);
+        if move_count > usize::from(pos.move_remain()) {
+            return;
+        }
+        let cost_lowers = [
+            cost + self.min_throw_cost * move_count as Cost,
+            cost + self.min_throw_cost * (move_count - 1) as Cost + 1,
+        ];
+        if self.cannot_improve(cost_lowers) {
+            return;
+        }
+
+        if !self
+            .dominance
+            .check_and_insert(&pos, cost, self.cur_solution.len())
+        {
+            return;
+        }
+
+        for (mv, pos_nxt, cost_mv, cost_throw) in pos.successors(moves) {
+            self.cur_solution.push(mv);
+            self.solve(moves, pos_nxt, cost + cost_mv, cost_throw);
+            self.cur_solution.pop().unwrap();
+        }
+    }
+
+    /// 最終面フラグごとの総コストの下界 `costs` が、どちらも暫定解のコスト以上かどうか。
+    fn cannot_improve(&self, costs: [Cost; 2]) -> bool {
+        self.best
+            .iter()
+            .zip(costs)
+            .all(|(&(_, cost_best), cost)| cost >= cost_best)
+    }
+}
+
+#[derive(Debug)]
+struct Solver<'a> {
+    best_solution: Option<Vec<Move>>,
+    best_cost: Cost,
+    cur_solution: Vec<Move>,
+    last_stage: bool,
+    node_count: u64,
+    node_limit: u64,
+    aborted: bool,
+    constraints: Vec<Constraint>,
+    hero_row_init: MoveSrc,
+    objective: Objective,
+    tie_break: Option<TieBreak>,
+    tt: Option<TranspositionTable>,
+    /// 複数の探索で共有する置換表。`tt` がなければこちらを使う。
+    #[cfg(feature = "std")]
+    shared_tt: Option<alloc::sync::Arc<crate::parallel::TtShards>>,
+    patterns: BTreeMap<u128, PatternEntry>,
+    tablebase: Option<Tablebase>,
+    /// 着手のブロック投げコストの目的関数での最小値 (最初の探索時に求める)。
+    min_throw_cost: Option<Cost>,
+    /// best_cost による枝刈りで切り捨てた部分木の総コストの下界の最小値。
+    cost_cut_min: Cost,
+    max_depth: usize,
+    lower_bound: bool,
+    move_ordering: MoveOrdering,
+    /// 支配による枝刈りの表。制約や第 2 の基準がある場合は使わない (経路に依存するため)。
+    dominance: Option<DominanceTable>,
+    /// `MoveOrdering::History` の場合の履歴表。
+    history: Option<HistoryTable>,
+    /// 下界による枝刈りで下界に掛ける重み (1 以上)。
+    weight: f64,
+    #[cfg(feature = "std")]
+    deadline: Option<std::time::Instant>,
+    cancel: Option<CancelToken>,
+    progress: Option<ProgressHook<'a>>,
+    improve: Option<ImproveHook<'a>>,
+    checkpoint: Option<CheckpointHook<'a>>,
+    /// スナップショットから再開する場合の、探索中だった局面への経路。経路から外れたら `None` にする。
+    resume: Option<Vec<Move>>,
+    /// 探索を打ち切ったときに探索中だった局面への経路。
+    abort_path: Option<Vec<Move>>,
+    stats: SearchStats,
+    /// 探索した木を記録する場合の記録先。
+    search_tree: Option<SearchTree>,
+    /// 探索前に貪欲法で求めた解とその総コスト。探索でこれ以下の解が見つからなければ (打ち切った場合) これを返す。
+    warm_start: Option<(Vec<Move>, Cost)>,
+    #[cfg(feature = "std")]
+    started: std::time::Instant,
+}
+
+impl Solver<'_> {
+    fn new(last_stage: bool) -> Self {
+        Self {
+            best_solution: None,
+            best_cost: Cost::MAX,
+            cur_solution: vec![],
+            last_stage,
+            node_count: 0,
+            node_limit: u64::MAX,
+            aborted: false,
+            constraints: vec![],
+            hero_row_init: MOVE_SRC_ROW_11,
+            objective: Objective::Frames,
+            tie_break: None,
+            tt: None,
+            #[cfg(feature = "std")]
+            shared_tt: None,
+            patterns: BTreeMap::new(),
+            tablebase: None,
+            min_throw_cost: None,
+            cost_cut_min: Cost::MAX,
+            max_depth: usize::MAX,
+            lower_bound: true,
+            move_ordering: MoveOrdering::Fixed,
+            history: None,
+            dominance: None,
+            weight: 1.0,
+            #[cfg(feature = "std")]
+            deadline: None,
+            cancel: None,
+            progress: None,
+            improve: None,
+            checkpoint: None,
+            resume: None,
+            abort_path: None,
+            stats: SearchStats::default(),
+            search_tree: None,
+            warm_start: None,
+            #[cfg(feature = "std")]
+            started: std::time::Instant::now(),
+        }
+    }
+
+    fn solve(&mut self, moves: &[Move], pos: Position, cost: Cost, cost_last_throw: Cost) {
+        let Some(tree) = &mut self.search_tree else {
+            self.solve_node(moves, pos, cost, cost_last_throw);
+            return;
+        };
+
+        // 探索した木を記録する場合、記録する手数の範囲の局面なら結果を記録する。
+        let src = self.cur_solution.last().map(|mv| mv.src());
+        let node = tree.enter(src, self.cur_solution.len(), cost);
+        let outcome = self.solve_node(moves, pos, cost, cost_last_throw);
+        if let Some(node) = node {
+            self.search_tree.as_mut().unwrap().leave(node, outcome);
+        }
+    }
+
+    /// 局面 `pos` を訪れ、その結果を返す。
+    fn solve_node(
+        &mut self,
+        moves: &[Move],
+        pos: Position,
+        cost: Cost,
+        cost_last_throw: Cost,
+    ) -> NodeOutcome {
+        // 探索局面数または探索時間が上限に達するか、打ち切りを要求されたら打ち切る。
+        if self.aborted {
+            return NodeOutcome::Aborted;
+        }
+        if self.node_count >= self.node_limit || self.should_stop() {
+            self.aborted = true;
+            self.abort_path = Some(self.cur_solution.clone());
+            return NodeOutcome::Aborted;
+        }
+        self.node_count += 1;
+        if self
+            .checkpoint
+            .as_ref()
+            .is_some_and(|checkpoint| self.node_count % checkpoint.interval == 0)
+        {
+            // この局面より前の部分木は探索済みなので、この局面への経路から再開できる。
+            self.save_checkpoint(Some(self.cur_solution.clone()));
+        }
+        self.stats.max_depth = self.stats.max_depth.max(self.cur_solution.len());
+        if self.progress.is_some() && self.node_count % PROGRESS_INTERVAL == 0 {
+            self.report_progress();
+        }
+
+        // 現局面が解けていると仮定したときの総コストを求める。
+        let cost_total = if self.last_stage {
+            // 最終面の場合、最終手のブロック投げコストは 1 (A ボタンを押して放置するだけ)とみなし、
+            // 面クリア時のブロック自動消去コストを 0 とする。
+            cost - cost_last_throw + 1
+        } else {
+            // 最終面でない場合、面クリア時のブロック自動消去コストを加算する。
+            cost + self.objective.clear_cost(pos.block_count())
+        };
+
+        // 現局面が解けていると仮定したときの総コストが制限を超えるならば枝刈り。
+        if !self
+            .constraints
+            .iter()
+            .all(|constraint| constraint.allows_cost_total(cost_total))
+        {
+            return NodeOutcome::Pruned(PruneReason::Constraint);
+        }
+
+        // 現局面が解けていると仮定したときの総コストが best_cost 以上ならば枝刈り。
+        // 第 2 の基準がある場合、総コストが best_cost に等しければ第 2 の基準で暫定解より良くなりうるかで判定する。
+        if cost_total > self.best_cost || cost_total == self.best_cost && !self.tie_can_improve() {
+            self.stats.prune_cost += 1;
+            self.cut(cost_total);
+            return NodeOutcome::Pruned(PruneReason::Cost);
+        }
+
+        // 面が終了しているなら、実際に解けていれば最適解を更新(更新されないケースは事前に枝刈りしていることに注意)。
+        if pos.is_stuck(moves) {
+            if pos.is_cleared() && self.satisfies_constraints() {
+                self.best_solution = Some(self.cur_solution.clone());
+                self.best_cost = cost_total;
+                self.on_improve();
+                return NodeOutcome::Improved;
+            }
+            return NodeOutcome::Ended;
+        }
+
+        // 入れ替え着手が許されている場合、面が終了しないまま手数が尽きることがある。
+        if pos.move_remain() == 0 {
+            return NodeOutcome::Ended;
+        }
+
+        if self.cur_solution.len() >= self.max_depth {
+            return NodeOutcome::Pruned(PruneReason::MaxDepth);
+        }
+
+        // 面を解くのに必要な着手数の下界から総コストの下界を求め、best_cost 以上ならば枝刈り。
+        // 着手のコストはブロック投げの最小コスト以上 (最終面の最終手は 1 とみなす)。
+        // 手数の上限がある場合、上限までに解けない局面も枝刈り。
+        let move_count = pos.min_moves_to_solve().max(1);
+        if self.lower_bound
+            && (move_count > usize::from(pos.move_remain())
+                || self.cur_solution.len() + move_count > self.max_depth)
+        {
+            self.stats.prune_lower_bound += 1;
+            return NodeOutcome::Pruned(PruneReason::LowerBound);
+        }
+        let objective = self.objective;
+        let min_throw_cost = *self.min_throw_cost.get_or_insert_with(|| {
+            moves
+                .iter()
+                .map(|mv| objective.throw_cost(calc_min_move_cost(mv.src())))
+                .min()
+                .unwrap_or(0)
+        });
+        let cost_lower = if self.last_stage {
+            cost + min_throw_cost * (move_count - 1) as Cost + 1
+        } else {
+            cost + min_throw_cost * move_count as Cost
+        };
+        // 重み付きの場合、下界を weight 倍して枝刈りする (最適解の weight 倍以内の解を保証する)。
+        let cost_lower_pruning = if self.weight == 1.0 {
+            cost_lower
+        } else {
+            (f64::from(cost_lower) * self.weight) as Cost
+        };
+        if self.lower_bound
+            && (cost_lower_pruning > self.best_cost
+                || cost_lower_pruning == self.best_cost && self.tie_break.is_none())
+        {
+            self.stats.prune_lower_bound += 1;
+            self.cut(cost_lower);
+            return NodeOutcome::Pruned(PruneReason::LowerBound);
+        }
+
+        // ライブラリに登録された局面なら、登録された着手列で面を終える。
+        if let Some(entry) = self.patterns.get(&pos.packed_key()) {
+            let cost_total = cost + entry.cost();
+            if cost_total < self.best_cost {
+                let mut solution = self.cur_solution.clone();
+                solution.extend(
+                    entry
+                        .srcs()
+                        .iter()
+                        .map(|&src| *moves.iter().find(|mv| mv.src() == src).unwrap()),
+                );
+                self.best_solution = Some(solution);
+                self.best_cost = cost_total;
+                self.on_improve();
+                return NodeOutcome::Improved;
+            }
+            self.cut(cost_total);
+            return NodeOutcome::Pruned(PruneReason::Cost);
+        }
+
+        // ブロック数が終盤表の上限以下なら、終盤表の最善手で面を終える。
+        if let Some(tablebase) = &mut self.tablebase {
+            if pos.block_count() <= tablebase.max_blocks() {
+                let cost_rest = tablebase.probe(moves, &pos);
+                if cost_rest != Cost::MAX && cost + cost_rest < self.best_cost {
+                    let mut solution = self.cur_solution.clone();
+                    solution.extend(tablebase.line(moves, &pos));
+                    self.best_solution = Some(solution);
+                    self.best_cost = cost + cost_rest;
+                    self.on_improve();
+                    return NodeOutcome::Improved;
+                }
+                if cost_rest == Cost::MAX {
+                    return NodeOutcome::Ended;
+                }
+                self.cut(cost + cost_rest);
+                return NodeOutcome::Pruned(PruneReason::Cost);
+            }
+        }
+
+        // 既に訪れた局面に支配されていれば枝刈り。
+        if let Some(dominance) = &mut self.dominance {
+            if !dominance.check_and_insert(&pos, cost, self.cur_solution.len()) {
+                self.stats.prune_dominance += 1;
+                return NodeOutcome::Pruned(PruneReason::Dominance);
+            }
+        }
+
+        // 置換表の下界で枝刈り。
+        if let Some(bound) = self.tt_get(&pos) {
+            self.stats.tt_hits += 1;
+            if cost.saturating_add(bound) >= self.best_cost {
+                self.stats.prune_tt += 1;
+                self.cut(cost.saturating_add(bound));
+                return NodeOutcome::Pruned(PruneReason::TranspositionTable);
+            }
+        }
+
+        let prev = self.cur_solution.last().map(|mv| mv.src());
+
+        let mut nexts: Vec<_> = pos
+            .successors(moves)
+            .filter(|&(mv, _, cost_mv, _)| {
+                self.constraints.iter().all(|constraint| {
+                    constraint.allows_next(prev, mv.src()) && constraint.allows_move_cost(cost_mv)
+                })
+            })
+            .collect();
+        self.move_ordering.sort(
+            self.objective,
+            self.history.as_ref(),
+            self.cur_solution.len(),
+            &mut nexts,
+        );
+
+        // 再開時は、探索中だった局面への経路上の子より前の子 (探索済み) を飛ばす。
+        let skip = self
+            .resume
+            .as_ref()
+            .and_then(|path| path.get(self.cur_solution.len()))
+            .and_then(|mv_resume| nexts.iter().position(|(mv, ..)| mv == mv_resume));
+        if skip.is_none() {
+            self.resume = None;
+        }
+
+        for (mv, pos_nxt, cost_mv, cost_throw) in nexts.into_iter().skip(skip.unwrap_or(0)) {
+            self.cur_solution.push(mv);
+            let cost_nxt = cost + self.objective.move_cost(cost_mv, cost_throw);
+            self.solve(
+                moves,
+                pos_nxt,
+                cost_nxt,
+                self.objective.throw_cost(cost_throw),
+            );
+            self.cur_solution.pop().unwrap();
+            self.resume = None;
+        }
+
+        // 部分木の探索を完了したら、この局面以降のコストの下界を記録する。
+        // 総コストが探索後の best_cost 未満になる解はこの局面以降にないので、(best_cost - cost) が下界となる。
+        // 最終面では最終手のブロック投げコストを除くので、best_cost が cost を下回ることがある (下界は 0)。
+        if !self.aborted {
+            let bound = if self.best_cost == Cost::MAX {
+                Cost::MAX
+            } else {
+                self.best_cost.saturating_sub(cost)
+            };
+            self.tt_update(&pos, bound);
+        }
+
+        NodeOutcome::Expanded
+    }
+
+    /// 局面 `pos` (目的関数の値 `cost`) から貪欲法で解を求め、見つかればその総コストで枝刈りを始める。
+    /// 探索の最初の局面から枝刈りを効かせるためのもの。
+    ///
+    /// 暫定解のコストは貪欲法の解の総コスト + 1 とし、それ以下の解だけを探す。
+    /// 貪欲法の解そのものは暫定解としないので、返す解は貪欲法を使わない場合と同じ (最初に見つかる最適解) になる。
+    /// ただし途中の解としては報告し (`on_improve()`)、スナップショットにも暫定解として保存する。
+    fn warm_start(&mut self, moves: &[Move], pos: &Position, cost: Cost) {
+        let Some((line, cost_total)) =
+            greedy_solution(moves, pos, cost, self.last_stage, self.objective)
+        else {
+            return;
+        };
+        if self.cur_solution.len() + line.len() > self.max_depth || cost_total >= self.best_cost {
+            return;
+        }
+        info!("warm start: {cost_total}");
+
+        let mut solution = self.cur_solution.clone();
+        solution.extend(line);
+        self.best_cost = cost_total + 1;
+        self.warm_start = Some((solution, cost_total));
+        self.on_improve();
+    }
+
+    /// 共有の置換表を使うかどうか。
+    fn uses_shared_tt(&self) -> bool {
+        #[cfg(feature = "std")]
+        return self.shared_tt.is_some();
+        #[cfg(not(feature = "std"))]
+        return false;
+    }
+
+    /// 置換表 (探索ごとのものか共有のもの) から局面 `pos` 以降のコストの下界を引く。
+    fn tt_get(&mut self, pos: &Position) -> Option<Cost> {
+        if let Some(tt) = &mut self.tt {
+            return tt.get(pos);
+        }
+        #[cfg(feature = "std")]
+        if let Some(shared_tt) = &self.shared_tt {
+            return shared_tt.get(pos);
+        }
+        None
+    }
+
+    /// 置換表 (探索ごとのものか共有のもの) に局面 `pos` 以降のコストの下界 `bound` を記録する。
+    fn tt_update(&mut self, pos: &Position, bound: Cost) {
+        if let Some(tt) = &mut self.tt {
+            tt.update(pos, bound);
+        }
+        #[cfg(feature = "std")]
+        if let Some(shared_tt) = &self.shared_tt {
+            shared_tt.update(pos, bound);
+        }
+    }
+
+    /// 見つかった最善の解とその総コスト。探索で見つかっていなければ貪欲法の解。
+    fn incumbent(&self) -> Option<(&[Move], Cost)> {
+        match &self.best_solution {
+            Some(solution) => Some((solution, self.best_cost)),
+            None => self
+                .warm_start
+                .as_ref()
+                .map(|(solution, cost)| (solution.as_slice(), *cost)),
+        }
+    }
+
+    /// 見つかった最善の解とその総コストを取り出す。探索で見つからなければ貪欲法の解を返す。
+    fn take_best(&mut self) -> Option<(Vec<Move>, Cost)> {
+        self.best_solution
+            .take()
+            .map(|solution| (solution, self.best_cost))
+            .or(self.warm_start.take())
+    }
+
+    /// 探索時間の上限を過ぎたか、打ち切りを要求されたかどうか。
+    /// 時刻の取得などは重いので、一定の局面数ごとにのみ調べる。
+    fn should_stop(&self) -> bool {
+        if self.node_count % 1024 != 0 {
+            return false;
+        }
+
+        #[cfg(feature = "std")]
+        if self
+            .deadline
+            .is_some_and(|deadline| std::time::Instant::now() >= deadline)
+        {
+            return true;
+        }
+
+        self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+
+    /// 暫定解 (貪欲法の解を含む) を更新したときに呼ぶ。
+    ///
+    /// 貪欲法の解は、着手順を変えないよう `MoveOrdering::History` の履歴には記録しない。
+    fn on_improve(&mut self) {
+        let (solution, cost) = self.incumbent().unwrap();
+        info!("improve: {cost} {solution:?}");
+        let solution = Solution::from_moves(solution);
+
+        if let (Some(history), Some(_)) = (&self.history, &self.best_solution) {
+            history.record(solution.moves().iter().copied());
+        }
+
+        if let Some(improve) = &mut self.improve {
+            (improve.0)(&solution, cost);
+        }
+    }
+
+    fn report_progress(&mut self) {
+        let best_cost = self.incumbent().map(|(_, cost)| cost);
+        let Some(progress) = &mut self.progress else {
+            return;
+        };
+        (progress.0)(&SearchProgress::new(
+            self.node_count,
+            self.cur_solution.len(),
+            best_cost,
+            #[cfg(feature = "std")]
+            self.started.elapsed(),
+        ));
+    }
+
+    /// 探索中の局面への経路 `path` (完了していれば `None`) のスナップショットを作って保存する。
+    fn save_checkpoint(&mut self, path: Option<Vec<Move>>) {
+        let best = self
+            .incumbent()
+            .map(|(solution, cost)| (Solution::from_moves(solution), cost));
+        let path = path.map(|path| path.iter().map(|mv| mv.src()).collect());
+        let checkpoint = self.checkpoint.as_mut().unwrap();
+        let snapshot = checkpoint.base.with_state(self.node_count, best, path);
+        (checkpoint.save)(&snapshot);
+    }
+
+    /// これまでの探索の統計。
+    fn stats(&self) -> SearchStats {
+        SearchStats {
+            node_count: self.node_count,
+            #[cfg(feature = "std")]
+            elapsed: self.started.elapsed(),
+            ..self.stats
+        }
+    }
+
+    /// best_cost による枝刈りを記録する。`cost_total` は枝刈りした局面以降の総コストの下界。
+    fn cut(&mut self, cost_total: Cost) {
+        self.cost_cut_min = self.cost_cut_min.min(cost_total);
+    }
+
+    /// 現在の着手列かそれに着手を追加した列が、第 2 の基準で暫定解より良くなりうるかどうか。
+    /// 第 2 の基準がなければ偽。
+    ///
+    /// 面が終了した局面でこれが真なら、現在の着手列自身が暫定解より良い。
+    fn tie_can_improve(&self) -> bool {
+        let (Some(tie_break), Some(best)) = (self.tie_break, &self.best_solution) else {
+            return false;
+        };
+        let srcs: Vec<_> = self.cur_solution.iter().map(|mv| mv.src()).collect();
+        let srcs_best: Vec<_> = best.iter().map(|mv| mv.src()).collect();
+
+        tie_break.can_improve(self.hero_row_init, &srcs, &srcs_best)
+    }
+
+    fn satisfies_constraints(&self) -> bool {
+        if self.constraints.is_empty() {
+            return true;
+        }
+
+        let srcs: Vec<_> = self.cur_solution.iter().map(|mv| mv.src()).collect();
+        self.constraints
+            .iter()
+            .all(|constraint| constraint.is_satisfied(self.hero_row_init, &srcs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn parse_problem(s: impl AsRef<str>) -> Problem {
+        s.as_ref().parse().unwrap()
+    }
+
+    fn parse_solution(s: impl AsRef<str>) -> Solution {
+        s.as_ref().parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_solution_error() {
+        assert_eq!(
+            "10 9\n10  12 11\n".parse::<Solution>(),
+            Err(ParseError::new(2, ParseErrorKind::InvalidMoveSrc(12)).with_col(5))
+        );
+    }
+
+    #[test]
+    fn test_solve_continuation() {
+        let problem = parse_problem(indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "});
+
+        let (solution_opt, cost_opt) = solve_problem(&problem, false).unwrap();
+
+        // 空の prefix なら最適解そのもの。
+        let cont = solve_continuation(
+            &problem,
+            &Solution::new(vec![]),
+            false,
+            &SolverConfig::new(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(cont.solution(), &solution_opt);
+        assert_eq!(cont.cost_prefix(), 0);
+        assert_eq!(cont.cost_total(), cost_opt);
+
+        // 最適解の最初の 1 手を prefix にしても最適解が得られる。
+        let prefix = Solution::new(solution_opt.moves()[..1].to_vec());
+        let cont = solve_continuation(&problem, &prefix, false, &SolverConfig::new())
+            .unwrap()
+            .unwrap();
+        assert_eq!(cont.solution(), &solution_opt);
+        assert_eq!(cont.suffix(), &solution_opt.moves()[1..]);
+        assert!(cont.cost_prefix() > 0);
+        assert_eq!(cont.cost_total(), cost_opt);
+        assert_eq!(
+            cont.solution().verify(&problem, false).unwrap(),
+            cont.cost_total()
+        );
+
+        // 不正な prefix はエラー。
+        let prefix = parse_solution("0");
+        let err = solve_continuation(&problem, &prefix, false, &SolverConfig::new()).unwrap_err();
+        assert!(matches!(
+            err,
+            VerifyError::IllegalMove {
+                index: 0,
+                dst: None,
+                ..
+            }
+        ));
+        assert_eq!(err.render(false), None);
+    }
+
+    #[test]
+    fn test_best_move() {
+        let problem: Problem = include_str!("../problem/01.in").parse().unwrap();
+        let (solution, cost_opt) = solve_problem(&problem, false).unwrap();
+        let (mut pos, moves) = problem.to_position_and_moves();
+
+        // 最適解に沿って進めると、各局面での最善の次の手のコストは残りのコストに一致する。
+        let mut cost_remain = cost_opt;
+        for &src in solution.moves() {
+            let (mv, cost) = best_move(&pos, &moves, false, u64::MAX).unwrap();
+            assert_eq!(cost, cost_remain);
+            assert!(pos.do_move(mv).is_some());
+            let (pos_nxt, cost_mv, _) = pos.do_move(problem.move_for_src(src).unwrap()).unwrap();
+            pos = pos_nxt;
+            cost_remain -= cost_mv;
+        }
+
+        // 面が終了していれば次の手はない。
+        assert_eq!(best_move(&pos, &moves, false, u64::MAX), None);
+
+        // 予算が少なくても、見つけた解の初手は合法手。
+        let (pos, _) = problem.to_position_and_moves();
+        if let Some((mv, _)) = best_move(&pos, &moves, false, 10) {
+            assert!(pos.do_move(mv).is_some());
+        }
+    }
+
+    #[test]
+    fn test_solve_problem_with() {
+        let problem = parse_problem(include_str!("../problem/10.in"));
+        let (solution_opt, cost_opt) = solve_problem(&problem, false).unwrap();
+
+        for config in [
+            SolverConfig::new(),
+            SolverConfig::new().with_lower_bound(false),
+            SolverConfig::new().with_dominance(false),
+            SolverConfig::new().with_memory_limit(Some(1 << 12)),
+            SolverConfig::new().with_memory_limit(Some(1 << 24)),
+            SolverConfig::new().with_threads(2),
+            SolverConfig::new()
+                .with_memory_limit(Some(1 << 16))
+                .with_threads(2),
+            SolverConfig::new().with_move_ordering(MoveOrdering::MostErased),
+            SolverConfig::new()
+                .with_move_ordering(MoveOrdering::MostErased)
+                .with_threads(2),
+            SolverConfig::new().with_move_ordering(MoveOrdering::History),
+            SolverConfig::new()
+                .with_move_ordering(MoveOrdering::History)
+                .with_threads(2),
+            SolverConfig::new().with_move_ordering(MoveOrdering::Random(1)),
+            SolverConfig::new()
+                .with_move_ordering(MoveOrdering::Random(2))
+                .with_threads(2),
+        ] {
+            let outcome = solve_problem_with(&problem, false, &config);
+            assert!(outcome.is_complete());
+            assert_eq!(outcome.best().map(|(_, cost)| *cost), Some(cost_opt));
+        }
+
+        // 局面数の上限で打ち切った場合、解は最適とは限らないが正しい。
+        let outcome =
+            solve_problem_with(&problem, false, &SolverConfig::new().with_node_limit(100));
+        assert!(!outcome.is_complete());
+        if let Some((solution, cost)) = outcome.best() {
+            assert_eq!(solution.verify(&problem, false), Ok(*cost));
+        }
+
+        // 手数の上限より長い解は探さない。
+        let max_depth = solution_opt.moves().len() - 1;
+        let outcome = solve_problem_with(
+            &problem,
+            false,
+            &SolverConfig::new().with_max_depth(max_depth),
+        );
+        assert!(outcome.is_complete());
+        if let Some((solution, cost)) = outcome.best() {
+            assert!(solution.moves().len() <= max_depth);
+            assert!(*cost >= cost_opt);
+        }
+
+        // 手数の上限ごとに、上限以下の解のうち最適なものを求める (下界による枝刈りの有無で結果は同じ)。
+        for max_depth in 0..=solution_opt.moves().len() {
+            let res = solve_problem_with_max_depth(&problem, false, max_depth);
+            let config = SolverConfig::new()
+                .with_max_depth(max_depth)
+                .with_lower_bound(false);
+            assert_eq!(
+                res.as_ref().map(|&(_, cost)| cost),
+                solve_problem_with(&problem, false, &config)
+                    .best()
+                    .map(|&(_, cost)| cost)
+            );
+            if let Some((solution, cost)) = &res {
+                assert!(solution.moves().len() <= max_depth);
+                assert_eq!(solution.verify(&problem, false), Ok(*cost));
+            }
+        }
+        assert_eq!(
+            solve_problem_with_max_depth(&problem, false, solution_opt.moves().len())
+                .map(|(_, cost)| cost),
+            Some(cost_opt)
+        );
+
+        // 先頭の着手列を固定すると、それに続く最適な残りを探す (`solve_continuation()` と同じ)。
+        let (pos, moves) = problem.to_position_and_moves();
+        for src in moves
+            .iter()
+            .filter(|&&mv| pos.do_move(mv).is_some())
+            .map(|mv| mv.src())
+        {
+            let prefix = Solution::new(vec![src]);
+            let expected = solve_continuation(&problem, &prefix, false, &SolverConfig::new())
+                .unwrap()
+                .map(|cont| cont.cost_total());
+            for threads in [1, 2] {
+                let config = SolverConfig::new()
+                    .with_prefix(vec![src])
+                    .with_threads(threads);
+                let outcome = solve_problem_with(&problem, false, &config);
+                assert!(outcome.is_complete());
+                assert_eq!(outcome.best().map(|(_, cost)| *cost), expected);
+                if let Some((solution, cost)) = outcome.best() {
+                    assert_eq!(solution.moves()[0], src);
+                    assert_eq!(solution.verify(&problem, false), Ok(*cost));
+                }
+            }
+        }
+
+        // 不正な着手列なら解はない。
+        let config = SolverConfig::new().with_prefix(vec![MoveSrc::Row11; 100]);
+        assert_eq!(solve_problem_with(&problem, false, &config).best(), None);
+
+        // 既知の解より良い解だけを探す。最適解を渡せばそれを返し、打ち切っても既知の解より悪くならない。
+        let (solution_beam, cost_beam) =
+            solve_problem_beam(&problem, false, NonZeroUsize::new(4).unwrap()).unwrap();
+        for threads in [1, 2] {
+            for (incumbent, node_limit) in [
+                ((solution_opt.clone(), cost_opt), u64::MAX),
+                ((solution_beam.clone(), cost_beam), u64::MAX),
+                ((solution_beam.clone(), cost_beam), 10),
+            ] {
+                let config = SolverConfig::new()
+                    .with_incumbent(Some(incumbent.clone()))
+                    .with_node_limit(node_limit)
+                    .with_threads(threads);
+                let outcome = solve_problem_with(&problem, false, &config);
+                let (solution, cost) = outcome.best().unwrap();
+                assert_eq!(solution.verify(&problem, false), Ok(*cost));
+                assert!(*cost <= incumbent.1);
+                if outcome.is_complete() {
+                    assert_eq!(*cost, cost_opt);
+                }
+            }
+            let config = SolverConfig::new()
+                .with_incumbent(Some((solution_opt.clone(), cost_opt)))
+                .with_threads(threads);
+            let outcome = solve_problem_with(&problem, false, &config);
+            assert!(outcome.is_complete());
+            assert_eq!(outcome.best(), Some(&(solution_opt.clone(), cost_opt)));
+        }
+    }
+
+    #[test]
+    fn test_warm_start() {
+        for (problem, last_stage) in [
+            (include_str!("../problem/10.in"), false),
+            (include_str!("../problem/10.in"), true),
+            (include_str!("../problem/20.in"), false),
+            (include_str!("../problem/20.in"), true),
+        ] {
+            let problem = parse_problem(problem);
+            let (solution_opt, cost_opt) = solve_problem(&problem, last_stage).unwrap();
+
+            // 貪欲法の解は正しく、最適解以上のコスト。
+            let (pos, moves) = problem.to_position_and_moves();
+            let (line, cost) =
+                greedy_solution(&moves, &pos, 0, last_stage, Objective::Frames).unwrap();
+            let solution = Solution::from_moves(&line);
+            assert_eq!(solution.verify(&problem, last_stage), Ok(cost));
+            assert!(cost >= cost_opt);
+
+            // 貪欲法を使っても使わなくても同じ解を返す。
+            for warm_start in [false, true] {
+                let config = SolverConfig::new().with_warm_start(warm_start);
+                let outcome = solve_problem_with(&problem, last_stage, &config);
+                assert_eq!(outcome.best(), Some(&(solution_opt.clone(), cost_opt)));
+            }
+
+            // すぐに打ち切っても貪欲法の解は返す。
+            let config = SolverConfig::new().with_node_limit(1);
+            let outcome = solve_problem_with(&problem, last_stage, &config);
+            assert!(!outcome.is_complete());
+            assert_eq!(outcome.best(), Some(&(solution.clone(), cost)));
+
+            // 貪欲法の解も途中の解として報告する。
+            let mut improvements = Vec::<(Solution, Cost)>::new();
+            solve_problem_with_improvements(
+                &problem,
+                last_stage,
+                &SolverConfig::new(),
+                |solution, cost| improvements.push((solution.clone(), cost)),
+            );
+            assert_eq!(improvements.first(), Some(&(solution.clone(), cost)));
+            assert_eq!(improvements.last().unwrap().1, cost_opt);
+
+            // スナップショットにも保存し、再開後も暫定解として引き継ぐ。
+            let mut last = None;
+            solve_problem_resumable(&problem, last_stage, &config, None, 100, |snapshot| {
+                last = Some(snapshot.clone())
+            });
+            let last = last.unwrap();
+            assert_eq!(last.best(), Some(&(solution, cost)));
+            let config = SolverConfig::new().with_node_limit(last.node_count() + 1);
+            let outcome =
+                solve_problem_resumable(&problem, last_stage, &config, Some(&last), 100, |_| {});
+            assert!(outcome
+                .best()
+                .is_some_and(|&(_, cost_res)| cost_res <= cost));
+            let outcome = solve_problem_resumable(
+                &problem,
+                last_stage,
+                &SolverConfig::new(),
+                Some(&last),
+                100,
+                |_| {},
+            );
+            assert_eq!(outcome.best().unwrap().1, cost_opt);
+        }
+    }
+
+    #[test]
+    fn test_solve_perfect_clear() {
+        let problem = parse_problem(indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "});
+        let problem_perfect = problem
+            .clone()
+            .with_ruleset(problem.ruleset().with_perfect_clear(true));
+
+        // 通常ルールの最適解はブロックを残すので、パーフェクトクリアのルールでは面クリアにならない。
+        let (solution, cost) = solve_problem(&problem, false).unwrap();
+        assert_eq!(solution.verify(&problem, false), Ok(cost));
+        assert!(matches!(
+            solution.verify(&problem_perfect, false),
+            Err(VerifyError::NotCleared { .. })
+        ));
+
+        // この問題はブロックを全て消すことはできない。
+        assert_eq!(solve_problem(&problem_perfect, false), None);
+
+        // 全て消せる問題なら、パーフェクトクリアのルールでも解ける。
+        let problem = parse_problem(indoc! {"
+            3 10
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            ........
+            ........
+            333.....
+            333.....
+        "});
+        let problem_perfect = problem
+            .clone()
+            .with_ruleset(problem.ruleset().with_perfect_clear(true));
+        let (solution, cost) = solve_problem(&problem_perfect, false).unwrap();
+        assert_eq!(solution.verify(&problem_perfect, false), Ok(cost));
+        assert_eq!(
+            solve_problem(&problem, false).map(|(_, cost)| cost),
+            Some(cost)
+        );
+    }
+
+    #[test]
+    fn test_solve_clear_max_blocks() {
+        let problem = parse_problem(include_str!("../problem/10.in"));
+        let (solution, cost) = solve_problem(&problem, false).unwrap();
+
+        // 上限 0 はパーフェクトクリアと同じ。
+        assert_eq!(
+            problem.ruleset().with_clear_max_blocks(0),
+            problem.ruleset().with_perfect_clear(true)
+        );
+
+        // 上限を緩めると、通常ルールの解はそのまま解であり、より安い解が見つかる。
+        let problem_loose = problem
+            .clone()
+            .with_ruleset(problem.ruleset().with_clear_max_blocks(8));
+        assert_eq!(solution.verify(&problem_loose, false), Ok(cost));
+        let (solution_loose, cost_loose) = solve_problem(&problem_loose, false).unwrap();
+        assert_eq!(solution_loose.verify(&problem_loose, false), Ok(cost_loose));
+        assert!(cost_loose < cost);
+        assert!(matches!(
+            solution_loose.verify(&problem, false),
+            Err(VerifyError::NotCleared { .. })
+        ));
+    }
+
+    #[test]
+    fn test_swap_count() {
+        let problem = parse_problem(include_str!("../problem/01.in"));
+        let problem_swap = problem
+            .clone()
+            .with_ruleset(problem.ruleset().with_allow_swap(true));
+
+        // 入れ替え着手を含まない解。
+        let (solution, _) = solve_problem(&problem_swap, false).unwrap();
+        assert_eq!(solution.swap_count(&problem_swap), 0);
+
+        // 入れ替え着手は、入れ替えを許すルールでのみ数える。
+        let (pos, moves) = problem_swap.to_position_and_moves();
+        let mv = moves
+            .iter()
+            .copied()
+            .find(|&mv| pos.is_swap_move(mv))
+            .unwrap();
+        let solution = Solution::new(vec![mv.src()]);
+        assert_eq!(solution.swap_count(&problem_swap), 1);
+        assert_eq!(solution.swap_count(&problem), 0);
+    }
+
+    #[test]
+    fn test_solve_move_exhausted() {
+        // 手数が少なく、探索の途中で手数が尽きる問題でも panic せず、正しい結果を返す。
+        for move_remain in 0..=4 {
+            let problem = parse_problem(format!(
+                "3 {move_remain}\n{}",
+                indoc! {"
+                    ####....
+                    ###.....
+                    ##......
+                    #.......
+                    ........
+                    ........
+                    ........
+                    ........
+                    2444....
+                    1222....
+                    3333....
+                    4111....
+                "}
+            ));
+            for problem in [
+                problem.clone(),
+                problem
+                    .clone()
+                    .with_ruleset(problem.ruleset().with_allow_swap(true)),
+            ] {
+                let cost_opt = solve_problem(&problem, false).map(|(_, cost)| cost);
+                if let Some((solution, cost)) = solve_problem(&problem, false) {
+                    assert_eq!(solution.verify(&problem, false), Ok(cost));
+                }
+                assert_eq!(
+                    solve_problem_ida(&problem, false).map(|(_, cost)| cost),
+                    cost_opt
+                );
+                assert_eq!(
+                    solve_problem_pareto(&problem, false)
+                        .iter()
+                        .map(|&(_, cost)| cost)
+                        .min(),
+                    cost_opt
+                );
+                if let Some((solution, cost)) =
+                    solve_problem_beam(&problem, false, NonZeroUsize::new(4).unwrap())
+                {
+                    assert_eq!(solution.verify(&problem, false), Ok(cost));
+                }
+                for config in [
+                    SolverConfig::new().with_lower_bound(false),
+                    SolverConfig::new().with_lower_bound(false).with_threads(2),
+                ] {
+                    let outcome = solve_problem_with(&problem, false, &config);
+                    assert!(outcome.is_complete());
+                    assert_eq!(outcome.best().map(|(_, cost)| *cost), cost_opt);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_problem_both_stages() {
+        for problem in [
+            include_str!("../problem/01.in"),
+            include_str!("../problem/02.in"),
+            include_str!("../problem/10.in"),
+        ] {
+            let problem = parse_problem(problem);
+            let both = solve_problem_both_stages(&problem);
+            for last_stage in [false, true] {
+                let (solution, cost) = both.get(last_stage).unwrap();
+                assert_eq!(
+                    Some(*cost),
+                    solve_problem(&problem, last_stage).map(|(_, cost)| cost)
+                );
+                assert_eq!(solution.verify(&problem, last_stage), Ok(*cost));
+            }
+        }
+    }
+
+    #[test]
+    fn test_incremental_solver() {
+        let base = include_str!("../problem/20.in");
+        let problems = [
+            base.to_owned(),
+            base.replace("1124....", "1134...."),
+            base.replace("1124....", "112....."),
+            base.replace("3313....", "3314...."),
+            base.to_owned(),
+        ]
+        .map(|problem| parse_problem(&problem));
+
+        let mut solver = IncrementalSolver::new(false);
+        for problem in &problems {
+            let res = solver.solve(problem);
+            assert_eq!(
+                res.as_ref().map(|&(_, cost)| cost),
+                solve_problem(problem, false).map(|(_, cost)| cost)
+            );
+            if let Some((solution, cost)) = &res {
+                assert_eq!(solution.verify(problem, false), Ok(*cost));
+            }
+            assert!(solver.tt_len() > 0);
+        }
+
+        // ルール設定が変われば置換表を作り直す。
+        let tt_len = solver.tt_len();
+        let problem = problems[0]
+            .clone()
+            .with_ruleset(problems[0].ruleset().with_perfect_clear(true));
+        assert_eq!(
+            solver.solve(&problem).map(|(_, cost)| cost),
+            solve_problem(&problem, false).map(|(_, cost)| cost)
+        );
+        assert!(solver.tt_len() < tt_len);
+    }
+
+    #[test]
+    fn test_solve_campaign() {
+        let problems = [
+            include_str!("../problem/01.in"),
+            include_str!("../problem/02.in"),
+            include_str!("../problem/20.in"),
+        ]
+        .map(parse_problem);
+
+        let campaign = solve_campaign(&problems);
+        assert_eq!(campaign.stages().len(), 3);
+
+        // 最後の面だけ最終面として解く。
+        for (i, (problem, stage)) in problems.iter().zip(campaign.stages()).enumerate() {
+            let last_stage = i == 2;
+            let (solution, cost) = stage.as_ref().unwrap();
+            assert_eq!(
+                Some(*cost),
+                solve_problem(problem, last_stage).map(|(_, cost)| cost)
+            );
+            assert_eq!(solution.verify(problem, last_stage), Ok(*cost));
+        }
+        assert_eq!(
+            campaign.total_cost(),
+            Some(
+                campaign
+                    .stages()
+                    .iter()
+                    .map(|stage| stage.as_ref().unwrap().1)
+                    .sum()
+            )
+        );
+
+        // 解けない面があれば総コストはない。
+        let problem_unsolvable = parse_problem(indoc! {"
+            1 10
+            #######.
+            #######.
+            #######.
+            #######.
+            #######.
+            #######.
+            1.......
+            .2....|.
+            ..3...|.
+            ...4..|.
+            ....1.|.
+            1.....|.
+        "});
+        let campaign = solve_campaign(&[problems[0].clone(), problem_unsolvable]);
+        assert!(campaign.stages()[0].is_some());
+        assert_eq!(campaign.stages()[1], None);
+        assert_eq!(campaign.total_cost(), None);
+    }
+
+    #[test]
+    fn test_search_stats() {
+        let problem = parse_problem(include_str!("../problem/10.in"));
+
+        for threads in [1, 2] {
+            let config = SolverConfig::new().with_threads(threads);
+            let outcome = solve_problem_with(&problem, false, &config);
+            let (solution, _) = outcome.best().unwrap();
+            let stats = outcome.stats();
+            assert!(stats.node_count() > 0);
+            assert!(stats.max_depth() >= solution.moves().len());
+            assert!(stats.prune_cost() > 0);
+            assert!(stats.prune_lower_bound() > 0);
+            assert!(stats.prune_tt() <= stats.tt_hits());
+            if threads > 1 {
+                // 並列探索では支配による枝刈りを行わない。
+                assert_eq!(stats.prune_dominance(), 0);
+            }
+        }
+
+        let outcome =
+            solve_problem_with(&problem, false, &SolverConfig::new().with_dominance(false));
+        assert_eq!(outcome.stats().prune_dominance(), 0);
+    }
+
+    #[test]
+    fn test_solve_allow_swap() {
+        // 入れ替え着手を許すと経路上で同じ局面が繰り返されうるので、支配による枝刈りは行わない。
+        // 結果は置換表も支配による枝刈りも使わない探索 (手数の上限があると置換表は使わない) と一致する。
+        for problem in [
+            include_str!("../problem/01.in"),
+            include_str!("../problem/02.in"),
+        ] {
+            let problem = parse_problem(problem);
+            let problem = problem
+                .clone()
+                .with_ruleset(problem.ruleset().with_allow_swap(true));
+
+            let outcome = solve_problem_with(&problem, false, &SolverConfig::new());
+            assert_eq!(outcome.stats().prune_dominance(), 0);
+            let config_ref = SolverConfig::new()
+                .with_dominance(false)
+                .with_max_depth(usize::from(problem.move_remain()));
+            let outcome_ref = solve_problem_with(&problem, false, &config_ref);
+            assert_eq!(outcome_ref.stats().tt_hits(), 0);
+            assert_eq!(
+                outcome.best().map(|(_, cost)| *cost),
+                outcome_ref.best().map(|(_, cost)| *cost)
+            );
+        }
+    }
+
+    #[test]
+    fn test_solve_problem_with_progress() {
+        let problem = parse_problem(include_str!("../problem/20.in"));
+        let (_, cost_opt) = solve_problem(&problem, false).unwrap();
+
+        for threads in [1, 2] {
+            let mut reports = Vec::<SearchProgress>::new();
+            let config = SolverConfig::new().with_threads(threads);
+            let outcome = solve_problem_with_progress(&problem, false, &config, |progress| {
+                reports.push(*progress)
+            });
+            assert_eq!(outcome.best().map(|(_, cost)| *cost), Some(cost_opt));
+
+            // 探索終了時には必ず報告があり、局面数と経過時間は減らない。
+            let last = reports.last().unwrap();
+            assert_eq!(last.best_cost(), Some(cost_opt));
+            assert!(reports
+                .iter()
+                .tuple_windows()
+                .all(|(lhs, rhs)| lhs.node_count() <= rhs.node_count()
+                    && lhs.elapsed() <= rhs.elapsed()));
+        }
+    }
+
+    #[test]
+    fn test_solve_problem_with_cancel() {
+        let problem = parse_problem(include_str!("../problem/40.in"));
+
+        // 別のスレッドから打ち切る。
+        let cancel = CancelToken::new();
+        let config = SolverConfig::new().with_cancel_token(Some(cancel.clone()));
+        let outcome = std::thread::scope(|scope| {
+            let handle = scope.spawn(|| solve_problem_with(&problem, false, &config));
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            cancel.cancel();
+            handle.join().unwrap()
+        });
+        assert!(!outcome.is_complete());
+        if let Some((solution, cost)) = outcome.best() {
+            assert_eq!(solution.verify(&problem, false), Ok(*cost));
+        }
+
+        // 打ち切り済みのトークンを渡すと、並列探索もすぐに終わる。
+        let outcome = solve_problem_with(&problem, false, &config.with_threads(2));
+        assert!(!outcome.is_complete());
+    }
+
+    #[test]
+    fn test_solve_problem_ida() {
+        for (problem, last_stage) in [
+            (include_str!("../problem/01.in"), false),
+            (include_str!("../problem/10.in"), false),
+            (include_str!("../problem/20.in"), true),
+        ] {
+            let problem = parse_problem(problem);
+            let expected = solve_problem(&problem, last_stage).map(|(_, cost)| cost);
+
+            let res = solve_problem_ida(&problem, last_stage);
+            assert_eq!(res.as_ref().map(|(_, cost)| *cost), expected);
+            let (solution, cost) = res.unwrap();
+            assert_eq!(solution.verify(&problem, last_stage), Ok(cost));
+        }
+    }
+
+    #[test]
+    fn test_solve_problem_beam() {
+        for (s, last_stage) in [
+            (include_str!("../problem/01.in"), false),
+            (include_str!("../problem/20.in"), false),
+            (include_str!("../problem/20.in"), true),
+        ] {
+            let problem = parse_problem(s);
+            let (_, cost_opt) = solve_problem(&problem, last_stage).unwrap();
+
+            // ビーム幅が小さいと解が見つからないことがある。
+            for beam_width in [1, 16, 256] {
+                let beam_width = NonZeroUsize::new(beam_width).unwrap();
+                let res = solve_problem_beam(&problem, last_stage, beam_width);
+                if let Some((solution, cost)) = &res {
+                    assert_eq!(solution.verify(&problem, last_stage), Ok(*cost));
+                    assert!(*cost >= cost_opt);
+                }
+                if beam_width.get() == 256 {
+                    assert!(res.is_some());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_problem_weighted() {
+        for (s, last_stage) in [
+            (include_str!("../problem/01.in"), false),
+            (include_str!("../problem/20.in"), false),
+            (include_str!("../problem/04.in"), true),
+        ] {
+            let problem = parse_problem(s);
+            let (_, cost_opt) = solve_problem(&problem, last_stage).unwrap();
+
+            let res = solve_problem_weighted(&problem, last_stage, 1.0).unwrap();
+            assert_eq!(res.cost(), cost_opt);
+
+            for weight in [1.2, 2.0] {
+                let res = solve_problem_weighted(&problem, last_stage, weight).unwrap();
+                assert_eq!(res.solution().verify(&problem, last_stage), Ok(res.cost()));
+                assert!(f64::from(res.cost()) <= f64::from(cost_opt) * weight);
+                assert!(res.lower_bound() <= cost_opt);
+                assert!(cost_opt <= res.cost());
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_problem_with_strategy() {
+        let problem = parse_problem(include_str!("../problem/01.in"));
+        let (_, cost_opt) = solve_problem(&problem, false).unwrap();
+
+        let mut strategies = vec![
+            BuiltinStrategy::DepthFirst,
+            BuiltinStrategy::Ida,
+            BuiltinStrategy::Beam {
+                width: NonZeroUsize::new(256).unwrap(),
+            },
+            BuiltinStrategy::Weighted { weight: 1.0 },
+        ];
+        #[cfg(feature = "std")]
+        strategies.push(BuiltinStrategy::Mcts {
+            playouts: u64::MAX,
+            seed: 0,
+        });
+        #[cfg(feature = "std")]
+        strategies.push(BuiltinStrategy::Restarts {
+            restart_nodes: core::num::NonZeroU64::new(16).unwrap(),
+            seed: 0,
+        });
+        for strategy in strategies {
+            let (solution, cost) = solve_problem_with_strategy(&problem, false, &strategy).unwrap();
+            assert_eq!(solution.verify(&problem, false), Ok(cost));
+            assert_eq!(cost, cost_opt, "{strategy:?}");
+        }
+
+        // 公開 API だけで書いた独自の方式 (毎回最初の合法手を選ぶ貪欲法)。
+        struct FirstMove;
+        impl SolverStrategy for FirstMove {
+            fn solve(&self, problem: &Problem, last_stage: bool) -> Option<(Solution, Cost)> {
+                let (mut pos, moves) = problem.to_position_and_moves();
+                let mut srcs = vec![];
+                while !pos.is_stuck(&moves) && pos.move_remain() > 0 {
+                    let (mv, pos_nxt) = moves
+                        .iter()
+                        .find_map(|&mv| pos.do_move(mv).map(|(pos_nxt, ..)| (mv, pos_nxt)))?;
+                    srcs.push(mv.src());
+                    pos = pos_nxt;
+                }
+                let solution = Solution::new(srcs);
+                let cost = solution.verify(problem, last_stage).ok()?;
+                Some((solution, cost))
+            }
+        }
+        let strategy: &dyn SolverStrategy = &FirstMove;
+        if let Some((solution, cost)) = solve_problem_with_strategy(&problem, false, strategy) {
+            assert_eq!(solution.verify(&problem, false), Ok(cost));
+            assert!(cost >= cost_opt);
+        }
+    }
+
+    #[test]
+    fn test_dominance_table() {
+        let blocks = indoc! {"
+            ......
+            ......
+            222222
+            333333
+            344444
+            311111
+        "};
+        let pos: Position = format!("11 3 5\n{blocks}").parse().unwrap();
+        let pos_fewer_moves: Position = format!("11 3 4\n{blocks}").parse().unwrap();
+
+        let mut table = DominanceTable::new(DOMINANCE_CAPACITY);
+        assert!(table.check_and_insert(&pos, 100, 3));
+        // コストが大きく残り手数が少ない局面は支配される。
+        assert!(!table.check_and_insert(&pos, 120, 3));
+        assert!(!table.check_and_insert(&pos_fewer_moves, 100, 4));
+        // コストが小さければ支配されない。
+        assert!(table.check_and_insert(&pos_fewer_moves, 90, 4));
+        assert!(!table.check_and_insert(&pos_fewer_moves, 95, 4));
+    }
+
+    #[test]
+    fn test_solve_problem_pareto() {
+        for s in [
+            include_str!("../problem/01.in"),
+            include_str!("../problem/04.in"),
+        ] {
+            let problem = parse_problem(s);
+
+            let frontier = solve_problem_pareto(&problem, false);
+            let (_, cost_opt) = solve_problem(&problem, false).unwrap();
+            let (_, move_count_min) =
+                solve_problem_with_objective(&problem, false, Objective::MoveCount).unwrap();
+
+            assert_eq!(
+                frontier.first().unwrap().0.moves().len(),
+                move_count_min as usize
+            );
+            assert_eq!(frontier.last().unwrap().1, cost_opt);
+            for w in frontier.windows(2) {
+                assert!(w[0].0.moves().len() < w[1].0.moves().len());
+                assert!(w[0].1 > w[1].1);
+            }
+            for (solution, cost) in &frontier {
+                assert_eq!(solution.verify(&problem, false), Ok(*cost));
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_problem_with_objective() {
+        let problem = parse_problem(indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "});
+
+        for last_stage in [false, true] {
+            let (_, cost_frames) = solve_problem(&problem, last_stage).unwrap();
+            assert_eq!(
+                solve_problem_with_objective(&problem, last_stage, Objective::Frames)
+                    .unwrap()
+                    .1,
+                cost_frames
+            );
+
+            let (solution, cost) =
+                solve_problem_with_objective(&problem, last_stage, Objective::ThrowOnly).unwrap();
+            assert!(cost <= cost_frames);
+            assert_eq!(
+                solution
+                    .verify_with_objective(&problem, last_stage, Objective::ThrowOnly)
+                    .unwrap(),
+                cost
+            );
+
+            // 手数最小の解のコストは手数に等しく、フレーム数最小の解の手数以下になる。
+            let (solution_frames, _) = solve_problem(&problem, last_stage).unwrap();
+            let (solution, cost) =
+                solve_problem_with_objective(&problem, last_stage, Objective::MoveCount).unwrap();
+            assert_eq!(cost as usize, solution.moves().len());
+            assert!(solution.moves().len() <= solution_frames.moves().len());
+            assert_eq!(
+                solution.verify_with_objective(&problem, last_stage, Objective::MoveCount),
+                Ok(cost)
+            );
+            #[cfg(feature = "std")]
+            {
+                let config = SolverConfig::new()
+                    .with_objective(Objective::MoveCount)
+                    .with_threads(2);
+                let outcome = solve_problem_with(&problem, last_stage, &config);
+                assert_eq!(outcome.best().unwrap().1, cost);
+            }
+        }
+
+        // 第 2 の基準を指定しても総コストは最適のまま、第 2 の基準で既定の解以下になる。
+        // 返す解は着手を試す順序によらず、総コストが最適な全ての解の中で第 2 の基準で最小のもの。
+        let (solution_opt, cost_opt) = solve_problem(&problem, false).unwrap();
+        let solutions_opt: Vec<_> =
+            crate::enumerate::enumerate_solutions(&problem, false, cost_opt)
+                .map(|(solution, _)| solution)
+                .collect();
+        for tie_break in [
+            TieBreak::Inputs,
+            TieBreak::HeroTravel,
+            TieBreak::DirectionChanges,
+            TieBreak::Lexicographic,
+            TieBreak::MoveCount,
+        ] {
+            let (solution, cost) =
+                solve_problem_lexicographic(&problem, false, Objective::Frames, tie_break).unwrap();
+            assert_eq!(cost, cost_opt);
+            assert_eq!(solution.verify(&problem, false).unwrap(), cost);
+            assert_ne!(
+                tie_break.compare(MOVE_SRC_ROW_11, solution.moves(), solution_opt.moves()),
+                core::cmp::Ordering::Greater
+            );
+
+            let solution_min = solutions_opt
+                .iter()
+                .min_by(|lhs, rhs| tie_break.compare(MOVE_SRC_ROW_11, lhs.moves(), rhs.moves()))
+                .unwrap();
+            assert_eq!(&solution, solution_min);
+            for move_ordering in [
+                MoveOrdering::MostErased,
+                MoveOrdering::History,
+                MoveOrdering::Random(1),
+            ] {
+                let config = SolverConfig::new()
+                    .with_tie_break(Some(tie_break))
+                    .with_move_ordering(move_ordering);
+                let outcome = solve_problem_with(&problem, false, &config);
+                assert_eq!(outcome.best(), Some(&(solution.clone(), cost)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_problem_with_constraints() {
+        let problem = parse_problem(indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "});
+
+        let (solution_opt, cost_opt) = solve_problem(&problem, false).unwrap();
+        let src_last = *solution_opt.moves().last().unwrap();
+        let src_other = MoveSrc::all()
+            .into_iter()
+            .find(|&src| src != src_last)
+            .unwrap();
+
+        // 最適解が満たす制約を課しても最適解が得られる。
+        let (solution, cost) =
+            solve_problem_with_constraints(&problem, false, &[Constraint::EndAt(src_last)])
+                .unwrap();
+        assert_eq!(cost, cost_opt);
+        assert_eq!(solution.verify(&problem, false).unwrap(), cost);
+
+        for constraint in [
+            Constraint::NoRepeatSrc,
+            Constraint::EndAt(src_other),
+            Constraint::BanSrc(solution_opt.moves()[0]),
+            Constraint::MinHeroTravel(30),
+        ] {
+            if let Some((solution, cost)) =
+                solve_problem_with_constraints(&problem, false, &[constraint])
+            {
+                assert!(cost >= cost_opt);
+                assert_eq!(solution.verify(&problem, false).unwrap(), cost);
+                assert!(constraint.is_satisfied(MOVE_SRC_ROW_11, solution.moves()));
+            }
+        }
+
+        // 複数の行を禁止すると、そのどの行からも投げない解が得られる。
+        let constraints: Vec<_> = solution_opt.moves()[..2]
+            .iter()
+            .map(|&src| Constraint::BanSrc(src))
+            .collect();
+        if let Some((solution, cost)) =
+            solve_problem_with_constraints(&problem, false, &constraints)
+        {
+            assert!(cost >= cost_opt);
+            assert_eq!(
+                solution.verify_with_constraints(&problem, false, &constraints),
+                Ok(cost)
+            );
+        }
+
+        // 1 手の所要コストの上限を課すと、上限を超える着手を含まない解が得られる。
+        let (mut pos, moves) = problem.to_position_and_moves();
+        let mut cost_mv_max = 0;
+        for &src in solution_opt.moves() {
+            let mv = moves.iter().copied().find(|mv| mv.src() == src).unwrap();
+            let (pos_nxt, cost_mv, _) = pos.do_move(mv).unwrap();
+            pos = pos_nxt;
+            cost_mv_max = cost_mv_max.max(cost_mv);
+        }
+        let constraint = Constraint::MaxMoveCost(cost_mv_max - 1);
+        if let Some((solution, cost)) =
+            solve_problem_with_constraints(&problem, false, &[constraint])
+        {
+            assert!(cost >= cost_opt);
+            assert_eq!(solution.verify(&problem, false).unwrap(), cost);
+        }
+        assert_eq!(
+            solve_problem_with_constraints(&problem, false, &[Constraint::MaxMoveCost(1)]),
+            None
+        );
+
+        // 時間制限は最適解のコスト以上なら最適解が得られ、未満なら解がない。
+        let constraint = Constraint::TimeLimit(cost_opt);
+        let (solution, cost) =
+            solve_problem_with_constraints(&problem, false, &[constraint]).unwrap();
+        assert_eq!(cost, cost_opt);
+        assert_eq!(
+            solution.verify_with_constraints(&problem, false, &[constraint]),
+            Ok(cost)
+        );
+        let constraint = Constraint::TimeLimit(cost_opt - 1);
+        assert_eq!(
+            solve_problem_with_constraints(&problem, false, &[constraint]),
+            None
+        );
+        assert_eq!(
+            solution.verify_with_constraints(&problem, false, &[constraint]),
+            Err(VerifyError::ConstraintViolated { constraint })
+        );
     }
 }
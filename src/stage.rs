@@ -0,0 +1,5 @@
+/// 面番号 (1 始まり) を表す型。
+pub type Stage = u8;
+
+/// 原作 ADVANCE モードの面の数。最終面の面番号でもある。
+pub const STAGE_COUNT: Stage = 50;
@@ -0,0 +1,228 @@
+use std::io::BufRead;
+use std::num::NonZeroUsize;
+use std::sync::mpsc;
+use std::sync::Mutex;
+
+use serde_json::json;
+
+use crate::convert::{read_problems, ProblemFormat};
+use crate::cost::Cost;
+use crate::error::Error;
+use crate::problem::Problem;
+use crate::ruleset::Ruleset;
+use crate::solver::{solve_problem, Solution};
+
+/// 逐次読む問題の形式。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StreamFormat {
+    /// 問題集。`Problem` の文字列表現を `---` だけの行 (または空行) で区切って並べたもの。
+    Collection,
+    /// 1 行に 1 問の JSON オブジェクト (`convert` の JSON 形式) を並べたもの。空行は無視する。
+    JsonLines,
+}
+
+/// 逐次読んだ問題 1 問分の結果。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StreamResult {
+    index: usize,
+    result: Result<Option<(Solution, Cost)>, Error>,
+}
+
+impl StreamResult {
+    /// 入力中での問題の位置 (0-based)。
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// 最適解とコスト (解がなければ `None`)、または問題の読み取りエラー。
+    pub fn result(&self) -> &Result<Option<(Solution, Cost)>, Error> {
+        &self.result
+    }
+
+    /// 1 行の JSON に変換する。
+    ///
+    /// 形式は `{"index":0,"solution":[10,9],"cost":443}`。解がなければ `solution` は `null`、
+    /// エラーなら `{"index":0,"error":"..."}`。
+    pub fn to_json_line(&self) -> String {
+        let value = match &self.result {
+            Ok(Some((solution, cost))) => {
+                let srcs: Vec<_> = solution.moves().iter().map(|src| src.to_inner()).collect();
+                json!({ "index": self.index, "solution": srcs, "cost": cost })
+            }
+            Ok(None) => json!({ "index": self.index, "solution": null }),
+            Err(e) => json!({ "index": self.index, "error": e.to_string() }),
+        };
+
+        value.to_string()
+    }
+}
+
+/// `input` から問題を 1 問ずつ読む。読み取りエラーは問題ごとに報告し、続きを読む。
+pub fn read_problem_stream(
+    input: impl BufRead,
+    format: StreamFormat,
+) -> impl Iterator<Item = Result<Problem, Error>> {
+    const SEPARATOR: &str = "---";
+
+    let mut lines = input.lines();
+    let mut chunk = String::new();
+    let mut eof = false;
+
+    std::iter::from_fn(move || loop {
+        if eof {
+            return None;
+        }
+        let line = match lines.next().transpose() {
+            Ok(Some(line)) => line,
+            Ok(None) => {
+                eof = true;
+                SEPARATOR.to_owned()
+            }
+            Err(e) => {
+                eof = true;
+                return Some(Err(e.into()));
+            }
+        };
+
+        match format {
+            StreamFormat::Collection => {
+                if line.trim().is_empty() || line == SEPARATOR {
+                    if !chunk.is_empty() {
+                        let res = chunk.parse::<Problem>();
+                        chunk.clear();
+                        return Some(res);
+                    }
+                } else {
+                    chunk.push_str(&line);
+                    chunk.push('\n');
+                }
+            }
+            StreamFormat::JsonLines => {
+                if eof || line.trim().is_empty() {
+                    continue;
+                }
+                let res =
+                    read_problems(line.as_bytes(), ProblemFormat::Json).and_then(|mut problems| {
+                        match problems.len() {
+                            1 => Ok(problems.pop().unwrap()),
+                            count => Err(Error::NotSingle { count }),
+                        }
+                    });
+                return Some(res);
+            }
+        }
+    })
+}
+
+/// `input` から問題を逐次読み、最大 `jobs` 問を並列に解く。解き終わった問題から順に `on_result` を呼ぶ
+/// (入力の順とは限らない)。
+///
+/// 読んだが解き始めていない問題は高々 `jobs` 問しか保持しないので、終わりのない入力 (キューなど) にも使える。
+/// 全ての問題に同じルール設定 `ruleset` を適用し、最終面かどうかは `last_stage` で指定する。
+pub fn solve_stream(
+    input: impl BufRead,
+    format: StreamFormat,
+    last_stage: bool,
+    ruleset: Ruleset,
+    jobs: NonZeroUsize,
+    mut on_result: impl FnMut(StreamResult) + Send,
+) {
+    let (tx_job, rx_job) = mpsc::sync_channel::<(usize, Problem)>(jobs.get());
+    let rx_job = Mutex::new(rx_job);
+    let (tx_result, rx_result) = mpsc::channel::<StreamResult>();
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.get() {
+            let tx_result = tx_result.clone();
+            let rx_job = &rx_job;
+            scope.spawn(move || loop {
+                let job = rx_job.lock().unwrap().recv();
+                let Ok((index, problem)) = job else {
+                    break;
+                };
+                let result = Ok(solve_problem(&problem, last_stage));
+                if tx_result.send(StreamResult { index, result }).is_err() {
+                    break;
+                }
+            });
+        }
+
+        scope.spawn(move || {
+            for result in rx_result {
+                on_result(result);
+            }
+        });
+
+        for (index, problem) in read_problem_stream(input, format).enumerate() {
+            match problem {
+                Ok(problem) => {
+                    tx_job.send((index, problem.with_ruleset(ruleset))).unwrap();
+                }
+                Err(e) => {
+                    let result = Err(e);
+                    tx_result.send(StreamResult { index, result }).unwrap();
+                }
+            }
+        }
+
+        // 送信側を閉じると、ワーカーは残りの問題を解き終えてから終了する。
+        drop(tx_job);
+        drop(tx_result);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_read_problem_stream() {
+        let problem = include_str!("../problem/01.in");
+
+        let s = format!("{problem}---\nfoo\n\n{problem}");
+        let problems: Vec<_> =
+            read_problem_stream(s.as_bytes(), StreamFormat::Collection).collect();
+        assert_eq!(problems.len(), 3);
+        assert_eq!(problems[0], Ok(problem.parse().unwrap()));
+        assert!(problems[1].is_err());
+        assert_eq!(problems[2], problems[0]);
+
+        let json = crate::convert::write_problems(&[problem.parse().unwrap()], ProblemFormat::Json)
+            .unwrap();
+        // 1 行にする。
+        let json = serde_json::from_slice::<serde_json::Value>(&json)
+            .unwrap()
+            .to_string();
+        let s = format!("{json}\n\n{json}\n");
+        let problems: Vec<_> = read_problem_stream(s.as_bytes(), StreamFormat::JsonLines).collect();
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0], Ok(problem.parse().unwrap()));
+    }
+
+    #[test]
+    fn test_solve_stream() {
+        let problem = include_str!("../problem/01.in");
+        let s = format!("{problem}---\nfoo\n---\n{problem}");
+
+        let mut results = Vec::<StreamResult>::new();
+        solve_stream(
+            s.as_bytes(),
+            StreamFormat::Collection,
+            false,
+            Ruleset::new(),
+            NonZeroUsize::new(2).unwrap(),
+            |result| results.push(result),
+        );
+        results.sort_unstable_by_key(StreamResult::index);
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0].to_json_line(),
+            r#"{"cost":443,"index":0,"solution":[10,9,10,11]}"#
+        );
+        assert!(results[1].result().is_err());
+        assert_eq!(results[2].result(), results[0].result());
+    }
+}
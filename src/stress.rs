@@ -0,0 +1,316 @@
+use rand::Rng as _;
+
+use crate::block::{Block, Blocks, BlocksCol, BlocksRow, BlocksSquare};
+use crate::generator::rng_from_seed;
+use crate::move_::{Move, MoveDst};
+use crate::position::Position;
+use crate::ruleset::Ruleset;
+
+/// 着手の結果 (結果の盤面, 次の保持ブロック, 置換前に最後にブロックが通った位置)。着手が無効なら `None`。
+pub type ThrowResult = Option<(Blocks, Block, BlocksSquare)>;
+
+/// `Blocks::do_move_*` と素朴な実装の結果が食い違った例。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Counterexample {
+    blocks: Blocks,
+    block_move: Block,
+    dst: MoveDst,
+    ruleset: Ruleset,
+    expected: ThrowResult,
+    actual: ThrowResult,
+}
+
+impl Counterexample {
+    pub fn blocks(&self) -> &Blocks {
+        &self.blocks
+    }
+
+    pub fn block_move(&self) -> Block {
+        self.block_move
+    }
+
+    pub fn dst(&self) -> MoveDst {
+        self.dst
+    }
+
+    pub fn ruleset(&self) -> Ruleset {
+        self.ruleset
+    }
+
+    /// 素朴な実装の結果。
+    pub fn expected(&self) -> &ThrowResult {
+        &self.expected
+    }
+
+    /// `Blocks::do_move_*` の結果。
+    pub fn actual(&self) -> &ThrowResult {
+        &self.actual
+    }
+}
+
+impl core::fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let write_result = |f: &mut core::fmt::Formatter<'_>, res: &ThrowResult| match res {
+            Some((blocks, block_holding, sq_last)) => {
+                writeln!(f, "holding={} last={sq_last:?}", block_holding.to_inner())?;
+                blocks.fmt(f)
+            }
+            None => writeln!(f, "(invalid)"),
+        };
+
+        writeln!(
+            f,
+            "block={} dst={:?} allow_swap={}",
+            self.block_move.to_inner(),
+            self.dst,
+            self.ruleset.allow_swap()
+        )?;
+        self.blocks.fmt(f)?;
+        writeln!(f, "expected:")?;
+        write_result(f, &self.expected)?;
+        writeln!(f, "actual:")?;
+        write_result(f, &self.actual)
+    }
+}
+
+/// ランダムな正しい盤面と着手で `Blocks::do_move_*` を素朴な実装と突き合わせる。
+/// 同じシードからは同じ盤面と着手の列が生成される。
+///
+/// 食い違いが見つかった場合、食い違いが残る範囲でブロックを減らした最小の例を返す。
+pub fn stress_do_move(seed: u64, iterations: usize) -> Result<(), Box<Counterexample>> {
+    stress_impl(seed, iterations, do_move_actual)
+}
+
+/// エミュレータなどで記録した (着手前の局面, 着手, 着手後の局面) の列を `Position::do_move()` と突き合わせ、
+/// 最初に食い違った位置を返す。
+pub fn check_trace(steps: &[(Position, Move, Position)]) -> Result<(), usize> {
+    for (i, (before, mv, after)) in steps.iter().enumerate() {
+        let ok = before
+            .do_move(*mv)
+            .is_some_and(|(pos_nxt, _, _)| pos_nxt == *after);
+        if !ok {
+            return Err(i);
+        }
+    }
+
+    Ok(())
+}
+
+fn do_move_actual(
+    blocks: &Blocks,
+    dst: MoveDst,
+    block_move: Block,
+    ruleset: Ruleset,
+) -> ThrowResult {
+    match dst {
+        MoveDst::Horizontal(row) => blocks.do_move_hori(row, block_move, ruleset),
+        MoveDst::Vertical(col) => blocks.do_move_vert(col, block_move, ruleset),
+    }
+}
+
+fn stress_impl(
+    seed: u64,
+    iterations: usize,
+    actual: impl Fn(&Blocks, MoveDst, Block, Ruleset) -> ThrowResult,
+) -> Result<(), Box<Counterexample>> {
+    let rng = &mut rng_from_seed(seed);
+
+    for _ in 0..iterations {
+        // 各列の高さと色をランダムに決める (ブロックは常に下に詰まっている)。
+        let kind_count = rng.gen_range(1..=4);
+        let mut blocks = Blocks::new();
+        for col in BlocksCol::all() {
+            let height = rng.gen_range(0..=6);
+            for row in &BlocksRow::all()[6 - height..] {
+                let block = Block::from_inner(rng.gen_range(1..=kind_count)).unwrap();
+                blocks[(col, *row)] = Some(block);
+            }
+        }
+        let block_move = if rng.gen_ratio(1, 8) {
+            Block::Wild
+        } else {
+            Block::from_inner(rng.gen_range(1..=kind_count)).unwrap()
+        };
+        let i = rng.gen_range(0..12);
+        let dst = if i < 6 {
+            MoveDst::Horizontal(BlocksRow::all()[i])
+        } else {
+            MoveDst::Vertical(BlocksCol::all()[i - 6])
+        };
+        let ruleset = Ruleset::new().with_allow_swap(rng.gen());
+
+        if let Some(cex) = check(&blocks, dst, block_move, ruleset, &actual) {
+            return Err(Box::new(minimize(cex, &actual)));
+        }
+    }
+
+    Ok(())
+}
+
+fn check(
+    blocks: &Blocks,
+    dst: MoveDst,
+    block_move: Block,
+    ruleset: Ruleset,
+    actual: &impl Fn(&Blocks, MoveDst, Block, Ruleset) -> ThrowResult,
+) -> Option<Counterexample> {
+    let expected = do_move_naive(blocks, dst, block_move, ruleset);
+    let actual = actual(blocks, dst, block_move, ruleset);
+
+    (expected != actual).then(|| Counterexample {
+        blocks: blocks.clone(),
+        block_move,
+        dst,
+        ruleset,
+        expected,
+        actual,
+    })
+}
+
+/// 食い違いが残る限り、各列の一番上のブロックを取り除くか、ブロックを種類 1 に置き換える。
+fn minimize(
+    mut cex: Counterexample,
+    actual: &impl Fn(&Blocks, MoveDst, Block, Ruleset) -> ThrowResult,
+) -> Counterexample {
+    loop {
+        let mut candidates = Vec::<Blocks>::new();
+        for col in BlocksCol::all() {
+            if let Some(&row) = BlocksRow::all()
+                .iter()
+                .find(|&&row| cex.blocks[(col, row)].is_some())
+            {
+                let mut blocks = cex.blocks.clone();
+                blocks[(col, row)] = None;
+                candidates.push(blocks);
+            }
+        }
+        for sq in BlocksSquare::all() {
+            if cex.blocks[sq].is_some_and(|block| block != Block::Normal1) {
+                let mut blocks = cex.blocks.clone();
+                blocks[sq] = Some(Block::Normal1);
+                candidates.push(blocks);
+            }
+        }
+
+        let Some(cex_smaller) = candidates
+            .iter()
+            .find_map(|blocks| check(blocks, cex.dst, cex.block_move, cex.ruleset, actual))
+        else {
+            return cex;
+        };
+        cex = cex_smaller;
+    }
+}
+
+/// `Blocks::do_move_*` とは独立に書いた素朴な実装。盤面を 2 次元配列として扱い、経路を明示的に列挙する。
+fn do_move_naive(
+    blocks: &Blocks,
+    dst: MoveDst,
+    block_move: Block,
+    ruleset: Ruleset,
+) -> ThrowResult {
+    // grid[row][col] (いずれも 0-based)。
+    let mut grid = [[None::<Block>; 6]; 6];
+    for (r, row) in BlocksRow::all().into_iter().enumerate() {
+        for (c, col) in BlocksCol::all().into_iter().enumerate() {
+            grid[r][c] = blocks[(col, row)];
+        }
+    }
+    let grid_orig = grid;
+
+    // 横方向なら右端から左端まで進み、左の壁に当たったら落ちる。縦方向なら上端から下端まで落ちる。
+    let (path, vert): (Vec<(usize, usize)>, bool) = match dst {
+        MoveDst::Horizontal(row) => {
+            let r = row.to_index();
+            let path = (0..6)
+                .rev()
+                .map(|c| (r, c))
+                .chain((r + 1..6).map(|r| (r, 0)));
+            (path.collect(), false)
+        }
+        MoveDst::Vertical(col) => {
+            let c = col.to_index();
+            ((0..6).map(|r| (r, c)).collect(), true)
+        }
+    };
+
+    let to_sq =
+        |(r, c): (usize, usize)| BlocksSquare::new(BlocksCol::all()[c], BlocksRow::all()[r]);
+    let to_blocks = |grid: &[[Option<Block>; 6]; 6]| {
+        let mut blocks = Blocks::new();
+        for (r, row) in BlocksRow::all().into_iter().enumerate() {
+            for (c, col) in BlocksCol::all().into_iter().enumerate() {
+                blocks[(col, row)] = grid[r][c];
+            }
+        }
+        blocks
+    };
+    // 消したマスには、横方向なら上のブロックが 1 マスずつ落ちてくる。
+    let erase = |grid: &mut [[Option<Block>; 6]; 6], (r, c): (usize, usize)| {
+        if vert {
+            grid[r][c] = None;
+        } else {
+            for rr in (1..=r).rev() {
+                grid[rr][c] = grid[rr - 1][c];
+            }
+            grid[0][c] = None;
+        }
+    };
+
+    let i_first = path.iter().position(|&(r, c)| grid_orig[r][c].is_some())?;
+    let rc_first = path[i_first];
+    let block_first = grid_orig[rc_first.0][rc_first.1].unwrap();
+
+    let erasable = block_move == Block::Wild || block_move == block_first;
+    if !erasable {
+        if !ruleset.allow_swap() {
+            return None;
+        }
+        grid[rc_first.0][rc_first.1] = Some(block_move);
+        return Some((to_blocks(&grid), block_first, to_sq(rc_first)));
+    }
+
+    erase(&mut grid, rc_first);
+    let mut block_holding = block_first;
+    let mut rc_last = rc_first;
+    for &(r, c) in &path[i_first + 1..] {
+        match grid_orig[r][c] {
+            Some(block) if block == block_first => erase(&mut grid, (r, c)),
+            Some(block) => {
+                grid[r][c] = Some(block_first);
+                block_holding = block;
+                break;
+            }
+            None => {}
+        }
+        rc_last = (r, c);
+    }
+
+    Some((to_blocks(&grid), block_holding, to_sq(rc_last)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stress_do_move() {
+        assert_eq!(stress_do_move(1, 20000), Ok(()));
+
+        // わざと壊した実装 (横方向で置換されたブロックを保持し忘れる) では、小さい反例が見つかる。
+        let broken = |blocks: &Blocks, dst, block_move, ruleset| {
+            let res = do_move_actual(blocks, dst, block_move, ruleset);
+            match (dst, res) {
+                (MoveDst::Horizontal(_), Some((blocks_res, block_holding, sq_last)))
+                    if block_holding != block_move && !block_move.is_wild() =>
+                {
+                    Some((blocks_res, block_move, sq_last))
+                }
+                (_, res) => res,
+            }
+        };
+        let cex = stress_impl(1, 20000, broken).unwrap_err();
+        assert_ne!(cex.expected(), cex.actual());
+        assert!(cex.blocks().block_count() <= 3, "{cex}");
+    }
+}
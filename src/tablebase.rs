@@ -0,0 +1,291 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::cost::{Cost, COST_CLEAR_ERASE_BLOCK};
+use crate::error::Error;
+use crate::move_::{Move, MoveSrc};
+use crate::position::Position;
+use crate::problem::Problem;
+
+/// 終盤表。ブロック数が `max_blocks()` 以下の局面から面が終わるまでの最小コスト (厳密値) と最善手の表。
+///
+/// ブロック数は着手によって増えないので、ブロックの少ない局面の厳密値はその先の局面だけから求まる。
+/// ソルバーは探索中にブロック数が上限以下の局面に達すると表を引き、表になければその場で求めて記録する。
+/// 深い面では同じ終盤を何度も探索し直すので、これを省ける。
+///
+/// 置換表と同様に、同じ問題、同じ最終面フラグ、同じルール設定で探索する限り、ファイルに保存して再利用できる。
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Tablebase {
+    rom: Vec<u8>,
+    last_stage: bool,
+    ruleset_flags: u16,
+    max_blocks: usize,
+    entries: BTreeMap<u128, TablebaseEntry>,
+}
+
+/// 面が終わるまでの最小コスト (解けなければ `Cost::MAX`) と、それを達成する最初の着手。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct TablebaseEntry {
+    cost: Cost,
+    src: Option<MoveSrc>,
+}
+
+impl Tablebase {
+    const MAGIC: &'static [u8; 4] = b"FATB";
+    const VERSION: u8 = 2;
+    const HEADER_LEN: usize = 4 + 1 + 1 + 2 + Problem::ROM_BYTES_LEN + 8;
+    const ENTRY_LEN: usize = 16 + 4 + 1;
+
+    /// 問題 `problem` 用の、ブロック数 `max_blocks` 以下の局面を扱う空の終盤表を作る。
+    pub fn new(problem: &Problem, last_stage: bool, max_blocks: usize) -> Self {
+        Self {
+            rom: problem.to_rom_bytes().to_vec(),
+            last_stage,
+            ruleset_flags: Self::ruleset_flags(problem),
+            max_blocks,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// 扱う局面のブロック数の上限を変更した終盤表を返す。記録済みのエントリは厳密値なのでそのまま使える。
+    pub fn with_max_blocks(self, max_blocks: usize) -> Self {
+        Self { max_blocks, ..self }
+    }
+
+    pub fn max_blocks(&self) -> usize {
+        self.max_blocks
+    }
+
+    /// 終盤表が問題 `problem` (最終面フラグ `last_stage`) 用のものかどうかを返す。
+    pub fn matches(&self, problem: &Problem, last_stage: bool) -> bool {
+        self.rom == problem.to_rom_bytes()
+            && self.last_stage == last_stage
+            && self.ruleset_flags == Self::ruleset_flags(problem)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 終了していない局面 `pos` (ブロック数 `max_blocks()` 以下、残り手数 1 以上) から面が終わるまでの最小コストを返す。
+    /// 解けなければ `Cost::MAX` を返す。
+    ///
+    /// コストの数え方はソルバーと同じ (最終面なら最終手のブロック投げコストを 1 とみなし、
+    /// 最終面でなければ面クリア時のブロック自動消去コストを含む)。
+    pub(crate) fn probe(&mut self, moves: &[Move], pos: &Position) -> Cost {
+        debug_assert!(pos.block_count() <= self.max_blocks);
+
+        let key = pos.packed_key();
+        if let Some(entry) = self.entries.get(&key) {
+            return entry.cost;
+        }
+
+        let mut best = TablebaseEntry {
+            cost: Cost::MAX,
+            src: None,
+        };
+        for &mv in moves {
+            let Some((pos_nxt, cost_mv, cost_throw)) = pos.do_move(mv) else {
+                continue;
+            };
+            let cost = if pos_nxt.is_stuck(moves) {
+                if !pos_nxt.is_cleared() {
+                    continue;
+                }
+                if self.last_stage {
+                    cost_mv - cost_throw + 1
+                } else {
+                    cost_mv + COST_CLEAR_ERASE_BLOCK * pos_nxt.block_count() as Cost
+                }
+            } else if pos_nxt.move_remain() == 0 {
+                continue;
+            } else {
+                match self.probe(moves, &pos_nxt) {
+                    Cost::MAX => continue,
+                    cost_rest => cost_mv + cost_rest,
+                }
+            };
+            if cost < best.cost {
+                best = TablebaseEntry {
+                    cost,
+                    src: Some(mv.src()),
+                };
+            }
+        }
+
+        self.entries.insert(key, best);
+
+        best.cost
+    }
+
+    /// `probe()` 済みの解ける局面 `pos` から面が終わるまでの最善の着手列を返す。
+    pub(crate) fn line(&self, moves: &[Move], pos: &Position) -> Vec<Move> {
+        let mut line = Vec::<Move>::new();
+        let mut pos = pos.clone();
+
+        while let Some(src) = self
+            .entries
+            .get(&pos.packed_key())
+            .and_then(|entry| entry.src)
+        {
+            let mv = *moves.iter().find(|mv| mv.src() == src).unwrap();
+            line.push(mv);
+            pos = pos.do_move(mv).unwrap().0;
+            if pos.is_stuck(moves) {
+                break;
+            }
+        }
+
+        line
+    }
+
+    /// バイト列に変換する。
+    ///
+    /// 形式はマジック `FATB`, バージョン, 最終面フラグ, ルール設定 (u16), 問題の ROM バイト列, エントリ数 (u64),
+    /// エントリ (局面 u128, 最小コスト u32, 最初の着手 u8 (なければ 0)) の並び。整数はリトルエンディアン。
+    /// ブロック数の上限は含まない。
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(Self::HEADER_LEN + Self::ENTRY_LEN * self.entries.len());
+
+        buf.extend(Self::MAGIC);
+        buf.push(Self::VERSION);
+        buf.push(u8::from(self.last_stage));
+        buf.extend(self.ruleset_flags.to_le_bytes());
+        buf.extend(&self.rom);
+        buf.extend((self.entries.len() as u64).to_le_bytes());
+        for (&key, entry) in &self.entries {
+            buf.extend(key.to_le_bytes());
+            buf.extend(entry.cost.to_le_bytes());
+            buf.push(entry.src.map_or(0, MoveSrc::to_inner));
+        }
+
+        buf
+    }
+
+    /// `to_bytes()` の出力から、ブロック数 `max_blocks` 以下の局面を扱う終盤表として復元する。
+    pub fn from_bytes(buf: &[u8], max_blocks: usize) -> Result<Self, Error> {
+        let (header, body) = buf
+            .split_at_checked(Self::HEADER_LEN)
+            .ok_or(Error::InvalidTablebaseFile)?;
+        if &header[..4] != Self::MAGIC || header[4] != Self::VERSION || header[5] > 1 {
+            return Err(Error::InvalidTablebaseFile);
+        }
+        let last_stage = header[5] != 0;
+        let ruleset_flags = u16::from_le_bytes([header[6], header[7]]);
+        let rom = header[8..8 + Problem::ROM_BYTES_LEN].to_vec();
+        let len = u64::from_le_bytes(header[8 + Problem::ROM_BYTES_LEN..].try_into().unwrap());
+
+        if body.len() as u64 != Self::ENTRY_LEN as u64 * len {
+            return Err(Error::InvalidTablebaseFile);
+        }
+        let entries = body
+            .chunks_exact(Self::ENTRY_LEN)
+            .map(|chunk| {
+                let key = u128::from_le_bytes(chunk[..16].try_into().unwrap());
+                let cost = Cost::from_le_bytes(chunk[16..20].try_into().unwrap());
+                let src = match chunk[20] {
+                    0 => None,
+                    inner => Some(MoveSrc::from_inner(inner).ok_or(Error::InvalidTablebaseFile)?),
+                };
+                Ok((key, TablebaseEntry { cost, src }))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Self {
+            rom,
+            last_stage,
+            ruleset_flags,
+            max_blocks,
+            entries,
+        })
+    }
+
+    /// ファイルから問題 `problem` (最終面フラグ `last_stage`) 用の、ブロック数 `max_blocks` 以下の局面を扱う終盤表を読み込む。
+    /// ファイルが存在しなければ空の終盤表を返す。別の問題用の終盤表だった場合はエラーを返す。
+    #[cfg(feature = "std")]
+    pub fn load(
+        path: impl AsRef<std::path::Path>,
+        problem: &Problem,
+        last_stage: bool,
+        max_blocks: usize,
+    ) -> Result<Self, Error> {
+        match std::fs::read(path) {
+            Ok(buf) => {
+                let this = Self::from_bytes(&buf, max_blocks)?;
+                if !this.matches(problem, last_stage) {
+                    return Err(Error::TablebaseMismatch);
+                }
+                Ok(this)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(Self::new(problem, last_stage, max_blocks))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// ファイルに書き込む。途中で中断してもファイルが壊れることはない (`atomic_write` 参照)。
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        use std::io::Write as _;
+
+        crate::fs::atomic_write(path, |w| w.write_all(&self.to_bytes()))?;
+
+        Ok(())
+    }
+
+    fn ruleset_flags(problem: &Problem) -> u16 {
+        problem.ruleset().to_flags()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::solver::{solve_problem, solve_problem_with_tablebase};
+
+    use super::*;
+
+    #[test]
+    fn test_tablebase() {
+        for (problem, last_stage) in [
+            (include_str!("../problem/10.in"), false),
+            (include_str!("../problem/20.in"), false),
+            (include_str!("../problem/20.in"), true),
+        ] {
+            let problem: Problem = problem.parse().unwrap();
+            let expected = solve_problem(&problem, last_stage).map(|(_, cost)| cost);
+
+            // 空の終盤表で解いても、埋まった終盤表で解き直しても最適コストは変わらない。
+            let mut tablebase = Tablebase::new(&problem, last_stage, 8);
+            let res = solve_problem_with_tablebase(&problem, last_stage, &mut tablebase);
+            assert_eq!(res.as_ref().map(|(_, cost)| *cost), expected);
+            assert!(!tablebase.is_empty());
+            let (solution, cost) = res.unwrap();
+            assert_eq!(solution.verify(&problem, last_stage), Ok(cost));
+            assert_eq!(
+                solve_problem_with_tablebase(&problem, last_stage, &mut tablebase)
+                    .map(|(_, cost)| cost),
+                expected
+            );
+
+            // バイト列を経由しても復元できる。
+            let restored = Tablebase::from_bytes(&tablebase.to_bytes(), 8).unwrap();
+            assert_eq!(restored, tablebase);
+            assert!(restored.matches(&problem, last_stage));
+            assert!(!restored.matches(&problem, !last_stage));
+        }
+
+        let problem: Problem = include_str!("../problem/10.in").parse().unwrap();
+        let bytes = Tablebase::new(&problem, false, 8).to_bytes();
+        assert_eq!(
+            Tablebase::from_bytes(&bytes[..bytes.len() - 1], 8),
+            Err(Error::InvalidTablebaseFile)
+        );
+    }
+}
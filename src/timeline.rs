@@ -0,0 +1,136 @@
+use alloc::vec::Vec;
+
+use crate::cost::{calc_hero_move_cost, Cost};
+use crate::error::VerifyError;
+use crate::move_::MoveSrc;
+use crate::problem::Problem;
+use crate::profile::TimingProfile;
+use crate::solver::{play_move, Solution};
+
+/// 1 手分のフレームタイムスタンプ (コストモデルから求めたもの)。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MoveTiming {
+    src: MoveSrc,
+    frame_start: Cost,
+    frame_press: Cost,
+    frame_settle: Cost,
+}
+
+impl MoveTiming {
+    pub fn src(&self) -> MoveSrc {
+        self.src
+    }
+
+    /// 自機が移動を始めるフレーム (移動しない場合は A ボタンを押すフレームと同じ)。
+    pub fn frame_start(&self) -> Cost {
+        self.frame_start
+    }
+
+    /// A ボタンを押すフレーム。
+    pub fn frame_press(&self) -> Cost {
+        self.frame_press
+    }
+
+    /// 盤面が落ち着き、次の手の入力を受け付けるフレーム。
+    pub fn frame_settle(&self) -> Cost {
+        self.frame_settle
+    }
+}
+
+/// 解の各手のフレームタイムスタンプを求める。最初の手の開始フレームを `frame_origin` とする。
+///
+/// 最終面の最終手の補正は行わず、各手のコストをそのまま積み上げる。
+pub fn solution_timeline(
+    problem: &Problem,
+    solution: &Solution,
+    frame_origin: Cost,
+) -> Result<Vec<MoveTiming>, VerifyError> {
+    solution_timeline_with_profile(problem, solution, frame_origin, TimingProfile::default())
+}
+
+/// `solution_timeline` と同様だが、タイミングプロファイルによる入力遅延とラグを反映する。
+pub fn solution_timeline_with_profile(
+    problem: &Problem,
+    solution: &Solution,
+    frame_origin: Cost,
+    profile: TimingProfile,
+) -> Result<Vec<MoveTiming>, VerifyError> {
+    let (mut pos, moves) = problem.to_position_and_moves();
+    let mut frame = frame_origin + profile.input_delay();
+    let mut timings = Vec::<MoveTiming>::with_capacity(solution.moves().len());
+
+    for (i, &src) in solution.moves().iter().enumerate() {
+        let cost_hero_move = calc_hero_move_cost(pos.hero_row(), src);
+        let (_, pos_nxt, cost_mv, _) = play_move(&pos, &moves, i, src)?;
+        let cost_mv = profile.adjust_move_cost(cost_mv);
+
+        timings.push(MoveTiming {
+            src,
+            frame_start: frame,
+            frame_press: frame + cost_hero_move,
+            frame_settle: frame + cost_mv,
+        });
+
+        pos = pos_nxt;
+        frame += cost_mv;
+    }
+
+    Ok(timings)
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use crate::cost::{COST_CLEAR_ERASE_BLOCK, COST_HERO_STEP};
+
+    use super::*;
+
+    #[test]
+    fn test_solution_timeline() {
+        let problem: Problem = indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "}
+        .parse()
+        .unwrap();
+        let solution: Solution = "10 9 10 11".parse().unwrap();
+
+        let timings = solution_timeline(&problem, &solution, 100).unwrap();
+        assert_eq!(timings.len(), 4);
+
+        // 自機は最下段 (11) から 10 へ 1 歩移動してから投げる。
+        assert_eq!(timings[0].frame_start(), 100);
+        assert_eq!(timings[0].frame_press(), 100 + COST_HERO_STEP);
+
+        for pair in timings.windows(2) {
+            assert_eq!(pair[0].frame_settle(), pair[1].frame_start());
+        }
+
+        // 最適コスト 443 は面クリア時に残る 3 個のブロックの消去コストを含む。
+        assert_eq!(
+            timings[3].frame_settle(),
+            100 + 443 - 3 * COST_CLEAR_ERASE_BLOCK
+        );
+
+        let timings =
+            solution_timeline_with_profile(&problem, &solution, 100, TimingProfile::Console)
+                .unwrap();
+        assert_eq!(
+            timings[3].frame_settle(),
+            TimingProfile::Console.adjust_total_cost(100 + 443 - 3 * COST_CLEAR_ERASE_BLOCK, 4)
+        );
+    }
+}
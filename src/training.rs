@@ -0,0 +1,255 @@
+use std::io::Write;
+
+use crate::block::{BlocksCol, BlocksRow};
+use crate::cost::Cost;
+use crate::move_::{Move, MoveSrc};
+use crate::position::Position;
+use crate::problem::Problem;
+use crate::solver::solve_with_prefix;
+
+/// 局面のエンコーディングの長さ。
+pub const POSITION_ENCODING_LEN: usize = 6 * 6 + 3;
+
+/// 学習データの 1 サンプル: (局面, 最適な残りコスト, 最善手)。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrainingSample {
+    position: Position,
+    cost_remain: Cost,
+    best_move: MoveSrc,
+}
+
+impl TrainingSample {
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// この局面から面を終えるまでの最適なコスト。
+    pub fn cost_remain(&self) -> Cost {
+        self.cost_remain
+    }
+
+    /// この局面での最善手。
+    pub fn best_move(&self) -> MoveSrc {
+        self.best_move
+    }
+}
+
+/// 局面を数値列にエンコードする。
+///
+/// 左下 6x6 の各マス (上の行から順、各行は左から順。空白は 0、ブロックはその値)、
+/// 保持ブロック、自機の位置、残り手数の順に並べる。
+pub fn encode_position(pos: &Position) -> [u8; POSITION_ENCODING_LEN] {
+    let mut buf = [0; POSITION_ENCODING_LEN];
+
+    for (i, (row, col)) in itertools::iproduct!(BlocksRow::all(), BlocksCol::all()).enumerate() {
+        buf[i] = pos.blocks()[(col, row)].map_or(0, |block| block.to_inner());
+    }
+
+    buf[36] = pos.block_holding().to_inner();
+    buf[37] = pos.hero_row().to_inner();
+    buf[38] = pos.move_remain();
+
+    buf
+}
+
+/// 初期局面から `max_depth` 手以内で到達できる局面たちについて、最適な残りコストと最善手を求める。
+///
+/// 面が終了している局面、および解けない局面はサンプルに含めない。
+/// 深さ `d` の局面の数は最大で 12^d 程度になり、その全てを解くので `max_depth` は小さく保つこと。
+pub fn collect_training_samples(
+    problem: &Problem,
+    last_stage: bool,
+    max_depth: usize,
+) -> Vec<TrainingSample> {
+    let (pos, moves) = problem.to_position_and_moves();
+
+    let mut collector = TrainingSampleCollector {
+        problem,
+        last_stage,
+        max_depth,
+        moves: &moves,
+        prefix: vec![],
+        samples: vec![],
+    };
+    collector.collect(pos, 0);
+
+    collector.samples
+}
+
+#[derive(Debug)]
+struct TrainingSampleCollector<'a> {
+    problem: &'a Problem,
+    last_stage: bool,
+    max_depth: usize,
+    moves: &'a [Move],
+    prefix: Vec<Move>,
+    samples: Vec<TrainingSample>,
+}
+
+impl TrainingSampleCollector<'_> {
+    fn collect(&mut self, pos: Position, cost: Cost) {
+        if pos.move_remain() == 0 || pos.is_stuck(self.moves) {
+            return;
+        }
+
+        let Some((solution, cost_total)) =
+            solve_with_prefix(self.problem, self.last_stage, &self.prefix)
+        else {
+            return;
+        };
+
+        self.samples.push(TrainingSample {
+            position: pos.clone(),
+            cost_remain: cost_total - cost,
+            best_move: solution.moves()[self.prefix.len()],
+        });
+
+        if self.prefix.len() == self.max_depth {
+            return;
+        }
+
+        for &mv in self.moves {
+            let Some((pos_nxt, cost_mv, _)) = pos.do_move(mv) else {
+                continue;
+            };
+            self.prefix.push(mv);
+            self.collect(pos_nxt, cost + cost_mv);
+            self.prefix.pop().unwrap();
+        }
+    }
+}
+
+/// サンプルたちをヘッダ付きの CSV として書き出す。
+///
+/// 各行は局面のエンコーディング (`encode_position`)、最適な残りコスト、最善手の順。全ての列は整数。
+pub fn write_training_csv<W: Write>(
+    wtr: &mut W,
+    samples: &[TrainingSample],
+) -> std::io::Result<()> {
+    let header: Vec<_> = (0..36)
+        .map(|i| format!("c{i}"))
+        .chain(
+            [
+                "holding",
+                "hero_row",
+                "move_remain",
+                "cost_remain",
+                "best_move",
+            ]
+            .map(str::to_owned),
+        )
+        .collect();
+    writeln!(wtr, "{}", header.join(","))?;
+
+    for sample in samples {
+        let values: Vec<_> = sample_to_row(sample).iter().map(u32::to_string).collect();
+        writeln!(wtr, "{}", values.join(","))?;
+    }
+
+    Ok(())
+}
+
+/// サンプルたちを NumPy の .npy 形式 (int32 の 2 次元配列) として書き出す。
+///
+/// 列の並びは `write_training_csv` と同じ。
+pub fn write_training_npy<W: Write>(
+    wtr: &mut W,
+    samples: &[TrainingSample],
+) -> std::io::Result<()> {
+    const MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+    const COL_COUNT: usize = POSITION_ENCODING_LEN + 2;
+
+    let mut header = format!(
+        "{{'descr': '<i4', 'fortran_order': False, 'shape': ({}, {COL_COUNT}), }}",
+        samples.len()
+    );
+    // マジック + ヘッダ長 (2 バイト) + ヘッダ + 改行 の長さを 64 の倍数にする。
+    let len_unpadded = MAGIC.len() + 2 + header.len() + 1;
+    header.extend(std::iter::repeat(' ').take(len_unpadded.next_multiple_of(64) - len_unpadded));
+    header.push('\n');
+
+    wtr.write_all(MAGIC)?;
+    wtr.write_all(&u16::try_from(header.len()).unwrap().to_le_bytes())?;
+    wtr.write_all(header.as_bytes())?;
+
+    for sample in samples {
+        for value in sample_to_row(sample) {
+            wtr.write_all(&(value as i32).to_le_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn sample_to_row(sample: &TrainingSample) -> Vec<u32> {
+    encode_position(&sample.position)
+        .into_iter()
+        .map(u32::from)
+        .chain([sample.cost_remain, u32::from(sample.best_move.to_inner())])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn parse_problem(s: impl AsRef<str>) -> Problem {
+        s.as_ref().parse().unwrap()
+    }
+
+    #[test]
+    fn test_training_samples() {
+        let problem = parse_problem(indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "});
+
+        let samples = collect_training_samples(&problem, false, 1);
+        assert!(samples.len() > 1);
+
+        // 初期局面のサンプルは最適解に一致する。
+        assert_eq!(samples[0].cost_remain(), 443);
+        assert_eq!(samples[0].best_move(), MoveSrc::Row10);
+        assert_eq!(
+            encode_position(samples[0].position()),
+            [
+                0, 0, 0, 0, 0, 0, //
+                0, 0, 0, 0, 0, 0, //
+                2, 4, 4, 4, 0, 0, //
+                1, 2, 2, 2, 0, 0, //
+                3, 3, 3, 3, 0, 0, //
+                4, 1, 1, 1, 0, 0, //
+                3, 11, 13,
+            ]
+        );
+
+        let mut csv = Vec::<u8>::new();
+        write_training_csv(&mut csv, &samples).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 1 + samples.len());
+        assert!(csv.lines().nth(1).unwrap().ends_with(",3,11,13,443,10"));
+
+        let mut npy = Vec::<u8>::new();
+        write_training_npy(&mut npy, &samples).unwrap();
+        let header_len = usize::from(u16::from_le_bytes([npy[8], npy[9]]));
+        assert_eq!((10 + header_len) % 64, 0);
+        assert_eq!(
+            npy.len(),
+            10 + header_len + 4 * samples.len() * (POSITION_ENCODING_LEN + 2)
+        );
+    }
+}
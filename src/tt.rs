@@ -0,0 +1,411 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::cost::Cost;
+use crate::error::Error;
+use crate::position::Position;
+use crate::problem::Problem;
+
+/// 置換表。局面から「その局面以降に必要な最小コスト」の下界への写像。
+///
+/// 同じ問題、同じ最終面フラグ、同じルール設定で探索する限り、ファイルに保存して次回以降の探索で再利用できる
+/// (オプションを変えて試す場合や、中断した探索を再開する場合など)。
+///
+/// キーの形式は `TtKeyMode` で選べる。
+/// `with_memory_limit()` でメモリ使用量の上限を設けると、上限に達したら最も長く参照されていないエントリから捨てる。
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TranspositionTable {
+    rom: Vec<u8>,
+    last_stage: bool,
+    ruleset_flags: u16,
+    key_mode: TtKeyMode,
+    entries: BTreeMap<u128, Cost>,
+    lru: Option<LruOrder>,
+}
+
+/// メモリ使用量に上限のある置換表の、1 エントリあたりのメモリ使用量の概算 (参照順の記録を含む)。
+pub(crate) const TT_ENTRY_BYTES: usize = 112;
+
+/// 表のエントリの参照順。上限を超えたら最も長く参照されていないエントリを捨てるのに使う (LRU)。
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct LruOrder {
+    capacity: usize,
+    clock: u64,
+    stamps: BTreeMap<u128, u64>,
+    order: BTreeMap<u64, u128>,
+}
+
+impl LruOrder {
+    /// `memory_limit` バイトに収まるエントリ数を上限とする。
+    pub(crate) fn with_memory_limit(memory_limit: usize) -> Self {
+        Self {
+            capacity: (memory_limit / TT_ENTRY_BYTES).max(1),
+            ..Self::default()
+        }
+    }
+
+    /// キー `key` を参照したことを記録する。エントリ数が上限を超えたら、捨てるべきキーを返す。
+    pub(crate) fn touch(&mut self, key: u128) -> Option<u128> {
+        self.clock += 1;
+        if let Some(stamp) = self.stamps.insert(key, self.clock) {
+            self.order.remove(&stamp);
+        }
+        self.order.insert(self.clock, key);
+
+        (self.stamps.len() > self.capacity).then(|| {
+            let (_, key_old) = self.order.pop_first().unwrap();
+            self.stamps.remove(&key_old);
+            key_old
+        })
+    }
+}
+
+/// 置換表のキーの形式。
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum TtKeyMode {
+    /// `Position::zobrist_hash()` (局面の 64 ビットのハッシュ値)。
+    /// 省メモリだが、衝突すると誤った下界で枝刈りし、最適でない解を最適と報告しうる。
+    #[default]
+    Hash64,
+
+    /// `Position::packed_key()` そのもの (128 ビット)。衝突しないので、最適性が保証される。
+    Full128,
+}
+
+impl TtKeyMode {
+    fn key_len(self) -> usize {
+        match self {
+            Self::Hash64 => 8,
+            Self::Full128 => 16,
+        }
+    }
+
+    fn to_inner(self) -> u8 {
+        match self {
+            Self::Hash64 => 0,
+            Self::Full128 => 1,
+        }
+    }
+
+    fn from_inner(inner: u8) -> Option<Self> {
+        match inner {
+            0 => Some(Self::Hash64),
+            1 => Some(Self::Full128),
+            _ => None,
+        }
+    }
+}
+
+impl TranspositionTable {
+    const MAGIC: &'static [u8; 4] = b"FATT";
+    const VERSION: u8 = 4;
+    const HEADER_LEN: usize = 4 + 1 + 1 + 2 + 1 + Problem::ROM_BYTES_LEN + 8;
+
+    /// 問題 `problem` 用の空の置換表を作る。
+    pub fn new(problem: &Problem, last_stage: bool) -> Self {
+        Self {
+            rom: problem.to_rom_bytes().to_vec(),
+            last_stage,
+            ruleset_flags: Self::ruleset_flags(problem),
+            key_mode: TtKeyMode::default(),
+            entries: BTreeMap::new(),
+            lru: None,
+        }
+    }
+
+    /// キーの形式を変更した置換表を返す。空の置換表でなければならない。
+    pub fn with_key_mode(self, key_mode: TtKeyMode) -> Self {
+        assert!(self.is_empty());
+
+        Self { key_mode, ..self }
+    }
+
+    pub fn key_mode(&self) -> TtKeyMode {
+        self.key_mode
+    }
+
+    /// メモリ使用量の上限 (バイト) を設けた置換表を返す。`None` なら上限なし。
+    /// 上限に達したら、最も長く参照されていないエントリから捨てる。捨てても探索結果は変わらない (枝刈りが減るだけ)。
+    /// 既に上限を超えていれば、キーの順に捨てる。上限はファイルには保存しない。
+    pub fn with_memory_limit(mut self, memory_limit: Option<usize>) -> Self {
+        self.lru = memory_limit.map(LruOrder::with_memory_limit);
+        if let Some(lru) = &mut self.lru {
+            let keys: Vec<_> = self.entries.keys().copied().collect();
+            for key in keys {
+                if let Some(key_old) = lru.touch(key) {
+                    self.entries.remove(&key_old);
+                }
+            }
+        }
+        self
+    }
+
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.lru.as_ref().map(|lru| lru.capacity * TT_ENTRY_BYTES)
+    }
+
+    /// 置換表が問題 `problem` (最終面フラグ `last_stage`) 用のものかどうかを返す。
+    pub fn matches(&self, problem: &Problem, last_stage: bool) -> bool {
+        self.rom == problem.to_rom_bytes()
+            && self.last_stage == last_stage
+            && self.ruleset_flags == Self::ruleset_flags(problem)
+    }
+
+    /// エントリを残したまま、問題 `problem` (最終面フラグ `last_stage`) 用に付け替えた置換表を返す。
+    /// 最終面フラグかルール設定が異なれば `None` を返す。
+    ///
+    /// 下界は局面だけで決まるので、ブロックの配置などが異なる問題でも、着手できる行の集合が同じなら使い回せる。
+    /// 着手できる行の集合が同じかどうかは呼び出し側で確かめること。
+    pub(crate) fn rebind(self, problem: &Problem, last_stage: bool) -> Option<Self> {
+        (self.last_stage == last_stage && self.ruleset_flags == Self::ruleset_flags(problem)).then(
+            || Self {
+                rom: problem.to_rom_bytes().to_vec(),
+                ..self
+            },
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 局面 `pos` 以降に必要な最小コストの下界を返す。
+    pub(crate) fn get(&mut self, pos: &Position) -> Option<Cost> {
+        let key = self.key(pos);
+        let bound = self.entries.get(&key).copied();
+        if let (Some(_), Some(lru)) = (bound, &mut self.lru) {
+            lru.touch(key);
+        }
+        bound
+    }
+
+    /// 局面 `pos` 以降に必要な最小コストの下界 `bound` を記録する。既存の値より大きい場合のみ更新する。
+    pub(crate) fn update(&mut self, pos: &Position, bound: Cost) {
+        let key = self.key(pos);
+        let entry = self.entries.entry(key).or_insert(0);
+        *entry = (*entry).max(bound);
+        if let Some(key_old) = self.lru.as_mut().and_then(|lru| lru.touch(key)) {
+            self.entries.remove(&key_old);
+        }
+    }
+
+    /// バイト列に変換する。
+    ///
+    /// 形式はマジック `FATT`, バージョン, 最終面フラグ, ルール設定 (u16), キーの形式, 問題の ROM バイト列,
+    /// エントリ数 (u64), エントリ (キー u64 または u128, 下界 u32) の並び。整数はリトルエンディアン。
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let key_len = self.key_mode.key_len();
+        let mut buf = Vec::with_capacity(Self::HEADER_LEN + (key_len + 4) * self.entries.len());
+
+        buf.extend(Self::MAGIC);
+        buf.push(Self::VERSION);
+        buf.push(u8::from(self.last_stage));
+        buf.extend(self.ruleset_flags.to_le_bytes());
+        buf.push(self.key_mode.to_inner());
+        buf.extend(&self.rom);
+        buf.extend((self.entries.len() as u64).to_le_bytes());
+        for (&key, &bound) in &self.entries {
+            buf.extend(&key.to_le_bytes()[..key_len]);
+            buf.extend(bound.to_le_bytes());
+        }
+
+        buf
+    }
+
+    /// `to_bytes()` の出力から復元する。
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, Error> {
+        let (header, body) = buf
+            .split_at_checked(Self::HEADER_LEN)
+            .ok_or(Error::InvalidTtFile)?;
+        if &header[..4] != Self::MAGIC || header[4] != Self::VERSION || header[5] > 1 {
+            return Err(Error::InvalidTtFile);
+        }
+        let last_stage = header[5] != 0;
+        let ruleset_flags = u16::from_le_bytes([header[6], header[7]]);
+        let key_mode = TtKeyMode::from_inner(header[8]).ok_or(Error::InvalidTtFile)?;
+        let rom = header[9..9 + Problem::ROM_BYTES_LEN].to_vec();
+        let len = u64::from_le_bytes(header[9 + Problem::ROM_BYTES_LEN..].try_into().unwrap());
+
+        let key_len = key_mode.key_len();
+        if body.len() as u64 != (key_len + 4) as u64 * len {
+            return Err(Error::InvalidTtFile);
+        }
+        let entries = body
+            .chunks_exact(key_len + 4)
+            .map(|chunk| {
+                let mut key = [0; 16];
+                key[..key_len].copy_from_slice(&chunk[..key_len]);
+                let key = u128::from_le_bytes(key);
+                let bound = Cost::from_le_bytes(chunk[key_len..].try_into().unwrap());
+                (key, bound)
+            })
+            .collect();
+
+        Ok(Self {
+            rom,
+            last_stage,
+            ruleset_flags,
+            key_mode,
+            entries,
+            lru: None,
+        })
+    }
+
+    /// ファイルから問題 `problem` (最終面フラグ `last_stage`) 用の置換表を読み込む。
+    /// ファイルが存在しなければ空の置換表を返す。別の問題用の置換表だった場合はエラーを返す。
+    #[cfg(feature = "std")]
+    pub fn load(
+        path: impl AsRef<std::path::Path>,
+        problem: &Problem,
+        last_stage: bool,
+    ) -> Result<Self, Error> {
+        match std::fs::read(path) {
+            Ok(buf) => {
+                let this = Self::from_bytes(&buf)?;
+                if !this.matches(problem, last_stage) {
+                    return Err(Error::TtMismatch);
+                }
+                Ok(this)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(Self::new(problem, last_stage))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// ファイルに書き込む。途中で中断してもファイルが壊れることはない (`atomic_write` 参照)。
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        use std::io::Write as _;
+
+        crate::fs::atomic_write(path, |w| w.write_all(&self.to_bytes()))?;
+
+        Ok(())
+    }
+
+    fn ruleset_flags(problem: &Problem) -> u16 {
+        problem.ruleset().to_flags()
+    }
+
+    fn key(&self, pos: &Position) -> u128 {
+        match self.key_mode {
+            TtKeyMode::Hash64 => u128::from(pos.zobrist_hash()),
+            TtKeyMode::Full128 => pos.packed_key(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use crate::solver::{solve_problem, solve_problem_with_tt, Solution};
+
+    use super::*;
+
+    fn parse_problem(s: impl AsRef<str>) -> Problem {
+        s.as_ref().parse().unwrap()
+    }
+
+    #[test]
+    fn test_tt() {
+        let problem = parse_problem(indoc! {"
+            3 13
+            ####....
+            ###.....
+            ##......
+            #.......
+            ........
+            ........
+            ........
+            ........
+            2444....
+            1222....
+            3333....
+            4111....
+        "});
+        let expected = solve_problem(&problem, false);
+
+        // 空の置換表で解いても、埋まった置換表で解き直しても結果は変わらない。
+        let mut tt = TranspositionTable::new(&problem, false);
+        assert_eq!(solve_problem_with_tt(&problem, false, &mut tt), expected);
+        assert!(!tt.is_empty());
+        assert_eq!(solve_problem_with_tt(&problem, false, &mut tt), expected);
+
+        // バイト列を経由しても復元できる。
+        let tt_restored = TranspositionTable::from_bytes(&tt.to_bytes()).unwrap();
+        assert_eq!(tt_restored, tt);
+        assert!(tt_restored.matches(&problem, false));
+        assert!(!tt_restored.matches(&problem, true));
+
+        // 128 ビットのキーでも結果は変わらない。
+        let mut tt_full =
+            TranspositionTable::new(&problem, false).with_key_mode(TtKeyMode::Full128);
+        assert_eq!(
+            solve_problem_with_tt(&problem, false, &mut tt_full),
+            expected
+        );
+        let tt_full_restored = TranspositionTable::from_bytes(&tt_full.to_bytes()).unwrap();
+        assert_eq!(tt_full_restored, tt_full);
+        assert_eq!(tt_full_restored.key_mode(), TtKeyMode::Full128);
+
+        // 最終面では暫定解のコストが局面までのコストを下回りうる。
+        let problem = parse_problem(include_str!("../problem/20.in"));
+        let expected = solve_problem(&problem, true);
+        let mut tt = TranspositionTable::new(&problem, true);
+        assert_eq!(
+            solve_problem_with_tt(&problem, true, &mut tt).map(|(_, cost)| cost),
+            expected.map(|(_, cost)| cost)
+        );
+
+        let bytes = tt.to_bytes();
+        assert_eq!(
+            TranspositionTable::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::InvalidTtFile)
+        );
+    }
+
+    #[test]
+    fn test_tt_memory_limit() {
+        let problem = parse_problem(include_str!("../problem/10.in"));
+        let cost_of = |res: Option<(Solution, Cost)>| res.map(|(_, cost)| cost);
+        let expected = cost_of(solve_problem(&problem, false));
+
+        // 上限に達したらエントリを捨てるが、総コストは変わらない。
+        let memory_limit = 20 * TT_ENTRY_BYTES;
+        let mut tt = TranspositionTable::new(&problem, false).with_memory_limit(Some(memory_limit));
+        assert_eq!(tt.memory_limit(), Some(memory_limit));
+        assert_eq!(
+            cost_of(solve_problem_with_tt(&problem, false, &mut tt)),
+            expected
+        );
+        assert_eq!(tt.len(), 20);
+        assert_eq!(
+            cost_of(solve_problem_with_tt(&problem, false, &mut tt)),
+            expected
+        );
+
+        // 既存の置換表に上限を設けると、上限まで減らす。
+        let mut tt = TranspositionTable::new(&problem, false);
+        solve_problem_with_tt(&problem, false, &mut tt);
+        assert!(tt.len() > 10);
+        let tt = tt.with_memory_limit(Some(10 * TT_ENTRY_BYTES));
+        assert_eq!(tt.len(), 10);
+    }
+
+    #[test]
+    fn test_lru_order() {
+        let mut lru = LruOrder::with_memory_limit(2 * TT_ENTRY_BYTES);
+        assert_eq!(lru.touch(1), None);
+        assert_eq!(lru.touch(2), None);
+        assert_eq!(lru.touch(1), None);
+        assert_eq!(lru.touch(3), Some(2));
+        assert_eq!(lru.touch(4), Some(1));
+    }
+}
@@ -0,0 +1,66 @@
+/// 局面の Zobrist ハッシュ用の乱数表。
+///
+/// 局面のハッシュ値は、各マスのブロック、自機の位置、保持ブロック、残り手数に対応する乱数の XOR。
+/// 着手で変化した要素の乱数だけを XOR し直せば更新できる。空白のマスの乱数は 0。
+#[derive(Debug)]
+pub(crate) struct ZobristTable {
+    /// `Blocks` の内部インデックスとブロックの内部値ごとの乱数。
+    pub(crate) cells: [[u64; 8]; 7 * 7],
+    /// `MoveSrc` の内部値ごとの乱数。
+    pub(crate) hero_row: [u64; 16],
+    /// `Block` の内部値ごとの乱数。
+    pub(crate) block_holding: [u64; 8],
+    pub(crate) move_remain: [u64; 256],
+}
+
+pub(crate) static ZOBRIST: ZobristTable = ZobristTable::new();
+
+impl ZobristTable {
+    /// splitmix64 で固定のシードから乱数表を作る (実行ごと、ビルドごとに同じ値になる)。
+    const fn new() -> Self {
+        let mut state = 0x0123_4567_89AB_CDEF_u64;
+
+        let mut table = Self {
+            cells: [[0; 8]; 7 * 7],
+            hero_row: [0; 16],
+            block_holding: [0; 8],
+            move_remain: [0; 256],
+        };
+
+        let mut i = 0;
+        while i < table.cells.len() {
+            let mut j = 0;
+            while j < table.cells[i].len() {
+                table.cells[i][j] = splitmix64(&mut state);
+                j += 1;
+            }
+            i += 1;
+        }
+        let mut i = 0;
+        while i < table.hero_row.len() {
+            table.hero_row[i] = splitmix64(&mut state);
+            i += 1;
+        }
+        let mut i = 0;
+        while i < table.block_holding.len() {
+            table.block_holding[i] = splitmix64(&mut state);
+            i += 1;
+        }
+        let mut i = 0;
+        while i < table.move_remain.len() {
+            table.move_remain[i] = splitmix64(&mut state);
+            i += 1;
+        }
+
+        table
+    }
+}
+
+/// splitmix64 の乱数列の次の値を返し、状態 `state` を進める。
+pub(crate) const fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut x = *state;
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}